@@ -0,0 +1,301 @@
+//! Scoping background work to async requests.
+//!
+//! [`request_scope`] anchors per-request data for the duration of a handler and runs the futures spawned through its [`RequestScope`] alongside it.
+//! These only receive weak portals, and are awaited before the request's borrow ends or cancelled along with the request,
+//! so that no background work can outlive the data it refers to.
+//!
+//! [`task_scoped!`](../macro.task_scoped.html) declares keys through which the code a future runs can access a portal installed for it,
+//! without threading it through as parameter.
+//! [`anchor_future`] anchors state for the duration of a single future instead.
+//!
+//! With the `async` feature, [`AsyncAnchor`] and [`AsyncRwAnchor`] anchor state that tasks share through portals,
+//! whose guards are awaited instead of blocking the executor's thread and can be held across `.await`.
+
+use {
+    crate::sync::{RwAnchor, RwPortal, WeakRwPortal},
+    std::{
+        fmt::{self, Debug, Formatter},
+        future::{self, Future},
+        mem,
+        pin::Pin,
+        sync::{Arc, Mutex, PoisonError},
+        task::{Context, Poll, Waker},
+    },
+    wyz::pipe::*,
+};
+
+#[cfg(feature = "async")]
+mod lock;
+#[cfg(feature = "async")]
+pub use lock::{
+    AsyncAnchor, AsyncPortal, AsyncReadGuard, AsyncRwAnchor, AsyncRwPortal, AsyncWeakPortal,
+    AsyncWeakRwPortal, AsyncWriteGuard,
+};
+mod task_local;
+pub use {
+    crate::task_scoped,
+    task_local::{TaskScope, TaskScopedKey},
+};
+
+/// A spawned background future.
+type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The background futures of one request.
+struct Tasks {
+    /// The futures that haven't completed yet, or `None` once the request scope has ended.
+    pending: Mutex<Option<Vec<Task>>>,
+
+    /// Wakes the request's future when a task is spawned.
+    waker: Mutex<Option<Waker>>,
+}
+
+impl Debug for Tasks {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tasks").finish_non_exhaustive()
+    }
+}
+
+impl Tasks {
+    /// Creates an open instance without tasks.
+    fn new() -> Self {
+        Self {
+            pending: Mutex::new(Some(Vec::new())),
+            waker: Mutex::new(None),
+        }
+    }
+
+    /// Polls all pending tasks, including those spawned meanwhile.
+    /// Ready once none are left.
+    fn poll(&self, cx: &mut Context<'_>) -> Poll<()> {
+        *self.waker.lock().unwrap_or_else(PoisonError::into_inner) = cx.waker().clone().pipe(Some);
+        let mut still_pending = Vec::new();
+        loop {
+            let tasks = match &mut *self.pending.lock().unwrap_or_else(PoisonError::into_inner) {
+                Some(tasks) if !tasks.is_empty() => mem::take(tasks),
+                _ => break,
+            };
+            for mut task in tasks {
+                if task.as_mut().poll(cx).is_pending() {
+                    still_pending.push(task);
+                }
+            }
+        }
+        let mut pending = self.pending.lock().unwrap_or_else(PoisonError::into_inner);
+        match &mut *pending {
+            Some(tasks) => {
+                tasks.append(&mut still_pending);
+                if tasks.is_empty() {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }
+            None => Poll::Ready(()),
+        }
+    }
+
+    /// Ends the scope, cancelling all pending tasks and any spawned later.
+    fn close(&self) {
+        let tasks = self
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        drop(tasks); // Outside the lock, in case a task's drop spawns.
+    }
+}
+
+/// Closes `Tasks` when dropped, including when the request is cancelled.
+struct Close<'a>(&'a Tasks);
+
+impl<'a> Drop for Close<'a> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// Handed to the handler of a [`request_scope`], to access the request's data and spawn background work.
+#[derive(Debug, Clone)]
+pub struct RequestScope<T: ?Sized> {
+    /// To the request's data.
+    portal: WeakRwPortal<T>,
+
+    /// Where spawned tasks go.
+    tasks: Arc<Tasks>,
+}
+
+impl<T: ?Sized> RequestScope<T> {
+    /// Creates a weak portal to the request's data.
+    /// Upgrading it fails once the request is done.
+    #[inline]
+    pub fn weak_portal(&self) -> WeakRwPortal<T> {
+        self.portal.clone()
+    }
+
+    /// Runs `task` alongside the handler, on the same task as the request.
+    /// The request doesn't complete until `task` does. If the request is cancelled instead, so is `task`.
+    ///
+    /// Futures spawned after the request scope ended are dropped immediately.
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        let task: Task = Box::pin(task);
+        let mut pending = self
+            .tasks
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(tasks) = &mut *pending {
+            tasks.push(task);
+            drop(pending);
+            if let Some(waker) = &*self
+                .tasks
+                .waker
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+            {
+                waker.wake_by_ref();
+            }
+        }
+    }
+}
+
+/// Anchors `data` while `handler` runs, then awaits the futures it spawned through the [`RequestScope`] it receives.
+/// All of them run concurrently on the calling task, so no executor is needed.
+///
+/// If the returned future is dropped early, the spawned futures are dropped along with it.
+///
+/// # Panics
+///
+/// If a strong portal to `data` outlives the request, like [`RwAnchor`]'s drop.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::asynch::request_scope,
+///     std::{
+///         future::Future,
+///         task::{Context, Poll, Waker},
+///     },
+/// };
+///
+/// let mut log = Vec::new();
+/// let request = request_scope(&mut log, |scope| async move {
+///     let portal = scope.weak_portal();
+///     scope.spawn(async move {
+///         // Runs before the request's borrow of `log` ends.
+///         portal.upgrade().write().push("background");
+///     });
+///     scope.weak_portal().upgrade().write().push("handler");
+///     200
+/// });
+///
+/// let mut request = Box::pin(request);
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(request.as_mut().poll(&mut cx), Poll::Ready(200));
+/// drop(request);
+/// assert_eq!(log, ["handler", "background"]);
+/// ```
+pub async fn request_scope<T: ?Sized, F: Future>(
+    data: &mut T,
+    handler: impl FnOnce(RequestScope<T>) -> F,
+) -> F::Output {
+    let anchor = RwAnchor::new(data);
+    let tasks = Arc::new(Tasks::new());
+    let close = Close(&tasks);
+
+    let mut handler = RequestScope {
+        portal: anchor.weak_portal(),
+        tasks: Arc::clone(&tasks),
+    }
+    .pipe(handler)
+    .pipe(Box::pin);
+    let mut handler_output = None;
+    let output = future::poll_fn(|cx| {
+        if handler_output.is_none() {
+            if let Poll::Ready(value) = handler.as_mut().poll(cx) {
+                handler_output = Some(value);
+            }
+        }
+        match (tasks.poll(cx), handler_output.take()) {
+            (Poll::Ready(()), Some(value)) => Poll::Ready(value),
+            (_, value) => {
+                handler_output = value;
+                Poll::Pending
+            }
+        }
+    })
+    .await;
+
+    drop(handler);
+    drop(close);
+    drop(anchor);
+    output
+}
+
+/// Anchors `state` for as long as the future returned by `f` runs, so that it can use it through an owned portal.
+/// This is the safe way to hand borrowed state to code that requires `'static` futures, e.g. `Pin<Box<dyn Future + 'static>>`.
+///
+/// The anchor is only dropped after the future created by `f` completed or was dropped, including when the returned future is cancelled.
+///
+/// # Panics
+///
+/// If a strong portal to `state` outlives the returned future (e.g. because it was moved into a spawned task), like [`RwAnchor`]'s drop.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::asynch::anchor_future,
+///     std::{
+///         future::Future,
+///         pin::Pin,
+///         task::{Context, Poll, Waker},
+///     },
+/// };
+///
+/// /// Only accepts futures that don't borrow anything.
+/// fn poll_boxed(mut future: Pin<Box<dyn Future<Output = usize> + 'static>>) -> Poll<usize> {
+///     future
+///         .as_mut()
+///         .poll(&mut Context::from_waker(Waker::noop()))
+/// }
+///
+/// let mut log = vec!["start"];
+/// let future = anchor_future(&mut log, |portal| async move {
+///     let boxed: Pin<Box<dyn Future<Output = usize>>> = Box::pin(async move {
+///         portal.write().push("boxed");
+///         portal.read().len()
+///     });
+///     poll_boxed(boxed)
+/// });
+///
+/// let mut future = Box::pin(future);
+/// let output = future
+///     .as_mut()
+///     .poll(&mut Context::from_waker(Waker::noop()));
+/// assert_eq!(output, Poll::Ready(Poll::Ready(2)));
+/// drop(future);
+/// assert_eq!(log, ["start", "boxed"]);
+///
+/// // Cancelling the outer future drops the inner one first.
+/// let future = anchor_future(&mut log, |portal| async move {
+///     std::future::pending::<()>().await;
+///     drop(portal);
+/// });
+/// let mut future = Box::pin(future);
+/// assert!(future
+///     .as_mut()
+///     .poll(&mut Context::from_waker(Waker::noop()))
+///     .is_pending());
+/// drop(future);
+/// ```
+pub async fn anchor_future<T: ?Sized, F: Future>(
+    state: &mut T,
+    f: impl FnOnce(RwPortal<T>) -> F,
+) -> F::Output {
+    let anchor = RwAnchor::new(state);
+    let future = f(anchor.portal());
+    let output = future.await;
+    drop(anchor);
+    output
+}
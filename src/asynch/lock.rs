@@ -0,0 +1,816 @@
+//! Anchors whose portals are awaited instead of blocking the thread, so that async tasks can share borrowed state.
+
+use {
+    crate::{
+        anchor_drop_panic, anchor_dropped_panic,
+        messages::{self, Report, Violation},
+        poison::Defuse,
+        Peek,
+    },
+    std::{
+        collections::BTreeMap,
+        fmt::{self, Debug, Formatter},
+        future::{self, Future},
+        marker::PhantomData,
+        ops::{Deref, DerefMut},
+        pin::Pin,
+        ptr::NonNull,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Condvar, Mutex, MutexGuard, PoisonError, Weak,
+        },
+        task::{Context, Poll, Waker},
+        thread,
+    },
+    wyz::pipe::*,
+};
+
+/// Lock and portal bookkeeping of an async anchor.
+#[derive(Default)]
+struct State {
+    /// The number of (strong) portals.
+    portals: usize,
+
+    /// The number of read guards.
+    readers: usize,
+
+    /// Whether a write guard exists.
+    writing: bool,
+
+    /// The number of pending writes, which new reads wait for so that writers don't starve.
+    writers_waiting: usize,
+
+    /// Set when a write guard is released while unwinding, unless defused.
+    poisoned: bool,
+
+    /// The ID of the next pending acquisition.
+    next_waiter: u64,
+
+    /// Woken whenever a guard is released.
+    waiters: BTreeMap<u64, Waker>,
+
+    /// Woken once no portals are left, while the anchor is being closed.
+    closing: Option<Waker>,
+}
+
+/// The state shared between an async anchor and its portals.
+struct Shared<T: ?Sized> {
+    /// Only dereferenced while `anchored` or through a guard.
+    target: NonNull<T>,
+
+    /// Cleared when the anchor is dropped.
+    anchored: AtomicBool,
+
+    /// Locks and counts access to `target`.
+    state: Mutex<State>,
+
+    /// Notified whenever a guard is released, for the anchor's drop.
+    released: Condvar,
+}
+
+unsafe impl<T: ?Sized + Send + Sync> Send for Shared<T> {
+    //SAFETY: Access to `target` is synchronised through `state`.
+}
+unsafe impl<T: ?Sized + Send + Sync> Sync for Shared<T> {
+    //SAFETY: Access to `target` is synchronised through `state`.
+}
+
+/// Panics with `ANCHOR_POISONED`.
+#[cold]
+#[track_caller]
+fn poisoned_panic() -> ! {
+    messages::panic(Report::new(Violation::AnchorPoisoned))
+}
+
+impl<T: ?Sized> Shared<T> {
+    /// Creates a new instance for an anchor of `target`, without portals.
+    fn new(target: NonNull<T>) -> Arc<Self> {
+        Arc::new(Self {
+            target,
+            anchored: AtomicBool::new(true),
+            state: Mutex::new(State::default()),
+            released: Condvar::new(),
+        })
+    }
+
+    /// Locks the bookkeeping, ignoring poison since it's never left inconsistent.
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns `true` iff the anchor hasn't been dropped yet.
+    fn is_anchored(&self) -> bool {
+        self.anchored.load(Ordering::Acquire)
+    }
+
+    /// Counts a new (strong) portal, and returns a handle for it.
+    fn portal(self: &Arc<Self>) -> Arc<Self> {
+        self.lock().portals += 1;
+        Arc::clone(self)
+    }
+
+    /// Counts and returns a new (strong) portal iff `weak`'s anchor still exists.
+    fn upgrade(weak: &Weak<Self>) -> Option<Arc<Self>> {
+        let shared = weak.upgrade()?;
+        let mut state = shared.lock();
+        if !shared.is_anchored() {
+            return None;
+        }
+        state.portals += 1;
+        drop(state);
+        Some(shared)
+    }
+
+    /// Uncounts a (strong) portal.
+    fn release_portal(&self) {
+        let mut state = self.lock();
+        state.portals -= 1;
+        if state.portals == 0 {
+            if let Some(closing) = state.closing.take() {
+                closing.wake();
+            }
+        }
+    }
+
+    /// Counts a guard iff that's possible now, otherwise registers `cx` (if any) to be woken when it may be.
+    /// `waiter` identifies this acquisition's registration.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped or poisoned.
+    #[track_caller]
+    fn poll_acquire(
+        &self,
+        exclusive: bool,
+        waiter: &mut Option<u64>,
+        cx: Option<&mut Context<'_>>,
+    ) -> Poll<()> {
+        let mut state = self.lock();
+        if !self.is_anchored() {
+            drop(state);
+            anchor_dropped_panic()
+        }
+        if state.poisoned {
+            drop(state);
+            poisoned_panic()
+        }
+        let available = if exclusive {
+            !state.writing && state.readers == 0
+        } else {
+            !state.writing && state.writers_waiting == 0
+        };
+        if available {
+            if let Some(id) = waiter.take() {
+                state.waiters.remove(&id);
+                if exclusive {
+                    state.writers_waiting -= 1;
+                }
+            }
+            if exclusive {
+                state.writing = true;
+            } else {
+                state.readers += 1;
+            }
+            Poll::Ready(())
+        } else {
+            if let Some(cx) = cx {
+                let id = if let Some(id) = *waiter {
+                    id
+                } else {
+                    let id = state.next_waiter;
+                    state.next_waiter += 1;
+                    if exclusive {
+                        state.writers_waiting += 1;
+                    }
+                    *waiter = Some(id);
+                    id
+                };
+                state.waiters.insert(id, cx.waker().clone());
+            }
+            Poll::Pending
+        }
+    }
+
+    /// Withdraws the pending acquisition registered as `id`.
+    fn cancel(&self, exclusive: bool, id: u64) {
+        let mut state = self.lock();
+        state.waiters.remove(&id);
+        if exclusive {
+            state.writers_waiting -= 1;
+            // Reads may have waited for this write.
+            wake_all(&state);
+        }
+    }
+
+    /// Uncounts a guard, poisoning the anchor iff it's a write guard released while unwinding and not `defused`.
+    fn release_guard(&self, exclusive: bool, defused: bool) {
+        let mut state = self.lock();
+        if exclusive {
+            state.writing = false;
+            if thread::panicking() && !defused {
+                state.poisoned = true;
+            }
+        } else {
+            state.readers -= 1;
+        }
+        wake_all(&state);
+        drop(state);
+        self.released.notify_all();
+    }
+
+    /// Ready once no portals are left, which also means no guards are.
+    fn poll_unused(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.lock();
+        if state.portals == 0 {
+            Poll::Ready(())
+        } else {
+            state.closing = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    /// Marks the anchor as dropped and waits for the remaining guards to be released.
+    /// Returns the number of remaining portals and whether the anchor is poisoned.
+    fn detach(&self) -> (usize, bool) {
+        let mut state = self.lock();
+        self.anchored.store(false, Ordering::Release);
+        // Pending acquisitions panic once polled again.
+        wake_all(&state);
+        while state.writing || state.readers > 0 {
+            state = self
+                .released
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        (state.portals, state.poisoned)
+    }
+
+    /// Calls `show` with the target iff it can be read right now,
+    /// counting as reader until `show` returns so that it can't be written meanwhile.
+    fn peek<R>(&self, show: impl FnOnce(Peek<'_, T>) -> R) -> R {
+        let mut state = self.lock();
+        if !self.is_anchored() {
+            show(Peek::Expired)
+        } else if state.poisoned {
+            show(Peek::Poisoned)
+        } else if state.writing {
+            show(Peek::Locked)
+        } else {
+            state.readers += 1;
+            drop(state);
+            let guard = AsyncReadGuard(self);
+            show(Peek::Value(&*guard))
+        }
+    }
+}
+
+/// Wakes all pending acquisitions of `state`, so that they try again.
+fn wake_all(state: &State) {
+    for waker in state.waiters.values() {
+        waker.wake_by_ref();
+    }
+}
+
+/// Panics like the threadsafe anchors do when dropped in use or poisoned.
+fn detach_anchor<T: ?Sized>(shared: &Shared<T>) {
+    let (portals, poisoned) = shared.detach();
+    if portals > 0 {
+        anchor_drop_panic::<T>(Violation::AnchorStillInUse, None, portals, &"")
+    }
+    if poisoned {
+        anchor_drop_panic::<T>(Violation::AnchorPoisoned, None, 0, &"")
+    }
+}
+
+/// Resolves once a guard has been counted, and withdraws from the queue if dropped before that.
+struct Acquire<'a, T: ?Sized> {
+    /// Where to acquire the guard.
+    shared: &'a Shared<T>,
+
+    /// Whether this acquires a write guard.
+    exclusive: bool,
+
+    /// Identifies the registration while pending.
+    waiter: Option<u64>,
+}
+
+impl<'a, T: ?Sized> Acquire<'a, T> {
+    /// Creates a new instance that's not registered yet.
+    const fn new(shared: &'a Shared<T>, exclusive: bool) -> Self {
+        Self {
+            shared,
+            exclusive,
+            waiter: None,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Future for Acquire<'a, T> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.shared
+            .poll_acquire(this.exclusive, &mut this.waiter, Some(cx))
+    }
+}
+
+impl<'a, T: ?Sized> Drop for Acquire<'a, T> {
+    fn drop(&mut self) {
+        if let Some(id) = self.waiter.take() {
+            self.shared.cancel(self.exclusive, id);
+        }
+    }
+}
+
+/// An immutable anchor whose portals async tasks can share, and which can be [closed](#method.close) without blocking the thread.
+///
+/// # Panics
+///
+/// On drop, iff any associated [`AsyncPortal`]s exist.
+pub struct AsyncAnchor<'a, T: ?Sized> {
+    /// Shared with the portals.
+    shared: Arc<Shared<T>>,
+
+    /// Borrows the target.
+    _phantom: PhantomData<&'a T>,
+}
+
+/// A mutable anchor whose portals async tasks can share, and which can be [closed](#method.close) without blocking the thread.
+///
+/// # Panics
+///
+/// On drop, iff any associated [`AsyncRwPortal`]s exist or, otherwise, iff the anchor has been poisoned.
+/// Dropping it also blocks the thread while guards are held, so close it instead while tasks may still use it.
+pub struct AsyncRwAnchor<'a, T: ?Sized> {
+    /// Shared with the portals.
+    shared: Arc<Shared<T>>,
+
+    /// Borrows the target exclusively.
+    _phantom: PhantomData<&'a mut T>,
+}
+
+/// An immutable portal of an [`AsyncAnchor`]. Dereference it directly with `*` or `.deref()`.
+#[must_use]
+pub struct AsyncPortal<T: ?Sized>(Arc<Shared<T>>);
+
+/// A mutable portal of an [`AsyncRwAnchor`], through which guards are awaited instead of blocking the thread.
+#[must_use]
+pub struct AsyncRwPortal<T: ?Sized>(Arc<Shared<T>>);
+
+/// A weak portal of an [`AsyncAnchor`], which doesn't keep it in use.
+#[must_use]
+pub struct AsyncWeakPortal<T: ?Sized>(Weak<Shared<T>>);
+
+/// A weak portal of an [`AsyncRwAnchor`], which doesn't keep it in use.
+#[must_use]
+pub struct AsyncWeakRwPortal<T: ?Sized>(Weak<Shared<T>>);
+
+/// A read guard acquired through an [`AsyncRwPortal`].
+/// Unlike the guards of [`sync::RwPortal`](`crate::sync::RwPortal`), it can be held across `.await` and sent to other threads.
+#[must_use]
+pub struct AsyncReadGuard<'a, T: ?Sized>(&'a Shared<T>);
+
+/// A write guard acquired through an [`AsyncRwPortal`].
+/// Unlike the guards of [`sync::RwPortal`](`crate::sync::RwPortal`), it can be held across `.await` and sent to other threads.
+///
+/// Releasing it while unwinding poisons the anchor, unless it was [defused](`Defuse`).
+#[must_use]
+pub struct AsyncWriteGuard<'a, T: ?Sized> {
+    /// The locked anchor.
+    shared: &'a Shared<T>,
+
+    /// Whether to skip poisoning the anchor.
+    defused: bool,
+}
+
+impl<'a, T: ?Sized> AsyncAnchor<'a, T> {
+    /// Creates a new `AsyncAnchor` instance, capturing `reference`.
+    #[must_use]
+    pub fn new(reference: &'a T) -> Self {
+        Self {
+            shared: Shared::new(reference.into()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a portal of indeterminate lifetime associated with this anchor.
+    #[inline]
+    pub fn portal(&self) -> AsyncPortal<T> {
+        self.shared.portal().pipe(AsyncPortal)
+    }
+
+    /// Creates a weak portal of indeterminate lifetime associated with this anchor.
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    #[inline]
+    pub fn weak_portal(&self) -> AsyncWeakPortal<T> {
+        Arc::downgrade(&self.shared).pipe(AsyncWeakPortal)
+    }
+
+    /// Resolves once all (strong) portals have been dropped, then drops this anchor,
+    /// so that the captured reference can be used again afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Iff weak portals were upgraded meanwhile, like dropping the anchor.
+    pub async fn close(self) {
+        future::poll_fn(|cx| self.shared.poll_unused(cx)).await;
+        drop(self);
+    }
+}
+
+impl<'a, T: ?Sized> AsyncRwAnchor<'a, T> {
+    /// Creates a new `AsyncRwAnchor` instance, capturing `reference`.
+    #[must_use]
+    pub fn new(reference: &'a mut T) -> Self {
+        Self {
+            shared: Shared::new(reference.into()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a portal of indeterminate lifetime associated with this anchor.
+    #[inline]
+    pub fn portal(&self) -> AsyncRwPortal<T> {
+        self.shared.portal().pipe(AsyncRwPortal)
+    }
+
+    /// Creates a weak portal of indeterminate lifetime associated with this anchor.
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    #[inline]
+    pub fn weak_portal(&self) -> AsyncWeakRwPortal<T> {
+        Arc::downgrade(&self.shared).pipe(AsyncWeakRwPortal)
+    }
+
+    /// Resolves once all (strong) portals and with them all guards have been dropped, then drops this anchor,
+    /// so that the captured reference can be used again afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or weak portals were upgraded meanwhile, like dropping the anchor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::asynch::AsyncRwAnchor;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let mut log = Vec::new();
+    ///     let anchor = AsyncRwAnchor::new(&mut log);
+    ///
+    ///     let tasks: Vec<_> = (0..4)
+    ///         .map(|i| {
+    ///             let portal = anchor.portal();
+    ///             tokio::spawn(async move {
+    ///                 let mut log = portal.write().await;
+    ///                 tokio::task::yield_now().await; // Held across `.await`.
+    ///                 log.push(i);
+    ///             })
+    ///         })
+    ///         .collect();
+    ///
+    ///     anchor.close().await; // Doesn't block the executor.
+    ///     log.sort_unstable();
+    ///     assert_eq!(log, [0, 1, 2, 3]);
+    ///     # for task in tasks { task.await.unwrap() }
+    /// }
+    /// ```
+    pub async fn close(self) {
+        future::poll_fn(|cx| self.shared.poll_unused(cx)).await;
+        drop(self);
+    }
+}
+
+impl<'a, T: ?Sized> Drop for AsyncAnchor<'a, T> {
+    fn drop(&mut self) {
+        detach_anchor(&self.shared);
+    }
+}
+
+impl<'a, T: ?Sized> Drop for AsyncRwAnchor<'a, T> {
+    fn drop(&mut self) {
+        detach_anchor(&self.shared);
+    }
+}
+
+impl<T: ?Sized> AsyncPortal<T> {
+    /// Creates a weak portal associated with the same anchor as `portal`.
+    /// This is an associated function because `AsyncPortal` dereferences to its target.
+    #[inline]
+    pub fn downgrade(portal: &Self) -> AsyncWeakPortal<T> {
+        Arc::downgrade(&portal.0).pipe(AsyncWeakPortal)
+    }
+}
+
+impl<T: ?Sized> AsyncRwPortal<T> {
+    /// Acquires a read guard, waiting while the target is locked for writing or a write is pending.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped or poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::asynch::AsyncRwAnchor,
+    ///     std::{
+    ///         future::Future,
+    ///         task::{Context, Poll, Waker},
+    ///     },
+    /// };
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = AsyncRwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// let mut cx = Context::from_waker(Waker::noop());
+    ///
+    /// let guard = portal.try_write().unwrap();
+    /// let mut read = Box::pin(portal.read());
+    /// assert!(read.as_mut().poll(&mut cx).is_pending());
+    ///
+    /// drop(guard);
+    /// match read.as_mut().poll(&mut cx) {
+    ///     Poll::Ready(guard) => assert_eq!(*guard, "Scoped"),
+    ///     Poll::Pending => unreachable!(),
+    /// }
+    /// # drop(read);
+    /// # drop(portal);
+    /// ```
+    pub async fn read(&self) -> AsyncReadGuard<'_, T> {
+        Acquire::new(&self.0, false).await;
+        AsyncReadGuard(&self.0)
+    }
+
+    /// Acquires a write guard, waiting while the target is locked.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped or poisoned.
+    pub async fn write(&self) -> AsyncWriteGuard<'_, T> {
+        Acquire::new(&self.0, true).await;
+        AsyncWriteGuard {
+            shared: &self.0,
+            defused: false,
+        }
+    }
+
+    /// Acquires a read guard iff that's possible without waiting.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped or poisoned.
+    #[track_caller]
+    #[must_use]
+    pub fn try_read(&self) -> Option<AsyncReadGuard<'_, T>> {
+        self.0
+            .poll_acquire(false, &mut None, None)
+            .is_ready()
+            .then(|| AsyncReadGuard(&self.0))
+    }
+
+    /// Acquires a write guard iff that's possible without waiting.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped or poisoned.
+    #[track_caller]
+    #[must_use]
+    pub fn try_write(&self) -> Option<AsyncWriteGuard<'_, T>> {
+        self.0
+            .poll_acquire(true, &mut None, None)
+            .is_ready()
+            .then(|| AsyncWriteGuard {
+                shared: &self.0,
+                defused: false,
+            })
+    }
+
+    /// Creates a weak portal associated with the same anchor.
+    #[inline]
+    pub fn downgrade(&self) -> AsyncWeakRwPortal<T> {
+        Arc::downgrade(&self.0).pipe(AsyncWeakRwPortal)
+    }
+}
+
+impl<T: ?Sized> AsyncWeakPortal<T> {
+    /// Upgrades this weak portal iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn try_upgrade(&self) -> Option<AsyncPortal<T>> {
+        Shared::upgrade(&self.0).map(AsyncPortal)
+    }
+
+    /// Upgrades this weak portal.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> AsyncPortal<T> {
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.upgrade().is_some_and(|shared| shared.is_anchored())
+    }
+}
+
+impl<T: ?Sized> AsyncWeakRwPortal<T> {
+    /// Upgrades this weak portal iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn try_upgrade(&self) -> Option<AsyncRwPortal<T>> {
+        Shared::upgrade(&self.0).map(AsyncRwPortal)
+    }
+
+    /// Upgrades this weak portal.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped.
+    #[inline]
+    pub fn upgrade(&self) -> AsyncRwPortal<T> {
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.upgrade().is_some_and(|shared| shared.is_anchored())
+    }
+}
+
+impl<T: ?Sized> Deref for AsyncPortal<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // Only fails if the anchor was dropped anyway, while panicking.
+        if !self.0.is_anchored() {
+            anchor_dropped_panic()
+        }
+        unsafe {
+            //SAFETY: Valid as long as the anchor exists, and never mutated.
+            self.0.target.as_ref()
+        }
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for AsyncPortal<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized> Deref for AsyncReadGuard<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            //SAFETY: Valid and not mutated while this guard is counted.
+            self.0.target.as_ref()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for AsyncWriteGuard<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            //SAFETY: Valid and exclusive while this guard is counted.
+            self.shared.target.as_ref()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for AsyncWriteGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        let mut target = self.shared.target;
+        unsafe {
+            //SAFETY: Valid and exclusive while this guard is counted. Can't be created from an immutable anchor.
+            target.as_mut()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Defuse for AsyncWriteGuard<'a, T> {
+    #[inline]
+    fn defuse(&mut self) {
+        self.defused = true;
+    }
+}
+
+impl<'a, T: ?Sized> Drop for AsyncReadGuard<'a, T> {
+    fn drop(&mut self) {
+        self.0.release_guard(false, false);
+    }
+}
+
+impl<'a, T: ?Sized> Drop for AsyncWriteGuard<'a, T> {
+    fn drop(&mut self) {
+        self.shared.release_guard(true, self.defused);
+    }
+}
+
+impl<T: ?Sized> Clone for AsyncPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.portal().pipe(Self)
+    }
+}
+
+impl<T: ?Sized> Clone for AsyncRwPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.portal().pipe(Self)
+    }
+}
+
+impl<T: ?Sized> Clone for AsyncWeakPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> Clone for AsyncWeakRwPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> Drop for AsyncPortal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.release_portal();
+    }
+}
+
+impl<T: ?Sized> Drop for AsyncRwPortal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.release_portal();
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for AsyncAnchor<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.shared
+            .peek(|peek| f.debug_tuple("AsyncAnchor").field(&peek).finish())
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for AsyncRwAnchor<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.shared
+            .peek(|peek| f.debug_tuple("AsyncRwAnchor").field(&peek).finish())
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for AsyncPortal<T> {
+    /// Shows the target, or `<expired>` if the anchor was dropped anyway while panicking.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0
+            .peek(|peek| f.debug_tuple("AsyncPortal").field(&peek).finish())
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for AsyncRwPortal<T> {
+    /// Shows the target iff it's not locked for writing, without waiting.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0
+            .peek(|peek| f.debug_tuple("AsyncRwPortal").field(&peek).finish())
+    }
+}
+
+impl<T: ?Sized> Debug for AsyncWeakPortal<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncWeakPortal").finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized> Debug for AsyncWeakRwPortal<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncWeakRwPortal").finish_non_exhaustive()
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for AsyncReadGuard<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncReadGuard").field(&&**self).finish()
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for AsyncWriteGuard<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AsyncWriteGuard").field(&&**self).finish()
+    }
+}
@@ -0,0 +1,183 @@
+//! Portals made available to the code a future runs, without passing them along explicitly.
+
+use {
+    crate::sync::Portal,
+    std::{
+        cell::RefCell,
+        fmt::{self, Debug, Formatter},
+        future::Future,
+        mem,
+        pin::Pin,
+        task::{Context, Poll},
+        thread::LocalKey,
+    },
+};
+
+/// Declares a [`TaskScopedKey`], through which the code a future runs can access a portal installed with [`TaskScopedKey::scope`].
+///
+/// Like `tokio::task_local!`, this works by installing the portal in thread-local storage for the duration of each poll,
+/// so it works with any executor, including ones that move tasks between threads.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::{asynch::task_scoped, sync::Anchor},
+///     std::{
+///         future::Future,
+///         task::{Context, Poll, Waker},
+///     },
+/// };
+///
+/// task_scoped! {
+///     /// The current request's user name.
+///     static USER: str;
+/// }
+///
+/// async fn greet() -> String {
+///     USER.with(|user| format!("Hello, {}!", user))
+/// }
+///
+/// let user = "Scoped".to_owned();
+/// let anchor = Anchor::new_str(&user);
+/// let mut request = Box::pin(USER.scope(anchor.portal(), greet()));
+///
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(request.as_mut().poll(&mut cx), Poll::Ready("Hello, Scoped!".to_owned()));
+/// assert!(!USER.is_set());
+/// drop(anchor); // The portal was released as soon as `greet()` completed.
+/// # drop(request);
+/// ```
+#[macro_export]
+macro_rules! task_scoped {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty;)+) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::asynch::TaskScopedKey<$t> = {
+                ::std::thread_local! {
+                    static SLOT: ::std::cell::RefCell<
+                        ::std::option::Option<$crate::sync::Portal<$t>>,
+                    > = ::std::cell::RefCell::new(::std::option::Option::None);
+                }
+                $crate::asynch::TaskScopedKey::__new(&SLOT)
+            };
+        )+
+    };
+}
+
+/// A key through which the code a future runs can access a threadsafe immutable portal, as declared by [`task_scoped!`](../macro.task_scoped.html).
+pub struct TaskScopedKey<T: ?Sized + 'static> {
+    /// Holds the portal while a future scoped with it is polled on the current thread.
+    slot: &'static LocalKey<RefCell<Option<Portal<T>>>>,
+}
+
+/// A future that installs a portal for its inner future, as returned by [`TaskScopedKey::scope`].
+/// The portal is released as soon as the inner future completes, or when this is dropped.
+#[must_use = "futures do nothing unless polled"]
+pub struct TaskScope<T: ?Sized + 'static, F> {
+    /// Where the portal is installed.
+    key: &'static TaskScopedKey<T>,
+
+    /// `None` once `future` has completed.
+    portal: Option<Portal<T>>,
+
+    /// Boxed so that this is `Unpin`.
+    future: Pin<Box<F>>,
+}
+
+/// Installs a portal for the duration of one poll, then moves it back out, also while unwinding.
+struct Enter<'a, T: ?Sized + 'static> {
+    /// Where the portal is installed.
+    key: &'static TaskScopedKey<T>,
+
+    /// Where the portal goes back to.
+    portal: &'a mut Option<Portal<T>>,
+
+    /// Installed by an enclosing scope with the same key, and restored afterwards.
+    outer: Option<Portal<T>>,
+}
+
+impl<T: ?Sized + 'static> TaskScopedKey<T> {
+    /// Used by [`task_scoped!`](../macro.task_scoped.html). Not public API.
+    #[doc(hidden)]
+    #[must_use]
+    pub const fn __new(slot: &'static LocalKey<RefCell<Option<Portal<T>>>>) -> Self {
+        Self { slot }
+    }
+
+    /// Makes the target of `portal` available through this key to the code `future` runs, until it completes.
+    /// Scopes with the same key can be nested, in which case the innermost one is visible.
+    pub fn scope<F: Future>(&'static self, portal: Portal<T>, future: F) -> TaskScope<T, F> {
+        TaskScope {
+            key: self,
+            portal: Some(portal),
+            future: Box::pin(future),
+        }
+    }
+
+    /// Calls `f` with the target of the portal installed through this key.
+    ///
+    /// # Panics
+    ///
+    /// Iff called outside of a future scoped with this key.
+    pub fn with<R>(&'static self, f: impl FnOnce(&T) -> R) -> R {
+        self.try_with(f)
+            .expect("No portal installed through this task-scoped key")
+    }
+
+    /// Calls `f` with the target of the portal installed through this key, iff called inside a future scoped with it.
+    pub fn try_with<R>(&'static self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        // Cloned out, so that `f` can enter nested scopes.
+        let portal = self.slot.with(|slot| slot.borrow().clone())?;
+        Some(f(&portal))
+    }
+
+    /// Returns `true` iff called inside a future scoped with this key.
+    #[must_use]
+    pub fn is_set(&'static self) -> bool {
+        self.slot.with(|slot| slot.borrow().is_some())
+    }
+}
+
+impl<T: ?Sized + 'static> Debug for TaskScopedKey<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskScopedKey").finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized + 'static, F: Future> Future for TaskScope<T, F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let output = {
+            let outer = this.key.slot.with(|slot| slot.replace(this.portal.take()));
+            let _enter = Enter {
+                key: this.key,
+                portal: &mut this.portal,
+                outer,
+            };
+            this.future.as_mut().poll(cx)
+        };
+        if output.is_ready() {
+            // Released right away, in case the anchor is dropped before this future.
+            this.portal = None;
+        }
+        output
+    }
+}
+
+impl<'a, T: ?Sized + 'static> Drop for Enter<'a, T> {
+    fn drop(&mut self) {
+        let outer = mem::take(&mut self.outer);
+        *self.portal = self.key.slot.with(|slot| slot.replace(outer));
+    }
+}
+
+impl<T: ?Sized + 'static, F> Debug for TaskScope<T, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskScope")
+            .field("completed", &self.portal.is_none())
+            .finish_non_exhaustive()
+    }
+}
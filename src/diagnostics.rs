@@ -0,0 +1,448 @@
+//! Introspection of live anchors, for debugging leaked portals.
+//! Only available with the `diagnostics` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ref_portals::{diagnostics, sync::Anchor};
+//!
+//! let x = "Scoped".to_owned();
+//! let anchor = Anchor::new(&x);
+//! let portal = anchor.portal();
+//!
+//! for info in diagnostics::dump() {
+//!     println!("{}", info); // sync::Anchor<alloc::string::String> created at src/main.rs:7:14: 1 strong and 0 weak portal(s)
+//! }
+//! # assert!(diagnostics::dump().iter().any(|info| info.portals.unwrap().strong == 1));
+//! # drop(portal);
+//! ```
+
+use {
+    std::{
+        any,
+        collections::BTreeMap,
+        fmt::{self, Display, Formatter},
+        mem::ManuallyDrop,
+        panic::Location,
+        rc,
+        sync::{
+            self,
+            atomic::{AtomicU64, Ordering},
+            Mutex, PoisonError,
+        },
+        thread::{self, ThreadId},
+    },
+    wyz::pipe::*,
+};
+
+/// All live anchors, by registration order.
+static REGISTRY: Mutex<BTreeMap<u64, Entry>> = Mutex::new(BTreeMap::new());
+
+/// The key of the next `Registration`.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Information about a live anchor, as returned by [`dump`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct AnchorInfo {
+    /// The anchor type, qualified by its module, e.g. `"sync::RwAnchor"`.
+    pub kind: &'static str,
+
+    /// The name of the anchored type, as returned by [`core::any::type_name`].
+    pub type_name: &'static str,
+
+    /// Where the anchor was created.
+    pub location: &'static Location<'static>,
+
+    /// The anchor's label, if it was created through `named`.
+    pub label: Option<&'static str>,
+
+    /// The portals currently associated with the anchor.
+    /// `None` for `!Send` anchors that live on a different thread, since their counts can't be read safely there.
+    pub portals: Option<PortalCounts>,
+
+    /// Whether the anchor was leaked intentionally, through `.leak()`.
+    pub leaked: bool,
+}
+
+/// Portal counts of a live anchor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PortalCounts {
+    /// The number of (strong) portals.
+    pub strong: usize,
+
+    /// The number of weak portals.
+    pub weak: usize,
+}
+
+impl Display for AnchorInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}<{}> ", self.kind, self.type_name)?;
+        if let Some(label) = self.label {
+            write!(f, "{label:?} ")?;
+        }
+        write!(f, "created at {}: ", self.location)?;
+        match self.portals {
+            Some(PortalCounts { strong, weak }) => {
+                write!(f, "{strong} strong and {weak} weak portal(s)")
+            }
+            None => write!(f, "portal counts unavailable on this thread"),
+        }?;
+        if self.leaked {
+            write!(f, " (leaked)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Lists all anchors that currently exist in this process, in creation order.
+#[must_use]
+pub fn dump() -> Vec<AnchorInfo> {
+    dump_filtered(|_, _| true)
+}
+
+/// Renders all anchors that currently exist in this process as a [Graphviz](https://graphviz.org/) DOT graph.
+///
+/// Each anchor is a box labelled with its type, creation site and label (if any),
+/// with its (strong) portals and weak portals as separate nodes pointing to it (dashed for weak ones).  
+/// Portals are grouped by anchor and counted rather than listed individually.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::{diagnostics, rc::Anchor};
+///
+/// let x = "Scoped".to_owned();
+/// let anchor = Anchor::new(&x);
+/// let portal = anchor.portal();
+///
+/// let dot = diagnostics::to_dot();
+/// assert!(dot.starts_with("digraph anchors {"));
+/// assert!(dot.contains("1 strong portal(s)"));
+/// # drop(portal);
+/// ```
+#[must_use]
+pub fn to_dot() -> String {
+    Dot(dump()).to_string()
+}
+
+/// Formats anchors as a DOT graph, see [`to_dot`].
+struct Dot(Vec<AnchorInfo>);
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph anchors {{")?;
+        for (i, info) in self.0.iter().enumerate() {
+            write!(
+                f,
+                "    anchor{} [label=\"{}<{}>\\n{}",
+                i,
+                escape_dot(info.kind),
+                escape_dot(info.type_name),
+                escape_dot(&info.location.to_string()),
+            )?;
+            if let Some(label) = info.label {
+                write!(f, "\\n\\\"{}\\\"", escape_dot(label))?;
+            }
+            if info.portals.is_none() {
+                write!(f, "\\n(portal counts unavailable on this thread)")?;
+            }
+            if info.leaked {
+                write!(f, "\\n(leaked)")?;
+            }
+            writeln!(f, "\", shape=box];")?;
+
+            if let Some(PortalCounts { strong, weak }) = info.portals {
+                if strong > 0 {
+                    writeln!(
+                        f,
+                        "    anchor{i}_strong [label=\"{strong} strong portal(s)\"];",
+                    )?;
+                    writeln!(f, "    anchor{i}_strong -> anchor{i};")?;
+                }
+                if weak > 0 {
+                    writeln!(
+                        f,
+                        "    anchor{i}_weak [label=\"{weak} weak portal(s)\", style=dashed];",
+                    )?;
+                    writeln!(f, "    anchor{i}_weak -> anchor{i} [style=dashed];")?;
+                }
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Escapes `text` for use in a quoted DOT string.
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Lists the live anchors whose key and entry match `filter`, in creation order.
+fn dump_filtered(mut filter: impl FnMut(u64, &Entry) -> bool) -> Vec<AnchorInfo> {
+    let current_thread = thread::current().id();
+    REGISTRY
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter(|(id, entry)| filter(**id, entry))
+        .map(|(_, entry)| AnchorInfo {
+            kind: entry.kind,
+            type_name: entry.type_name,
+            location: entry.location,
+            label: entry.label,
+            portals: if entry.thread.map_or(true, |thread| thread == current_thread) {
+                Some(unsafe {
+                    //SAFETY: Either on the owning thread or threadsafe.
+                    (entry.counts)(entry.shared)
+                })
+            } else {
+                None
+            },
+            leaked: entry.leaked,
+        })
+        .collect()
+}
+
+/// A registry entry, keeping the anchor's shared state allocated through a type-erased weak reference.
+struct Entry {
+    /// See [`AnchorInfo::kind`].
+    kind: &'static str,
+
+    /// See [`AnchorInfo::type_name`].
+    type_name: &'static str,
+
+    /// See [`AnchorInfo::location`].
+    location: &'static Location<'static>,
+
+    /// See [`AnchorInfo::label`].
+    label: Option<&'static str>,
+
+    /// The thread that owns a `!Send` anchor, `None` for threadsafe anchors.
+    thread: Option<ThreadId>,
+
+    /// The thread the anchor was created on.
+    created_on: ThreadId,
+
+    /// See [`AnchorInfo::leaked`].
+    leaked: bool,
+
+    /// A weak reference to the anchor's shared state, from `Weak::into_raw`.
+    shared: *const (),
+
+    /// Reads the portal counts from `shared`.
+    counts: unsafe fn(*const ()) -> PortalCounts,
+
+    /// Drops `shared`.
+    release: unsafe fn(*const ()),
+}
+
+unsafe impl Send for Entry {
+    //SAFETY: `counts` is only called on `thread`, if set, and the entry is only dropped on the anchor's thread.
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        unsafe {
+            //SAFETY: Called exactly once, on the anchor's thread.
+            (self.release)(self.shared);
+        }
+    }
+}
+
+/// Keeps an anchor listed in the registry while it exists.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    /// The key of the entry in `REGISTRY`.
+    id: u64,
+}
+
+impl Registration {
+    /// Lists an `!Send` anchor, given its shared state.
+    #[track_caller]
+    pub(crate) fn rc<T: ?Sized, S>(kind: &'static str, shared: &rc::Rc<S>) -> Self {
+        /// Reads the counts from an `rc::Weak<S>`, excluding the anchor and the entry itself.
+        unsafe fn counts<S>(shared: *const ()) -> PortalCounts {
+            let weak = ManuallyDrop::new(rc::Weak::from_raw(shared.cast::<S>()));
+            PortalCounts {
+                strong: weak.strong_count().saturating_sub(1),
+                weak: weak.weak_count().saturating_sub(1),
+            }
+        }
+
+        /// Drops an `rc::Weak<S>`.
+        unsafe fn release<S>(shared: *const ()) {
+            drop(rc::Weak::from_raw(shared.cast::<S>()));
+        }
+
+        Self::register(Entry {
+            kind,
+            type_name: any::type_name::<T>(),
+            location: Location::caller(),
+            label: None,
+            thread: Some(thread::current().id()),
+            created_on: thread::current().id(),
+            leaked: false,
+            shared: rc::Rc::downgrade(shared).pipe(rc::Weak::into_raw).cast(),
+            counts: counts::<S>,
+            release: release::<S>,
+        })
+    }
+
+    /// Lists a threadsafe anchor, given its shared state.
+    #[track_caller]
+    pub(crate) fn sync<T: ?Sized, S>(kind: &'static str, shared: &sync::Arc<S>) -> Self {
+        /// Reads the counts from a `sync::Weak<S>`, excluding the anchor and the entry itself.
+        unsafe fn counts<S>(shared: *const ()) -> PortalCounts {
+            let weak = ManuallyDrop::new(sync::Weak::from_raw(shared.cast::<S>()));
+            PortalCounts {
+                strong: weak.strong_count().saturating_sub(1),
+                weak: weak.weak_count().saturating_sub(1),
+            }
+        }
+
+        /// Drops a `sync::Weak<S>`.
+        unsafe fn release<S>(shared: *const ()) {
+            drop(sync::Weak::from_raw(shared.cast::<S>()));
+        }
+
+        Self::register(Entry {
+            kind,
+            type_name: any::type_name::<T>(),
+            location: Location::caller(),
+            label: None,
+            thread: None,
+            created_on: thread::current().id(),
+            leaked: false,
+            shared: sync::Arc::downgrade(shared)
+                .pipe(sync::Weak::into_raw)
+                .cast(),
+            counts: counts::<S>,
+            release: release::<S>,
+        })
+    }
+
+    /// Records the anchor's label, right after its creation.
+    pub(crate) fn set_label(&self, label: &'static str) {
+        if let Some(entry) = REGISTRY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(&self.id)
+        {
+            entry.label = Some(label);
+        }
+    }
+
+    /// Marks the anchor as intentionally leaked, before it's forgotten along with this registration.
+    pub(crate) fn mark_leaked(&self) {
+        if let Some(entry) = REGISTRY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_mut(&self.id)
+        {
+            entry.leaked = true;
+        }
+    }
+
+    /// Removes the anchor from the registry ahead of dropping this registration, e.g. so that its shared state is unique again.
+    pub(crate) fn unregister(&self) {
+        let entry = REGISTRY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+        // Release the weak reference outside the lock.
+        drop(entry);
+    }
+
+    /// Inserts `entry` into the registry.
+    fn register(entry: Entry) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        REGISTRY
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, entry);
+        Self { id }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.unregister();
+    }
+}
+
+/// Test fixture that panics when dropped if any anchor created on the current thread during its lifetime still has portals.  
+/// The panic message lists the offending anchors' creation locations.
+///
+/// Anchors created on other threads are ignored, so tests running in parallel don't interfere with each other.  
+/// No check is performed if the thread is already panicking.
+///
+/// # Example
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::{diagnostics::AnchorLeakGuard, sync::Anchor};
+///
+/// let x = "Scoped".to_owned();
+/// let guard = AnchorLeakGuard::new();
+/// let anchor = Anchor::new(&x);
+/// let portal = anchor.portal();
+///
+/// assert_panic!(drop(guard), String, starts with "Leaked portals:");
+/// # drop(portal);
+/// ```
+#[derive(Debug)]
+#[must_use = "The guard checks for leaks only when dropped."]
+pub struct AnchorLeakGuard {
+    /// The first registry key assigned during the guard's lifetime.
+    first_id: u64,
+
+    /// The thread whose anchors are checked.
+    thread: ThreadId,
+}
+
+impl AnchorLeakGuard {
+    /// Starts watching anchors created on the current thread from now on.
+    pub fn new() -> Self {
+        Self {
+            first_id: NEXT_ID.load(Ordering::Relaxed),
+            thread: thread::current().id(),
+        }
+    }
+
+    /// Lists the anchors created during the guard's lifetime that still have (strong) portals.
+    /// Intentionally leaked anchors are not included.
+    #[must_use]
+    pub fn leaks(&self) -> Vec<AnchorInfo> {
+        let mut leaks = dump_filtered(|id, entry| {
+            id >= self.first_id && entry.created_on == self.thread && !entry.leaked
+        });
+        leaks.retain(|info| info.portals.is_some_and(|portals| portals.strong > 0));
+        leaks
+    }
+}
+
+impl Default for AnchorLeakGuard {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for AnchorLeakGuard {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            return;
+        }
+        let leaks = self.leaks();
+        if !leaks.is_empty() {
+            let mut message = "Leaked portals:".to_owned();
+            for info in leaks {
+                message.push_str("\n    ");
+                message.push_str(&info.to_string());
+            }
+            panic!("{}", message)
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Reuse of the threadsafe anchors' control blocks, so that anchors created and dropped in a hot loop don't hit the allocator each time.
+//! Only available with the `freelist` feature.
+//!
+//! Each thread keeps up to [`capacity`] released control blocks, which anchors created on it later take over if their layout fits.
+//! This is transparent: `sync::Anchor`, `sync::RwAnchor` and `sync::WAnchor` use it automatically.
+//!
+//! Control blocks are only recycled if no (weak) portals outlive their anchor.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ref_portals::{freelist, sync::RwAnchor};
+//!
+//! let before = freelist::stats();
+//! for mut x in 0..100 {
+//!     let anchor = RwAnchor::new(&mut x);
+//!     *anchor.portal().write() += 1;
+//! }
+//! let stats = freelist::stats();
+//! assert!(stats.hits - before.hits >= 99);
+//! ```
+
+use std::sync::Arc;
+#[cfg(feature = "freelist")]
+use std::{
+    alloc::Layout,
+    cell::RefCell,
+    mem::{self, MaybeUninit},
+    ptr::{self, NonNull},
+};
+
+/// The capacity of each thread's freelist, unless changed through [`set_capacity`].
+#[cfg(feature = "freelist")]
+pub const DEFAULT_CAPACITY: usize = 32;
+
+/// How the current thread's freelist was used so far, as returned by [`stats`].
+#[cfg(feature = "freelist")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct FreelistStats {
+    /// The number of control blocks taken from the freelist instead of being allocated.
+    pub hits: usize,
+
+    /// The number of control blocks allocated because none with a fitting layout was free.
+    pub misses: usize,
+
+    /// The number of released control blocks put into the freelist.
+    pub recycled: usize,
+
+    /// The number of released control blocks deallocated because the freelist was full.
+    pub discarded: usize,
+}
+
+/// A control block without value.
+#[cfg(feature = "freelist")]
+struct Block {
+    /// From [`Arc::into_raw`] of an `Arc` that was unique, with its value dropped in place.
+    pointer: NonNull<()>,
+
+    /// The layout of the dropped value.
+    layout: Layout,
+
+    /// Deallocates `pointer`.
+    release: unsafe fn(NonNull<()>),
+}
+
+#[cfg(feature = "freelist")]
+impl Drop for Block {
+    fn drop(&mut self) {
+        unsafe {
+            //SAFETY: `release` matches the layout of `pointer`, which isn't used elsewhere.
+            (self.release)(self.pointer);
+        }
+    }
+}
+
+/// Deallocates a [`Block`] that held an `S`.
+#[cfg(feature = "freelist")]
+unsafe fn release<S>(pointer: NonNull<()>) {
+    // `MaybeUninit<S>` has the layout of `S`, but its value isn't dropped.
+    drop(Arc::from_raw(pointer.cast::<MaybeUninit<S>>().as_ptr()));
+}
+
+/// A thread's free control blocks.
+#[cfg(feature = "freelist")]
+struct Freelist {
+    /// Most recently released last.
+    blocks: Vec<Block>,
+
+    /// At most this many `blocks` are kept.
+    capacity: usize,
+
+    /// Counted since the thread started.
+    stats: FreelistStats,
+}
+
+#[cfg(feature = "freelist")]
+thread_local! {
+    /// The current thread's free control blocks.
+    static FREE: RefCell<Freelist> = RefCell::new(Freelist {
+        blocks: Vec::new(),
+        capacity: DEFAULT_CAPACITY,
+        stats: FreelistStats::default(),
+    });
+}
+
+/// Returns how the current thread's freelist was used since the thread started, e.g. to verify its hit rate.
+#[cfg(feature = "freelist")]
+#[must_use]
+pub fn stats() -> FreelistStats {
+    FREE.with(|free| free.borrow().stats)
+}
+
+/// Returns how many released control blocks the current thread keeps at most.
+#[cfg(feature = "freelist")]
+#[must_use]
+pub fn capacity() -> usize {
+    FREE.with(|free| free.borrow().capacity)
+}
+
+/// Sets how many released control blocks the current thread keeps at most, deallocating any beyond that.
+/// `0` disables the freelist on this thread.
+#[cfg(feature = "freelist")]
+pub fn set_capacity(capacity: usize) {
+    let excess = FREE.with(|free| {
+        let mut free = free.borrow_mut();
+        free.capacity = capacity;
+        let keep = free.blocks.len().min(capacity);
+        free.blocks.split_off(keep)
+    });
+    // Not deallocated while borrowed.
+    drop(excess);
+}
+
+/// Moves `value` into a control block, which is taken from the current thread's freelist if one with a fitting layout is free.
+#[inline]
+pub(crate) fn allocate<S>(value: S) -> Arc<S> {
+    #[cfg(feature = "freelist")]
+    {
+        let layout = Layout::new::<S>();
+        let block = FREE
+            .try_with(|free| {
+                let mut free = free.borrow_mut();
+                let block = free
+                    .blocks
+                    .iter()
+                    .rposition(|block| block.layout == layout)
+                    .map(|index| free.blocks.swap_remove(index));
+                if block.is_some() {
+                    free.stats.hits += 1;
+                } else {
+                    free.stats.misses += 1;
+                }
+                block
+            })
+            .ok()
+            .flatten();
+        if let Some(block) = block {
+            let pointer = block.pointer.cast::<S>().as_ptr();
+            mem::forget(block);
+            return unsafe {
+                //SAFETY: The block came from a unique `Arc` of a value with the same size and alignment,
+                // which `Arc::from_raw` allows, and is initialised here.
+                pointer.write(value);
+                Arc::from_raw(pointer)
+            };
+        }
+    }
+    Arc::new(value)
+}
+
+/// Drops `shared`, putting its control block into the current thread's freelist iff it was unique and there's room.
+#[inline]
+pub(crate) fn recycle<S>(shared: Arc<S>) {
+    #[cfg(feature = "freelist")]
+    let mut shared = shared;
+    #[cfg(feature = "freelist")]
+    {
+        if Arc::get_mut(&mut shared).is_some() {
+            let pointer = Arc::into_raw(shared).cast_mut();
+            let block = Block {
+                pointer: unsafe {
+                    //SAFETY: Unique, so nothing else can access the value while or after it's dropped.
+                    ptr::drop_in_place(pointer);
+                    NonNull::new_unchecked(pointer)
+                }
+                .cast(),
+                layout: Layout::new::<S>(),
+                release: release::<S>,
+            };
+            let rejected = FREE
+                .try_with(|free| {
+                    let mut free = free.borrow_mut();
+                    if free.blocks.len() < free.capacity {
+                        free.blocks.push(block);
+                        free.stats.recycled += 1;
+                        None
+                    } else {
+                        free.stats.discarded += 1;
+                        Some(block)
+                    }
+                })
+                // During thread teardown, `block` is dropped along with the closure.
+                .ok()
+                .flatten();
+            // Not deallocated while borrowed.
+            drop(rejected);
+            return;
+        }
+    }
+    drop(shared);
+}
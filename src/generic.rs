@@ -0,0 +1,314 @@
+//! Immutable anchors and portals that are generic over their threading model,
+//! so that framework code can support both [`rc`](`crate::rc`) and [`sync`](`crate::sync`) without being written twice.
+//!
+//! The [`Model`] is a marker type parameter, [`Local`] or [`Threaded`], that's picked once where an [`Anchor`] is created.
+//! Portals are `Send` and `Sync` exactly when the selected module's are.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ref_portals::generic::{Anchor, Local, Model, Portal, Threaded};
+//!
+//! /// Framework code, written once.
+//! fn greeter<M: Model>(portal: Portal<String, M>) -> impl Fn() -> String {
+//!     move || format!("Hello, {}!", *portal)
+//! }
+//!
+//! let name = "Scoped".to_owned();
+//! let local = Anchor::<String, Local>::new(&name);
+//! let threaded = Anchor::<String, Threaded>::new(&name);
+//!
+//! assert_eq!(greeter(local.portal())(), "Hello, Scoped!");
+//! assert_eq!(greeter(threaded.portal())(), "Hello, Scoped!");
+//! ```
+
+use {
+    crate::{rc, sync, AnchorId},
+    private::Sealed,
+    std::{
+        borrow::Borrow,
+        fmt::{self, Debug, Formatter},
+        ops::Deref,
+    },
+    wyz::pipe::*,
+};
+
+/// A threading model, which selects the module whose anchors and portals the types in [`generic`](`self`) wrap.
+/// Only [`Local`] and [`Threaded`] implement this.
+pub trait Model: Sealed {}
+
+/// Selects [`rc`](`crate::rc`)'s anchors and portals, which stay on one thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Local {}
+
+/// Selects [`sync`](`crate::sync`)'s anchors and portals, which can be shared between threads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Threaded {}
+
+impl Model for Local {}
+impl Model for Threaded {}
+
+/// Keeps [`Model`] from being implemented elsewhere, and its plumbing out of the public API.
+mod private {
+    use std::ops::Deref;
+
+    /// Maps a [`Model`](`super::Model`) to one module's types.
+    pub trait Sealed {
+        /// The module's immutable anchor.
+        type Anchor<'a, T: ?Sized + 'a>;
+
+        /// The module's immutable portal.
+        type Portal<T: ?Sized>: Deref<Target = T> + Clone;
+
+        /// The module's weak immutable portal.
+        type WeakPortal<T: ?Sized>: Clone;
+
+        /// Creates an anchor capturing `reference`.
+        fn anchor<T: ?Sized>(reference: &T) -> Self::Anchor<'_, T>;
+
+        /// Creates a portal through `anchor`.
+        fn portal<T: ?Sized>(anchor: &Self::Anchor<'_, T>) -> Self::Portal<T>;
+
+        /// Creates a weak portal through `anchor`.
+        fn weak_portal<T: ?Sized>(anchor: &Self::Anchor<'_, T>) -> Self::WeakPortal<T>;
+
+        /// Creates a weak portal associated with the same anchor as `portal`.
+        fn downgrade<T: ?Sized>(portal: &Self::Portal<T>) -> Self::WeakPortal<T>;
+
+        /// Returns the unique ID of the anchor `portal` is associated with.
+        fn anchor_id<T: ?Sized>(portal: &Self::Portal<T>) -> crate::AnchorId;
+
+        /// Upgrades `weak_portal` iff its anchor still exists (and, for threadsafe anchors, isn't sealed).
+        fn try_upgrade<T: ?Sized>(weak_portal: &Self::WeakPortal<T>) -> Option<Self::Portal<T>>;
+    }
+}
+
+impl Sealed for Local {
+    type Anchor<'a, T: ?Sized + 'a> = rc::Anchor<'a, T>;
+    type Portal<T: ?Sized> = rc::Portal<T>;
+    type WeakPortal<T: ?Sized> = rc::WeakPortal<T>;
+
+    #[inline]
+    fn anchor<T: ?Sized>(reference: &T) -> Self::Anchor<'_, T> {
+        rc::Anchor::new(reference)
+    }
+
+    #[inline]
+    fn portal<T: ?Sized>(anchor: &Self::Anchor<'_, T>) -> Self::Portal<T> {
+        anchor.portal()
+    }
+
+    #[inline]
+    fn weak_portal<T: ?Sized>(anchor: &Self::Anchor<'_, T>) -> Self::WeakPortal<T> {
+        anchor.weak_portal()
+    }
+
+    #[inline]
+    fn downgrade<T: ?Sized>(portal: &Self::Portal<T>) -> Self::WeakPortal<T> {
+        rc::Portal::downgrade(portal)
+    }
+
+    #[inline]
+    fn anchor_id<T: ?Sized>(portal: &Self::Portal<T>) -> AnchorId {
+        rc::Portal::anchor_id(portal)
+    }
+
+    #[inline]
+    fn try_upgrade<T: ?Sized>(weak_portal: &Self::WeakPortal<T>) -> Option<Self::Portal<T>> {
+        weak_portal.try_upgrade()
+    }
+}
+
+impl Sealed for Threaded {
+    type Anchor<'a, T: ?Sized + 'a> = sync::Anchor<'a, T>;
+    type Portal<T: ?Sized> = sync::Portal<T>;
+    type WeakPortal<T: ?Sized> = sync::WeakPortal<T>;
+
+    #[inline]
+    fn anchor<T: ?Sized>(reference: &T) -> Self::Anchor<'_, T> {
+        sync::Anchor::new(reference)
+    }
+
+    #[inline]
+    fn portal<T: ?Sized>(anchor: &Self::Anchor<'_, T>) -> Self::Portal<T> {
+        anchor.portal()
+    }
+
+    #[inline]
+    fn weak_portal<T: ?Sized>(anchor: &Self::Anchor<'_, T>) -> Self::WeakPortal<T> {
+        anchor.weak_portal()
+    }
+
+    #[inline]
+    fn downgrade<T: ?Sized>(portal: &Self::Portal<T>) -> Self::WeakPortal<T> {
+        sync::Portal::downgrade(portal)
+    }
+
+    #[inline]
+    fn anchor_id<T: ?Sized>(portal: &Self::Portal<T>) -> AnchorId {
+        sync::Portal::anchor_id(portal)
+    }
+
+    #[inline]
+    fn try_upgrade<T: ?Sized>(weak_portal: &Self::WeakPortal<T>) -> Option<Self::Portal<T>> {
+        weak_portal.try_upgrade()
+    }
+}
+
+/// An immutable anchor of the module selected by `M`, i.e. an [`rc::Anchor`] or a [`sync::Anchor`].
+///
+/// # Panics
+///
+/// On drop, like the wrapped anchor, if any associated [`Portal`]s exist.
+pub struct Anchor<'a, T: ?Sized + 'a, M: Model>(M::Anchor<'a, T>);
+
+/// An immutable portal of the module selected by `M`, i.e. an [`rc::Portal`] or a [`sync::Portal`].
+///
+/// Like those, it dereferences to its target, so its own methods are associated functions.
+#[must_use]
+pub struct Portal<T: ?Sized, M: Model>(M::Portal<T>);
+
+/// A weak immutable portal of the module selected by `M`, i.e. an [`rc::WeakPortal`] or a [`sync::WeakPortal`].
+#[must_use]
+pub struct WeakPortal<T: ?Sized, M: Model>(M::WeakPortal<T>);
+
+impl<'a, T: ?Sized + 'a, M: Model> Anchor<'a, T, M> {
+    /// Creates a new `Anchor` instance, capturing `reference`.
+    #[inline]
+    #[must_use]
+    pub fn new(reference: &'a T) -> Self {
+        M::anchor(reference).pipe(Self)
+    }
+
+    /// Creates an infallible portal of indeterminate lifetime associated with this anchor.
+    #[inline]
+    pub fn portal(&self) -> Portal<T, M> {
+        M::portal(&self.0).pipe(Portal)
+    }
+
+    /// Creates a weak portal of indeterminate lifetime associated with this anchor.
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    #[inline]
+    pub fn weak_portal(&self) -> WeakPortal<T, M> {
+        M::weak_portal(&self.0).pipe(WeakPortal)
+    }
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T, Local> {
+    /// Returns the wrapped anchor, e.g. to use features only it has.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> rc::Anchor<'a, T> {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T, Threaded> {
+    /// Returns the wrapped anchor, e.g. to use features only it has.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> sync::Anchor<'a, T> {
+        self.0
+    }
+}
+
+impl<'a, T: ?Sized> From<rc::Anchor<'a, T>> for Anchor<'a, T, Local> {
+    #[inline]
+    fn from(anchor: rc::Anchor<'a, T>) -> Self {
+        anchor.pipe(Self)
+    }
+}
+
+impl<'a, T: ?Sized> From<sync::Anchor<'a, T>> for Anchor<'a, T, Threaded> {
+    #[inline]
+    fn from(anchor: sync::Anchor<'a, T>) -> Self {
+        anchor.pipe(Self)
+    }
+}
+
+impl<T: ?Sized, M: Model> Portal<T, M> {
+    /// Creates a weak portal associated with the same anchor as `portal`.
+    #[inline]
+    pub fn downgrade(portal: &Self) -> WeakPortal<T, M> {
+        M::downgrade(&portal.0).pipe(WeakPortal)
+    }
+
+    /// Returns the unique ID of the anchor `portal` is associated with.
+    #[inline]
+    #[must_use]
+    pub fn anchor_id(portal: &Self) -> AnchorId {
+        M::anchor_id(&portal.0)
+    }
+}
+
+impl<T: ?Sized, M: Model> WeakPortal<T, M> {
+    /// Upgrades this weak portal iff its anchor still exists and, with [`Threaded`], hasn't been sealed.
+    #[inline]
+    #[must_use]
+    pub fn try_upgrade(&self) -> Option<Portal<T, M>> {
+        M::try_upgrade(&self.0).map(Portal)
+    }
+}
+
+impl<T: ?Sized, M: Model> Deref for Portal<T, M> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: ?Sized, M: Model> Borrow<T> for Portal<T, M> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, M: Model> AsRef<T> for Portal<T, M> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized, M: Model> Clone for Portal<T, M> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized, M: Model> Clone for WeakPortal<T, M> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<'a, T: ?Sized + 'a, M: Model> Debug for Anchor<'a, T, M>
+where
+    M::Anchor<'a, T>: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: ?Sized, M: Model> Debug for Portal<T, M>
+where
+    M::Portal<T>: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: ?Sized, M: Model> Debug for WeakPortal<T, M>
+where
+    M::WeakPortal<T>: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
@@ -2,6 +2,7 @@
 
 #![doc(html_root_url = "https://docs.rs/ref-portals/1.0.0-beta.2")]
 #![doc(test(no_crate_inject))]
+#![cfg_attr(nightly, feature(must_not_suspend))]
 #![warn(
     clippy::as_conversions,
     clippy::cargo,
@@ -59,19 +60,392 @@
 //! eternal(); // Scoped
 //! ```
 //!
+//! # Features
+//!
+//! - `async`: Adds [`asynch::AsyncAnchor`](asynch/struct.AsyncAnchor.html) and [`asynch::AsyncRwAnchor`](asynch/struct.AsyncRwAnchor.html), whose portals are awaited instead of blocking the thread.
+//! - `backtrace`: Captures a backtrace whenever a (strong) portal is created.
+//!   If an anchor is dropped while in use, the surviving portals' backtraces are appended to the panic message (which is then a `String`)
+//!   or, for `rc::Anchor`, to the error logged before deadlocking.  
+//!   Guards borrowed through `rc::RwPortal`s are traced too, and listed when a borrow conflicts with them or their `rc::RwAnchor` deadlocks.
+//! - `buf`: Adds [`sync::BufPortal`](sync/struct.BufPortal.html), which reads an anchored byte slice as `bytes::Buf`. Implies `bytes`.
+//! - `bytes`: Adds [`sync::BytesAnchor`](sync/struct.BytesAnchor.html), which views anchored bytes as `bytes::Bytes` without copying them.
+//! - `crossbeam`: Lets [`sync::ReceiverAnchor`](sync/struct.ReceiverAnchor.html) and `sync::WPortal::recv` also work with `crossbeam_channel::Receiver`s.
+//! - `diagnostics`: Enables the [`diagnostics`](diagnostics/index.html) module, which lists live anchors and their portal counts.
+//! - `events`: Adds `events` methods to the threadsafe anchors, which subscribe to their lifecycle events as [`sync::AnchorEvents`](sync/struct.AnchorEvents.html).
+//!   With the `stream` feature, these are also a `futures_core::Stream`.
+//! - `ffi`: Adds [`sync::ArcWriteGuard::into_raw_parts`](sync/struct.ArcWriteGuard.html#method.into_raw_parts) and `from_raw_parts`, to keep a write lock held across a C callback,
+//!   and `into_raw` and `from_raw` to [`sync::Fence`](sync/struct.Fence.html) and [`sync::Fenced`](sync/struct.Fenced.html), to pass them through a C callback's user data.
+//! - `freelist`: Enables the [`freelist`](freelist/index.html) module, which reuses the threadsafe anchors' control blocks through a bounded thread-local freelist.
+//! - `mmap`: Adds [`sync::MapAnchor`](sync/struct.MapAnchor.html), which anchors a `memmap2::Mmap` and only unmaps it once its portals are gone.
+//! - `observe`: Adds [`sync::RwAnchor::observed`](sync/struct.RwAnchor.html#method.observed), which creates portals that publish typed change events to [`sync::Subscription`](sync/struct.Subscription.html)s.
+//! - `plugins`: Enables the [`plugins`](plugins/index.html) module, which hands anchored read-only state to dynamically loaded libraries through `libloading` and keeps them loaded while it's borrowed.
+//! - `stats`: Adds `stats` methods to the threadsafe anchors, which count portals, acquired guards, contention and poison in an [`AnchorStats`](sync/struct.AnchorStats.html) snapshot.
+//! - `stream`: Adds [`sync::StreamPortal`](sync/struct.StreamPortal.html), which forwards an anchored `futures_core::Stream`.
+//! - `swap`: Adds [`sync::SwapAnchor`](sync/struct.SwapAnchor.html), which replaces its target through `arc-swap` so that its portals read it without locking.
+//! - `ui`: Enables the [`ui`](ui/index.html) module, which wraps weak portals into main loop callbacks that stop once their anchor is gone.
+//! - `watchdog`: Enables the [`watchdog`](watchdog/index.html) module, which reports portal guards that were held for too long.
+//!   Threads that would block on an anchor they hold a conflicting guard for panic instead of deadlocking.
+//!
+//! When compiled with `--cfg nightly` on a nightly toolchain, portal guards are marked `#[must_not_suspend]`,
+//! so that the `must_not_suspend` lint (if enabled in the using crate) flags guards held across `.await`.
+//!
 //! # Notes
-//! 
+//!
 //! Panic assertions in this documentation use [assert_panic](https://crates.io/crates/assert-panic).
 
+use {
+    messages::{Message, Report},
+    std::{
+        fmt::{self, Debug, Display, Formatter},
+        mem,
+        num::NonZeroU64,
+        process,
+        sync::atomic::{AtomicU64, Ordering},
+        thread,
+    },
+    wyz::pipe::*,
+};
+
+pub mod asynch;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+#[cfg(feature = "freelist")]
+pub mod freelist;
+#[cfg(not(feature = "freelist"))]
+mod freelist;
+pub mod generic;
+pub mod messages;
+#[cfg(feature = "plugins")]
+pub mod plugins;
+pub mod poison;
+pub mod progress;
 pub mod rc;
+pub mod spmc;
 pub mod sync;
+mod trace;
+#[cfg(feature = "ui")]
+pub mod ui;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
+#[cfg(not(feature = "watchdog"))]
+mod watchdog;
+
+pub use messages::Violation;
+
+/// Panics with the message for `violation`, followed by details about the anchor being dropped:
+/// Its label (if any), the anchored type, the number of remaining strong portals, whether the thread is already unwinding
+/// (in which case this aborts the process after printing the message) and the thread's name (if any).  
+/// `extra` is appended verbatim.
+#[cold]
+fn anchor_drop_panic<T: ?Sized>(
+    violation: Violation,
+    label: Option<&str>,
+    strong_portals: usize,
+    extra: &dyn Display,
+) -> ! {
+    Report::new(violation)
+        .dropping::<T>(label, strong_portals, thread::current().name())
+        .with_details(extra)
+        .pipe(messages::panic)
+}
+
+/// Prints the message [`anchor_drop_panic`] would panic with to standard error, then aborts the process.
+#[cold]
+fn anchor_drop_abort<T: ?Sized>(
+    violation: Violation,
+    label: Option<&str>,
+    strong_portals: usize,
+    extra: &dyn Display,
+) -> ! {
+    eprintln!(
+        "{}",
+        Report::new(violation)
+            .dropping::<T>(label, strong_portals, thread::current().name())
+            .with_details(extra)
+            .pipe(Message),
+    );
+    process::abort()
+}
+
+/// Panics because a portal was used after its anchor was dropped.
+#[cold]
+#[track_caller]
+fn anchor_dropped_panic() -> ! {
+    messages::panic(Report::new(Violation::AnchorDropped))
+}
+
+/// Asserts that no (strong) portals of the given anchor currently exist.
+/// Weak portals are not counted.
+///
+/// Works with any anchor type in this crate.
+/// Like [`assert!`], accepts an optional custom panic message.
+///
+/// # Example
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::{assert_no_live_portals, rc::Anchor};
+///
+/// let x = "Scoped".to_owned();
+/// let anchor = Anchor::new(&x);
+/// let weak_portal = anchor.weak_portal();
+/// assert_no_live_portals!(anchor);
+///
+/// let portal = anchor.portal();
+/// assert_panic!(
+///     assert_no_live_portals!(anchor),
+///     String,
+///     "assertion failed: `anchor` has no live portals (found 1)",
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_no_live_portals {
+    ($anchor:expr $(,)?) => {
+        match $anchor.strong_portal_count() {
+            0 => (),
+            count => panic!(
+                "assertion failed: `{}` has no live portals (found {})",
+                stringify!($anchor),
+                count,
+            ),
+        }
+    };
+    ($anchor:expr, $($arg:tt)+) => {
+        if $anchor.strong_portal_count() != 0 {
+            panic!($($arg)+)
+        }
+    };
+}
+
+/// Runs `$body`, then asserts that no guard borrowed or locked through `$portal` (or another portal of its anchor) is still held.  
+/// Evaluates to the value of `$body`.
+///
+/// `$portal` is anything implementing [`GuardState`].
+/// The panic message lists where the held guards were acquired, as far as that's recorded (see [`GuardState::acquisitions`]).
+///
+/// # Example
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::{assert_released, sync::RwAnchor};
+///
+/// let mut x = vec![1, 2, 3];
+/// let anchor = RwAnchor::new(&mut x);
+/// let portal = anchor.portal();
+///
+/// let len = assert_released!(portal, { portal.read().len() });
+/// assert_eq!(len, 3);
+///
+/// let mut kept = None;
+/// assert_panic!(
+///     assert_released!(portal, { kept = Some(portal.write()); }),
+///     String,
+///     starts with "assertion failed: `portal` has no held guards",
+/// );
+/// drop(kept);
+/// ```
+#[macro_export]
+macro_rules! assert_released {
+    ($portal:expr, $body:block $(,)?) => {{
+        let portal = &$portal;
+        let result = $body;
+        if $crate::GuardState::is_guarded(portal) {
+            panic!(
+                "assertion failed: `{}` has no held guards{}",
+                stringify!($portal),
+                $crate::GuardState::acquisitions(portal),
+            )
+        }
+        result
+    }};
+}
+
+/// Portals that can tell whether guards borrowed or locked through them are held, as checked by [`assert_released!`].
+pub trait GuardState {
+    /// Returns `true` iff a guard borrowed or locked through this portal or another one of the same anchor is currently held.  
+    /// This is only a snapshot and may be outdated by the time it returns.
+    fn is_guarded(&self) -> bool;
+
+    /// Lists where the held guards were acquired, each after a leading line break, as far as that's recorded:  
+    /// For `rc::RwPortal`s with the `backtrace` feature, and for threadsafe portals with the `watchdog` feature,
+    /// in which case only guards held on the current thread are listed.
+    fn acquisitions(&self) -> String;
+}
+
+/// Closures and function pointers that can be called mutably with the argument tuple `Args`,
+/// as used by [`sync::RwPortal::call_mut`] and [`rc::RwPortal::call_mut`].  
+/// Implemented for everything that implements `FnMut` with up to eight arguments, including unsized `dyn FnMut(...)`.
+pub trait CallMut<Args> {
+    /// What the closure returns.
+    type Output;
+
+    /// Calls the closure with `args` spread out as its arguments.
+    fn call_with(&mut self, args: Args) -> Self::Output;
+}
+
+/// Implements [`CallMut`] for `FnMut` with the given arguments.
+macro_rules! impl_call_mut {
+    ($($arg:ident: $type:ident),*) => {
+        impl<F: ?Sized + FnMut($($type),*) -> R, R, $($type),*> CallMut<($($type,)*)> for F {
+            type Output = R;
+            #[inline]
+            fn call_with(&mut self, ($($arg,)*): ($($type,)*)) -> R {
+                self($($arg),*)
+            }
+        }
+    };
+}
+impl_call_mut!();
+impl_call_mut!(a: A);
+impl_call_mut!(a: A, b: B);
+impl_call_mut!(a: A, b: B, c: C);
+impl_call_mut!(a: A, b: B, c: C, d: D);
+impl_call_mut!(a: A, b: B, c: C, d: D, e: E);
+impl_call_mut!(a: A, b: B, c: C, d: D, e: E, f: G);
+impl_call_mut!(a: A, b: B, c: C, d: D, e: E, f: G, g: H);
+impl_call_mut!(a: A, b: B, c: C, d: D, e: E, f: G, g: H, h: I);
+
+/// Identifies an anchor uniquely within the process, unlike its address, which may be reused once it's dropped.  
+/// Returned by `id` on anchors and by `anchor_id` on their portals and weak portals, even after the anchor has been dropped,
+/// so that e.g. log entries from different threads can be correlated.
+///
+/// Formats as its number.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::RwAnchor;
+///
+/// let (mut x, mut y) = (1, 2);
+/// let anchor_x = RwAnchor::new(&mut x);
+/// let anchor_y = RwAnchor::new(&mut y);
+/// let weak_portal = anchor_x.weak_portal();
+///
+/// assert_eq!(anchor_x.portal().anchor_id(), anchor_x.id());
+/// assert_ne!(anchor_x.id(), anchor_y.id());
+///
+/// let id = anchor_x.id();
+/// drop(anchor_x);
+/// assert_eq!(weak_portal.anchor_id(), id);
+/// println!("anchor {} expired", id);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AnchorId(NonZeroU64);
+
+impl AnchorId {
+    /// Allocates a new ID.
+    fn next() -> Self {
+        /// The next ID to allocate.
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        NEXT.fetch_add(1, Ordering::Relaxed)
+            .pipe(NonZeroU64::new)
+            .unwrap_or_else(|| unreachable!("Not enough anchors to overflow"))
+            .pipe(Self)
+    }
+
+    /// Returns the ID as number.
+    #[inline]
+    #[must_use]
+    pub const fn get(self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl Display for AnchorId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A portal's target as shown by its `Debug` implementation or a `PortalDisplay`,
+/// or why it couldn't be read without blocking.
+enum Peek<'a, T: ?Sized> {
+    /// Readable.
+    Value(&'a T),
+
+    /// Locked exclusively elsewhere, or on the current thread.
+    Locked,
+
+    /// The anchor is poisoned.
+    Poisoned,
+
+    /// The anchor is gone (or sealed, for threadsafe weak portals).
+    Expired,
+}
+
+impl<'a, T: ?Sized + Debug> Debug for Peek<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(value) => value.fmt(f),
+            Self::Locked => f.write_str("<locked>"),
+            Self::Poisoned => f.write_str("<poisoned>"),
+            Self::Expired => f.write_str("<expired>"),
+        }
+    }
+}
+
+impl<'a, T: ?Sized + Display> Display for Peek<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(value) => value.fmt(f),
+            Self::Locked => f.write_str("<locked>"),
+            Self::Poisoned => f.write_str("<poisoned>"),
+            Self::Expired => f.write_str("<expired>"),
+        }
+    }
+}
+
+/// Type-erased callbacks registered on an anchor.  
+/// Formats only as its length, since closures aren't `Debug`.
+struct Callbacks<F: ?Sized>(Vec<Box<F>>);
+
+impl<F: ?Sized> Callbacks<F> {
+    /// Creates an empty callback list.
+    #[inline]
+    const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Registers `callback` after all previously registered ones.
+    #[inline]
+    fn push(&mut self, callback: Box<F>) {
+        self.0.push(callback);
+    }
+
+    /// Moves all callbacks from `later` after the ones in `self`.
+    #[inline]
+    fn append(&mut self, mut later: Self) {
+        self.0.append(&mut later.0);
+    }
+}
+
+impl<F: ?Sized + FnMut()> Callbacks<F> {
+    /// Invokes all callbacks, in registration order.
+    fn invoke_each(&mut self) {
+        for callback in &mut self.0 {
+            callback();
+        }
+    }
+}
 
-/// Panicked when upgrading weak portals iff the anchor has been destroyed already.
-const ANCHOR_DROPPED: &str = "Anchor dropped";
+impl<F: ?Sized + FnOnce()> Callbacks<F> {
+    /// Invokes and removes all callbacks, in registration order.
+    fn invoke_once(&mut self) {
+        for callback in mem::take(&mut self.0) {
+            callback();
+        }
+    }
+}
 
-/// Panicked when borrowing through a portal or dropping an anchor if the anchor has been poisoned.
-/// Only mutable anchors can be poisoned.
-const ANCHOR_POISONED: &str = "Anchor poisoned";
+impl<F: ?Sized> Default for Callbacks<F> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-/// Panicked when dropping an anchor if any (strong) portals still exist.
-const ANCHOR_STILL_IN_USE: &str = "Anchor still in use (at least one portal exists)";
+impl<F: ?Sized> Debug for Callbacks<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Callbacks").field(&self.0.len()).finish()
+    }
+}
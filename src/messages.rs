@@ -0,0 +1,332 @@
+//! Customizing the messages this crate panics with when anchors or portals are misused.
+//!
+//! By default, panics use short English messages like `Anchor poisoned`, followed by details about the anchor.
+//! Applications can install a [`MessageFormatter`] once, e.g. to add remediation hints or to emit structured output for crash reporting.
+//! The same messages are used to display [`UpgradeError`](`crate::poison::UpgradeError`) and [`AccessError`](`crate::poison::AccessError`).
+//!
+//! Panics caused by these violations always carry a `String` payload.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use assert_panic::assert_panic;
+//! use {
+//!     ref_portals::{
+//!         messages::{self, MessageFormatter, Report, Violation},
+//!         sync::Anchor,
+//!     },
+//!     std::fmt::{self, Formatter},
+//! };
+//!
+//! struct Json;
+//! impl MessageFormatter for Json {
+//!     fn format(&self, report: &Report<'_>, f: &mut Formatter<'_>) -> fmt::Result {
+//!         write!(
+//!             f,
+//!             r#"{{"violation":"{:?}","type":{:?}}}"#,
+//!             report.violation(),
+//!             report.type_name().unwrap_or("?"),
+//!         )
+//!     }
+//! }
+//!
+//! messages::set_formatter(Box::new(Json)).unwrap();
+//! assert!(messages::set_formatter(Box::new(Json)).is_err());
+//!
+//! let x = 1_u8;
+//! let anchor = Anchor::new(&x);
+//! let portal = anchor.portal();
+//! assert_panic!(
+//!     drop(anchor),
+//!     String,
+//!     r#"{"violation":"AnchorStillInUse","type":"u8"}"#,
+//! );
+//! # drop(portal);
+//! ```
+
+use std::{
+    any,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    sync::OnceLock,
+    thread,
+};
+
+/// The formatter installed through [`set_formatter`], if any.
+static FORMATTER: OnceLock<Box<dyn MessageFormatter>> = OnceLock::new();
+
+/// A misuse of an anchor or portal that this crate panics on.
+/// Also re-exported at the crate root, and only depends on `core`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Violation {
+    /// A weak portal was upgraded, or a threadsafe immutable portal dereferenced, after its anchor was dropped.
+    AnchorDropped,
+
+    /// A weak threadsafe portal was upgraded after its anchor was sealed.
+    AnchorSealed,
+
+    /// A poisoned anchor was accessed or dropped.
+    AnchorPoisoned,
+
+    /// An anchor was dropped while (strong) portals still existed.
+    AnchorStillInUse,
+
+    /// A threadsafe anchor was dropped before the anchors that depend on it.
+    AnchorHasDependents,
+
+    /// A thread blocked on a guard it holds itself, as detected with the `watchdog` feature.
+    SelfDeadlock,
+
+    /// [`lock_all!`](../macro.lock_all.html) was asked for more than one guard through the same anchor.
+    LockAllDuplicate,
+
+    /// A portal was created beyond the limit of a threadsafe anchor created through `with_limit`.
+    PortalLimitExceeded,
+
+    /// A write guard was requested on a thread other than the writer thread of an `RwAnchor` created through `with_writer_thread`.
+    ForeignWriter,
+}
+
+impl Violation {
+    /// Returns the message the crate uses for this violation by default.
+    #[must_use]
+    pub const fn default_message(self) -> &'static str {
+        match self {
+            Self::AnchorDropped => "Anchor dropped",
+            Self::AnchorSealed => "Anchor sealed",
+            Self::AnchorPoisoned => "Anchor poisoned",
+            Self::AnchorStillInUse => "Anchor still in use (at least one portal exists)",
+            Self::AnchorHasDependents => "Anchor dropped before its dependents",
+            Self::SelfDeadlock => "Would deadlock on a guard held by this thread",
+            Self::LockAllDuplicate => {
+                "lock_all! requested more than one guard through the same anchor"
+            }
+            Self::PortalLimitExceeded => "Portal limit exceeded",
+            Self::ForeignWriter => "Write access outside the anchor's writer thread",
+        }
+    }
+}
+
+impl Display for Violation {
+    /// Writes the [default message](#method.default_message), regardless of the installed formatter.
+    ///
+    /// ```rust
+    /// use ref_portals::Violation;
+    ///
+    /// assert_eq!(Violation::AnchorPoisoned.to_string(), "Anchor poisoned");
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.default_message())
+    }
+}
+
+/// What's known about the anchor an anchor drop violation was detected on.
+#[derive(Clone, Copy)]
+struct Anchored<'a> {
+    /// The anchored type's name.
+    type_name: &'static str,
+
+    /// The anchor's label, if any.
+    label: Option<&'a str>,
+
+    /// Still alive as the anchor was dropped.
+    strong_portals: usize,
+
+    /// Whether the thread was already unwinding as the anchor was dropped.
+    unwinding: bool,
+
+    /// The name of the thread that dropped the anchor, if it has one.
+    thread: Option<&'a str>,
+}
+
+/// A [`Violation`] with the details known about it, as passed to a [`MessageFormatter`].
+///
+/// Displays as the crate's default message.
+#[derive(Clone, Copy)]
+pub struct Report<'a> {
+    /// What went wrong.
+    violation: Violation,
+
+    /// Only for violations detected while dropping an anchor.
+    anchored: Option<Anchored<'a>>,
+
+    /// Appended to the default message verbatim.
+    details: Option<&'a dyn Display>,
+}
+
+impl<'a> Report<'a> {
+    /// Creates a report without details.
+    pub(crate) const fn new(violation: Violation) -> Self {
+        Self {
+            violation,
+            anchored: None,
+            details: None,
+        }
+    }
+
+    /// Adds what's known about an anchor of `T` that's being dropped on the thread named `thread`.
+    pub(crate) fn dropping<T: ?Sized>(
+        self,
+        label: Option<&'a str>,
+        strong_portals: usize,
+        thread: Option<&'a str>,
+    ) -> Self {
+        Self {
+            anchored: Some(Anchored {
+                type_name: any::type_name::<T>(),
+                label,
+                strong_portals,
+                unwinding: thread::panicking(),
+                thread,
+            }),
+            ..self
+        }
+    }
+
+    /// Adds `details`, which are appended to the default message verbatim.
+    pub(crate) const fn with_details(self, details: &'a dyn Display) -> Self {
+        Self {
+            details: Some(details),
+            ..self
+        }
+    }
+
+    /// Returns what went wrong.
+    #[must_use]
+    pub const fn violation(&self) -> Violation {
+        self.violation
+    }
+
+    /// Returns the name of the anchored type, iff the violation was detected while dropping the anchor.
+    #[must_use]
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.anchored.map(|anchored| anchored.type_name)
+    }
+
+    /// Returns the anchor's label, iff the violation was detected while dropping the anchor and it has one.
+    #[must_use]
+    pub fn label(&self) -> Option<&'a str> {
+        self.anchored.and_then(|anchored| anchored.label)
+    }
+
+    /// Returns the number of strong portals that were still alive, iff the violation was detected while dropping the anchor.
+    #[must_use]
+    pub fn strong_portals(&self) -> Option<usize> {
+        self.anchored.map(|anchored| anchored.strong_portals)
+    }
+
+    /// Returns whether the thread was already unwinding, iff the violation was detected while dropping the anchor.
+    /// In that case, the process aborts after the message has been printed.
+    #[must_use]
+    pub fn unwinding(&self) -> Option<bool> {
+        self.anchored.map(|anchored| anchored.unwinding)
+    }
+
+    /// Returns the name of the thread that dropped the anchor, iff the violation was detected while dropping the anchor and that thread is named.
+    #[must_use]
+    pub fn thread_name(&self) -> Option<&'a str> {
+        self.anchored.and_then(|anchored| anchored.thread)
+    }
+
+    /// Returns further details, like where the anchor was poisoned or where surviving portals were created.
+    /// These are formatted to start with a separator, like `": "` or a line break.
+    #[must_use]
+    pub const fn details(&self) -> Option<&'a dyn Display> {
+        self.details
+    }
+}
+
+impl<'a> Display for Report<'a> {
+    /// Formats the crate's default message, followed by the anchor's details (including whether and which thread is already unwinding)
+    /// and [`details`](#method.details).
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.violation, f)?;
+        if let Some(anchored) = self.anchored {
+            write!(
+                f,
+                " [{}anchored type: `{}`, strong portals: {}, unwinding: {}{}]",
+                anchored
+                    .label
+                    .map_or_else(String::new, |label| format!("label: {label:?}, ")),
+                anchored.type_name,
+                anchored.strong_portals,
+                anchored.unwinding,
+                anchored
+                    .thread
+                    .map_or_else(String::new, |thread| format!(", thread: {thread:?}")),
+            )?;
+        }
+        if let Some(details) = self.details {
+            details.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Debug for Report<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Report")
+            .field("violation", &self.violation)
+            .field("type_name", &self.type_name())
+            .field("label", &self.label())
+            .field("strong_portals", &self.strong_portals())
+            .field("unwinding", &self.unwinding())
+            .field("thread_name", &self.thread_name())
+            .field("details", &self.details.map(ToString::to_string))
+            .finish()
+    }
+}
+
+/// Formats the messages this crate panics with, as installed through [`set_formatter`].
+///
+/// Formatters are also called while the thread is already unwinding and must not panic themselves, since that would abort the process.
+pub trait MessageFormatter: Send + Sync {
+    /// Writes the message for `report`.
+    /// [`Report`]'s `Display` implementation writes the default one, so it can be wrapped, e.g. to append a hint.
+    ///
+    /// # Errors
+    ///
+    /// Iff writing to `f` fails.
+    fn format(&self, report: &Report<'_>, f: &mut Formatter<'_>) -> fmt::Result;
+}
+
+/// Installs `formatter` for the rest of the program's runtime.
+///
+/// # Errors
+///
+/// Iff a formatter was installed already.
+pub fn set_formatter(formatter: Box<dyn MessageFormatter>) -> Result<(), FormatterAlreadySet> {
+    FORMATTER.set(formatter).map_err(|_| FormatterAlreadySet)
+}
+
+/// Returned by [`set_formatter`] if a formatter was installed already.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FormatterAlreadySet;
+
+impl Display for FormatterAlreadySet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("A message formatter was installed already")
+    }
+}
+
+impl Error for FormatterAlreadySet {}
+
+/// Displays a report through the installed formatter, or as default message.
+pub(crate) struct Message<'a>(pub(crate) Report<'a>);
+
+impl<'a> Display for Message<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match FORMATTER.get() {
+            Some(formatter) => formatter.format(&self.0, f),
+            None => Display::fmt(&self.0, f),
+        }
+    }
+}
+
+/// Panics with the message for `report`.
+#[cold]
+#[track_caller]
+pub(crate) fn panic(report: Report<'_>) -> ! {
+    panic!("{}", Message(report))
+}
@@ -0,0 +1,213 @@
+//! Anchored state for dynamically loaded plugins, which must stay loaded while any of their code may still run on it.
+//! Only available with the `plugins` feature.
+//!
+//! The host packages read-only portals into an [`Env`] and hands clones of it to a plugin,
+//! which can keep them e.g. in threads or callbacks it leaves behind.
+//! The [`Plugin`] that owns the library only unloads it once all of these clones are gone,
+//! the same way anchors don't release their target while it's borrowed.
+//!
+//! Plugins must be built with the same compiler as the host, since [`Env::get`] identifies portals by their `TypeId`.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use {
+//!     libloading::{Library, Symbol},
+//!     ref_portals::{
+//!         plugins::{Env, Plugin},
+//!         sync::Anchor,
+//!     },
+//! };
+//!
+//! let config = "verbose".to_owned();
+//! let anchor = Anchor::new(&config);
+//! let env = Env::builder().portal("config", anchor.portal()).build();
+//!
+//! let plugin = Plugin::new(unsafe { Library::new("./libplugin.so") }.unwrap(), &env);
+//! unsafe {
+//!     let init: Symbol<fn(Env)> = plugin.library().get(b"init").unwrap();
+//!     init(env);
+//! }
+//!
+//! // Only unloads the library once the plugin has dropped its `Env`.
+//! let _ = plugin.try_unload();
+//! # drop(anchor);
+//! ```
+
+use {
+    crate::sync::Portal,
+    libloading::Library,
+    log::error,
+    std::{
+        any::Any,
+        fmt::{self, Debug, Formatter},
+        mem::ManuallyDrop,
+        sync::{Arc, Weak},
+    },
+};
+
+/// A named, type-erased [`Portal`].
+type Entry = (&'static str, Box<dyn Any + Send + Sync>);
+
+/// A cheaply clonable set of named read-only portals, to hand to plugins.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::{plugins::Env, sync::{Anchor, Portal}};
+///
+/// let limit = 3_usize;
+/// let anchor = Anchor::new(&limit);
+/// let env = Env::builder().portal("limit", anchor.portal()).build();
+///
+/// let plugin_env = env.clone();
+/// assert_eq!(env.strong_count(), 2);
+/// assert_eq!(**plugin_env.get::<usize>("limit").unwrap(), 3);
+/// assert!(plugin_env.get::<u32>("limit").is_none());
+/// assert!(plugin_env.get::<usize>("other").is_none());
+///
+/// drop((env, plugin_env));
+/// drop(anchor);
+/// ```
+#[derive(Clone)]
+pub struct Env(Arc<Vec<Entry>>);
+
+impl Env {
+    /// Starts collecting the portals of a new [`Env`].
+    pub fn builder() -> EnvBuilder {
+        EnvBuilder {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the portal added as `name`, iff one with target type `T` was.
+    #[must_use]
+    pub fn get<T: ?Sized + 'static>(&self, name: &str) -> Option<&Portal<T>> {
+        self.0
+            .iter()
+            .filter(|(entry_name, _)| *entry_name == name)
+            .find_map(|(_, portal)| portal.downcast_ref())
+    }
+
+    /// Returns the number of live handles to this environment, including this one.
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.0)
+    }
+}
+
+impl Debug for Env {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.0.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+/// Collects the portals of an [`Env`], as returned by [`Env::builder`].
+#[must_use]
+pub struct EnvBuilder {
+    /// In insertion order, so that [`Env::get`] finds the first one added under a name.
+    entries: Vec<Entry>,
+}
+
+impl EnvBuilder {
+    /// Adds `portal` as `name`.
+    pub fn portal<T: ?Sized + Send + Sync + 'static>(
+        mut self,
+        name: &'static str,
+        portal: Portal<T>,
+    ) -> Self {
+        self.entries.push((name, Box::new(portal)));
+        self
+    }
+
+    /// Creates the environment.
+    #[must_use]
+    pub fn build(self) -> Env {
+        Env(Arc::new(self.entries))
+    }
+}
+
+impl Debug for EnvBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set()
+            .entries(self.entries.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+/// A loaded library that isn't unloaded while the [`Env`] handed to it is still in use.
+///
+/// Dropping a `Plugin` whose environment is still in use logs an error and leaks the library instead of unloading it.
+pub struct Plugin {
+    /// Only dropped once `env` has no strong handles left.
+    library: ManuallyDrop<Library>,
+
+    /// The environment handed to `library`.
+    env: Weak<Vec<Entry>>,
+}
+
+impl Plugin {
+    /// Ties unloading `library` to the handles of `env`, which should be the environment handed to it.
+    #[must_use]
+    pub fn new(library: Library, env: &Env) -> Self {
+        Self {
+            library: ManuallyDrop::new(library),
+            env: Arc::downgrade(&env.0),
+        }
+    }
+
+    /// Returns the library, to look up its symbols.
+    #[must_use]
+    pub fn library(&self) -> &Library {
+        &self.library
+    }
+
+    /// Returns the number of live handles to the plugin's environment.
+    /// The library is unloaded only while this is `0`.
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        self.env.strong_count()
+    }
+
+    /// Unloads the library.
+    ///
+    /// # Errors
+    ///
+    /// Iff the plugin's environment is still in use, with the plugin unchanged.
+    pub fn try_unload(self) -> Result<(), Self> {
+        if self.strong_count() == 0 {
+            drop(self);
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        let strong_count = self.strong_count();
+        if strong_count == 0 {
+            unsafe {
+                //SAFETY: Dropped only here. Nothing can reach the plugin's environment anymore.
+                ManuallyDrop::drop(&mut self.library);
+            }
+        } else {
+            error!(
+                "Leaking plugin {:?}, since its environment still has {} handle(s).",
+                self.library, strong_count,
+            );
+        }
+    }
+}
+
+impl Debug for Plugin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin")
+            .field("library", &*self.library)
+            .field("strong_count", &self.strong_count())
+            .finish_non_exhaustive()
+    }
+}
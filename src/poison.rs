@@ -0,0 +1,393 @@
+//! Reports which panic poisoned an anchor.
+//!
+//! Mutable anchors are poisoned when a guard is released during a panic, after which all further access panics with `Anchor poisoned`.
+//! Those follow-up panics don't say what went wrong originally, unless [`install_hook`] was called beforehand.
+//!
+//! Weak portals to mutable anchors can also check for poison while upgrading, returning an [`UpgradeError`] instead of a portal that would panic on first use,
+//! or an [`AccessError`] if they lock the target right away.
+//!
+//! Guards for sections that leave the target valid even if they panic can be [`Defuse`]d, so that they don't poison the anchor.
+
+use {
+    crate::messages::{Message, Report, Violation},
+    std::{
+        cell::RefCell,
+        error::Error,
+        fmt::{self, Display, Formatter},
+        panic,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex, PoisonError,
+        },
+        time::Duration,
+    },
+};
+
+/// Whether [`install_hook`] has been called.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The location and message of the latest panic on this thread, recorded by the hook.
+    static LAST_PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Wraps the current panic hook so that each panic's location and message are attached to the anchors it poisons.
+/// `Anchor poisoned` panics then include that origin.
+///
+/// Calling this more than once has no further effect.
+/// Panic hooks set later must call the previous one for origins to be recorded.
+///
+/// # Example
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use {
+///     ref_portals::{poison, sync::RwAnchor},
+///     std::panic::{self, AssertUnwindSafe},
+/// };
+///
+/// poison::install_hook();
+///
+/// let mut x = "Scoped".to_owned();
+/// let anchor = RwAnchor::new(&mut x);
+/// let portal = anchor.portal();
+/// assert_panic!({
+///     let _guard = portal.write();
+///     panic!("Oh no!")
+/// });
+///
+/// let message = panic::catch_unwind(AssertUnwindSafe(|| drop(portal.read())))
+///     .unwrap_err()
+///     .downcast::<String>()
+///     .unwrap();
+/// assert!(message.starts_with("Anchor poisoned"));
+/// assert!(message.ends_with(": Oh no!"));
+/// # drop(portal);
+/// # assert_panic!(drop(anchor));
+/// ```
+pub fn install_hook() {
+    if INSTALLED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        let origin = match info.location() {
+            Some(location) => format!("{location}: {message}"),
+            None => message.to_owned(),
+        };
+        // Not recorded for panics during thread-local destruction.
+        LAST_PANIC
+            .try_with(|last| *last.borrow_mut() = Some(origin))
+            .ok();
+        previous(info);
+    }));
+}
+
+/// Returned by the `try_upgrade_checked` methods of weak portals to mutable anchors if upgrading or borrowing would fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UpgradeError {
+    /// The anchor has been dropped.
+    Dropped,
+
+    /// The threadsafe anchor has been sealed, e.g. through a [`ShutdownToken`](`crate::sync::ShutdownToken`), so that weak portals can't be upgraded anymore.
+    Sealed,
+
+    /// The anchor is poisoned, so that the first borrow through the portal would panic.
+    Poisoned,
+
+    /// The threadsafe anchor has as many strong portals as the limit it was created with allows.
+    LimitExceeded,
+}
+
+impl Display for UpgradeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dropped => Message(Report::new(Violation::AnchorDropped)).fmt(f),
+            Self::Sealed => Message(Report::new(Violation::AnchorSealed)).fmt(f),
+            Self::Poisoned => Message(Report::new(Violation::AnchorPoisoned)).fmt(f),
+            Self::LimitExceeded => Message(Report::new(Violation::PortalLimitExceeded)).fmt(f),
+        }
+    }
+}
+
+impl Error for UpgradeError {}
+
+/// Returned by `WeakPortal::read_with` if the anchor has been dropped or, for threadsafe anchors, sealed.  
+/// Also returned by [`ScratchPortal`](`crate::sync::ScratchPortal`) access once its anchor has been reset or dropped,
+/// and by [`RwPortal::tick`](`crate::sync::RwPortal::tick`) once its anchor has been sealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Expired;
+
+impl Display for Expired {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Anchor expired")
+    }
+}
+
+impl Error for Expired {}
+
+/// Returned by [`Lease::read`](`crate::sync::Lease::read`) once the lease can't be used anymore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LeaseError {
+    /// The anchor revoked the lease, or was dropped.
+    Revoked,
+
+    /// The lease's duration has passed.
+    Expired,
+}
+
+impl Display for LeaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Revoked => f.write_str("Lease revoked"),
+            Self::Expired => f.write_str("Lease expired"),
+        }
+    }
+}
+
+impl Error for LeaseError {}
+
+/// Returned by the `try_portal` methods of threadsafe anchors created through `with_limit`, if that many strong portals exist already.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PortalLimitExceeded {
+    /// The anchor's limit.
+    limit: usize,
+}
+
+impl PortalLimitExceeded {
+    /// Creates an instance for an anchor limited to `limit` strong portals.
+    pub(crate) const fn new(limit: usize) -> Self {
+        Self { limit }
+    }
+
+    /// Returns the maximum number of strong portals the anchor allows.
+    #[must_use]
+    pub const fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl Display for PortalLimitExceeded {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Message(
+            Report::new(Violation::PortalLimitExceeded)
+                .with_details(&format_args!(" (limit: {})", self.limit)),
+        )
+        .fmt(f)
+    }
+}
+
+impl Error for PortalLimitExceeded {}
+
+/// Returned by the `…_interruptible` methods of threadsafe portals if they were cancelled before acquiring the guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("Acquisition cancelled")
+    }
+}
+
+impl Error for Cancelled {}
+
+/// Returned by [`WPortal::recv`](`crate::sync::WPortal::recv`) and [`WPortal::try_recv`](`crate::sync::WPortal::try_recv`) if no message could be received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RecvError {
+    /// The channel is currently empty. Only returned by `try_recv`.
+    Empty,
+
+    /// The channel is empty and all of its senders have been dropped.
+    Disconnected,
+
+    /// The [`ReceiverAnchor`](`crate::sync::ReceiverAnchor`) has been closed or is being dropped.
+    /// Messages that weren't received yet remain in the channel.
+    Closed,
+}
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("Channel empty"),
+            Self::Disconnected => f.write_str("Channel disconnected"),
+            Self::Closed => f.write_str("Receiver anchor closed"),
+        }
+    }
+}
+
+impl Error for RecvError {}
+
+/// Returned by [`RwPortal::try_write_throttled`](`crate::sync::RwPortal::try_write_throttled`)
+/// while the anchor's [`WriteThrottle`](`crate::sync::WriteThrottle`) doesn't grant a write guard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Throttled {
+    /// How long it will take until a write guard is granted again.
+    retry_after: Duration,
+}
+
+impl Throttled {
+    /// Creates an instance for a throttle that grants the next write guard after `retry_after`.
+    pub(crate) const fn new(retry_after: Duration) -> Self {
+        Self { retry_after }
+    }
+
+    /// Returns how long it will take until the throttle grants a write guard again, unless another writer takes it first.
+    #[must_use]
+    pub const fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+}
+
+impl Display for Throttled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Write throttled (retry after {:?})", self.retry_after)
+    }
+}
+
+impl Error for Throttled {}
+
+/// Returned by the `assume_init_portal` methods of [`UninitAnchor`](`crate::sync::UninitAnchor`) and [`UninitPortal`](`crate::sync::UninitPortal`)
+/// while an element of the buffer hasn't been initialized yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Uninitialized {
+    /// The first uninitialized element.
+    index: usize,
+}
+
+impl Uninitialized {
+    /// Creates an instance for a buffer whose element at `index` is the first uninitialized one.
+    pub(crate) const fn new(index: usize) -> Self {
+        Self { index }
+    }
+
+    /// Returns the index of the first element that hasn't been initialized.
+    #[must_use]
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl Display for Uninitialized {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Buffer element {} not initialized", self.index)
+    }
+}
+
+impl Error for Uninitialized {}
+
+/// Returned by the `try_…_upgraded` methods of weak threadsafe portals if upgrading or locking without blocking fails,
+/// by the `…_inner` functions of portals to anchored cells and locks if their contents can't be accessed,
+/// and by [`spmc::Portal`](`crate::spmc::Portal`)s if they can't acquire a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessError {
+    /// The anchor has been dropped.
+    Dropped,
+
+    /// The anchor has been sealed, so that weak portals can't be upgraded anymore.
+    Sealed,
+
+    /// The anchor is poisoned.
+    Poisoned,
+
+    /// The anchor has as many strong portals as its limit allows.
+    LimitExceeded,
+
+    /// The target is locked in a way that would block the requested access.
+    WouldBlock,
+}
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dropped => Message(Report::new(Violation::AnchorDropped)).fmt(f),
+            Self::Sealed => Message(Report::new(Violation::AnchorSealed)).fmt(f),
+            Self::Poisoned => Message(Report::new(Violation::AnchorPoisoned)).fmt(f),
+            Self::LimitExceeded => Message(Report::new(Violation::PortalLimitExceeded)).fmt(f),
+            Self::WouldBlock => f.write_str("Anchor locked"),
+        }
+    }
+}
+
+impl Error for AccessError {}
+
+impl From<UpgradeError> for AccessError {
+    #[inline]
+    fn from(error: UpgradeError) -> Self {
+        match error {
+            UpgradeError::Dropped => Self::Dropped,
+            UpgradeError::Sealed => Self::Sealed,
+            UpgradeError::Poisoned => Self::Poisoned,
+            UpgradeError::LimitExceeded => Self::LimitExceeded,
+        }
+    }
+}
+
+/// Implemented by the mutable portal guards, to release them without poisoning the anchor during a panic.
+pub trait Defuse {
+    /// Marks this guard so that releasing it while unwinding doesn't poison the anchor.  
+    /// Only call this if a panic before the guard is released leaves the target in a valid state.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::{poison::Defuse, sync::RwAnchor};
+    ///
+    /// let mut x = vec![1, 2, 3];
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_panic!({
+    ///     let mut guard = portal.write();
+    ///     guard.defuse();
+    ///     guard.push(4); // The target is valid no matter what happens after this.
+    ///     panic!("Oh no!")
+    /// });
+    ///
+    /// assert_eq!(*portal.read(), [1, 2, 3, 4]);
+    /// drop(portal);
+    /// drop(anchor); // Doesn't panic.
+    /// ```
+    fn defuse(&mut self);
+}
+
+/// Where a poisoned anchor was poisoned, if known.
+#[derive(Debug, Default)]
+pub(crate) struct Origin(Mutex<Option<String>>);
+
+impl Origin {
+    /// Records the current panic as origin, unless one is known already.
+    /// Call this only while unwinding through a guard that poisons the anchor.
+    pub(crate) fn record(&self) {
+        let mut origin = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        if origin.is_none() {
+            *origin = LAST_PANIC
+                .try_with(|last| last.borrow().clone())
+                .ok()
+                .flatten();
+        }
+    }
+
+    /// Returns `true` iff the origin was recorded.
+    pub(crate) fn is_known(&self) -> bool {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+}
+
+/// Formats as the origin after a paragraph break, or as nothing if it's unknown.
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &*self.0.lock().unwrap_or_else(PoisonError::into_inner) {
+            Some(origin) => write!(f, "\n\nPoisoned by panic at {origin}"),
+            None => Ok(()),
+        }
+    }
+}
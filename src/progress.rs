@@ -0,0 +1,195 @@
+//! Reporting a worker's progress to another thread, e.g. a UI's, through a [`sync::RwPortal<Progress>`](`RwPortal`).
+//!
+//! The numeric fields are atomic, so that the worker advances them through a shared guard that doesn't block readers.
+//! Only changing the status text takes the write lock.
+//!
+//! # Example
+//!
+//! ```rust
+//! use {
+//!     ref_portals::{progress::Progress, sync::RwAnchor},
+//!     std::thread,
+//! };
+//!
+//! let mut progress = Progress::new(3);
+//! let anchor = RwAnchor::new(&mut progress);
+//!
+//! let worker = anchor.portal();
+//! let ui = anchor.portal();
+//! thread::spawn(move || {
+//!     worker.set_status("Copying");
+//!     for _ in 0..3 {
+//!         worker.advance(1);
+//!     }
+//!     worker.set_status("Done");
+//! })
+//! .join()
+//! .unwrap();
+//!
+//! let progress = ui.read();
+//! assert_eq!((progress.completed(), progress.total()), (3, 3));
+//! assert_eq!(progress.percent(), Some(100));
+//! assert_eq!(progress.status(), "Done");
+//! # drop(progress);
+//! # drop(ui);
+//! ```
+
+use {
+    crate::sync::RwPortal,
+    std::{
+        convert::TryFrom,
+        fmt::{self, Debug, Formatter},
+        sync::atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// A change hook, as set through [`Progress::on_change`].
+type Hook = dyn Fn(&Progress) + Send + Sync;
+
+/// A worker's progress, as shared through a [`sync::RwAnchor`](`crate::sync::RwAnchor`).
+/// Report through the methods on [`RwPortal<Progress>`](`RwPortal`), which also invoke the change hook.
+///
+/// `completed` and `total` are updated independently, so a reader may briefly see one of them ahead of the other.
+pub struct Progress {
+    /// Units of work done so far.
+    completed: AtomicU64,
+
+    /// Units of work expected in total.
+    total: AtomicU64,
+
+    /// Describes the current step.
+    status: String,
+
+    /// Invoked after each change reported through a portal.
+    on_change: Option<Box<Hook>>,
+}
+
+impl Progress {
+    /// Creates an instance expecting `total` units of work, none of which are completed yet.
+    #[must_use]
+    pub const fn new(total: u64) -> Self {
+        Self {
+            completed: AtomicU64::new(0),
+            total: AtomicU64::new(total),
+            status: String::new(),
+            on_change: None,
+        }
+    }
+
+    /// Sets a hook that's invoked after each change reported through a portal, e.g. to wake a UI's main loop.
+    ///
+    /// The hook runs on the reporting thread while it holds a read guard,
+    /// so reporting through a portal of the same anchor from within it deadlocks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{progress::Progress, sync::RwAnchor},
+    ///     std::sync::mpsc,
+    /// };
+    ///
+    /// let (wake, woken) = mpsc::sync_channel(1);
+    /// let mut progress = Progress::new(10).on_change(move |progress| {
+    ///     drop(wake.try_send(progress.completed()));
+    /// });
+    /// let anchor = RwAnchor::new(&mut progress);
+    ///
+    /// anchor.portal().advance(4);
+    /// assert_eq!(woken.try_recv(), Ok(4));
+    /// ```
+    #[must_use]
+    pub fn on_change(mut self, hook: impl Fn(&Self) + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Box::new(hook));
+        self
+    }
+
+    /// Returns the units of work done so far.
+    #[inline]
+    #[must_use]
+    pub fn completed(&self) -> u64 {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the units of work expected in total.
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
+    }
+
+    /// Returns the completed percentage, capped at 100, or `None` if no work is expected.
+    #[must_use]
+    pub fn percent(&self) -> Option<u8> {
+        let total = self.total();
+        (total != 0).then(|| {
+            let percent = u128::from(self.completed().min(total)) * 100 / u128::from(total);
+            u8::try_from(percent).unwrap_or(100)
+        })
+    }
+
+    /// Returns `true` iff at least as much work as expected has been completed.
+    #[inline]
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.completed() >= self.total()
+    }
+
+    /// Returns the description of the current step.
+    #[inline]
+    #[must_use]
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// Invokes the change hook, if any.
+    fn changed(&self) {
+        if let Some(on_change) = &self.on_change {
+            on_change(self);
+        }
+    }
+}
+
+impl RwPortal<Progress> {
+    /// Adds `by` to the completed units of work, without blocking readers.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn advance(&self, by: u64) {
+        let progress = self.read();
+        progress.completed.fetch_add(by, Ordering::Relaxed);
+        progress.changed();
+    }
+
+    /// Sets the completed units of work, without blocking readers.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn set_completed(&self, completed: u64) {
+        let progress = self.read();
+        progress.completed.store(completed, Ordering::Relaxed);
+        progress.changed();
+    }
+
+    /// Sets the units of work expected in total, without blocking readers.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn set_total(&self, total: u64) {
+        let progress = self.read();
+        progress.total.store(total, Ordering::Relaxed);
+        progress.changed();
+    }
+
+    /// Replaces the status text.
+    /// This briefly takes the write lock, but the change hook runs only after it was released.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn set_status(&self, status: impl Into<String>) {
+        self.write().status = status.into();
+        self.read().changed();
+    }
+}
+
+impl Debug for Progress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("completed", &self.completed())
+            .field("total", &self.total())
+            .field("status", &self.status)
+            .finish_non_exhaustive()
+    }
+}
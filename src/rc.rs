@@ -1,30 +1,237 @@
 //! Single-threaded anchors and portals.  
 //! These don't implement `Send` or `Sync`, but are more efficient for use cases where that's not needed.
 
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::Registration;
 use {
-    crate::{ANCHOR_DROPPED, ANCHOR_POISONED, ANCHOR_STILL_IN_USE},
+    crate::{
+        anchor_drop_panic, anchor_dropped_panic,
+        messages::{self, Report, Violation},
+        poison::{Defuse, Expired, Origin, UpgradeError},
+        trace::{TraceId, Traced, Traces},
+        watchdog::{Held, HeldFor},
+        AnchorId, CallMut, Callbacks, GuardState, Peek,
+    },
     log::error,
     std::{
+        any,
         borrow::Borrow,
-        cell::{Ref, RefCell, RefMut},
-        fmt::Debug,
+        cell::{Cell, RefCell},
+        convert::TryFrom,
+        fmt::{self, Debug, Display, Formatter},
+        hash::{Hash, Hasher},
+        iter::{self, FromIterator},
         marker::PhantomData,
-        mem::ManuallyDrop,
+        mem::{self, ManuallyDrop},
         ops::{Deref, DerefMut},
         panic::{RefUnwindSafe, UnwindSafe},
-        ptr::NonNull,
+        process,
+        ptr::{self, NonNull},
         rc::{Rc, Weak},
-        sync::Mutex, // Only to deadlock.
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex, // Only to deadlock.
+        },
         thread,
+        time::Duration,
     },
     wyz::pipe::*,
 };
 
-/// Poison helper for `!Send` mutable anchors.
+mod dispatch;
+pub use dispatch::Dispatcher;
+mod inner;
+mod isolate;
+pub use isolate::{isolated, ViolationEvent};
+mod map;
+pub use map::{MappedPortal, MappedReadPortal, MappedRef, MappedRefMut, MappedWritePortal};
+
+/// Borrow and poison bookkeeping for `!Send` mutable anchors.
+#[derive(Debug)]
+struct Poisonable<T: ?Sized> {
+    /// Only dereferenced through a guard counted in `borrows`.
+    pointer: NonNull<T>,
+    /// The number of live `PortalRef`s, or [`EXCLUSIVE`] while a `PortalRefMut` exists.
+    borrows: Cell<usize>,
+    /// Set when a `PortalRefMut` is released while unwinding, unless defused.
+    poisoned: Cell<bool>,
+    /// What set `poisoned`, if known.
+    origin: Origin,
+}
+
+/// The value of [`Poisonable::borrows`] while the target is borrowed mutably.
+const EXCLUSIVE: usize = usize::MAX;
+
+impl<T: ?Sized> Poisonable<T> {
+    /// Creates a new instance that's neither borrowed nor poisoned.
+    #[inline]
+    fn new(pointer: NonNull<T>) -> Self {
+        Self {
+            pointer,
+            borrows: Cell::new(0),
+            poisoned: Cell::new(false),
+            origin: Origin::default(),
+        }
+    }
+
+    /// Returns whether and how the target is currently borrowed.
+    #[inline]
+    const fn state(&self) -> BorrowState {
+        match self.borrows.get() {
+            0 => BorrowState::Unborrowed,
+            EXCLUSIVE => BorrowState::Exclusive,
+            readers => BorrowState::Shared(readers),
+        }
+    }
+
+    /// Counts a shared borrow until the returned value is dropped, iff the target isn't borrowed mutably and the count can't overflow.
+    #[inline]
+    fn share(&self) -> Option<SharedBorrow<'_, T>> {
+        let readers = self.borrows.get();
+        if readers >= EXCLUSIVE - 1 {
+            return None;
+        }
+        self.borrows.set(readers + 1);
+        Some(SharedBorrow(self))
+    }
+}
+
+/// A shared borrow counted in [`Poisonable::borrows`].
+struct SharedBorrow<'a, T: ?Sized>(&'a Poisonable<T>);
+
+impl<'a, T: ?Sized> Drop for SharedBorrow<'a, T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.borrows.set(self.0.borrows.get() - 1);
+    }
+}
+
+/// Panics with `error`, followed by where the conflicting guards were borrowed if that was recorded.
+#[cold]
+#[track_caller]
+fn borrow_conflict_panic(error: &dyn Display, guards: &Traces) -> ! {
+    panic!("{}{}", error, guards.listed("Conflicting guard"))
+}
+
+/// Panics with `ANCHOR_POISONED`, followed by the `origin` if known.
+#[cold]
+fn poisoned_panic(origin: &Origin) -> ! {
+    let report = Report::new(Violation::AnchorPoisoned);
+    if origin.is_known() {
+        messages::panic(report.with_details(origin))
+    } else {
+        messages::panic(report)
+    }
+}
+
+/// The deadlock timeout in nanoseconds, or `u64::MAX` while disabled.
+static DEADLOCK_TIMEOUT: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Sets how long a thread that dropped an anchor while it's in use stays deadlocked before the process is aborted.
+/// `None` (the default) deadlocks it indefinitely.
+///
+/// The thread can't continue either way, since that could cause UB. The timeout makes the hang visible:
+/// On abort, the reason is printed to standard error once more, in case no logger was set up to show the `log` error emitted on drop.
+///
+/// # Example
+///
+/// ```rust
+/// use {ref_portals::rc, std::time::Duration};
+///
+/// rc::set_deadlock_timeout(Some(Duration::from_secs(10)));
+/// ```
+pub fn set_deadlock_timeout(timeout: Option<Duration>) {
+    let nanos = timeout.map_or(u64::MAX, |timeout| {
+        u64::try_from(timeout.as_nanos()).unwrap_or(u64::MAX - 1)
+    });
+    DEADLOCK_TIMEOUT.store(nanos, Ordering::Relaxed);
+}
+
+/// Logs `reason` and the surviving portals' (and guards') `traces`, then blocks the current thread forever as last resort measure to prevent UB,
+/// or until the deadlock timeout, if set, after which the process is aborted.
+///
+/// On threads running the body of [`isolated`], reports the `violation` and parks forever instead.
+#[cold]
+fn deadlock(violation: Violation, reason: &dyn Display, traces: &dyn Display) -> ! {
+    isolate::intercept(violation, reason, traces);
+    match DEADLOCK_TIMEOUT.load(Ordering::Relaxed) {
+        u64::MAX => {
+            error!("{reason} Deadlocking thread to prevent UB.{traces}");
+            let deadlock_mutex = Mutex::new(());
+            let _deadlock_guard = deadlock_mutex.lock().unwrap();
+            let _never = deadlock_mutex.lock();
+            // Congratulations.
+            unreachable!()
+        }
+        nanos => {
+            let timeout = Duration::from_nanos(nanos);
+            error!(
+                "{reason} Deadlocking thread to prevent UB, then aborting after {timeout:?}.{traces}",
+            );
+            thread::sleep(timeout);
+            // Not only logged, since an operator may have to find out why the process stopped responding.
+            eprintln!(
+                "ref-portals: {reason} Aborting after deadlocking thread for {timeout:?} to prevent UB.{traces}",
+            );
+            process::abort()
+        }
+    }
+}
+
+/// The borrow state of an `RwAnchor`'s target, as returned by [`RwPortal::borrow_state`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BorrowState {
+    /// Neither borrowed mutably nor immutably.
+    Unborrowed,
+
+    /// Borrowed immutably through this many guards.
+    Shared(usize),
+
+    /// Borrowed mutably.
+    Exclusive,
+}
+
+/// State shared between an `!Send` anchor and its portals.
 #[derive(Debug)]
-struct Poisonable<T> {
-    pointer: T,
-    poisoned: bool,
+struct Shared<P> {
+    /// Access to the target of the captured reference.
+    target: P,
+
+    /// Identifies the anchor, also to weak portals.
+    id: AnchorId,
+
+    /// Invoked whenever the last strong portal is dropped.
+    idle_callbacks: RefCell<Callbacks<dyn FnMut()>>,
+
+    /// Where the live strong portals were created.
+    traces: Traces,
+
+    /// Where the live guards were borrowed, for `RwAnchor`s.
+    guards: Traces,
+}
+
+impl<P> Shared<P> {
+    /// Creates a new instance without callbacks.
+    #[inline]
+    fn new(target: P) -> Self {
+        Self {
+            target,
+            id: AnchorId::next(),
+            idle_callbacks: RefCell::new(Callbacks::new()),
+            traces: Traces::default(),
+            guards: Traces::default(),
+        }
+    }
+
+    /// Invokes all idle callbacks.  
+    /// Callbacks registered meanwhile are kept but not invoked.
+    fn notify_idle(&self) {
+        let mut callbacks = self.idle_callbacks.replace(Callbacks::new());
+        callbacks.invoke_each();
+        let mut idle_callbacks = self.idle_callbacks.borrow_mut();
+        callbacks.append(mem::take(&mut *idle_callbacks));
+        *idle_callbacks = callbacks;
+    }
 }
 
 /// An `!Send` immutable anchor.  
@@ -44,11 +251,22 @@ struct Poisonable<T> {
 ///
 /// assert_deadlock!(drop(anchor), Duration::from_secs(1));
 /// ```
+///
+/// Use [`set_deadlock_timeout`] to abort the process instead after a while.
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct Anchor<'a, T: ?Sized> {
     /// Internal pointer to the target of the captured reference.
-    reference: ManuallyDrop<Rc<NonNull<T>>>,
+    reference: ManuallyDrop<Rc<Shared<NonNull<T>>>>,
+
+    /// Invoked once the reference has been released.
+    drop_callbacks: RefCell<Callbacks<dyn FnOnce()>>,
+
+    /// Identifies this anchor in panic messages and diagnostics.
+    label: Option<&'static str>,
+
+    /// Lists this anchor while it exists.
+    #[cfg(feature = "diagnostics")]
+    registration: Registration,
 
     /// Act as sharing borrower.
     _phantom: PhantomData<&'a T>,
@@ -73,6 +291,8 @@ pub struct Anchor<'a, T: ?Sized> {
 /// assert_deadlock!(drop(anchor), Duration::from_secs(1));
 /// ```
 ///
+/// Use [`set_deadlock_timeout`] to abort the process instead after a while.
+///
 /// # Panics
 ///
 /// On drop, if any associated `RwPortal`s exist:
@@ -87,8 +307,8 @@ pub struct Anchor<'a, T: ?Sized> {
 ///
 /// assert_panic!(
 ///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
 /// );
 /// ```
 ///
@@ -110,15 +330,24 @@ pub struct Anchor<'a, T: ?Sized> {
 ///
 /// assert_panic!(
 ///     drop(anchor),
-///     &str,
-///     "Anchor poisoned",
+///     String,
+///     starts with "Anchor poisoned",
 /// );
 /// ```
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct RwAnchor<'a, T: ?Sized> {
     /// Internal pointer to the target of the captured reference.
-    reference: ManuallyDrop<Rc<RefCell<Poisonable<NonNull<T>>>>>,
+    reference: ManuallyDrop<Rc<Shared<Poisonable<T>>>>,
+
+    /// Invoked once the reference has been released.
+    drop_callbacks: RefCell<Callbacks<dyn FnOnce()>>,
+
+    /// Identifies this anchor in panic messages and diagnostics.
+    label: Option<&'static str>,
+
+    /// Lists this anchor while it exists.
+    #[cfg(feature = "diagnostics")]
+    registration: Registration,
 
     /// Act as exclusive borrower.
     _phantom: PhantomData<&'a mut T>,
@@ -126,13 +355,115 @@ pub struct RwAnchor<'a, T: ?Sized> {
 
 impl<'a, T: ?Sized> Anchor<'a, T> {
     /// Creates a new `Anchor` instance, capturing `reference`.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     pub fn new(reference: &'a T) -> Anchor<'a, T> {
+        unsafe {
+            //SAFETY: Derived from a reference that is valid and shared for `'a`.
+            Self::from_raw(NonNull::from(reference))
+        }
+    }
+
+    /// Creates a new `Anchor` instance from a raw pointer, e.g. one received through FFI
+    /// with an externally guaranteed lifetime.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` must be valid for reads for `'a`,
+    /// and its target must not be mutated (except through interior mutability) while this anchor or any of its portals exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::rc::Anchor, std::ptr::NonNull};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let pointer = NonNull::from(&x); // E.g. received through FFI.
+    /// let anchor = unsafe { Anchor::from_raw(pointer) };
+    /// let portal = anchor.portal();
+    ///
+    /// assert_eq!(*portal, "Scoped");
+    /// ```
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn from_raw(pointer: NonNull<T>) -> Self {
+        let reference = Rc::new(Shared::new(pointer));
         Self {
-            reference: ManuallyDrop::new(Rc::new(reference.into())),
+            #[cfg(feature = "diagnostics")]
+            registration: Registration::rc::<T, _>("rc::Anchor", &reference),
+            reference: ManuallyDrop::new(reference),
+            label: None,
+            drop_callbacks: RefCell::new(Callbacks::new()),
             _phantom: PhantomData,
         }
     }
 
+    /// Creates a new `Anchor` instance, capturing `reference` and labelling the anchor as `label`.  
+    /// The label appears in this anchor's panic messages and diagnostics, to tell apart anchors of the same type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::named("greeting", &x);
+    ///
+    /// assert_eq!(anchor.label(), Some("greeting"));
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn named(label: &'static str, reference: &'a T) -> Self {
+        let mut anchor = Self::new(reference);
+        anchor.label = Some(label);
+        #[cfg(feature = "diagnostics")]
+        anchor.registration.set_label(label);
+        anchor
+    }
+
+    /// Returns this anchor's label, if it was created through `named`.
+    #[inline]
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Returns this anchor's unique ID, which its portals and weak portals report as `anchor_id`, even after it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        self.reference.id
+    }
+
+    /// Forgets this anchor without checking for remaining portals, so that they stay valid indefinitely.  
+    /// Drop callbacks are not invoked. With the `diagnostics` feature, the anchor stays listed and is marked as leaked.
+    ///
+    /// # Safety
+    ///
+    /// The captured reference must stay valid for as long as any portal may still access it,
+    /// i.e. the target must actually be `'static` or never be freed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::Anchor;
+    ///
+    /// let x: &'static String = Box::leak(Box::new("Eternal".to_owned()));
+    /// let anchor = Anchor::new(x);
+    /// let portal = anchor.portal();
+    /// unsafe {
+    ///     // The target is never freed.
+    ///     anchor.leak();
+    /// }
+    ///
+    /// assert_eq!(*portal, "Eternal");
+    /// ```
+    #[allow(clippy::missing_const_for_fn)] // Not with the `diagnostics` feature.
+    pub unsafe fn leak(self) {
+        #[cfg(feature = "diagnostics")]
+        self.registration.mark_leaked();
+        mem::forget(self);
+    }
+
     /// Creates an infallible portal of indefinite lifetime associated with this anchor.
     ///
     /// # Example
@@ -152,29 +483,221 @@ impl<'a, T: ?Sized> Anchor<'a, T> {
     ///
     #[inline]
     pub fn portal(&self) -> Portal<T> {
-        self.reference.pipe_deref(Rc::clone).pipe(Portal)
+        self.reference.pipe_deref(Rc::clone).pipe(Portal::new)
     }
 
     /// Creates a weak portal of indefinite lifetime associated with this anchor.  
     /// Dropping an anchor doesn't panic if only weak portals exist.
     #[inline]
     pub fn weak_portal(&self) -> WeakPortal<T> {
-        Portal::downgrade(&self.portal())
+        WeakPortal(Rc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Creates `n` weak portals associated with this anchor at once, e.g. to hand one to each of several observers.
+    #[inline]
+    pub fn weak_portals(&self, n: usize) -> Vec<WeakPortal<T>> {
+        iter::repeat_with(|| self.weak_portal()).take(n).collect()
+    }
+
+    /// Creates a `'static` closure that upgrades a weak portal associated with this anchor and calls `body` with the target and its argument.  
+    /// Use a tuple as `A` to pass several arguments.
+    ///
+    /// # Panics
+    ///
+    /// The returned closure panics iff the anchor has been dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::rc::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let repeat = anchor.portal_fn(|x, times| x.repeat(times));
+    /// assert_eq!(repeat(2), "ScopedScoped");
+    ///
+    /// drop(anchor);
+    /// assert_panic!({ repeat(1); }, String, "Anchor dropped");
+    /// ```
+    #[inline]
+    pub fn portal_fn<A, R>(&self, body: impl Fn(&T, A) -> R + 'static) -> impl Fn(A) -> R + 'static
+    where
+        T: 'static,
+    {
+        let weak_portal = self.weak_portal();
+        move |argument| body(&*weak_portal.upgrade(), argument)
+    }
+
+    /// Returns the number of (strong) portals currently associated with this anchor.
+    #[inline]
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        Rc::strong_count(&self.reference) - 1
+    }
+
+    /// Registers `callback` to be invoked whenever the last (strong) portal associated with this anchor is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::rc::Anchor, std::{cell::Cell, rc::Rc}};
+    ///
+    /// let idle_count = Rc::new(Cell::new(0));
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// anchor.on_idle({
+    ///     let idle_count = Rc::clone(&idle_count);
+    ///     move || idle_count.set(idle_count.get() + 1)
+    /// });
+    ///
+    /// let portal = anchor.portal();
+    /// let portal_2 = portal.clone();
+    /// drop(portal);
+    /// assert_eq!(idle_count.get(), 0);
+    /// drop(portal_2);
+    /// assert_eq!(idle_count.get(), 1);
+    /// ```
+    pub fn on_idle(&self, callback: impl FnMut() + 'static) {
+        self.reference
+            .idle_callbacks
+            .borrow_mut()
+            .push(Box::new(callback));
+    }
+
+    /// Registers `callback` to be invoked when this anchor is dropped.  
+    /// Callbacks run in registration order once the anchor has released its reference,
+    /// so weak portals can't be upgraded from within them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::rc::Anchor, std::{cell::Cell, rc::Rc}};
+    ///
+    /// let dropped = Rc::new(Cell::new(false));
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let weak_portal = anchor.weak_portal();
+    /// anchor.on_drop({
+    ///     let dropped = Rc::clone(&dropped);
+    ///     move || dropped.set(true)
+    /// });
+    ///
+    /// drop(anchor);
+    /// assert!(dropped.get());
+    /// assert!(weak_portal.try_upgrade().is_none());
+    /// ```
+    pub fn on_drop(&self, callback: impl FnOnce() + 'static) {
+        self.drop_callbacks.borrow_mut().push(Box::new(callback));
+    }
+}
+
+impl<'a, T> Anchor<'a, [T]> {
+    /// Creates a new `Anchor` instance, capturing `reference` as slice.  
+    /// Unlike with `new`, a reference to e.g. a `Vec` is unsized here, so the target type doesn't need to be spelled out.
+    ///
+    /// Trait objects can be anchored through `new` by naming the target type instead,
+    /// as in `Anchor::<dyn Trait>::new(&value)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{Anchor, Portal};
+    ///
+    /// let x = vec![1, 2, 3];
+    /// let anchor = Anchor::new_slice(&x);
+    /// let portal: Portal<[i32]> = anchor.portal();
+    ///
+    /// assert_eq!(portal.len(), 3);
+    /// # drop(portal);
+    /// ```
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_slice(reference: &'a [T]) -> Self {
+        Self::new(reference)
+    }
+}
+
+impl<'a> Anchor<'a, str> {
+    /// Creates a new `Anchor` instance, capturing `reference` as string slice.  
+    /// Unlike with `new`, a reference to e.g. a `String` is unsized here, so the target type doesn't need to be spelled out.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_str(reference: &'a str) -> Self {
+        Self::new(reference)
     }
 }
 
 impl<'a, T: ?Sized> RwAnchor<'a, T> {
     /// Creates a new `RwAnchor` instance, capturing `reference`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     pub fn new(reference: &'a mut T) -> Self {
+        unsafe {
+            //SAFETY: Derived from a reference that is valid and exclusive for `'a`.
+            Self::from_raw(NonNull::from(reference))
+        }
+    }
+
+    /// Creates a new `RwAnchor` instance from a raw pointer, e.g. one received through FFI
+    /// with an externally guaranteed lifetime.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` must be valid for reads and writes for `'a`,
+    /// and its target must not be accessed other than through this anchor while it or any of its portals exist.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn from_raw(pointer: NonNull<T>) -> Self {
+        let reference = Rc::new(Shared::new(Poisonable::new(pointer)));
         Self {
-            reference: ManuallyDrop::new(Rc::new(RefCell::new(Poisonable {
-                pointer: reference.into(),
-                poisoned: false,
-            }))),
+            #[cfg(feature = "diagnostics")]
+            registration: Registration::rc::<T, _>("rc::RwAnchor", &reference),
+            reference: ManuallyDrop::new(reference),
+            label: None,
+            drop_callbacks: RefCell::new(Callbacks::new()),
             _phantom: PhantomData,
         }
     }
 
+    /// Creates a new `RwAnchor` instance, capturing `reference` and labelling the anchor as `label`.  
+    /// The label appears in this anchor's panic messages and diagnostics, to tell apart anchors of the same type.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn named(label: &'static str, reference: &'a mut T) -> Self {
+        let mut anchor = Self::new(reference);
+        anchor.label = Some(label);
+        #[cfg(feature = "diagnostics")]
+        anchor.registration.set_label(label);
+        anchor
+    }
+
+    /// Returns this anchor's label, if it was created through `named`.
+    #[inline]
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Returns this anchor's unique ID, which its portals and weak portals report as `anchor_id`, even after it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        self.reference.id
+    }
+
+    /// Forgets this anchor without checking for remaining portals, so that they stay valid indefinitely.  
+    /// Drop callbacks are not invoked. With the `diagnostics` feature, the anchor stays listed and is marked as leaked.
+    ///
+    /// # Safety
+    ///
+    /// The captured reference must stay valid for as long as any portal may still access it,
+    /// i.e. the target must actually be `'static` or never be freed or otherwise accessed.
+    #[allow(clippy::missing_const_for_fn)] // Not with the `diagnostics` feature.
+    pub unsafe fn leak(self) {
+        #[cfg(feature = "diagnostics")]
+        self.registration.mark_leaked();
+        mem::forget(self);
+    }
+
     /// Creates a fallible portal with unbounded lifetime supporting overlapping reads.
     ///
     /// # Example
@@ -200,12 +723,67 @@ impl<'a, T: ?Sized> RwAnchor<'a, T> {
     ///
     #[inline]
     pub fn portal(&self) -> RwPortal<T> {
-        self.reference.pipe_deref(Rc::clone).pipe(RwPortal)
+        self.reference.pipe_deref(Rc::clone).pipe(RwPortal::new)
     }
 
     #[inline]
     pub fn weak_portal(&self) -> WeakRwPortal<T> {
-        self.portal().downgrade()
+        WeakRwPortal(Rc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Creates a `'static` closure that upgrades a weak portal associated with this anchor and calls `body` with the target and its argument.  
+    /// Use a tuple as `A` to pass several arguments.
+    ///
+    /// # Panics
+    ///
+    /// The returned closure panics if the anchor has been dropped or poisoned, or if the target is already borrowed, for example by a reentrant call.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let push = anchor.portal_fn(|x, c| x.push(c));
+    /// push('!');
+    /// drop(push);
+    /// drop(anchor);
+    /// assert_eq!(x, "Scoped!");
+    /// ```
+    #[inline]
+    pub fn portal_fn<A, R>(
+        &self,
+        body: impl Fn(&mut T, A) -> R + 'static,
+    ) -> impl Fn(A) -> R + 'static
+    where
+        T: 'static,
+    {
+        let weak_portal = self.weak_portal();
+        move |argument| body(&mut *weak_portal.upgrade().borrow_mut(), argument)
+    }
+
+    /// Returns the number of (strong) portals currently associated with this anchor.
+    #[inline]
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        Rc::strong_count(&self.reference) - 1
+    }
+
+    /// Registers `callback` to be invoked whenever the last (strong) portal associated with this anchor is dropped.  
+    /// Callbacks are discarded when the anchor is dropped.
+    pub fn on_idle(&self, callback: impl FnMut() + 'static) {
+        self.reference
+            .idle_callbacks
+            .borrow_mut()
+            .push(Box::new(callback));
+    }
+
+    /// Registers `callback` to be invoked when this anchor is dropped.  
+    /// Callbacks run in registration order once the anchor has released its reference,
+    /// before any panic caused by the drop.
+    pub fn on_drop(&self, callback: impl FnOnce() + 'static) {
+        self.drop_callbacks.borrow_mut().push(Box::new(callback));
     }
 }
 
@@ -217,16 +795,22 @@ impl<'a, T: ?Sized> Drop for Anchor<'a, T> {
             ManuallyDrop::take(&mut self.reference)
         }
         .pipe(Rc::try_unwrap)
-        .unwrap_or_else(|_pointer| {
+        .unwrap_or_else(|reference| {
             // Immutable portals are always active borrows, so we need to deadlock immediately here,
             // since a reference could have been sent to another thread.
-            error!("!Send `Anchor` dropped while at least one Portal still exists. Deadlocking thread to prevent UB.");
-            let deadlock_mutex = Mutex::new(());
-            let _deadlock_guard = deadlock_mutex.lock().unwrap();
-            let _never = deadlock_mutex.lock();
-            // Congratulations.
-            unreachable!()
+            deadlock(
+                Violation::AnchorStillInUse,
+                &format_args!(
+                    "!Send `Anchor<{}>`{} dropped while {} Portal(s) still exist.",
+                    any::type_name::<T>(),
+                    self.label
+                        .map_or_else(String::new, |label| format!(" labelled {label:?}")),
+                    Rc::strong_count(&reference) - 1,
+                ),
+                &reference.traces,
+            )
         });
+        self.drop_callbacks.get_mut().invoke_once();
     }
 }
 
@@ -253,41 +837,52 @@ impl<'a, T: ?Sized> Drop for RwAnchor<'a, T> {
     ///
     /// assert_panic!(
     ///     drop(anchor),
-    ///     &str,
-    ///     "Anchor still in use (at least one portal exists)",
+    ///     String,
+    ///     starts with "Anchor still in use (at least one portal exists)",
     /// );
     /// ```
     fn drop(&mut self) {
-        unsafe {
+        let poisonable = unsafe {
             //SAFETY: Dropping.
             ManuallyDrop::take(&mut self.reference)
         }
         .pipe(Rc::try_unwrap)
         .unwrap_or_else(|reference| {
-            reference
-                .try_borrow_mut()
-                .unwrap_or_else(|_| {
-                    // So at this point we know that something else has taken out a borrow of the poisonable value,
-                    // and we know that that borrow will never be released because all the types leading there are `!Send`,
-                    // and we also don't know whether that's only used on this one thread because a derived reference could have been sent elsewhere.
-                    // Meaning this is the only way to prevent UB here:
-                    error!("!Send `RwAnchor` dropped while borrowed from. Deadlocking thread to prevent UB.");
-                    let deadlock_mutex = Mutex::new(());
-                    let _deadlock_guard = deadlock_mutex.lock().unwrap();
-                    let _never = deadlock_mutex.lock();
-                    // Congratulations.
-                    unreachable!()
-                })
-                .poisoned = true;
-            panic!(ANCHOR_STILL_IN_USE)
-        })
-        .into_inner() // Not fallible.
-        .poisoned
-        .pipe(|poisoned| {
-            if poisoned {
-                panic!(ANCHOR_POISONED)
+            if reference.target.borrows.get() != 0 {
+                // So at this point we know that something else has taken out a borrow of the poisonable value,
+                // and we know that that borrow will never be released because all the types leading there are `!Send`,
+                // and we also don't know whether that's only used on this one thread because a derived reference could have been sent elsewhere.
+                // Meaning this is the only way to prevent UB here:
+                deadlock(
+                    Violation::AnchorStillInUse,
+                    &format_args!(
+                        "!Send `RwAnchor<{}>`{} dropped while borrowed from through one of {} RwPortal(s).",
+                        any::type_name::<T>(),
+                        self.label.map_or_else(String::new, |label| format!(" labelled {label:?}")),
+                        Rc::strong_count(&reference) - 1,
+                    ),
+                    &format_args!(
+                        "{}{}",
+                        reference.traces,
+                        reference.guards.listed("Outstanding guard"),
+                    ),
+                )
             }
+            reference.target.poisoned.set(true);
+            drop(reference.idle_callbacks.take());
+            self.drop_callbacks.get_mut().invoke_once();
+            anchor_drop_panic::<T>(
+                Violation::AnchorStillInUse,
+                self.label,
+                Rc::strong_count(&reference) - 1,
+                &reference.traces,
+            )
         })
+        .target;
+        self.drop_callbacks.get_mut().invoke_once();
+        if poisonable.poisoned.get() {
+            anchor_drop_panic::<T>(Violation::AnchorPoisoned, self.label, 0, &poisonable.origin)
+        }
     }
 }
 
@@ -320,12 +915,12 @@ impl<'a, T: ?Sized> UnwindSafe for Anchor<'a, T> where T: RefUnwindSafe {}
 ///
 /// assert_panic!(
 ///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
 /// );
 /// assert_panic!(
 ///     { portal.borrow_mut(); },
-///     &str,
+///     String,
 ///     "Anchor poisoned",
 /// );
 /// ```
@@ -333,155 +928,1007 @@ impl<'a, T: ?Sized> UnwindSafe for RwAnchor<'a, T> where T: RefUnwindSafe {}
 
 /// An `!Send` immutable portal.  
 /// Dereference it directly with `*` or `.deref()`.
-#[derive(Debug)]
 #[must_use]
-#[repr(transparent)]
-pub struct Portal<T: ?Sized>(Rc<NonNull<T>>);
+#[cfg_attr(not(feature = "backtrace"), repr(transparent))]
+pub struct Portal<T: ?Sized>(Rc<Shared<NonNull<T>>>, TraceId);
 
 /// An `!Send` mutable portal with overlapping immutable borrows.  
 /// Acquire a guard by calling `.borrow()` or `.borrow_mut()`.
-#[derive(Debug)]
 #[must_use]
-#[repr(transparent)]
-pub struct RwPortal<T: ?Sized>(Rc<RefCell<Poisonable<NonNull<T>>>>);
+#[cfg_attr(not(feature = "backtrace"), repr(transparent))]
+pub struct RwPortal<T: ?Sized>(Rc<Shared<Poisonable<T>>>, TraceId);
 
 impl<T: ?Sized> Portal<T> {
+    /// Wraps `shared`, which must already count this portal, recording where it was created.
+    #[inline]
+    fn new(shared: Rc<Shared<NonNull<T>>>) -> Self {
+        let trace = shared.traces.capture();
+        Self(shared, trace)
+    }
+
     /// Creates a weak portal associated with the same anchor as `portal`.  
     /// Dropping an anchor doesn't panic if only weak portals exist.
     #[inline]
     pub fn downgrade(portal: &Self) -> WeakPortal<T> {
-        Rc::downgrade(&portal.0).pipe(WeakPortal)
+        WeakPortal(Rc::downgrade(&portal.0), portal.0.id)
     }
-}
 
-impl<T: ?Sized> Deref for Portal<T> {
-    type Target = T;
+    /// Returns the unique ID of the anchor `portal` is associated with.
     #[inline]
-    fn deref(&self) -> &Self::Target {
-        let pointer = self.0.deref();
-        unsafe {
-            //SAFETY: Valid as long as self.0 is.
-            pointer.as_ref()
-        }
+    #[must_use]
+    pub fn anchor_id(portal: &Self) -> AnchorId {
+        portal.0.id
     }
-}
 
-impl<T: ?Sized> Borrow<T> for Portal<T> {
+    /// Returns `true` iff `portal` and `other` are associated with the same anchor.  
+    /// This doesn't dereference either portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{Anchor, Portal};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let y = x.clone();
+    /// let anchor_x = Anchor::new(&x);
+    /// let anchor_y = Anchor::new(&y);
+    ///
+    /// let portal = anchor_x.portal();
+    /// assert!(Portal::ptr_eq(&portal, &portal.clone()));
+    /// assert!(!Portal::ptr_eq(&portal, &anchor_y.portal()));
+    /// assert!(Portal::is_from(&portal, &anchor_x));
+    /// ```
     #[inline]
-    fn borrow(&self) -> &T {
-        &*self
+    #[must_use]
+    pub fn ptr_eq(portal: &Self, other: &Self) -> bool {
+        Rc::ptr_eq(&portal.0, &other.0)
+    }
+
+    /// Returns `true` iff `portal` is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(portal: &Self, anchor: &Anchor<'_, T>) -> bool {
+        Rc::ptr_eq(&portal.0, &anchor.reference)
+    }
+
+    /// Returns the address of the anchored value, e.g. for identity comparisons or as map key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::rc::{Anchor, Portal}, std::ptr};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    ///
+    /// assert!(ptr::eq(Portal::as_ptr(&portal), &x));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn as_ptr(portal: &Self) -> *const T {
+        portal.0.target.as_ptr()
+    }
+}
+
+impl<T: Clone> Portal<T> {
+    /// Clones the anchored value.
+    #[inline]
+    #[must_use]
+    pub fn cloned(portal: &Self) -> T {
+        T::clone(portal)
+    }
+}
+
+impl<T: ?Sized> Deref for Portal<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        let pointer = &self.0.target;
+        unsafe {
+            //SAFETY: Valid as long as self.0 is.
+            pointer.as_ref()
+        }
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for Portal<T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for Portal<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
     }
 }
 
 impl<T: ?Sized> RwPortal<T> {
+    /// Wraps `shared`, which must already count this portal, recording where it was created.
+    #[inline]
+    fn new(shared: Rc<Shared<Poisonable<T>>>) -> Self {
+        let trace = shared.traces.capture();
+        Self(shared, trace)
+    }
+
+    /// Returns the address of the anchored value without borrowing it, e.g. for identity comparisons or as map key.  
+    /// Dereferencing the pointer is only sound while a matching guard is held.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::rc::RwAnchor, std::ptr};
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let expected: *mut String = &mut x;
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let _guard = portal.borrow_mut();
+    /// assert!(ptr::eq(portal.data_ptr(), expected));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn data_ptr(&self) -> *mut T {
+        self.0.target.pointer.as_ptr()
+    }
+
     /// Creates a weak portal associated with the same anchor as this one.  
     /// Dropping an anchor doesn't panic if only weak portals exist.
     #[inline]
     pub fn downgrade(&self) -> WeakRwPortal<T> {
-        Rc::downgrade(&self.0).pipe(WeakRwPortal)
+        WeakRwPortal(Rc::downgrade(&self.0), self.0.id)
+    }
+
+    /// Returns the unique ID of the anchor this portal is associated with.
+    #[inline]
+    #[must_use]
+    pub fn anchor_id(&self) -> AnchorId {
+        self.0.id
+    }
+
+    /// Returns `true` iff this portal and `other` are associated with the same anchor.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns `true` iff this portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &RwAnchor<'_, T>) -> bool {
+        Rc::ptr_eq(&self.0, &anchor.reference)
+    }
+
+    /// Returns whether and how the anchored value is currently borrowed through any portal.  
+    /// Use this to defer work instead of panicking on a conflicting borrow.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{BorrowState, RwAnchor};
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_eq!(portal.borrow_state(), BorrowState::Unborrowed);
+    ///
+    /// let guards = (portal.borrow(), portal.borrow());
+    /// assert_eq!(portal.borrow_state(), BorrowState::Shared(2));
+    /// drop(guards);
+    ///
+    /// let guard = portal.borrow_mut();
+    /// assert_eq!(portal.borrow_state(), BorrowState::Exclusive);
+    /// # drop(guard);
+    /// ```
+    #[must_use]
+    pub fn borrow_state(&self) -> BorrowState {
+        self.0.target.state()
     }
 
     #[inline]
-    pub fn borrow<'a>(&'a self) -> impl Deref<Target = T> + 'a {
-        let guard = self.0.as_ref().borrow();
-        if guard.poisoned {
-            panic!(ANCHOR_POISONED)
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn borrow<'a>(&'a self) -> impl Deref<Target = T> + AsRef<T> + HeldFor + 'a {
+        let poisonable = &self.0.target;
+        let borrow = match poisonable.share() {
+            Some(borrow) => borrow,
+            None if poisonable.borrows.get() == EXCLUSIVE => {
+                borrow_conflict_panic(&"already mutably borrowed", &self.0.guards)
+            }
+            None => borrow_conflict_panic(&"too many immutable borrows", &self.0.guards),
+        };
+        if poisonable.poisoned.get() {
+            poisoned_panic(&poisonable.origin)
         }
-        PortalRef(guard)
+        PortalRef(
+            borrow,
+            Held::start::<T>("rc::RwPortal::borrow"),
+            self.0.guards.capture_scoped(),
+        )
+    }
+
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn borrow_mut<'a>(
+        &'a self,
+    ) -> impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a {
+        let poisonable = &self.0.target;
+        if poisonable.borrows.get() != 0 {
+            borrow_conflict_panic(&"already borrowed", &self.0.guards)
+        }
+        if poisonable.poisoned.get() {
+            poisoned_panic(&poisonable.origin)
+        }
+        poisonable.borrows.set(EXCLUSIVE);
+        PortalRefMut(
+            poisonable,
+            Held::start::<T>("rc::RwPortal::borrow_mut"),
+            false,
+            self.0.guards.capture_scoped(),
+        )
+    }
+
+    /// Calls the anchored closure with `args` while borrowing it mutably, e.g. for an `RwPortal<dyn FnMut(f32)>`.  
+    /// `args` is a tuple with one element per argument.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or the closure is already borrowed, e.g. because it calls itself through this portal.
+    /// If the closure panics, the anchor is poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let mut log = Vec::new();
+    /// let mut record = |event: &str| log.push(event.to_owned());
+    /// let callback: &mut dyn FnMut(&str) = &mut record;
+    /// let anchor = RwAnchor::new(callback);
+    /// let portal = anchor.portal();
+    ///
+    /// portal.call_mut(("spawned",));
+    /// portal.call_mut(("moved",));
+    /// drop(portal);
+    /// drop(anchor);
+    ///
+    /// assert_eq!(log, ["spawned", "moved"]);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn call_mut<Args>(&self, args: Args) -> T::Output
+    where
+        T: CallMut<Args>,
+    {
+        self.borrow_mut().call_with(args)
+    }
+}
+
+impl<T: Clone> RwPortal<T> {
+    /// Clones the anchored value out under a short-lived shared guard,
+    /// so that no guard is held while the copy is processed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let snapshot: String = portal.snapshot();
+    /// portal.borrow_mut().push_str(" and changed");
+    /// assert_eq!(snapshot, "Scoped");
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot(&self) -> T {
+        T::clone(&*self.borrow())
+    }
+}
+
+impl<T> RwPortal<T> {
+    /// Replaces the anchored value with `value`, returning the old one.  
+    /// The guard is released before this method returns.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let mut x = 1;
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// assert_eq!(portal.replace(2), 1);
+    /// portal.set(3);
+    /// assert_eq!(portal.take(), 3);
+    /// drop(portal);
+    /// drop(anchor);
+    ///
+    /// assert_eq!(x, 0);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.borrow_mut(), value)
     }
 
+    /// Replaces the anchored value with `value`.  
+    /// The old value is dropped after the guard has been released.
     #[inline]
-    pub fn borrow_mut<'a>(&'a self) -> impl DerefMut<Target = T> + 'a {
-        let guard = self.0.as_ref().borrow_mut();
-        if guard.poisoned {
-            panic!(ANCHOR_POISONED)
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn set(&self, value: T) {
+        drop(self.replace(value));
+    }
+
+    /// Takes the anchored value, leaving `Default::default()` in its place.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    /// Swaps the anchored values of this portal and `other`.  
+    /// Does nothing if both are associated with the same anchor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let (mut a, mut b) = (1, 2);
+    /// let (anchor_a, anchor_b) = (RwAnchor::new(&mut a), RwAnchor::new(&mut b));
+    ///
+    /// anchor_a.portal().swap_with(&anchor_b.portal());
+    /// drop((anchor_a, anchor_b));
+    ///
+    /// assert_eq!((a, b), (2, 1));
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn swap_with(&self, other: &Self) {
+        if !self.ptr_eq(other) {
+            mem::swap(&mut *self.borrow_mut(), &mut *other.borrow_mut());
         }
-        PortalRefMut(guard)
     }
 }
 
 impl<T: ?Sized> Clone for Portal<T> {
     #[inline]
     fn clone(&self) -> Self {
-        self.0.pipe_ref(Rc::clone).pipe(Self)
+        self.0.pipe_ref(Rc::clone).pipe(Self::new)
+    }
+}
+
+impl<T: ?Sized> GuardState for RwPortal<T> {
+    #[inline]
+    fn is_guarded(&self) -> bool {
+        self.borrow_state() != BorrowState::Unborrowed
+    }
+
+    fn acquisitions(&self) -> String {
+        self.0.guards.listed("Held guard").to_string()
     }
 }
 
 impl<T: ?Sized> Clone for RwPortal<T> {
     #[inline]
     fn clone(&self) -> Self {
-        self.0.pipe_ref(Rc::clone).pipe(Self)
+        self.0.pipe_ref(Rc::clone).pipe(Self::new)
+    }
+}
+
+impl<T: ?Sized> Drop for Portal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.traces.release(&self.1);
+        // The anchor holds the remaining reference.
+        if Rc::strong_count(&self.0) == 2 {
+            self.0.notify_idle();
+        }
+    }
+}
+
+impl<T: ?Sized> Drop for RwPortal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.traces.release(&self.1);
+        // The anchor holds the remaining reference (or dropped its callbacks already).
+        if Rc::strong_count(&self.0) == 2 {
+            self.0.notify_idle();
+        }
+    }
+}
+
+/// Calls `f` with the target iff it can be borrowed immutably, which it is meanwhile, or otherwise with why not.
+fn peek<T: ?Sized, R>(poisonable: &Poisonable<T>, f: impl FnOnce(&Peek<'_, T>) -> R) -> R {
+    match poisonable.share() {
+        None => f(&Peek::Locked),
+        Some(_) if poisonable.poisoned.get() => f(&Peek::Poisoned),
+        Some(_borrow) => f(&Peek::Value(unsafe {
+            //SAFETY: Valid and not mutated while borrowed.
+            poisonable.pointer.as_ref()
+        })),
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Portal<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Portal").field(&&**self).finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for RwPortal<T> {
+    /// Shows the target iff it isn't borrowed mutably, otherwise `<locked>`, or `<poisoned>` if the anchor is poisoned.  
+    /// This doesn't count as borrow, so it also works while the current thread holds a mutable guard.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_eq!(format!("{:?}", portal), r#"RwPortal("Scoped")"#);
+    ///
+    /// let guard = portal.borrow_mut();
+    /// assert_eq!(format!("{:?}", portal), "RwPortal(<locked>)");
+    /// # drop(guard);
+    /// # drop(portal);
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        peek(&self.0.target, |peek| {
+            f.debug_tuple("RwPortal").field(peek).finish()
+        })
     }
 }
 
+//TODO: Docs, test.
+impl<T: ?Sized> UnwindSafe for Portal<T> where T: RefUnwindSafe {}
+
 //TODO: Docs, test.
 impl<T: ?Sized> RefUnwindSafe for RwPortal<T> where T: RefUnwindSafe {}
 
 //TODO: Docs, test.
 impl<T: ?Sized> UnwindSafe for RwPortal<T> where T: RefUnwindSafe {}
 
-#[derive(Debug)]
 #[must_use]
-#[repr(transparent)]
-pub struct WeakPortal<T: ?Sized>(Weak<NonNull<T>>);
+pub struct WeakPortal<T: ?Sized>(Weak<Shared<NonNull<T>>>, AnchorId);
 
-#[derive(Debug)]
 #[must_use]
-#[repr(transparent)]
-pub struct WeakRwPortal<T: ?Sized>(Weak<RefCell<Poisonable<NonNull<T>>>>);
+pub struct WeakRwPortal<T: ?Sized>(Weak<Shared<Poisonable<T>>>, AnchorId);
 
 impl<T: ?Sized> WeakPortal<T> {
     #[inline]
     pub fn try_upgrade(&self) -> Option<Portal<T>> {
-        self.0.upgrade().map(Portal)
+        self.0.upgrade().map(Portal::new)
+    }
+
+    /// Calls `f` with the target iff the anchor still exists.  
+    /// Unlike upgrading first, this doesn't record where a portal was created, so it's cheaper where that's done in a hot loop.
+    /// The anchor still counts `f` as a strong portal while it runs.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been dropped, without calling `f`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::{poison::Expired, rc::Anchor};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(weak_portal.read_with(|x| x.len()), Ok(6));
+    ///
+    /// drop(anchor);
+    /// assert_eq!(weak_portal.read_with(|x| x.len()), Err(Expired));
+    /// ```
+    pub fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, Expired> {
+        let shared = self.0.upgrade().ok_or(Expired)?;
+        let target = unsafe {
+            //SAFETY: Valid as long as `shared` is.
+            shared.target.as_ref()
+        };
+        Ok(f(target))
     }
 
     #[inline]
     pub fn upgrade(&self) -> Portal<T> {
-        self.try_upgrade().expect(ANCHOR_DROPPED)
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Returns `true` iff the anchor still exists, so that upgrading would succeed.  
+    /// Unlike `try_upgrade`, this doesn't create a portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let weak_portal = anchor.weak_portal();
+    /// let portal = anchor.portal();
+    /// assert!(weak_portal.is_alive());
+    /// assert_eq!(weak_portal.strong_count(), 1);
+    ///
+    /// drop(portal);
+    /// drop(anchor);
+    /// assert!(!weak_portal.is_alive());
+    /// assert_eq!(weak_portal.strong_count(), 0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+
+    /// Returns the number of (strong) portals currently associated with the anchor, `0` once it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        self.0.strong_count().saturating_sub(1)
+    }
+
+    /// Returns the unique ID of the anchor this weak portal is associated with.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub const fn anchor_id(&self) -> AnchorId {
+        self.1
+    }
+
+    /// Returns `true` iff this weak portal and `other` are associated with the same anchor.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    /// Returns `true` iff this weak portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &Anchor<'_, T>) -> bool {
+        ptr::addr_eq(self.0.as_ptr(), Rc::as_ptr(&anchor.reference))
     }
 }
 
 impl<T: ?Sized> WeakRwPortal<T> {
     #[inline]
     pub fn try_upgrade(&self) -> Option<RwPortal<T>> {
-        self.0.upgrade().map(RwPortal)
+        self.0.upgrade().map(RwPortal::new)
     }
 
     #[inline]
     pub fn upgrade(&self) -> RwPortal<T> {
-        self.try_upgrade().expect(ANCHOR_DROPPED)
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Like `try_upgrade`, but also fails if the anchor is poisoned, so that the first borrow through the portal can't panic because of that.  
+    /// The anchor can still be poisoned later, by a panic while it's borrowed mutably.
+    ///
+    /// # Errors
+    ///
+    /// [`UpgradeError::Dropped`] if the anchor has been dropped,  
+    /// [`UpgradeError::Poisoned`] if it has been poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::{poison::UpgradeError, rc::RwAnchor};
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert!(weak_portal.try_upgrade_checked().is_ok());
+    ///
+    /// let portal = weak_portal.upgrade();
+    /// assert_panic!({
+    ///     let _guard = portal.borrow_mut();
+    ///     panic!()
+    /// });
+    /// drop(portal);
+    /// assert_eq!(weak_portal.try_upgrade_checked().unwrap_err(), UpgradeError::Poisoned);
+    ///
+    /// assert_panic!(drop(anchor), String, starts with "Anchor poisoned");
+    /// assert_eq!(weak_portal.try_upgrade_checked().unwrap_err(), UpgradeError::Dropped);
+    /// ```
+    #[inline]
+    pub fn try_upgrade_checked(&self) -> Result<RwPortal<T>, UpgradeError> {
+        let portal = self.try_upgrade().ok_or(UpgradeError::Dropped)?;
+        if portal.0.target.poisoned.get() {
+            return Err(UpgradeError::Poisoned);
+        }
+        Ok(portal)
+    }
+
+    /// Returns `true` iff the anchor still exists, so that upgrading would succeed.  
+    /// Unlike `try_upgrade`, this doesn't create a portal.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+
+    /// Returns the number of (strong) portals currently associated with the anchor, `0` once it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        self.0.strong_count().saturating_sub(1)
+    }
+
+    /// Returns the unique ID of the anchor this weak portal is associated with.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub const fn anchor_id(&self) -> AnchorId {
+        self.1
+    }
+
+    /// Returns `true` iff this weak portal and `other` are associated with the same anchor.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    /// Returns `true` iff this weak portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &RwAnchor<'_, T>) -> bool {
+        ptr::addr_eq(self.0.as_ptr(), Rc::as_ptr(&anchor.reference))
+    }
+}
+
+impl<T: Clone> WeakRwPortal<T> {
+    /// Clones the anchored value out like [`RwPortal::snapshot`], or returns `default` if the anchor has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or its target is borrowed mutably.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(weak_portal.snapshot_or("Gone".to_owned()), "Scoped");
+    ///
+    /// drop(anchor);
+    /// assert_eq!(weak_portal.snapshot_or("Gone".to_owned()), "Gone");
+    /// assert_eq!(weak_portal.snapshot_or_default(), "");
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot_or(&self, default: T) -> T {
+        self.try_upgrade()
+            .map_or(default, |portal| portal.snapshot())
+    }
+
+    /// Clones the anchored value out like [`RwPortal::snapshot`], or returns `T::default()` if the anchor has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or its target is borrowed mutably.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot_or_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.try_upgrade()
+            .map_or_else(T::default, |portal| portal.snapshot())
     }
 }
 
 impl<T: ?Sized> Clone for WeakPortal<T> {
     #[inline]
     fn clone(&self) -> Self {
-        self.0.pipe_ref(Weak::clone).pipe(Self)
+        Self(Weak::clone(&self.0), self.1)
     }
 }
 
 impl<T: ?Sized> Clone for WeakRwPortal<T> {
     #[inline]
     fn clone(&self) -> Self {
-        self.0.pipe_ref(Weak::clone).pipe(Self)
+        Self(Weak::clone(&self.0), self.1)
     }
 }
 
-#[repr(transparent)]
-struct PortalRef<'a, T: 'a + ?Sized>(Ref<'a, Poisonable<NonNull<T>>>);
+impl<T: ?Sized + Debug> Debug for WeakPortal<T> {
+    /// Shows the target iff the anchor still exists, otherwise `<expired>`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let shared = self.0.upgrade();
+        let target = shared.as_ref().map_or(Peek::Expired, |shared| {
+            Peek::Value(unsafe {
+                //SAFETY: Valid as long as `shared` is.
+                shared.target.as_ref()
+            })
+        });
+        f.debug_tuple("WeakPortal").field(&target).finish()
+    }
+}
 
-#[repr(transparent)]
-struct PortalRefMut<'a, T: 'a + ?Sized>(RefMut<'a, Poisonable<NonNull<T>>>);
+impl<T: ?Sized + Debug> Debug for WeakRwPortal<T> {
+    /// Shows the target iff the anchor still exists and the target isn't borrowed mutably.  
+    /// Otherwise, shows `<expired>`, `<locked>` or `<poisoned>`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("WeakRwPortal");
+        match self.0.upgrade() {
+            Some(shared) => peek(&shared.target, |peek| tuple.field(peek).finish()),
+            None => tuple.field(&Peek::<T>::Expired).finish(),
+        }
+    }
+}
+
+/// A `HashMap` or `HashSet` key that hashes and compares by anchor identity, not by value.  
+/// Obtained from any portal or weak portal via `From`, without borrowing the target.
+///
+/// Keys don't keep the anchor alive, but do keep its bookkeeping allocated, so that identities aren't reused while a key exists.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::rc::{Anchor, PortalKey},
+///     std::collections::HashSet,
+/// };
+///
+/// let x = "Scoped".to_owned();
+/// let y = "Scoped".to_owned();
+/// let anchor_x = Anchor::new(&x);
+/// let anchor_y = Anchor::new(&y);
+/// let portal = anchor_x.portal();
+///
+/// let mut observers = HashSet::new();
+/// observers.insert(PortalKey::from(&portal));
+/// assert!(observers.contains(&PortalKey::from(&anchor_x.weak_portal())));
+/// assert!(!observers.contains(&PortalKey::from(&anchor_y.portal())));
+/// # drop(portal);
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct PortalKey<T: ?Sized>(KeyTarget<T>);
+
+/// The anchor a [`PortalKey`] identifies.
+#[derive(Debug)]
+enum KeyTarget<T: ?Sized> {
+    /// From an [`Anchor`].
+    Portal(WeakPortal<T>),
+    /// From an [`RwAnchor`].
+    RwPortal(WeakRwPortal<T>),
+}
+
+impl<T: ?Sized> PortalKey<T> {
+    /// The address of the shared anchor state, which identifies the anchor.
+    fn address(&self) -> *const () {
+        match &self.0 {
+            KeyTarget::Portal(weak) => weak.0.as_ptr().cast(),
+            KeyTarget::RwPortal(weak) => weak.0.as_ptr().cast(),
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for PortalKey<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match &self.0 {
+            KeyTarget::Portal(weak) => KeyTarget::Portal(weak.clone()),
+            KeyTarget::RwPortal(weak) => KeyTarget::RwPortal(weak.clone()),
+        }
+        .pipe(Self)
+    }
+}
+
+impl<T: ?Sized> PartialEq for PortalKey<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.address(), other.address())
+    }
+}
+
+impl<T: ?Sized> Eq for PortalKey<T> {}
+
+impl<T: ?Sized> Hash for PortalKey<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.address().hash(state);
+    }
+}
+
+impl<T: ?Sized> From<&Portal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(portal: &Portal<T>) -> Self {
+        Portal::downgrade(portal).pipe(KeyTarget::Portal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&RwPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(portal: &RwPortal<T>) -> Self {
+        portal.downgrade().pipe(KeyTarget::RwPortal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&WeakPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(weak_portal: &WeakPortal<T>) -> Self {
+        weak_portal.clone().pipe(KeyTarget::Portal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&WeakRwPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(weak_portal: &WeakRwPortal<T>) -> Self {
+        weak_portal.clone().pipe(KeyTarget::RwPortal).pipe(Self)
+    }
+}
+
+/// A list of weak portals to notify, e.g. the observers of a subject.  
+/// Portals whose anchor has been dropped are pruned while iterating.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::rc::{Anchor, Observers};
+///
+/// let a = "A".to_owned();
+/// let b = "B".to_owned();
+/// let anchor_a = Anchor::new(&a);
+/// let anchor_b = Anchor::new(&b);
+///
+/// let mut observers = Observers::new();
+/// observers.push(anchor_a.weak_portal());
+/// observers.push(anchor_b.weak_portal());
+/// drop(anchor_a);
+///
+/// let mut notified = Vec::new();
+/// observers.for_each_alive(|observer| notified.push(observer.clone()));
+/// assert_eq!(notified, ["B"]);
+/// assert_eq!(observers.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct Observers<T: ?Sized>(Vec<WeakPortal<T>>);
+
+impl<T: ?Sized> Observers<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds `observer` at the end of the list.
+    #[inline]
+    pub fn push(&mut self, observer: WeakPortal<T>) {
+        self.0.push(observer);
+    }
+
+    /// Calls `f` with the target of each observer whose anchor still exists, in the order they were added.  
+    /// Removes the other observers.
+    pub fn for_each_alive(&mut self, mut f: impl FnMut(&T)) {
+        self.0.retain(|observer| observer.read_with(&mut f).is_ok());
+    }
+
+    /// Removes observers whose anchor has been dropped without iterating the others, and releases the freed capacity.  
+    /// Returns the number of observers removed.
+    ///
+    /// Call this periodically on lists that are rarely iterated but often pushed to, e.g. in long-running registries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{Anchor, Observers};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let mut observers: Observers<_> = anchor.weak_portals(3).into_iter().collect();
+    /// drop(anchor);
+    ///
+    /// assert_eq!(observers.compact(), 3);
+    /// assert!(observers.is_empty());
+    /// ```
+    pub fn compact(&mut self) -> usize {
+        let count = self.0.len();
+        self.0.retain(WeakPortal::is_alive);
+        self.0.shrink_to_fit();
+        count - self.0.len()
+    }
+
+    /// Returns the number of observers, including any whose anchor was dropped since they were last iterated.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` iff there are no observers, not even ones whose anchor was dropped since they were last iterated.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: ?Sized> Default for Observers<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> Extend<WeakPortal<T>> for Observers<T> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = WeakPortal<T>>>(&mut self, observers: I) {
+        self.0.extend(observers);
+    }
+}
+
+impl<T: ?Sized> FromIterator<WeakPortal<T>> for Observers<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = WeakPortal<T>>>(observers: I) -> Self {
+        observers.into_iter().collect::<Vec<_>>().pipe(Self)
+    }
+}
+
+#[cfg_attr(
+    not(any(feature = "backtrace", feature = "watchdog")),
+    repr(transparent)
+)]
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can cause borrow conflicts with other tasks on this thread"
+)]
+struct PortalRef<'a, T: 'a + ?Sized>(
+    SharedBorrow<'a, T>,
+    Held,
+    #[allow(dead_code)] // Only dropped.
+    Traced<'a>,
+);
+
+/// Poisons the anchor if released while unwinding, unless defused.
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can cause borrow conflicts with other tasks on this thread"
+)]
+struct PortalRefMut<'a, T: 'a + ?Sized>(
+    &'a Poisonable<T>,
+    Held,
+    bool,
+    #[allow(dead_code)] // Only dropped.
+    Traced<'a>,
+);
 
 impl<'a, T: ?Sized> Deref for PortalRef<'a, T> {
     type Target = T;
     #[inline]
     fn deref(&self) -> &Self::Target {
-        let pointer = &self.0.deref().pointer;
         unsafe {
-            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
-            pointer.as_ref()
+            //SAFETY: Valid and not mutated while this guard is counted. Can't be created from a read-only anchor.
+            (self.0).0.pointer.as_ref()
         }
     }
 }
@@ -490,10 +1937,9 @@ impl<'a, T: ?Sized> Deref for PortalRefMut<'a, T> {
     type Target = T;
     #[inline]
     fn deref(&self) -> &Self::Target {
-        let pointer = &self.0.deref().pointer;
         unsafe {
-            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
-            pointer.as_ref()
+            //SAFETY: Valid and not mutated while this guard is counted. Can't be created from a read-only anchor.
+            self.0.pointer.as_ref()
         }
     }
 }
@@ -501,23 +1947,106 @@ impl<'a, T: ?Sized> Deref for PortalRefMut<'a, T> {
 impl<'a, T: ?Sized> DerefMut for PortalRefMut<'a, T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut Self::Target {
-        let pointer = &mut self.0.deref_mut().pointer;
+        let mut pointer = self.0.pointer;
         unsafe {
-            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
+            //SAFETY: Valid and exclusive while this guard exists. Can't be created from a read-only anchor.
             pointer.as_mut()
         }
     }
 }
 
+impl<'a, T: ?Sized> AsRef<T> for PortalRef<'a, T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized> AsRef<T> for PortalRefMut<'a, T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized> AsMut<T> for PortalRefMut<'a, T> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut T {
+        &mut *self
+    }
+}
+
+impl<'a, T: ?Sized> HeldFor for PortalRef<'a, T> {
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    fn held_for(&self) -> Duration {
+        self.1.held_for()
+    }
+}
+
+impl<'a, T: ?Sized> HeldFor for PortalRefMut<'a, T> {
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    fn held_for(&self) -> Duration {
+        self.1.held_for()
+    }
+}
+
+impl<'a, T: ?Sized> Defuse for PortalRefMut<'a, T> {
+    #[inline]
+    fn defuse(&mut self) {
+        self.2 = true;
+    }
+}
+
 impl<'a, T: ?Sized> Drop for PortalRefMut<'a, T> {
     #[inline]
     fn drop(&mut self) {
-        if thread::panicking() {
-            self.0.poisoned = true;
+        if thread::panicking() && !self.2 {
+            self.0.poisoned.set(true);
+            self.0.origin.record();
         }
+        self.0.borrows.set(0);
     }
 }
 
+/// Anchors part of a mutably borrowed value, to re-lend it while the guard is held.  
+/// Implemented by the guards returned from [`RwPortal::borrow_mut`].
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::rc::{AnchorField, RwAnchor};
+///
+/// let mut x = ("Scoped".to_owned(), 0);
+/// let anchor = RwAnchor::new(&mut x);
+/// let portal = anchor.portal();
+/// {
+///     let mut guard = portal.borrow_mut();
+///     let count_anchor = guard.anchor_field(|x| &mut x.1);
+///     let count_portal = count_anchor.portal();
+///     *count_portal.borrow_mut() += 1;
+/// }
+/// drop(portal);
+/// drop(anchor);
+///
+/// assert_eq!(x.1, 1);
+/// ```
+pub trait AnchorField: DerefMut {
+    /// Anchors the part of the borrowed value selected by `project`.  
+    /// The returned anchor borrows this guard, so it (and with it, the use check on its portals) ends before the guard can be released.
+    #[inline]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    fn anchor_field<'a, F: ?Sized>(
+        &'a mut self,
+        project: impl FnOnce(&'a mut Self::Target) -> &'a mut F,
+    ) -> RwAnchor<'a, F> {
+        RwAnchor::new(project(self))
+    }
+}
+
+impl<'a, T: ?Sized> AnchorField for PortalRefMut<'a, T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -533,6 +2062,11 @@ mod tests {
             RwPortal<()>,
             PortalRef<'_, ()>,
             PortalRefMut<'_, ()>,
+            MappedPortal<()>,
+            MappedReadPortal<()>,
+            MappedWritePortal<()>,
+            MappedRef<'_, ()>,
+            MappedRefMut<'_, ()>,
         );
 
         assert_impl!(
@@ -542,6 +2076,11 @@ mod tests {
             RwPortal<()>,
             PortalRef<'_, ()>,
             PortalRefMut<'_, ()>,
+            MappedPortal<()>,
+            MappedReadPortal<()>,
+            MappedWritePortal<()>,
+            MappedRef<'_, ()>,
+            MappedRefMut<'_, ()>,
         );
 
         assert_impl!(
@@ -0,0 +1,238 @@
+//! Callbacks on a read-write portal's target, deferred while it's borrowed.
+
+use {
+    super::{BorrowState, RwPortal},
+    std::{
+        cell::{Cell, RefCell},
+        collections::VecDeque,
+        fmt::{self, Debug, Formatter},
+        mem::ManuallyDrop,
+        ops::{Deref, DerefMut},
+        rc::Rc,
+        thread,
+    },
+};
+
+/// A callback queued through a [`Dispatcher`].
+type Callback<T> = Box<dyn FnOnce(&mut T)>;
+
+/// The callbacks queued through a [`Dispatcher`] and its clones.
+struct Queue<T: ?Sized> {
+    /// Oldest first.
+    callbacks: RefCell<VecDeque<Callback<T>>>,
+
+    /// Set while a flush is running further up the stack.
+    flushing: Cell<bool>,
+}
+
+/// Clears [`Queue::flushing`] when dropped, also while unwinding.
+struct Flush<'a, T: ?Sized>(&'a Queue<T>);
+
+impl<'a, T: ?Sized> Drop for Flush<'a, T> {
+    fn drop(&mut self) {
+        self.0.flushing.set(false);
+    }
+}
+
+/// Runs callbacks on the target of an `!Send` [`RwPortal`], queueing them instead while it's borrowed on this thread.
+/// Clones share the queue, so callbacks dispatched from inside a callback run after it rather than hitting a borrow conflict.
+///
+/// Queued callbacks run as soon as possible: after the current callback, when a guard borrowed through the dispatcher is dropped,
+/// or on the next call to [`dispatch`](#method.dispatch) or [`flush`](#method.flush).
+/// Callbacks that are still queued when the last clone is dropped are dropped without running.
+/// A queued callback that holds a clone of its dispatcher keeps the portal alive until it runs.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::rc::{Dispatcher, RwAnchor};
+///
+/// let mut log = Vec::new();
+/// let anchor = RwAnchor::new(&mut log);
+/// let dispatcher = Dispatcher::new(anchor.portal());
+///
+/// let inner = dispatcher.clone();
+/// dispatcher.dispatch(move |log| {
+///     log.push("outer");
+///     // Borrowing through the portal here would panic.
+///     inner.dispatch(|log| log.push("inner"));
+///     log.push("outer done");
+/// });
+///
+/// let guard = dispatcher.borrow();
+/// dispatcher.dispatch(|log| log.push("after borrow"));
+/// assert_eq!(dispatcher.pending(), 1);
+/// drop(guard);
+/// assert_eq!(dispatcher.pending(), 0);
+///
+/// drop(dispatcher);
+/// drop(anchor);
+/// assert_eq!(log, ["outer", "outer done", "inner", "after borrow"]);
+/// ```
+#[must_use]
+pub struct Dispatcher<T: ?Sized> {
+    /// Borrowed mutably for each callback.
+    portal: RwPortal<T>,
+
+    /// Shared between clones.
+    queue: Rc<Queue<T>>,
+}
+
+/// Flushes the dispatcher's queue once the wrapped guard is released.
+struct Flushing<'a, T: ?Sized, G> {
+    /// Released first.
+    guard: ManuallyDrop<G>,
+
+    /// Flushed after releasing `guard`.
+    dispatcher: &'a Dispatcher<T>,
+}
+
+impl<T: ?Sized> Dispatcher<T> {
+    /// Creates a dispatcher for the target of `portal`, with a new, empty queue.
+    pub fn new(portal: RwPortal<T>) -> Self {
+        Self {
+            portal,
+            queue: Rc::new(Queue {
+                callbacks: RefCell::new(VecDeque::new()),
+                flushing: Cell::new(false),
+            }),
+        }
+    }
+
+    /// Calls `callback` with the target now if it isn't borrowed, otherwise queues it until it's released.
+    /// Callbacks run in the order in which they were dispatched.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned, or if a callback panics.
+    /// A panicking callback poisons the anchor, and the callbacks after it stay queued.
+    pub fn dispatch(&self, callback: impl FnOnce(&mut T) + 'static) {
+        self.queue
+            .callbacks
+            .borrow_mut()
+            .push_back(Box::new(callback));
+        self.flush();
+    }
+
+    /// Runs the queued callbacks iff the target isn't borrowed and no flush is running further up the stack.
+    ///
+    /// # Panics
+    ///
+    /// Like [`dispatch`](#method.dispatch).
+    pub fn flush(&self) {
+        if self.queue.flushing.get() {
+            return;
+        }
+        self.queue.flushing.set(true);
+        let _flush = Flush(&*self.queue);
+        while self.portal.borrow_state() == BorrowState::Unborrowed {
+            // Not borrowed while running, so that callbacks can dispatch.
+            let callback = self.queue.callbacks.borrow_mut().pop_front();
+            match callback {
+                Some(callback) => callback(&mut *self.portal.borrow_mut()),
+                None => break,
+            }
+        }
+    }
+
+    /// Returns the number of queued callbacks.
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.queue.callbacks.borrow().len()
+    }
+
+    /// Borrows the target immutably, then flushes the queue once the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Like [`RwPortal::borrow`].
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn borrow<'a>(&'a self) -> impl Deref<Target = T> + AsRef<T> + 'a {
+        Flushing {
+            guard: ManuallyDrop::new(self.portal.borrow()),
+            dispatcher: self,
+        }
+    }
+
+    /// Borrows the target mutably, then flushes the queue once the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Like [`RwPortal::borrow_mut`].
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn borrow_mut<'a>(&'a self) -> impl DerefMut<Target = T> + AsRef<T> + AsMut<T> + 'a {
+        Flushing {
+            guard: ManuallyDrop::new(self.portal.borrow_mut()),
+            dispatcher: self,
+        }
+    }
+
+    /// Returns the portal callbacks are run through.
+    /// Guards borrowed through it directly don't flush the queue when dropped.
+    pub const fn portal(&self) -> &RwPortal<T> {
+        &self.portal
+    }
+}
+
+impl<T: ?Sized> Clone for Dispatcher<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            portal: self.portal.clone(),
+            queue: Rc::clone(&self.queue),
+        }
+    }
+}
+
+impl<T: ?Sized> Debug for Dispatcher<T> {
+    /// Shows the number of queued callbacks, without borrowing the target.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("pending", &self.pending())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T: ?Sized, G: Deref<Target = T>> Deref for Flushing<'a, T, G> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<'a, T: ?Sized, G: DerefMut<Target = T>> DerefMut for Flushing<'a, T, G> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<'a, T: ?Sized, G: Deref<Target = T>> AsRef<T> for Flushing<'a, T, G> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized, G: DerefMut<Target = T>> AsMut<T> for Flushing<'a, T, G> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut T {
+        &mut *self
+    }
+}
+
+impl<'a, T: ?Sized, G> Drop for Flushing<'a, T, G> {
+    fn drop(&mut self) {
+        unsafe {
+            //SAFETY: Dropped only once, here.
+            ManuallyDrop::drop(&mut self.guard);
+        }
+        // Callbacks aren't run while unwinding, since the anchor is poisoned anyway if `guard` was mutable.
+        if !thread::panicking() {
+            self.dispatcher.flush();
+        }
+    }
+}
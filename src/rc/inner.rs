@@ -0,0 +1,55 @@
+//! Access to the contents of anchored cells in one step.
+
+use {
+    super::Portal,
+    crate::poison::AccessError,
+    std::cell::{Ref, RefCell, RefMut},
+};
+
+impl<U: ?Sized> Portal<RefCell<U>> {
+    /// Borrows the contents of the anchored cell immutably.
+    ///
+    /// This is an associated function because `Portal` dereferences to its target.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::WouldBlock`] iff the contents are borrowed mutably.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{
+    ///         poison::AccessError,
+    ///         rc::{Anchor, Portal},
+    ///     },
+    ///     std::cell::RefCell,
+    /// };
+    ///
+    /// let log = RefCell::new(vec![]);
+    /// let anchor = Anchor::new(&log);
+    /// let portal = anchor.portal();
+    ///
+    /// Portal::borrow_inner_mut(&portal).unwrap().push("started");
+    /// let entries = Portal::borrow_inner(&portal).unwrap();
+    /// assert_eq!(*entries, ["started"]);
+    /// assert_eq!(
+    ///     Portal::borrow_inner_mut(&portal).unwrap_err(),
+    ///     AccessError::WouldBlock,
+    /// );
+    /// # drop(entries);
+    /// # drop(portal);
+    /// ```
+    pub fn borrow_inner(portal: &Self) -> Result<Ref<'_, U>, AccessError> {
+        portal.try_borrow().map_err(|_| AccessError::WouldBlock)
+    }
+
+    /// Borrows the contents of the anchored cell mutably.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::WouldBlock`] iff the contents are borrowed.
+    pub fn borrow_inner_mut(portal: &Self) -> Result<RefMut<'_, U>, AccessError> {
+        portal.try_borrow_mut().map_err(|_| AccessError::WouldBlock)
+    }
+}
@@ -0,0 +1,143 @@
+//! Running code whose `!Send` anchors may deadlock on a thread that's abandoned instead, so that tests can observe the misuse.
+
+use {
+    crate::messages::Violation,
+    std::{
+        cell::Cell,
+        fmt::{self, Display, Formatter},
+        panic::{self, AssertUnwindSafe},
+        sync::mpsc,
+        thread,
+    },
+};
+
+/// A misuse that would have deadlocked (or aborted) the thread running [`isolated`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViolationEvent {
+    /// What happened.
+    violation: Violation,
+
+    /// What would have been logged, including any traces.
+    message: String,
+}
+
+impl ViolationEvent {
+    /// Returns what happened.
+    #[must_use]
+    pub const fn violation(&self) -> Violation {
+        self.violation
+    }
+
+    /// Returns the message that would have been logged before deadlocking, including the surviving portals' traces if recorded.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for ViolationEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// How an isolated thread ended.
+enum Outcome<R> {
+    /// `body` returned or panicked.
+    Finished(thread::Result<R>),
+
+    /// `body` would have deadlocked.
+    Violated(ViolationEvent),
+}
+
+/// A callback reporting a [`ViolationEvent`].
+type Sink = Box<dyn FnOnce(ViolationEvent)>;
+
+thread_local! {
+    /// Set while running the body of [`isolated`].
+    static SINK: Cell<Option<Sink>> = Cell::new(None);
+}
+
+/// Runs `body` on a new thread and returns its result, or the [`ViolationEvent`] instead of deadlocking
+/// if it drops an [`Anchor`](`super::Anchor`) or [`RwAnchor`](`super::RwAnchor`) while that's still in use.
+///
+/// The thread is then parked forever rather than unwound, since its stack may still be borrowed from.
+/// It's leaked along with everything it owns, which is fine in tests but shouldn't be repeated indefinitely.
+/// The deadlock timeout set through [`set_deadlock_timeout`](`super::set_deadlock_timeout`) doesn't apply to isolated threads.
+///
+/// # Errors
+///
+/// Iff `body` would have deadlocked, with the violation that caused it.
+///
+/// # Panics
+///
+/// Iff `body` panics, with its payload, or the thread can't be spawned.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::{messages::Violation, rc};
+///
+/// let event = rc::isolated(|| {
+///     let x = "Scoped".to_owned();
+///     let anchor = rc::Anchor::new(&x);
+///     let portal = anchor.portal();
+///     drop(anchor);
+///     drop(portal);
+/// })
+/// .unwrap_err();
+/// assert_eq!(event.violation(), Violation::AnchorStillInUse);
+/// assert!(event.message().contains("dropped while 1 Portal(s) still exist"));
+///
+/// assert_eq!(rc::isolated(|| 1 + 1), Ok(2));
+/// ```
+pub fn isolated<R: Send + 'static>(
+    body: impl FnOnce() -> R + Send + 'static,
+) -> Result<R, ViolationEvent> {
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::Builder::new()
+        .name("ref-portals isolated".to_owned())
+        .spawn(move || {
+            SINK.with(|sink| {
+                let sender = sender.clone();
+                sink.set(Some(Box::new(move |event| {
+                    // The receiver only stops listening after an outcome was sent.
+                    sender.send(Outcome::Violated(event)).ok();
+                })));
+            });
+            let result = panic::catch_unwind(AssertUnwindSafe(body));
+            drop(SINK.with(Cell::take));
+            sender.send(Outcome::Finished(result)).ok();
+        })
+        .expect("Failed to spawn isolated thread");
+    match receiver
+        .recv()
+        .expect("The isolated thread always sends an outcome")
+    {
+        Outcome::Finished(result) => {
+            handle.join().ok();
+            match result {
+                Ok(result) => Ok(result),
+                Err(payload) => panic::resume_unwind(payload),
+            }
+        }
+        Outcome::Violated(event) => {
+            // The thread is parked forever.
+            drop(handle);
+            Err(event)
+        }
+    }
+}
+
+/// Reports the violation and parks the current thread forever iff it's running the body of [`isolated`], and returns otherwise.
+pub(super) fn intercept(violation: Violation, reason: &dyn Display, traces: &dyn Display) {
+    if let Some(sink) = SINK.with(Cell::take) {
+        sink(ViolationEvent {
+            violation,
+            message: format!("{reason}{traces}"),
+        });
+        loop {
+            thread::park();
+        }
+    }
+}
@@ -0,0 +1,435 @@
+//! Portals and guards projected onto a part of the anchored value.
+
+#[cfg(feature = "watchdog")]
+use std::time::Duration;
+use {
+    super::{Portal, RwPortal},
+    crate::{poison::Defuse, watchdog::HeldFor},
+    std::{
+        fmt::{self, Debug, Formatter},
+        ops::{Deref, DerefMut},
+        ptr::NonNull,
+        rc::Rc,
+    },
+};
+
+/// A portal with its target type erased, so that mapped portals can keep it.
+trait Keep {
+    /// Clones the portal, which counts as another strong portal.
+    fn clone_boxed(&self) -> Box<dyn Keep>;
+}
+
+impl<T: ?Sized + 'static> Keep for Portal<T> {
+    fn clone_boxed(&self) -> Box<dyn Keep> {
+        Box::new(self.clone())
+    }
+}
+
+/// An `!Send` immutable portal to a part of the anchored value, as returned by [`Portal::map`].
+/// It counts as (strong) portal of the anchor. Dereference it directly with `*` or `.deref()`.
+#[must_use]
+pub struct MappedPortal<U: ?Sized> {
+    /// Points into the anchored value, or to a part of it.
+    target: NonNull<U>,
+
+    /// Keeps the anchor in use.
+    portal: Box<dyn Keep>,
+}
+
+impl<T: ?Sized + 'static> Portal<T> {
+    /// Projects `portal` onto a part of its target, e.g. a field.
+    /// The mapped portal counts as strong portal instead.
+    /// This is an associated function so that it can't shadow a method of the target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{Anchor, MappedPortal, Portal};
+    ///
+    /// let x = ("Scoped".to_owned(), 1);
+    /// let anchor = Anchor::new(&x);
+    /// let name: MappedPortal<str> = Portal::map(anchor.portal(), |x| x.0.as_str());
+    /// assert_eq!(&*name, "Scoped");
+    /// # drop(name);
+    /// ```
+    pub fn map<U: ?Sized>(portal: Self, project: impl FnOnce(&T) -> &U) -> MappedPortal<U> {
+        MappedPortal {
+            target: project(&portal).into(),
+            portal: Box::new(portal),
+        }
+    }
+}
+
+impl<U: ?Sized> MappedPortal<U> {
+    /// Projects `portal` further onto a part of its target.
+    /// This is an associated function so that it can't shadow a method of the target.
+    pub fn map<V: ?Sized>(portal: Self, project: impl FnOnce(&U) -> &V) -> MappedPortal<V> {
+        MappedPortal {
+            target: project(&portal).into(),
+            portal: portal.portal,
+        }
+    }
+}
+
+impl<U: ?Sized> Deref for MappedPortal<U> {
+    type Target = U;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            //SAFETY: Valid as long as `self.portal` is.
+            self.target.as_ref()
+        }
+    }
+}
+
+impl<U: ?Sized> AsRef<U> for MappedPortal<U> {
+    #[inline]
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<U: ?Sized> Clone for MappedPortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            portal: self.portal.clone_boxed(),
+        }
+    }
+}
+
+impl<U: ?Sized + Debug> Debug for MappedPortal<U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MappedPortal").field(&&**self).finish()
+    }
+}
+
+/// A projection of a read-write portal for reading, as created by [`RwPortal::map_read`].
+trait ReadLens<U: ?Sized> {
+    /// Borrows the target and projects the guard.
+    fn borrow(&self) -> MappedRef<'_, U>;
+}
+
+/// A projection of a read-write portal for writing, as created by [`RwPortal::map_write`].
+trait WriteLens<U: ?Sized> {
+    /// Borrows the target mutably and projects the guard.
+    fn borrow_mut(&self) -> MappedRefMut<'_, U>;
+}
+
+/// Implements [`ReadLens`] and [`WriteLens`] through `project`.
+struct Lens<T: ?Sized, F> {
+    /// Counts as strong portal of the anchor.
+    portal: RwPortal<T>,
+
+    /// Applied to the target each time it's borrowed.
+    project: F,
+}
+
+impl<T: ?Sized, U: ?Sized, F: Fn(&T) -> &U> ReadLens<U> for Lens<T, F> {
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn borrow(&self) -> MappedRef<'_, U> {
+        MappedRef::map(MappedRef::new(self.portal.borrow()), &self.project)
+    }
+}
+
+impl<T: ?Sized, U: ?Sized, F: Fn(&mut T) -> &mut U> WriteLens<U> for Lens<T, F> {
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn borrow_mut(&self) -> MappedRefMut<'_, U> {
+        MappedRefMut::map(MappedRefMut::new(self.portal.borrow_mut()), &self.project)
+    }
+}
+
+/// An `!Send` portal that can only borrow a part of the anchored value, as created by [`RwPortal::map_read`].
+/// Together with its clones, it counts as one (strong) portal of the anchor. Its guards borrow all of the target.
+#[must_use]
+pub struct MappedReadPortal<U: ?Sized>(Rc<dyn ReadLens<U>>);
+
+/// An `!Send` portal that can only borrow a part of the anchored value mutably, as created by [`RwPortal::map_write`].
+/// Together with its clones, it counts as one (strong) portal of the anchor. Its guards borrow all of the target.
+#[must_use]
+pub struct MappedWritePortal<U: ?Sized>(Rc<dyn WriteLens<U>>);
+
+impl<T: ?Sized + 'static> RwPortal<T> {
+    /// Creates a portal that can only borrow the part of the target selected by `project`, e.g. a field.
+    /// `project` is called each time a guard is acquired, so it can follow changes of the target.
+    ///
+    /// Use [`Portal::map`] to project immutable portals instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{MappedReadPortal, RwAnchor};
+    ///
+    /// let mut state = ("Scoped".to_owned(), 1);
+    /// let anchor = RwAnchor::new(&mut state);
+    /// let portal = anchor.portal();
+    ///
+    /// let name: MappedReadPortal<str> = portal.map_read(|state| state.0.as_str());
+    /// portal.borrow_mut().0.push_str(" value");
+    /// assert_eq!(&*name.borrow(), "Scoped value");
+    /// # drop(name);
+    /// # drop(portal);
+    /// ```
+    pub fn map_read<U: ?Sized>(&self, project: impl Fn(&T) -> &U + 'static) -> MappedReadPortal<U> {
+        MappedReadPortal(Rc::new(Lens {
+            portal: self.clone(),
+            project,
+        }))
+    }
+
+    /// Creates a portal that can only borrow the part of the target selected by `project` mutably, e.g. a field.
+    /// `project` is called each time a guard is acquired, so it can follow changes of the target.
+    ///
+    /// Releasing its guards while unwinding poisons the anchor, as with the guards of this portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::rc::{MappedWritePortal, RwAnchor};
+    ///
+    /// let mut state = ("Scoped".to_owned(), 1);
+    /// let anchor = RwAnchor::new(&mut state);
+    /// let portal = anchor.portal();
+    ///
+    /// let count: MappedWritePortal<i32> = portal.map_write(|state| &mut state.1);
+    /// *count.borrow_mut() += 1;
+    /// drop(count);
+    ///
+    /// # drop(portal);
+    /// drop(anchor);
+    /// assert_eq!(state.1, 2);
+    /// ```
+    pub fn map_write<U: ?Sized>(
+        &self,
+        project: impl Fn(&mut T) -> &mut U + 'static,
+    ) -> MappedWritePortal<U> {
+        MappedWritePortal(Rc::new(Lens {
+            portal: self.clone(),
+            project,
+        }))
+    }
+}
+
+impl<U: ?Sized> MappedReadPortal<U> {
+    /// Borrows the part of the target.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or the target is already borrowed mutably.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn borrow(&self) -> MappedRef<'_, U> {
+        self.0.borrow()
+    }
+}
+
+impl<U: ?Sized> MappedWritePortal<U> {
+    /// Borrows the part of the target mutably.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or the target is already borrowed.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn borrow_mut(&self) -> MappedRefMut<'_, U> {
+        self.0.borrow_mut()
+    }
+}
+
+impl<U: ?Sized> Clone for MappedReadPortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<U: ?Sized> Clone for MappedWritePortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Rc::clone(&self.0))
+    }
+}
+
+impl<U: ?Sized> Debug for MappedReadPortal<U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedReadPortal").finish_non_exhaustive()
+    }
+}
+
+impl<U: ?Sized> Debug for MappedWritePortal<U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedWritePortal").finish_non_exhaustive()
+    }
+}
+
+/// A guard that borrows a part of the anchored value, as returned by [`MappedReadPortal::borrow`].
+/// It can be projected further through [`map`](#method.map), like a [`Ref`](`std::cell::Ref`).
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can cause borrow conflicts with other tasks on this thread"
+)]
+#[must_use]
+pub struct MappedRef<'a, U: ?Sized> {
+    /// Points into the borrowed target, or to a part of it.
+    target: NonNull<U>,
+
+    /// The guard of the whole target.
+    guard: Box<dyn HeldFor + 'a>,
+}
+
+/// A guard that borrows a part of the anchored value mutably, as returned by [`MappedWritePortal::borrow_mut`].
+/// It can be projected further through [`map`](#method.map), like a [`RefMut`](`std::cell::RefMut`).
+///
+/// Releasing it while unwinding poisons the anchor, unless it was [defused](`Defuse`).
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can cause borrow conflicts with other tasks on this thread"
+)]
+#[must_use]
+pub struct MappedRefMut<'a, U: ?Sized> {
+    /// Points into the borrowed target, or to a part of it.
+    target: NonNull<U>,
+
+    /// The guard of the whole target.
+    guard: Box<dyn WriteGuard + 'a>,
+}
+
+/// Erases the target type of a mutable guard, so that mapped guards can keep it.
+trait WriteGuard: Defuse + HeldFor {}
+impl<G: Defuse + HeldFor> WriteGuard for G {}
+
+impl<'a, U: ?Sized> MappedRef<'a, U> {
+    /// Keeps `guard`, pointing at its whole target.
+    fn new(guard: impl Deref<Target = U> + HeldFor + 'a) -> Self {
+        let guard = Box::new(guard);
+        Self {
+            target: NonNull::from(&**guard),
+            guard,
+        }
+    }
+
+    /// Projects `guard` onto a part of its target, keeping the borrow.
+    /// This is an associated function so that it can't shadow a method of the target.
+    #[inline]
+    pub fn map<V: ?Sized>(guard: Self, project: impl FnOnce(&U) -> &V) -> MappedRef<'a, V> {
+        MappedRef {
+            target: project(&guard).into(),
+            guard: guard.guard,
+        }
+    }
+}
+
+impl<'a, U: ?Sized> MappedRefMut<'a, U> {
+    /// Keeps `guard`, pointing at its whole target.
+    fn new(guard: impl DerefMut<Target = U> + Defuse + HeldFor + 'a) -> Self {
+        let mut guard = Box::new(guard);
+        Self {
+            target: NonNull::from(&mut **guard),
+            guard,
+        }
+    }
+
+    /// Projects `guard` onto a part of its target, keeping the borrow.
+    /// This is an associated function so that it can't shadow a method of the target.
+    ///
+    /// If `project` panics, the borrow is released while unwinding and poisons the anchor like any other mutable guard would.
+    #[inline]
+    pub fn map<V: ?Sized>(
+        mut guard: Self,
+        project: impl FnOnce(&mut U) -> &mut V,
+    ) -> MappedRefMut<'a, V> {
+        MappedRefMut {
+            target: project(&mut guard).into(),
+            guard: guard.guard,
+        }
+    }
+}
+
+impl<'a, U: ?Sized> Deref for MappedRef<'a, U> {
+    type Target = U;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            //SAFETY: Valid and not mutated as long as `self.guard` is held.
+            self.target.as_ref()
+        }
+    }
+}
+
+impl<'a, U: ?Sized> Deref for MappedRefMut<'a, U> {
+    type Target = U;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            //SAFETY: Valid and exclusive as long as `self.guard` is held.
+            self.target.as_ref()
+        }
+    }
+}
+
+impl<'a, U: ?Sized> DerefMut for MappedRefMut<'a, U> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe {
+            //SAFETY: Valid and exclusive as long as `self.guard` is held.
+            self.target.as_mut()
+        }
+    }
+}
+
+impl<'a, U: ?Sized> AsRef<U> for MappedRef<'a, U> {
+    #[inline]
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<'a, U: ?Sized> AsRef<U> for MappedRefMut<'a, U> {
+    #[inline]
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<'a, U: ?Sized> AsMut<U> for MappedRefMut<'a, U> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut U {
+        &mut *self
+    }
+}
+
+impl<'a, U: ?Sized> HeldFor for MappedRef<'a, U> {
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    fn held_for(&self) -> Duration {
+        self.guard.held_for()
+    }
+}
+
+impl<'a, U: ?Sized> HeldFor for MappedRefMut<'a, U> {
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    fn held_for(&self) -> Duration {
+        self.guard.held_for()
+    }
+}
+
+impl<'a, U: ?Sized> Defuse for MappedRefMut<'a, U> {
+    #[inline]
+    fn defuse(&mut self) {
+        self.guard.defuse();
+    }
+}
+
+impl<'a, U: ?Sized + Debug> Debug for MappedRef<'a, U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MappedRef").field(&&**self).finish()
+    }
+}
+
+impl<'a, U: ?Sized + Debug> Debug for MappedRefMut<'a, U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MappedRefMut").field(&&**self).finish()
+    }
+}
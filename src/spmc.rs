@@ -0,0 +1,345 @@
+//! Single-producer multi-consumer handoff of borrowed frames, e.g. between the stages of a frame pipeline.
+//!
+//! The [`Anchor`] publishes one `&T` at a time, which opens a window for it.
+//! Each [`Portal`] can [`acquire`](`Portal::acquire`) one [`Token`] per window to read the frame through.
+//! Publishing the next frame closes the window and blocks until all of its tokens have been returned,
+//! so that the previous frame can be reused or dropped afterwards.
+//!
+//! # Example
+//!
+//! ```rust
+//! use {
+//!     ref_portals::spmc::Anchor,
+//!     std::{sync::mpsc, thread},
+//! };
+//!
+//! let frames = [[1_u8; 4], [2; 4], [3; 4]];
+//! let mut anchor = Anchor::<[u8; 4]>::new();
+//! let (sums, sum_receiver) = mpsc::channel();
+//! let consumers: Vec<_> = (0..2)
+//!     .map(|_| {
+//!         let portal = anchor.portal();
+//!         let sums = sums.clone();
+//!         thread::spawn(move || {
+//!             while let Ok(frame) = portal.acquire() {
+//!                 sums.send(frame.iter().sum::<u8>()).unwrap();
+//!             }
+//!         })
+//!     })
+//!     .collect();
+//!
+//! for frame in &frames {
+//!     anchor.publish(frame);
+//! }
+//! drop(anchor); // Waits for the last frame's tokens, then ends the consumers' loops.
+//! for consumer in consumers {
+//!     consumer.join().unwrap();
+//! }
+//!
+//! drop(sums);
+//! let sums: Vec<_> = sum_receiver.iter().collect();
+//! assert!(sums.len() <= 6);
+//! assert!(sums.iter().all(|sum| [4, 8, 12].contains(sum)));
+//! ```
+//!
+//! Consumers that are slower than the producer skip frames.
+
+use {
+    crate::poison::AccessError,
+    std::{
+        fmt::{self, Debug, Formatter},
+        marker::PhantomData,
+        ops::Deref,
+        ptr::NonNull,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Condvar, Mutex, MutexGuard, PoisonError,
+        },
+    },
+};
+
+/// The publication state shared between an [`Anchor`] and its portals.
+struct State<T: ?Sized> {
+    /// The published frame, while its window is open.
+    frame: Option<NonNull<T>>,
+
+    /// The number of the current (or last) window, counting from 1. `0` before the first publication.
+    window: u64,
+
+    /// The number of live tokens, which are all for the current (or last) window.
+    tokens: usize,
+
+    /// Whether the anchor still exists.
+    anchored: bool,
+}
+
+/// State shared between an [`Anchor`] and its portals.
+struct Shared<T: ?Sized> {
+    /// Only held briefly, never while calling user code.
+    state: Mutex<State<T>>,
+
+    /// Notified when a window opens or closes and when the last token of a window is returned.
+    changed: Condvar,
+}
+
+unsafe impl<T: ?Sized + Sync> Send for Shared<T> {}
+unsafe impl<T: ?Sized + Sync> Sync for Shared<T> {}
+
+impl<T: ?Sized> Shared<T> {
+    /// Locks the state. There's no user code that could poison it.
+    fn state(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Closes the current window, if any, and waits until all of its tokens have been returned.
+    fn close<'a>(&'a self, state: MutexGuard<'a, State<T>>) -> MutexGuard<'a, State<T>> {
+        let mut state = state;
+        if state.frame.take().is_some() {
+            self.changed.notify_all();
+        }
+        self.changed
+            .wait_while(state, |state| state.tokens != 0)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Publishes frames that live for at least `'a` to its [`Portal`]s, one at a time.
+///
+/// Dropping the anchor closes the current window and blocks until its tokens have been returned.
+/// Acquiring a token through its portals fails afterwards.
+pub struct Anchor<'a, T: ?Sized> {
+    /// Shared with the portals.
+    shared: Arc<Shared<T>>,
+
+    /// Published frames are borrowed for `'a`.
+    _phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Creates an anchor with no open window.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    frame: None,
+                    window: 0,
+                    tokens: 0,
+                    anchored: true,
+                }),
+                changed: Condvar::new(),
+            }),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Closes the current window, blocks until all of its tokens have been returned and then opens a new one for `frame`.
+    ///
+    /// This deadlocks if a token of the current window is held (or was leaked) on the current thread.
+    pub fn publish(&mut self, frame: &'a T) {
+        let mut state = self.shared.close(self.shared.state());
+        state.frame = Some(NonNull::from(frame));
+        state.window += 1;
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+
+    /// Closes the current window without opening a new one, and blocks until all of its tokens have been returned.
+    pub fn close(&mut self) {
+        drop(self.shared.close(self.shared.state()));
+    }
+
+    /// Returns the number of the current (or last) window, counting from 1, or `0` if nothing was published yet.
+    #[must_use]
+    pub fn window(&self) -> u64 {
+        self.shared.state().window
+    }
+
+    /// Creates a portal that can acquire tokens for this anchor's windows.
+    /// It skips windows that were opened before it was created.
+    #[must_use]
+    pub fn portal(&self) -> Portal<T> {
+        Portal {
+            seen: AtomicU64::new(self.window()),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Default for Anchor<'a, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T: ?Sized> Drop for Anchor<'a, T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.close(self.shared.state());
+        state.anchored = false;
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+}
+
+impl<'a, T: ?Sized> Debug for Anchor<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.shared.state();
+        f.debug_struct("Anchor")
+            .field("window", &state.window)
+            .field("open", &state.frame.is_some())
+            .field("tokens", &state.tokens)
+            .finish()
+    }
+}
+
+/// Acquires [`Token`]s for the windows of an [`Anchor`], at most one per window.
+///
+/// Portals track which windows they have acquired tokens for, so each consumer should use its own (clone).
+pub struct Portal<T: ?Sized> {
+    /// Shared with the anchor.
+    shared: Arc<Shared<T>>,
+
+    /// The last window this portal acquired a token for, or the window that was current when it was created.
+    seen: AtomicU64,
+}
+
+impl<T: ?Sized> Portal<T> {
+    /// Blocks until a window this portal hasn't acquired a token for yet is open, then acquires a token for it.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] iff the anchor has been dropped.
+    pub fn acquire(&self) -> Result<Token<'_, T>, AccessError> {
+        let state = self
+            .shared
+            .changed
+            .wait_while(self.shared.state(), |state| {
+                state.anchored && !self.is_new(state)
+            })
+            .unwrap_or_else(PoisonError::into_inner);
+        self.take(state)
+    }
+
+    /// Acquires a token for the current window iff it's open and this portal hasn't acquired one for it yet.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::{poison::AccessError, spmc::Anchor};
+    ///
+    /// let (first, second) = ("first".to_owned(), "second".to_owned());
+    /// let mut anchor = Anchor::new();
+    /// let portal = anchor.portal();
+    /// assert_eq!(portal.try_acquire().unwrap_err(), AccessError::WouldBlock);
+    ///
+    /// anchor.publish(&first);
+    /// let token = portal.try_acquire().unwrap();
+    /// assert_eq!((token.as_str(), token.window()), ("first", 1));
+    /// assert_eq!(portal.try_acquire().unwrap_err(), AccessError::WouldBlock);
+    ///
+    /// drop(token); // Otherwise, this would deadlock:
+    /// anchor.publish(&second);
+    /// assert_eq!(*portal.try_acquire().unwrap(), "second");
+    ///
+    /// drop(anchor);
+    /// assert_eq!(portal.try_acquire().unwrap_err(), AccessError::Dropped);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] iff the anchor has been dropped,
+    /// [`AccessError::WouldBlock`] iff no new window is open.
+    pub fn try_acquire(&self) -> Result<Token<'_, T>, AccessError> {
+        self.take(self.shared.state())
+    }
+
+    /// Returns `true` iff a window is open that this portal hasn't acquired a token for.
+    fn is_new(&self, state: &State<T>) -> bool {
+        state.frame.is_some() && state.window > self.seen.load(Ordering::Relaxed)
+    }
+
+    /// Acquires a token for the current window, if possible.
+    fn take(&self, mut state: MutexGuard<'_, State<T>>) -> Result<Token<'_, T>, AccessError> {
+        if !state.anchored {
+            return Err(AccessError::Dropped);
+        }
+        match state.frame {
+            Some(frame) if self.is_new(&state) => {
+                state.tokens += 1;
+                self.seen.store(state.window, Ordering::Relaxed);
+                Ok(Token {
+                    shared: &self.shared,
+                    frame,
+                    window: state.window,
+                })
+            }
+            _ => Err(AccessError::WouldBlock),
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for Portal<T> {
+    /// The clone skips the same windows as this portal.
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+            seen: AtomicU64::new(self.seen.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<T: ?Sized> Debug for Portal<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Portal")
+            .field("seen", &self.seen.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the frame of a window, which the anchor can't close until this has been dropped.
+#[must_use]
+pub struct Token<'a, T: ?Sized> {
+    /// Counts this token.
+    shared: &'a Shared<T>,
+
+    /// Stays valid until this token is returned.
+    frame: NonNull<T>,
+
+    /// The window the frame was published for.
+    window: u64,
+}
+
+impl<'a, T: ?Sized> Token<'a, T> {
+    /// Returns the number of the window this token was acquired for.
+    #[must_use]
+    pub const fn window(&self) -> u64 {
+        self.window
+    }
+}
+
+impl<'a, T: ?Sized> Deref for Token<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            //SAFETY: The anchor waits for this token before releasing the frame.
+            self.frame.as_ref()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Drop for Token<'a, T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state();
+        state.tokens -= 1;
+        if state.tokens == 0 {
+            drop(state);
+            self.shared.changed.notify_all();
+        }
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for Token<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Token").field(&&**self).finish()
+    }
+}
@@ -1,23 +1,177 @@
 //! Theadsafe anchors and portals.  
 //! These (but not their guards) are various degrees of `Send` and `Sync` depending on their type parameter.
+//!
+//! When compiled with `--cfg loom`, the atomics, locks and condition variables used here are [loom](https://crates.io/crates/loom)'s,
+//! so that anchor lifecycles can be model-checked inside `loom::model`.
 
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::Registration;
+#[cfg(feature = "ffi")]
+use std::ffi::c_void;
 use {
-    crate::{ANCHOR_DROPPED, ANCHOR_POISONED, ANCHOR_STILL_IN_USE},
+    crate::{
+        anchor_drop_abort, anchor_drop_panic, anchor_dropped_panic, freelist,
+        messages::{self, Report, Violation},
+        poison::{
+            AccessError, Defuse, Expired, Origin, PortalLimitExceeded, Throttled, UpgradeError,
+        },
+        trace::{TraceId, Traces},
+        watchdog::{check_blocking, held_here, holders, Held, HeldFor},
+        AnchorId, CallMut, Callbacks, GuardState, Peek,
+    },
     std::{
         borrow::Borrow,
-        fmt::Debug,
+        cell::UnsafeCell,
+        fmt::{self, Debug, Display, Formatter},
+        hash::{Hash, Hasher},
+        iter::{self, FromIterator},
         marker::PhantomData,
-        mem::ManuallyDrop,
+        mem::{self, ManuallyDrop},
+        num::NonZeroUsize,
         ops::{Deref, DerefMut},
-        panic::{RefUnwindSafe, UnwindSafe},
-        ptr::NonNull,
-        sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak},
+        panic::{self, AssertUnwindSafe, RefUnwindSafe, UnwindSafe},
+        ptr::{self, NonNull},
+        sync::{Arc, LockResult, OnceLock, PoisonError, TryLockError, TryLockResult, Weak},
+        thread::{self, JoinHandle, ThreadId},
+        time::{Duration, Instant},
     },
     wyz::pipe::*,
 };
 
+// Loom can't model `Arc`s with weak references, so only the primitives that synchronise the anchor lifecycle are swapped.
+#[cfg(loom)]
+use loom::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+#[cfg(not(loom))]
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Condvar, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+};
+
+mod arena;
+pub use arena::{AnchorArena, ArenaFrame};
+mod batch;
+pub use batch::BatchWriter;
+mod cached;
+pub use cached::CachedPortal;
+mod capability;
+pub use capability::{AnchorBuilder, CapAnchor, CapPortal, WeakCapPortal};
+mod channel;
+pub use channel::{ChannelReceiver, ReceiverAnchor};
+mod clock;
+pub use clock::{Clock, SystemClock};
+mod close;
+pub use close::CloseReport;
+mod compat;
+pub use compat::{ReadGuard, WriteGuard};
+mod cow;
+pub use cow::{CowPortal, PortalCow};
+mod detach;
+pub use detach::{DetachedRwAnchor, DetachedWAnchor};
+mod drive;
+pub use drive::{Driver, DriverStop, Interval, Tick, TickSource};
+mod events;
+#[cfg(feature = "events")]
+pub use events::{AnchorEvent, AnchorEvents};
+mod exchange;
+pub use exchange::Exchange;
+mod fence;
+pub use fence::{Fence, FenceAnchor, Fenced};
+mod graceful;
+mod group;
+pub use group::{AnchorGroup, ViolationPolicy};
+mod hand_off;
+pub use hand_off::{HandOff, HandedOff};
+mod inner;
+mod interrupt;
+mod io;
+#[cfg(feature = "buf")]
+pub use io::BufPortal;
+mod lease;
+pub use lease::Lease;
+use lease::Leases;
+mod lend;
+pub use lend::{Job, Ticket};
+mod lock_all;
+pub use lock_all::{LockAll, LockOrder, LockRequest, ReadSet, TryLockAll, WouldBlock};
+mod map;
+pub use map::{MappedPortal, MappedWeakPortal};
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mmap")]
+pub use mmap::MapAnchor;
+mod mutable;
+pub use mutable::{MutAnchor, MutAnchorBuilder, MutPortal};
+#[cfg(feature = "observe")]
+mod observe;
+#[cfg(feature = "observe")]
+pub use observe::{ObservingPortal, Subscription};
+mod owning;
+pub use owning::OwningAnchor;
+mod project;
+pub use project::{MappedReadPortal, MappedWritePortal};
+mod registry;
+pub use registry::PortalRegistry;
+mod restrict;
+pub use restrict::Restrict;
+mod retry;
+pub use retry::{Backoff, RetryPolicy};
+mod scope;
+pub use scope::RwScope;
+mod scratch;
+pub use scratch::{ScratchAnchor, ScratchPortal};
+mod sentinel;
+pub use sentinel::{Sentinel, SentinelPortal};
+mod sharded;
+pub use sharded::{ShardedAnchor, ShardedMap, ShardedPortal};
+mod snapshot;
+pub use snapshot::{ArcMemo, ToArc};
+mod shutdown;
+use shutdown::Idle;
+pub use shutdown::{SealPolicy, ShutdownToken};
+mod signal;
+pub use signal::{AsyncSignalSafePortal, SignalAnchor};
+mod spawn;
+pub use spawn::PortalJoinHandle;
+mod split;
+pub use split::{ReadPortal, WeakReadPortal, WritePortal};
+mod stats;
+#[cfg(feature = "stats")]
+pub use stats::AnchorStats;
+use stats::Counters;
+#[cfg(feature = "swap")]
+mod swap;
+#[cfg(feature = "swap")]
+pub use swap::{Snapshot, SwapAnchor, SwapPortal};
+mod teardown;
+use teardown::Dependents;
+pub use teardown::TeardownParent;
+mod text;
+pub use text::PortalDisplay;
+mod throttle;
+use throttle::Throttle;
+pub use throttle::WriteThrottle;
+mod transact;
+pub use transact::Backup;
+mod uninit;
+pub use uninit::{InitPortal, UninitAnchor, UninitPortal, UninitWriteGuard};
+#[cfg(feature = "stream")]
+mod stream;
+mod yielding;
+#[cfg(feature = "stream")]
+pub use stream::StreamPortal;
+#[cfg(feature = "bytes")]
+mod zero_copy;
+#[cfg(feature = "bytes")]
+pub use zero_copy::BytesAnchor;
+
 /// An externally synchronised `NonNull<T>`.
 /// SS stands for Send Sync.
+///
+/// Only constructed from references or from pointers the caller of an anchor's `from_raw` vouches for,
+/// so it always carries their provenance. It's never converted to or from an integer.
 #[derive(Debug, Clone, Copy)]
 #[repr(transparent)]
 struct SSNonNull<T: ?Sized>(NonNull<T>);
@@ -27,16 +181,25 @@ unsafe impl<T: ?Sized + Send> Send for SSNonNull<T> {
 unsafe impl<T: ?Sized + Sync> Sync for SSNonNull<T> {
     //SAFETY: Externally synchronised in this crate.
 }
-impl<T: ?Sized> From<&T> for SSNonNull<T> {
+impl<T: ?Sized> SSNonNull<T> {
+    /// Wraps `pointer`, keeping its provenance.
     #[inline]
-    fn from(value: &T) -> Self {
-        Self(value.into())
+    const fn new(pointer: NonNull<T>) -> Self {
+        Self(pointer)
     }
 }
-impl<T: ?Sized> From<&mut T> for SSNonNull<T> {
-    #[inline]
-    fn from(value: &mut T) -> Self {
-        Self(value.into())
+impl<T: ?Sized> SSNonNull<T> {
+    /// Replaces the pointer with a dangling one that keeps its metadata,
+    /// so that accessing the target through it faults instead of reading whatever is at its old address by then.  
+    /// Call this only while the target is still valid.
+    fn dangle(&mut self) {
+        let align = unsafe {
+            //SAFETY: Still valid.
+            mem::align_of_val(self.0.as_ref())
+        };
+        self.0 = self
+            .0
+            .with_addr(NonZeroUsize::new(align).unwrap_or(NonZeroUsize::MIN));
     }
 }
 impl<T: ?Sized> Deref for SSNonNull<T> {
@@ -53,9 +216,428 @@ impl<T: ?Sized> DerefMut for SSNonNull<T> {
     }
 }
 
+/// The address of an anchor's target, for comparisons only.  
+/// Never dereferenced, so it's threadsafe and unwind safe regardless of `T`.
+///
+/// Only replaced while re-attaching a detached anchor, when no strong portals can read it.
+#[derive(Debug)]
+#[repr(transparent)]
+struct Address<T: ?Sized>(UnsafeCell<NonNull<T>>);
+unsafe impl<T: ?Sized> Send for Address<T> {
+    //SAFETY: Never dereferenced.
+}
+unsafe impl<T: ?Sized> Sync for Address<T> {
+    //SAFETY: Never dereferenced, and only replaced while no strong portals can read it.
+}
+impl<T: ?Sized> UnwindSafe for Address<T> {}
+impl<T: ?Sized> RefUnwindSafe for Address<T> {}
+impl<T: ?Sized> Address<T> {
+    /// Wraps `pointer`.
+    #[inline]
+    const fn new(pointer: NonNull<T>) -> Self {
+        Self(UnsafeCell::new(pointer))
+    }
+
+    /// Returns the wrapped pointer.
+    #[inline]
+    fn as_ptr(&self) -> *mut T {
+        unsafe {
+            //SAFETY: Only replaced while no strong portals exist, and only these call this.
+            *self.0.get()
+        }
+        .as_ptr()
+    }
+
+    /// Replaces the wrapped pointer.
+    ///
+    /// # Safety
+    ///
+    /// Only call this while the anchor is detached, so that no strong portals exist to read it concurrently.
+    unsafe fn set(&self, pointer: NonNull<T>) {
+        *self.0.get() = pointer;
+    }
+}
+
+/// Set in `Shared::state` while the anchor exists.
+const ANCHORED: usize = !(usize::MAX >> 1);
+
+/// State shared between a threadsafe anchor and its portals.
+#[derive(Debug)]
+struct Shared<L, A = ()> {
+    /// Access to the target of the captured reference.
+    target: L,
+
+    /// The target's address, readable without locking `target`.
+    /// `()` where `target` isn't a lock.
+    address: A,
+
+    /// Identifies the anchor, also to weak portals.
+    id: AnchorId,
+
+    /// The number of strong portals, plus `ANCHORED` while the anchor exists.
+    state: AtomicUsize,
+
+    /// The maximum number of strong portals, or `usize::MAX` for no limit.
+    limit: AtomicUsize,
+
+    /// Notified whenever the last strong portal is dropped.
+    /// Shared with the `ShutdownToken`s the anchor is bound to.
+    idle: Arc<Idle>,
+
+    /// Invoked whenever the last strong portal is dropped, while the anchor exists.
+    idle_callbacks: Mutex<Callbacks<dyn FnMut() + Send>>,
+
+    /// Where the live strong portals were created.
+    traces: Traces,
+
+    /// What poisoned `target`, if it's a lock and that's known.
+    poison_origin: Origin,
+
+    /// Held while a defused guard clears the poison its release caused.
+    defusing: Mutex<()>,
+
+    /// Counts acquisitions through the portals, with the `stats` feature.
+    counters: Counters,
+
+    /// The number of readers currently blocked on `target`, for `RwPortal::write_yielding`.
+    readers_waiting: AtomicUsize,
+
+    /// The number of write guards released (and targets attached) so far, for `RwPortal::update_cloned` and `CachedPortal`.
+    writes: AtomicUsize,
+
+    /// The only thread write guards may be acquired on, if the anchor was created through `with_writer_thread`.
+    writer: OnceLock<ThreadId>,
+
+    /// Limits how often write guards are granted, if the anchor was created through `with_write_throttle`.
+    throttle: OnceLock<Throttle>,
+}
+
+/// State shared between an `RwAnchor` and its portals.
+type RwShared<T> = Shared<RwLock<SSNonNull<T>>, Address<T>>;
+
+/// State shared between a `WAnchor` and its portals.
+type WShared<T> = Shared<Mutex<SSNonNull<T>>, Address<T>>;
+
+impl<L, A> Shared<L, A> {
+    /// Creates a new anchored instance without portals.
+    fn new(target: L, address: A) -> Self {
+        Self {
+            target,
+            address,
+            id: AnchorId::next(),
+            state: AtomicUsize::new(ANCHORED),
+            limit: AtomicUsize::new(usize::MAX),
+            idle: Arc::new(Idle::new()),
+            idle_callbacks: Mutex::new(Callbacks::new()),
+            traces: Traces::default(),
+            poison_origin: Origin::default(),
+            defusing: Mutex::new(()),
+            counters: Counters::default(),
+            readers_waiting: AtomicUsize::new(0),
+            writes: AtomicUsize::new(0),
+            writer: OnceLock::new(),
+            throttle: OnceLock::new(),
+        }
+    }
+
+    /// Resets this instance to a new anchored one without portals around `target` and `address`, keeping the allocations where possible.
+    fn recycle(&mut self, target: L, address: A) {
+        if let Some(idle) = Arc::get_mut(&mut self.idle) {
+            *idle = Idle::new();
+        } else {
+            self.idle = Arc::new(Idle::new());
+        }
+        self.target = target;
+        self.address = address;
+        self.id = AnchorId::next();
+        self.state = AtomicUsize::new(ANCHORED);
+        self.limit = AtomicUsize::new(usize::MAX);
+        self.idle_callbacks = Mutex::new(Callbacks::new());
+        self.traces = Traces::default();
+        self.poison_origin = Origin::default();
+        self.defusing = Mutex::new(());
+        self.counters = Counters::default();
+        self.readers_waiting = AtomicUsize::new(0);
+        self.writes = AtomicUsize::new(0);
+        self.writer = OnceLock::new();
+        self.throttle = OnceLock::new();
+    }
+
+    /// Returns the number of strong portals.
+    #[inline]
+    fn strong_portal_count(&self) -> usize {
+        self.state.load(Ordering::Acquire) & !ANCHORED
+    }
+
+    /// Returns the maximum number of strong portals, if limited.
+    #[inline]
+    fn limit(&self) -> Option<usize> {
+        match self.limit.load(Ordering::Relaxed) {
+            usize::MAX => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Registers an additional strong portal.  
+    /// Only call this while the anchor or another strong portal exists.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor's portal limit has been reached.
+    #[inline]
+    #[track_caller]
+    fn acquire(&self) {
+        if let Err(error) = self.try_acquire_within_limit() {
+            messages::panic(
+                Report::new(Violation::PortalLimitExceeded)
+                    .with_details(&format_args!(" (limit: {})", error.limit())),
+            )
+        }
+    }
+
+    /// Registers an additional strong portal for internal use, regardless of the anchor's portal limit.  
+    /// Only call this while the anchor or another strong portal exists.
+    #[inline]
+    fn acquire_pin(&self) {
+        let state = self.state.fetch_add(1, Ordering::Relaxed);
+        self.idle.events.minted((state & !ANCHORED) + 1);
+    }
+
+    /// Registers an additional strong portal iff the anchor's portal limit hasn't been reached yet.  
+    /// Only call this while the anchor or another strong portal exists.
+    #[inline]
+    fn try_acquire_within_limit(&self) -> Result<(), PortalLimitExceeded> {
+        let limit = self.limit.load(Ordering::Relaxed);
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            // Checked before counting, so that a rejected portal never appears to exist.
+            if state & !ANCHORED >= limit {
+                return Err(PortalLimitExceeded::new(limit));
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    self.idle.events.minted((state & !ANCHORED) + 1);
+                    return Ok(());
+                }
+                Err(actual) => state = actual,
+            }
+        }
+    }
+
+    /// Registers an additional strong portal iff the anchor still exists and isn't sealed.
+    fn try_acquire(&self) -> Result<(), UpgradeError> {
+        self.check_unsealed()?;
+        self.try_acquire_sealed()
+    }
+
+    /// Registers an additional strong portal for internal use iff the anchor still exists and isn't sealed,
+    /// regardless of its portal limit.
+    fn try_pin(&self) -> Result<(), UpgradeError> {
+        self.check_unsealed()?;
+        self.try_acquire_below(usize::MAX)
+    }
+
+    /// Fails iff the anchor has been sealed, with [`UpgradeError::Dropped`] if it has been dropped since.
+    fn check_unsealed(&self) -> Result<(), UpgradeError> {
+        if self.idle.is_sealed() {
+            return Err(if self.is_anchored() {
+                UpgradeError::Sealed
+            } else {
+                UpgradeError::Dropped
+            });
+        }
+        Ok(())
+    }
+
+    /// Registers an additional strong portal iff the anchor still exists, even if it's sealed.
+    fn try_acquire_sealed(&self) -> Result<(), UpgradeError> {
+        self.try_acquire_below(self.limit.load(Ordering::Relaxed))
+    }
+
+    /// Registers an additional strong portal iff the anchor still exists and fewer than `limit` strong portals do,
+    /// even if it's sealed.
+    fn try_acquire_below(&self, limit: usize) -> Result<(), UpgradeError> {
+        let mut state = self.state.load(Ordering::Relaxed);
+        loop {
+            if state & ANCHORED == 0 {
+                return Err(UpgradeError::Dropped);
+            }
+            if state & !ANCHORED >= limit {
+                return Err(UpgradeError::LimitExceeded);
+            }
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::Acquire,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => {
+                    self.idle.events.minted((state & !ANCHORED) + 1);
+                    return Ok(());
+                }
+                Err(actual) => state = actual,
+            }
+        }
+    }
+
+    /// Unregisters a strong portal, notifying waiters and callbacks iff it was the last one.
+    #[inline]
+    fn release(&self) {
+        if self.state.fetch_sub(1, Ordering::Release) == ANCHORED | 1 {
+            self.notify_idle();
+        }
+    }
+
+    /// Invokes all idle callbacks, then wakes all `.wait_idle()` callers.  
+    /// Callbacks registered meanwhile are kept but not invoked.
+    fn notify_idle(&self) {
+        let mut callbacks = mem::take(
+            &mut *self
+                .idle_callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        callbacks.invoke_each();
+        let mut idle_callbacks = self
+            .idle_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Otherwise, the anchor has discarded them already.
+        if self.state.load(Ordering::Relaxed) & ANCHORED != 0 {
+            callbacks.append(mem::take(&mut *idle_callbacks));
+            *idle_callbacks = callbacks;
+        }
+        drop(idle_callbacks);
+
+        self.idle.events.idle();
+        self.idle.notify();
+    }
+
+    /// Blocks the current thread until no strong portals exist, or until the shutdown deadline.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn wait_idle(&self) {
+        check_blocking("wait_idle", self, true);
+        self.idle.wait_while(|| self.strong_portal_count() > 0);
+    }
+
+    /// Like `wait_idle`, but also returns once `timeout` has passed.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn wait_idle_within(&self, timeout: Duration) {
+        check_blocking("wait_idle", self, true);
+        self.idle
+            .wait_while_within(timeout, || self.strong_portal_count() > 0);
+    }
+
+    /// Starts timing and tracking a guard for `T` that was just acquired by `kind`, and counts it.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    #[allow(clippy::missing_const_for_fn)] // Not with the `events`, `watchdog` or `stats` feature.
+    fn held<T: ?Sized>(&self, kind: &'static str, exclusive: bool) -> Held {
+        self.counters.acquired(exclusive);
+        self.idle.events.acquired(kind, exclusive);
+        Held::start::<T>(kind).tracked(self, exclusive)
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    #[inline]
+    fn is_anchored(&self) -> bool {
+        self.state.load(Ordering::Acquire) & ANCHORED != 0
+    }
+
+    /// Waits until no defused guard is clearing poison.  
+    /// Call this before treating `target` as poisoned, after releasing any guard to it.
+    fn settle(&self) {
+        drop(self.defusing.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    /// Panics with `ANCHOR_POISONED`, followed by `error` and the poison origin if known.
+    #[cold]
+    fn poisoned_panic(&self, error: &dyn Debug) -> ! {
+        messages::panic(
+            Report::new(Violation::AnchorPoisoned)
+                .with_details(&format_args!(": {:?}{}", error, self.poison_origin)),
+        )
+    }
+
+    /// Marks the anchor as dropped, discards idle callbacks and closes event subscriptions.  
+    /// Returns the number of remaining strong portals.
+    fn detach(&self) -> usize {
+        let count = self.state.fetch_and(!ANCHORED, Ordering::AcqRel) & !ANCHORED;
+        drop(mem::take(
+            &mut *self
+                .idle_callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        ));
+        self.idle.events.dropped();
+        count
+    }
+}
+
+impl<T: ?Sized> RwShared<T> {
+    /// Panics iff the anchor has a writer thread other than the current one, naming `operation`.  
+    /// Call this before locking `target` for writing, so that the panic doesn't poison it.
+    #[inline]
+    #[track_caller]
+    fn check_writer(&self, operation: &'static str) {
+        if let Some(&writer) = self.writer.get() {
+            let current = thread::current();
+            if current.id() != writer {
+                messages::panic(
+                    Report::new(Violation::ForeignWriter).with_details(&format_args!(
+                        ": {} on thread {:?} ({:?}), but the writer thread is {:?}",
+                        operation,
+                        current.name().unwrap_or("<unnamed>"),
+                        current.id(),
+                        writer,
+                    )),
+                )
+            }
+        }
+    }
+
+    /// Locks `target` for reading, counting as waiting reader while that blocks.
+    fn read_waiting(&self) -> LockResult<RwLockReadGuard<'_, SSNonNull<T>>> {
+        match self.target.try_read() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(error)) => Err(error),
+            Err(TryLockError::WouldBlock) => {
+                self.counters.contended();
+                self.readers_waiting.fetch_add(1, Ordering::Relaxed);
+                let result = self.target.read();
+                self.readers_waiting.fetch_sub(1, Ordering::Relaxed);
+                result
+            }
+        }
+    }
+
+    /// Locks `target` for reading.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    fn read(&self) -> RwLockReadGuard<'_, SSNonNull<T>> {
+        self.read_waiting()
+            .or_else(|error| {
+                drop(error);
+                self.settle();
+                self.target.read()
+            })
+            .unwrap_or_else(|error| self.poisoned_panic(&error))
+    }
+}
+
 /// A threadsafe immutable anchor with concurrent read access.  
 /// Use this to capture immutable references in a threaded environment.
 ///
+/// Portals are only `Send` for `Sync` targets, since the borrowing thread can keep using its reference alongside them.
+/// To hand a `!Sync` target between threads, capture a mutable reference in a [`WAnchor`] instead.
+///
 /// # Panics
 ///
 /// On drop, if any associated `Portal`s exist:
@@ -70,15 +652,33 @@ impl<T: ?Sized> DerefMut for SSNonNull<T> {
 ///
 /// assert_panic!(
 ///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
 /// );
 /// ```
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct Anchor<'a, T: ?Sized> {
     /// Internal pointer to the target of the captured reference.
-    reference: ManuallyDrop<Arc<SSNonNull<T>>>,
+    reference: ManuallyDrop<Arc<Shared<SSNonNull<T>>>>,
+
+    /// Invoked once the reference has been released.
+    drop_callbacks: Mutex<Callbacks<dyn FnOnce() + Send>>,
+
+    /// Join threads bound through `.bind_join(…)`, before the reference is released.
+    bound_threads: Mutex<Callbacks<dyn FnOnce() + Send>>,
+
+    /// Anchors registered through their `.depends_on(…)`, which must be dropped before this one.
+    dependents: Dependents,
+
+    /// Handed out through `.lease(…)`, revoked before the reference is released.
+    leases: Leases,
+
+    /// Identifies this anchor in panic messages and diagnostics.
+    label: Option<&'static str>,
+
+    /// Lists this anchor while it exists.
+    #[cfg(feature = "diagnostics")]
+    registration: Registration,
 
     /// Act as sharing borrower.
     _phantom: PhantomData<&'a T>,
@@ -101,8 +701,8 @@ pub struct Anchor<'a, T: ?Sized> {
 ///
 /// assert_panic!(
 ///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
 /// );
 /// ```
 ///
@@ -125,14 +725,29 @@ pub struct Anchor<'a, T: ?Sized> {
 /// assert_panic!(
 ///     drop(anchor),
 ///     String,
-///     starts with "Anchor poisoned:",
+///     starts with "Anchor poisoned [anchored type: `alloc::string::String`",
 /// );
 /// ```
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct RwAnchor<'a, T: ?Sized> {
     /// Internal pointer to the target of the captured reference.
-    reference: ManuallyDrop<Arc<RwLock<SSNonNull<T>>>>,
+    reference: ManuallyDrop<Arc<RwShared<T>>>,
+
+    /// Invoked once the reference has been released.
+    drop_callbacks: Mutex<Callbacks<dyn FnOnce() + Send>>,
+
+    /// Join threads bound through `.bind_join(…)`, before the reference is released.
+    bound_threads: Mutex<Callbacks<dyn FnOnce() + Send>>,
+
+    /// Anchors registered through their `.depends_on(…)`, which must be dropped before this one.
+    dependents: Dependents,
+
+    /// Identifies this anchor in panic messages and diagnostics.
+    label: Option<&'static str>,
+
+    /// Lists this anchor while it exists.
+    #[cfg(feature = "diagnostics")]
+    registration: Registration,
 
     /// Act as exclusive borrower.
     _phantom: PhantomData<&'a mut T>,
@@ -155,8 +770,8 @@ pub struct RwAnchor<'a, T: ?Sized> {
 ///
 /// assert_panic!(
 ///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
 /// );
 /// ```
 ///
@@ -179,14 +794,29 @@ pub struct RwAnchor<'a, T: ?Sized> {
 /// assert_panic!(
 ///     drop(anchor),
 ///     String,
-///     starts with "Anchor poisoned:",
+///     starts with "Anchor poisoned [anchored type: `alloc::string::String`",
 /// );
 /// ```
 #[derive(Debug)]
-#[repr(transparent)]
 pub struct WAnchor<'a, T: ?Sized> {
     /// Internal pointer to the target of the captured reference.
-    reference: ManuallyDrop<Arc<Mutex<SSNonNull<T>>>>,
+    reference: ManuallyDrop<Arc<WShared<T>>>,
+
+    /// Invoked once the reference has been released.
+    drop_callbacks: Mutex<Callbacks<dyn FnOnce() + Send>>,
+
+    /// Join threads bound through `.bind_join(…)`, before the reference is released.
+    bound_threads: Mutex<Callbacks<dyn FnOnce() + Send>>,
+
+    /// Anchors registered through their `.depends_on(…)`, which must be dropped before this one.
+    dependents: Dependents,
+
+    /// Identifies this anchor in panic messages and diagnostics.
+    label: Option<&'static str>,
+
+    /// Lists this anchor while it exists.
+    #[cfg(feature = "diagnostics")]
+    registration: Registration,
 
     /// Act as exclusive borrower.
     _phantom: PhantomData<&'a mut T>,
@@ -194,467 +824,3997 @@ pub struct WAnchor<'a, T: ?Sized> {
 
 impl<'a, T: ?Sized> Anchor<'a, T> {
     #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
     pub fn new(reference: &'a T) -> Self {
-        Self {
-            reference: ManuallyDrop::new(Arc::new(reference.into())),
-            _phantom: PhantomData,
-        }
-    }
-
-    #[inline]
-    pub fn portal(&self) -> Portal<T> {
-        self.reference.pipe_deref(Arc::clone).pipe(Portal)
-    }
-
-    #[inline]
-    pub fn weak_portal(&self) -> WeakPortal<T> {
-        Portal::downgrade(&self.portal())
-    }
-}
-
-impl<'a, T: ?Sized> RwAnchor<'a, T> {
-    #[inline]
-    pub fn new(reference: &'a mut T) -> Self {
-        Self {
-            reference: ManuallyDrop::new(Arc::new(RwLock::new(reference.into()))),
-            _phantom: PhantomData,
+        unsafe {
+            //SAFETY: Derived from a reference that is valid and shared for `'a`.
+            Self::from_raw(NonNull::from(reference))
         }
     }
 
+    /// Creates a new `Anchor` instance from a raw pointer, e.g. one received through FFI
+    /// with an externally guaranteed lifetime.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` must be valid for reads for `'a`,
+    /// and its target must not be mutated (except through interior mutability) while this anchor or any of its portals exist.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::Anchor, std::ptr::NonNull};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let pointer = NonNull::from(&x); // E.g. received through FFI.
+    /// let anchor = unsafe { Anchor::from_raw(pointer) };
+    /// let portal = anchor.portal();
+    ///
+    /// assert_eq!(*portal, "Scoped");
+    /// ```
     #[inline]
-    pub fn portal(&self) -> RwPortal<T> {
-        self.reference.pipe_deref(Arc::clone).pipe(RwPortal)
-    }
-
-    #[inline]
-    pub fn weak_portal(&self) -> WeakRwPortal<T> {
-        self.portal().downgrade()
-    }
-}
-
-impl<'a, T: ?Sized> WAnchor<'a, T> {
-    #[inline]
-    pub fn new(reference: &'a mut T) -> Self {
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn from_raw(pointer: NonNull<T>) -> Self {
+        let reference = freelist::allocate(Shared::new(SSNonNull::new(pointer), ()));
         Self {
-            reference: ManuallyDrop::new(Arc::new(Mutex::new(reference.into()))),
+            #[cfg(feature = "diagnostics")]
+            registration: Registration::sync::<T, _>("sync::Anchor", &reference),
+            reference: ManuallyDrop::new(reference),
+            label: None,
+            drop_callbacks: Mutex::new(Callbacks::new()),
+            bound_threads: Mutex::new(Callbacks::new()),
+            dependents: Dependents::new(),
+            leases: Leases::new(),
             _phantom: PhantomData,
         }
     }
 
-    #[inline]
-    pub fn portal(&self) -> WPortal<T> {
-        self.reference.pipe_deref(Arc::clone).pipe(WPortal)
+    /// Creates a new `Anchor` instance, capturing `reference` and sealing it at `deadline`,
+    /// after which its weak portals can't be upgraded anymore, like after a [`ShutdownToken`] signalled shutdown.  
+    /// Existing portals stay valid, and the anchor itself can still create new ones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::Anchor,
+    ///     std::{
+    ///         thread,
+    ///         time::{Duration, Instant},
+    ///     },
+    /// };
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::with_deadline(&x, Instant::now() + Duration::from_millis(100));
+    /// let weak_portal = anchor.weak_portal();
+    /// assert!(weak_portal.try_upgrade().is_some());
+    /// assert!(anchor.remaining().unwrap() <= Duration::from_millis(100));
+    ///
+    /// thread::sleep(Duration::from_millis(150));
+    /// assert!(weak_portal.try_upgrade().is_none());
+    /// assert_eq!(anchor.remaining(), Some(Duration::from_secs(0)));
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_deadline(reference: &'a T, deadline: Instant) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.expire_at(deadline);
+        anchor
     }
 
-    #[inline]
-    pub fn weak_portal(&self) -> WeakWPortal<T> {
-        self.portal().downgrade()
+    /// Creates a new `Anchor` instance like [`with_deadline`](#method.with_deadline), but measuring time with `clock`,
+    /// e.g. to drive the deadline with virtual time in a simulation.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{Anchor, Clock},
+    ///     std::{
+    ///         sync::{Arc, Mutex},
+    ///         time::{Duration, Instant},
+    ///     },
+    /// };
+    ///
+    /// #[derive(Debug)]
+    /// struct VirtualClock(Mutex<Instant>);
+    ///
+    /// impl Clock for VirtualClock {
+    ///     fn now(&self) -> Instant {
+    ///         *self.0.lock().unwrap()
+    ///     }
+    /// }
+    ///
+    /// let start = Instant::now();
+    /// let clock = Arc::new(VirtualClock(Mutex::new(start)));
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::with_deadline_on(&x, start + Duration::from_secs(60), clock.clone());
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(anchor.remaining(), Some(Duration::from_secs(60)));
+    ///
+    /// *clock.0.lock().unwrap() += Duration::from_secs(60);
+    /// assert!(weak_portal.try_upgrade().is_none());
+    /// assert_eq!(anchor.remaining(), Some(Duration::from_secs(0)));
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_deadline_on(reference: &'a T, deadline: Instant, clock: Arc<dyn Clock>) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.use_clock(clock);
+        anchor.reference.idle.expire_at(deadline);
+        anchor
     }
-}
 
-impl<'a, T: ?Sized> Drop for Anchor<'a, T> {
-    /// Executes the destructor for this type. [Read more](https://doc.rust-lang.org/nightly/core/ops/drop/trait.Drop.html#tymethod.drop)
+    /// Creates a new `Anchor` instance, capturing `reference` and allowing at most `limit` (strong) portals at a time.  
+    /// Creating a portal beyond that fails through [`try_portal`](#method.try_portal) and upgrades, and panics otherwise.
+    /// This catches portal leaks, e.g. cloning in a loop, before the anchor is dropped.
     ///
-    /// # Panics
+    /// Portals that this crate creates internally, e.g. for [`WeakPortal::read_with`], [`Portal::map`] or the portal
+    /// owned by an [`ArcReadGuard`], still count as strong portals but aren't limited.
     ///
-    /// If any associated `Portal`s exist:
+    /// # Example
     ///
     /// ```rust
     /// # use assert_panic::assert_panic;
     /// use ref_portals::sync::Anchor;
     ///
     /// let x = "Scoped".to_owned();
-    /// let anchor = Anchor::new(&x);
-    /// let portal = anchor.portal();
+    /// let anchor = Anchor::with_limit(&x, 2);
+    /// let weak_portal = anchor.weak_portal();
+    /// let portals = vec![anchor.portal(), anchor.portal()];
     ///
+    /// assert_eq!(anchor.try_portal().unwrap_err().limit(), 2);
+    /// assert!(weak_portal.try_upgrade().is_none());
     /// assert_panic!(
-    ///     drop(anchor),
-    ///     &str,
-    ///     "Anchor still in use (at least one portal exists)",
+    ///     { portals[0].clone(); },
+    ///     String,
+    ///     "Portal limit exceeded (limit: 2)",
     /// );
+    ///
+    /// drop(portals);
+    /// assert!(anchor.try_portal().is_ok());
     /// ```
-    fn drop(&mut self) {
-        unsafe {
-            //SAFETY: Dropping.
-            ManuallyDrop::take(&mut self.reference)
-        }
-        .pipe(Arc::try_unwrap)
-        .unwrap_or_else(|_| panic!(ANCHOR_STILL_IN_USE));
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_limit(reference: &'a T, limit: usize) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.limit.store(limit, Ordering::Relaxed);
+        anchor
     }
-}
 
-impl<'a, T: ?Sized> Drop for RwAnchor<'a, T> {
-    /// Executes the destructor for this type. [Read more](https://doc.rust-lang.org/nightly/core/ops/drop/trait.Drop.html#tymethod.drop)
-    ///
-    /// # Panics
+    /// Creates a new `Anchor` instance, capturing `reference` and labelling the anchor as `label`.  
+    /// The label appears in this anchor's panic messages and diagnostics, to tell apart anchors of the same type.
     ///
-    /// If any associated `RwPortal`s exist or, otherwise, iff the anchor has been poisoned:
+    /// # Example
     ///
     /// ```rust
     /// # use assert_panic::assert_panic;
-    /// use ref_portals::sync::RwAnchor;
+    /// use ref_portals::sync::Anchor;
     ///
-    /// let mut x = "Scoped".to_owned();
-    /// let anchor = RwAnchor::new(&mut x);
-    /// let portal = anchor.portal();
-    /// assert_panic!({
-    ///     // Poison anchor.
-    ///     let _guard = portal.write();
-    ///     panic!()
-    /// });
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::named("greeting", &x);
+    /// assert_eq!(anchor.label(), Some("greeting"));
     ///
+    /// Box::leak(Box::new(anchor.portal()));
     /// assert_panic!(
     ///     drop(anchor),
-    ///     &str,
-    ///     "Anchor still in use (at least one portal exists)",
+    ///     String,
+    ///     starts with "Anchor still in use (at least one portal exists) [label: \"greeting\", ",
     /// );
     /// ```
-    fn drop(&mut self) {
-        unsafe {
-            //SAFETY: Dropping.
-            ManuallyDrop::take(&mut self.reference)
-        }
-        .pipe(Arc::try_unwrap)
-        .unwrap_or_else(|reference| {
-            // Poison RwLock.
-            let _guard = reference.write();
-            panic!(ANCHOR_STILL_IN_USE);
-        })
-        .into_inner()
-        .unwrap_or_else(|error| Err(error).expect(ANCHOR_POISONED));
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn named(label: &'static str, reference: &'a T) -> Self {
+        let mut anchor = Self::new(reference);
+        anchor.label = Some(label);
+        #[cfg(feature = "diagnostics")]
+        anchor.registration.set_label(label);
+        anchor
     }
-}
 
-impl<'a, T: ?Sized> Drop for WAnchor<'a, T> {
-    /// Executes the destructor for this type. [Read more](https://doc.rust-lang.org/nightly/core/ops/drop/trait.Drop.html#tymethod.drop)
-    ///
-    /// # Panics
-    ///
-    /// If any associated `WPortal`s exist or, otherwise, iff the anchor has been poisoned:
-    ///
+    /// Returns this anchor's label, if it was created through `named`.
+    #[inline]
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Returns this anchor's unique ID, which its portals and weak portals report as `anchor_id`, even after it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        self.reference.id
+    }
+
+    /// Returns the time left until this anchor seals itself, if it was created through `with_deadline`.
+    #[inline]
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.reference.idle.remaining()
+    }
+
+    /// Forgets this anchor without checking for remaining portals, so that they stay valid indefinitely.  
+    /// Drop callbacks are not invoked. With the `diagnostics` feature, the anchor stays listed and is marked as leaked.
+    ///
+    /// # Safety
+    ///
+    /// The captured reference must stay valid for as long as any portal may still access it,
+    /// i.e. the target must actually be `'static` or never be freed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::Anchor;
+    ///
+    /// let x: &'static String = Box::leak(Box::new("Eternal".to_owned()));
+    /// let anchor = Anchor::new(x);
+    /// let portal = anchor.portal();
+    /// unsafe {
+    ///     // The target is never freed.
+    ///     anchor.leak();
+    /// }
+    ///
+    /// assert_eq!(*portal, "Eternal");
+    /// ```
+    #[allow(clippy::missing_const_for_fn)] // Not with the `diagnostics` feature.
+    pub unsafe fn leak(self) {
+        #[cfg(feature = "diagnostics")]
+        self.registration.mark_leaked();
+        mem::forget(self);
+    }
+
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    ///
+    /// # Panics
+    ///
+    /// Iff this anchor was created through `with_limit` and that many portals exist already.
+    #[inline]
+    #[track_caller]
+    pub fn portal(&self) -> Portal<T> {
+        self.reference.acquire();
+        self.reference.pipe_deref(Arc::clone).pipe(Portal::new)
+    }
+
+    /// Creates a portal iff fewer (strong) portals than this anchor's limit exist.
+    ///
+    /// # Errors
+    ///
+    /// Iff this anchor was created through `with_limit` and that many portals exist already.
+    #[inline]
+    pub fn try_portal(&self) -> Result<Portal<T>, PortalLimitExceeded> {
+        self.reference.try_acquire_within_limit()?;
+        Ok(self.reference.pipe_deref(Arc::clone).pipe(Portal::new))
+    }
+
+    #[inline]
+    pub fn weak_portal(&self) -> WeakPortal<T> {
+        WeakPortal(Arc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Creates `n` weak portals associated with this anchor at once, e.g. to hand one to each of several observers.
+    #[inline]
+    pub fn weak_portals(&self, n: usize) -> Vec<WeakPortal<T>> {
+        iter::repeat_with(|| self.weak_portal()).take(n).collect()
+    }
+
+    /// Creates a `'static` closure that upgrades a weak portal associated with this anchor and calls `body` with the target and its argument.  
+    /// Use a tuple as `A` to pass several arguments.
+    ///
+    /// # Panics
+    ///
+    /// The returned closure panics iff the anchor has been dropped.
+    #[inline]
+    pub fn portal_fn<A, R>(&self, body: impl Fn(&T, A) -> R + 'static) -> impl Fn(A) -> R + 'static
+    where
+        T: 'static,
+    {
+        let weak_portal = self.weak_portal();
+        move |argument| body(&*weak_portal.upgrade(), argument)
+    }
+
+    /// Returns the number of (strong) portals currently associated with this anchor.
+    #[inline]
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        self.reference.strong_portal_count()
+    }
+
+    /// Returns the maximum number of (strong) portals, if this anchor was created through `with_limit`.
+    #[inline]
+    #[must_use]
+    pub fn portal_limit(&self) -> Option<usize> {
+        self.reference.limit()
+    }
+
+    /// Blocks the current thread until no (strong) portals associated with this anchor exist.  
+    /// Dropping the anchor afterwards won't panic unless another portal was created meanwhile.  
+    /// If the anchor is bound to a [`ShutdownToken`] that signalled shutdown with a deadline, this returns at that deadline regardless.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::Anchor,
+    ///     std::{thread, time::Duration},
+    /// };
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     println!("{}", *portal);
+    /// });
+    ///
+    /// anchor.wait_idle();
+    /// assert_eq!(anchor.strong_portal_count(), 0);
+    /// drop(anchor); // Doesn't panic.
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn wait_idle(&self) {
+        self.reference.wait_idle();
+    }
+
+    /// Binds this anchor to `token`, so that signalling shutdown seals it against weak portal upgrades
+    /// and limits [`wait_idle`](#method.wait_idle) to the shutdown deadline.
+    pub fn bind_shutdown(&self, token: &ShutdownToken) {
+        token.bind(&self.reference.idle);
+    }
+
+    /// Registers `callback` to be invoked whenever the last (strong) portal associated with this anchor is dropped.  
+    /// Callbacks run on the thread that dropped the portal and are discarded when the anchor is dropped.
+    pub fn on_idle(&self, callback: impl FnMut() + Send + 'static) {
+        self.reference
+            .idle_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Registers `callback` to be invoked when this anchor is dropped.  
+    /// Callbacks run in registration order on the dropping thread once the anchor has released its reference,
+    /// before any panic caused by the drop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::Anchor,
+    ///     std::sync::{
+    ///         atomic::{AtomicBool, Ordering},
+    ///         Arc,
+    ///     },
+    /// };
+    ///
+    /// let dropped = Arc::new(AtomicBool::new(false));
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// anchor.on_drop({
+    ///     let dropped = Arc::clone(&dropped);
+    ///     move || dropped.store(true, Ordering::SeqCst)
+    /// });
+    ///
+    /// drop(anchor);
+    /// assert!(dropped.load(Ordering::SeqCst));
+    /// ```
+    pub fn on_drop(&self, callback: impl FnOnce() + Send + 'static) {
+        self.drop_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Binds the thread behind `handle` to this anchor, so that dropping the anchor first joins it.  
+    /// Any portals that thread holds are then gone before the anchor checks for remaining ones.
+    /// The thread's result, including a panic, is discarded.
+    ///
+    /// Dropping the anchor on a bound thread deadlocks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::Anchor,
+    ///     std::{thread, time::Duration},
+    /// };
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    /// anchor.bind_join(thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     println!("{}", *portal);
+    /// }));
+    ///
+    /// drop(anchor); // Joins the thread first, so this doesn't panic.
+    /// ```
+    pub fn bind_join<R: Send + 'static>(&self, handle: JoinHandle<R>) {
+        self.bound_threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(move || drop(handle.join())));
+    }
+}
+
+impl<'a, T> Anchor<'a, [T]> {
+    /// Creates a new `Anchor` instance, capturing `reference` as slice.  
+    /// Unlike with `new`, a reference to e.g. a `Vec` is unsized here, so the target type doesn't need to be spelled out.
+    ///
+    /// Trait objects can be anchored through `new` by naming the target type instead,
+    /// as in `Anchor::<dyn Trait>::new(&value)`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{Anchor, Portal};
+    ///
+    /// let x = vec![1, 2, 3];
+    /// let anchor = Anchor::new_slice(&x);
+    /// let portal: Portal<[i32]> = anchor.portal();
+    ///
+    /// assert_eq!(portal.len(), 3);
+    /// # drop(portal);
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_slice(reference: &'a [T]) -> Self {
+        Self::new(reference)
+    }
+}
+
+impl<'a> Anchor<'a, str> {
+    /// Creates a new `Anchor` instance, capturing `reference` as string slice.  
+    /// Unlike with `new`, a reference to e.g. a `String` is unsized here, so the target type doesn't need to be spelled out.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_str(reference: &'a str) -> Self {
+        Self::new(reference)
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    #[inline]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(reference: &'a mut T) -> Self {
+        unsafe {
+            //SAFETY: Derived from a reference that is valid and exclusive for `'a`.
+            Self::from_raw(NonNull::from(reference))
+        }
+    }
+
+    /// Creates a new `RwAnchor` instance from a raw pointer, e.g. one received through FFI
+    /// with an externally guaranteed lifetime.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` must be valid for reads and writes for `'a`,
+    /// and its target must not be accessed other than through this anchor while it or any of its portals exist.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn from_raw(pointer: NonNull<T>) -> Self {
+        Self::from_shared(freelist::allocate(Shared::new(
+            RwLock::new(SSNonNull::new(pointer)),
+            Address::new(pointer),
+        )))
+    }
+
+    /// Creates a new `RwAnchor` instance around `reference`, which must be anchored and not shared yet.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    fn from_shared(reference: Arc<RwShared<T>>) -> Self {
+        Self {
+            #[cfg(feature = "diagnostics")]
+            registration: Registration::sync::<T, _>("sync::RwAnchor", &reference),
+            reference: ManuallyDrop::new(reference),
+            label: None,
+            drop_callbacks: Mutex::new(Callbacks::new()),
+            bound_threads: Mutex::new(Callbacks::new()),
+            dependents: Dependents::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new `RwAnchor` instance, capturing `reference` and sealing it at `deadline`,
+    /// after which its weak portals can't be upgraded anymore, like after a [`ShutdownToken`] signalled shutdown.  
+    /// Existing portals stay valid, and the anchor itself can still create new ones.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_deadline(reference: &'a mut T, deadline: Instant) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.expire_at(deadline);
+        anchor
+    }
+
+    /// Creates a new `RwAnchor` instance like [`with_deadline`](#method.with_deadline), but measuring time with `clock`.
+    /// See [`Anchor::with_deadline_on`].
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_deadline_on(
+        reference: &'a mut T,
+        deadline: Instant,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.use_clock(clock);
+        anchor.reference.idle.expire_at(deadline);
+        anchor
+    }
+
+    /// Creates a new `RwAnchor` instance, capturing `reference` and allowing at most `limit` (strong) portals at a time.
+    /// See [`Anchor::with_limit`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = vec!["Scoped".to_owned()];
+    /// let anchor = RwAnchor::with_limit(&mut x, 1);
+    /// let portal = anchor.portal();
+    ///
+    /// portal.write_arc().push("Owned".to_owned());
+    /// assert_eq!(portal.read_arc().len(), 2);
+    /// assert!(anchor.try_portal().is_err());
+    /// # drop(portal);
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_limit(reference: &'a mut T, limit: usize) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.limit.store(limit, Ordering::Relaxed);
+        anchor
+    }
+
+    /// Creates a new `RwAnchor` instance, capturing `reference` and permitting write guards only on the current thread.  
+    /// Read guards can still be acquired on any thread, which suits designs with one writer and many reader threads.
+    ///
+    /// The writer thread stays the same if the anchor is moved to another thread.
+    ///
+    /// # Panics
+    ///
+    /// Acquiring a write guard through any of the anchor's portals on another thread panics, before locking the target.
+    /// This includes `try_write`, which doesn't return `None` in this case.
+    ///
+    /// # Example
+    ///
     /// ```rust
     /// # use assert_panic::assert_panic;
-    /// use ref_portals::sync::WAnchor;
+    /// use {ref_portals::sync::RwAnchor, std::thread};
+    ///
+    /// let mut x = 1;
+    /// let anchor = RwAnchor::with_writer_thread(&mut x);
+    /// let portal = anchor.portal();
+    /// *portal.write() += 1;
+    ///
+    /// thread::spawn({
+    ///     let portal = portal.clone();
+    ///     move || {
+    ///         assert_eq!(*portal.read(), 2);
+    ///         assert_panic!(
+    ///             { portal.write(); },
+    ///             String,
+    ///             starts with "Write access outside the anchor's writer thread: sync::RwPortal::write on thread",
+    ///         );
+    ///     }
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// // Not poisoned.
+    /// assert_eq!(*portal.read(), 2);
+    /// # drop(portal);
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_writer_thread(reference: &'a mut T) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.writer.set(thread::current().id()).ok();
+        anchor
+    }
+
+    /// Creates a new `RwAnchor` instance, capturing `reference` and labelling the anchor as `label`.  
+    /// The label appears in this anchor's panic messages and diagnostics, to tell apart anchors of the same type.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn named(label: &'static str, reference: &'a mut T) -> Self {
+        let mut anchor = Self::new(reference);
+        anchor.label = Some(label);
+        #[cfg(feature = "diagnostics")]
+        anchor.registration.set_label(label);
+        anchor
+    }
+
+    /// Returns this anchor's label, if it was created through `named`.
+    #[inline]
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Returns this anchor's unique ID, which its portals and weak portals report as `anchor_id`, even after it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        self.reference.id
+    }
+
+    /// Returns the time left until this anchor seals itself, if it was created through `with_deadline`.
+    #[inline]
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.reference.idle.remaining()
+    }
+
+    /// Returns the only thread write guards may be acquired on, if this anchor was created through `with_writer_thread`.
+    #[inline]
+    #[must_use]
+    pub fn writer_thread(&self) -> Option<ThreadId> {
+        self.reference.writer.get().copied()
+    }
+
+    /// Forgets this anchor without checking for remaining portals, so that they stay valid indefinitely.  
+    /// Drop callbacks are not invoked. With the `diagnostics` feature, the anchor stays listed and is marked as leaked.
+    ///
+    /// # Safety
+    ///
+    /// The captured reference must stay valid for as long as any portal may still access it,
+    /// i.e. the target must actually be `'static` or never be freed or otherwise accessed.
+    #[allow(clippy::missing_const_for_fn)] // Not with the `diagnostics` feature.
+    pub unsafe fn leak(self) {
+        #[cfg(feature = "diagnostics")]
+        self.registration.mark_leaked();
+        mem::forget(self);
+    }
+
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    ///
+    /// # Panics
+    ///
+    /// Iff this anchor was created through `with_limit` and that many portals exist already.
+    #[inline]
+    #[track_caller]
+    pub fn portal(&self) -> RwPortal<T> {
+        self.reference.acquire();
+        self.reference.pipe_deref(Arc::clone).pipe(RwPortal::new)
+    }
+
+    /// Creates a portal iff fewer (strong) portals than this anchor's limit exist.
+    ///
+    /// # Errors
+    ///
+    /// Iff this anchor was created through `with_limit` and that many portals exist already.
+    #[inline]
+    pub fn try_portal(&self) -> Result<RwPortal<T>, PortalLimitExceeded> {
+        self.reference.try_acquire_within_limit()?;
+        Ok(self.reference.pipe_deref(Arc::clone).pipe(RwPortal::new))
+    }
+
+    #[inline]
+    pub fn weak_portal(&self) -> WeakRwPortal<T> {
+        WeakRwPortal(Arc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Creates a `'static` closure that upgrades a weak portal associated with this anchor and calls `body` with the target and its argument.  
+    /// Use a tuple as `A` to pass several arguments.
+    ///
+    /// # Panics
+    ///
+    /// The returned closure panics if the anchor has been dropped or poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::RwAnchor, std::thread};
     ///
     /// let mut x = "Scoped".to_owned();
-    /// let anchor = WAnchor::new(&mut x);
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let push = anchor.portal_fn(|x, c| x.push(c));
+    /// thread::spawn(move || push('!')).join().unwrap();
+    /// drop(anchor);
+    /// assert_eq!(x, "Scoped!");
+    /// ```
+    #[inline]
+    pub fn portal_fn<A, R>(
+        &self,
+        body: impl Fn(&mut T, A) -> R + 'static,
+    ) -> impl Fn(A) -> R + 'static
+    where
+        T: 'static,
+    {
+        let weak_portal = self.weak_portal();
+        move |argument| body(&mut *weak_portal.upgrade().write(), argument)
+    }
+
+    /// Returns the number of (strong) portals currently associated with this anchor.
+    #[inline]
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        self.reference.strong_portal_count()
+    }
+
+    /// Returns the maximum number of (strong) portals, if this anchor was created through `with_limit`.
+    #[inline]
+    #[must_use]
+    pub fn portal_limit(&self) -> Option<usize> {
+        self.reference.limit()
+    }
+
+    /// Blocks the current thread until no (strong) portals associated with this anchor exist.  
+    /// Dropping the anchor afterwards won't panic unless another portal was created meanwhile.  
+    /// If the anchor is bound to a [`ShutdownToken`] that signalled shutdown with a deadline, this returns at that deadline regardless.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn wait_idle(&self) {
+        self.reference.wait_idle();
+    }
+
+    /// Binds this anchor to `token`, so that signalling shutdown seals it against weak portal upgrades
+    /// and limits [`wait_idle`](#method.wait_idle) to the shutdown deadline.
+    pub fn bind_shutdown(&self, token: &ShutdownToken) {
+        token.bind(&self.reference.idle);
+    }
+
+    /// Registers `callback` to be invoked whenever the last (strong) portal associated with this anchor is dropped.  
+    /// Callbacks run on the thread that dropped the portal and are discarded when the anchor is dropped.
+    pub fn on_idle(&self, callback: impl FnMut() + Send + 'static) {
+        self.reference
+            .idle_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Registers `callback` to be invoked when this anchor is dropped.  
+    /// Callbacks run in registration order on the dropping thread once the anchor has released its reference,
+    /// before any panic caused by the drop.
+    ///
+    /// Portals that are still around by then are locked out, so callbacks don't race them:
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use {
+    ///     ref_portals::sync::RwAnchor,
+    ///     std::{
+    ///         panic::{self, AssertUnwindSafe},
+    ///         sync::{
+    ///             atomic::{AtomicBool, Ordering},
+    ///             Arc,
+    ///         },
+    ///     },
+    /// };
+    ///
+    /// let locked_out = Arc::new(AtomicBool::new(false));
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
     /// let portal = anchor.portal();
-    /// assert_panic!({
-    ///     // Poison anchor.
-    ///     let _guard = portal.lock();
-    ///     panic!()
+    /// anchor.on_drop({
+    ///     let locked_out = Arc::clone(&locked_out);
+    ///     move || {
+    ///         let read = panic::catch_unwind(AssertUnwindSafe(|| drop(portal.read())));
+    ///         locked_out.store(read.is_err(), Ordering::SeqCst);
+    ///     }
     /// });
     ///
     /// assert_panic!(
     ///     drop(anchor),
-    ///     &str,
-    ///     "Anchor still in use (at least one portal exists)",
+    ///     String,
+    ///     starts with "Anchor still in use (at least one portal exists)",
     /// );
+    /// assert!(locked_out.load(Ordering::SeqCst));
     /// ```
-    fn drop(&mut self) {
+    pub fn on_drop(&self, callback: impl FnOnce() + Send + 'static) {
+        self.drop_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Binds the thread behind `handle` to this anchor, so that dropping the anchor first joins it.  
+    /// Any portals that thread holds are then gone before the anchor checks for remaining ones.
+    /// The thread's result, including a panic, is discarded.
+    ///
+    /// Dropping the anchor on a bound thread deadlocks.
+    pub fn bind_join<R: Send + 'static>(&self, handle: JoinHandle<R>) {
+        self.bound_threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(move || drop(handle.join())));
+    }
+}
+
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    #[inline]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(reference: &'a mut T) -> Self {
         unsafe {
-            //SAFETY: Dropping.
-            ManuallyDrop::take(&mut self.reference)
+            //SAFETY: Derived from a reference that is valid and exclusive for `'a`.
+            Self::from_raw(NonNull::from(reference))
+        }
+    }
+
+    /// Creates a new `WAnchor` instance from a raw pointer, e.g. one received through FFI
+    /// with an externally guaranteed lifetime.
+    ///
+    /// # Safety
+    ///
+    /// `pointer` must be valid for reads and writes for `'a`,
+    /// and its target must not be accessed other than through this anchor while it or any of its portals exist.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub unsafe fn from_raw(pointer: NonNull<T>) -> Self {
+        let reference = freelist::allocate(Shared::new(
+            Mutex::new(SSNonNull::new(pointer)),
+            Address::new(pointer),
+        ));
+        Self {
+            #[cfg(feature = "diagnostics")]
+            registration: Registration::sync::<T, _>("sync::WAnchor", &reference),
+            reference: ManuallyDrop::new(reference),
+            label: None,
+            drop_callbacks: Mutex::new(Callbacks::new()),
+            bound_threads: Mutex::new(Callbacks::new()),
+            dependents: Dependents::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new `WAnchor` instance, capturing `reference` and sealing it at `deadline`,
+    /// after which its weak portals can't be upgraded anymore, like after a [`ShutdownToken`] signalled shutdown.  
+    /// Existing portals stay valid, and the anchor itself can still create new ones.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_deadline(reference: &'a mut T, deadline: Instant) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.expire_at(deadline);
+        anchor
+    }
+
+    /// Creates a new `WAnchor` instance like [`with_deadline`](#method.with_deadline), but measuring time with `clock`.
+    /// See [`Anchor::with_deadline_on`].
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_deadline_on(
+        reference: &'a mut T,
+        deadline: Instant,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.use_clock(clock);
+        anchor.reference.idle.expire_at(deadline);
+        anchor
+    }
+
+    /// Creates a new `WAnchor` instance, capturing `reference` and allowing at most `limit` (strong) portals at a time.
+    /// See [`Anchor::with_limit`].
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_limit(reference: &'a mut T, limit: usize) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.limit.store(limit, Ordering::Relaxed);
+        anchor
+    }
+
+    /// Creates a new `WAnchor` instance, capturing `reference` and labelling the anchor as `label`.  
+    /// The label appears in this anchor's panic messages and diagnostics, to tell apart anchors of the same type.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn named(label: &'static str, reference: &'a mut T) -> Self {
+        let mut anchor = Self::new(reference);
+        anchor.label = Some(label);
+        #[cfg(feature = "diagnostics")]
+        anchor.registration.set_label(label);
+        anchor
+    }
+
+    /// Returns this anchor's label, if it was created through `named`.
+    #[inline]
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Returns this anchor's unique ID, which its portals and weak portals report as `anchor_id`, even after it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        self.reference.id
+    }
+
+    /// Returns the time left until this anchor seals itself, if it was created through `with_deadline`.
+    #[inline]
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.reference.idle.remaining()
+    }
+
+    /// Forgets this anchor without checking for remaining portals, so that they stay valid indefinitely.  
+    /// Drop callbacks are not invoked. With the `diagnostics` feature, the anchor stays listed and is marked as leaked.
+    ///
+    /// # Safety
+    ///
+    /// The captured reference must stay valid for as long as any portal may still access it,
+    /// i.e. the target must actually be `'static` or never be freed or otherwise accessed.
+    #[allow(clippy::missing_const_for_fn)] // Not with the `diagnostics` feature.
+    pub unsafe fn leak(self) {
+        #[cfg(feature = "diagnostics")]
+        self.registration.mark_leaked();
+        mem::forget(self);
+    }
+
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    ///
+    /// # Panics
+    ///
+    /// Iff this anchor was created through `with_limit` and that many portals exist already.
+    #[inline]
+    #[track_caller]
+    pub fn portal(&self) -> WPortal<T> {
+        self.reference.acquire();
+        self.reference.pipe_deref(Arc::clone).pipe(WPortal::new)
+    }
+
+    /// Creates a portal iff fewer (strong) portals than this anchor's limit exist.
+    ///
+    /// # Errors
+    ///
+    /// Iff this anchor was created through `with_limit` and that many portals exist already.
+    #[inline]
+    pub fn try_portal(&self) -> Result<WPortal<T>, PortalLimitExceeded> {
+        self.reference.try_acquire_within_limit()?;
+        Ok(self.reference.pipe_deref(Arc::clone).pipe(WPortal::new))
+    }
+
+    #[inline]
+    pub fn weak_portal(&self) -> WeakWPortal<T> {
+        WeakWPortal(Arc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Creates a `'static` closure that upgrades a weak portal associated with this anchor and calls `body` with the target and its argument.  
+    /// Use a tuple as `A` to pass several arguments.
+    ///
+    /// # Panics
+    ///
+    /// The returned closure panics if the anchor has been dropped or poisoned.
+    #[inline]
+    pub fn portal_fn<A, R>(
+        &self,
+        body: impl Fn(&mut T, A) -> R + 'static,
+    ) -> impl Fn(A) -> R + 'static
+    where
+        T: 'static,
+    {
+        let weak_portal = self.weak_portal();
+        move |argument| body(&mut *weak_portal.upgrade().lock(), argument)
+    }
+
+    /// Returns the number of (strong) portals currently associated with this anchor.
+    #[inline]
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        self.reference.strong_portal_count()
+    }
+
+    /// Returns the maximum number of (strong) portals, if this anchor was created through `with_limit`.
+    #[inline]
+    #[must_use]
+    pub fn portal_limit(&self) -> Option<usize> {
+        self.reference.limit()
+    }
+
+    /// Blocks the current thread until no (strong) portals associated with this anchor exist.  
+    /// Dropping the anchor afterwards won't panic unless another portal was created meanwhile.  
+    /// If the anchor is bound to a [`ShutdownToken`] that signalled shutdown with a deadline, this returns at that deadline regardless.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn wait_idle(&self) {
+        self.reference.wait_idle();
+    }
+
+    /// Binds this anchor to `token`, so that signalling shutdown seals it against weak portal upgrades
+    /// and limits [`wait_idle`](#method.wait_idle) to the shutdown deadline.
+    pub fn bind_shutdown(&self, token: &ShutdownToken) {
+        token.bind(&self.reference.idle);
+    }
+
+    /// Registers `callback` to be invoked whenever the last (strong) portal associated with this anchor is dropped.  
+    /// Callbacks run on the thread that dropped the portal and are discarded when the anchor is dropped.
+    pub fn on_idle(&self, callback: impl FnMut() + Send + 'static) {
+        self.reference
+            .idle_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Registers `callback` to be invoked when this anchor is dropped.  
+    /// Callbacks run in registration order on the dropping thread once the anchor has released its reference,
+    /// before any panic caused by the drop.
+    pub fn on_drop(&self, callback: impl FnOnce() + Send + 'static) {
+        self.drop_callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(callback));
+    }
+
+    /// Binds the thread behind `handle` to this anchor, so that dropping the anchor first joins it.  
+    /// Any portals that thread holds are then gone before the anchor checks for remaining ones.
+    /// The thread's result, including a panic, is discarded.
+    ///
+    /// Dropping the anchor on a bound thread deadlocks.
+    pub fn bind_join<R: Send + 'static>(&self, handle: JoinHandle<R>) {
+        self.bound_threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(move || drop(handle.join())));
+    }
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Invokes and removes all callbacks registered through `.on_drop(…)`.
+    fn invoke_drop_callbacks(&mut self) {
+        self.drop_callbacks
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .invoke_once();
+    }
+
+    /// Joins and unbinds all threads bound through `.bind_join(…)`.
+    fn join_bound_threads(&mut self) {
+        self.bound_threads
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .invoke_once();
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Invokes and removes all callbacks registered through `.on_drop(…)`.
+    fn invoke_drop_callbacks(&mut self) {
+        self.drop_callbacks
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .invoke_once();
+    }
+
+    /// Joins and unbinds all threads bound through `.bind_join(…)`.
+    fn join_bound_threads(&mut self) {
+        self.bound_threads
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .invoke_once();
+    }
+}
+
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Invokes and removes all callbacks registered through `.on_drop(…)`.
+    fn invoke_drop_callbacks(&mut self) {
+        self.drop_callbacks
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .invoke_once();
+    }
+
+    /// Joins and unbinds all threads bound through `.bind_join(…)`.
+    fn join_bound_threads(&mut self) {
+        self.bound_threads
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .invoke_once();
+    }
+}
+
+impl<'a, T: ?Sized> Drop for Anchor<'a, T> {
+    /// Executes the destructor for this type. [Read more](https://doc.rust-lang.org/nightly/core/ops/drop/trait.Drop.html#tymethod.drop)
+    ///
+    /// # Panics
+    ///
+    /// If any associated `Portal`s exist:
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    ///
+    /// assert_panic!(
+    ///     drop(anchor),
+    ///     String,
+    ///     starts with "Anchor still in use (at least one portal exists)",
+    /// );
+    ///
+    /// // Surviving portals panic on access, rather than reading from a target that may be gone.
+    /// assert_panic!({ portal.len(); }, String, "Anchor dropped");
+    /// ```
+    fn drop(&mut self) {
+        self.join_bound_threads();
+        self.leases.revoke_all();
+        if self.reference.idle.policy() == ViolationPolicy::Block {
+            self.reference.wait_idle();
+        }
+        let reference = unsafe {
+            //SAFETY: Dropping.
+            ManuallyDrop::take(&mut self.reference)
+        };
+        let strong_portals = reference.detach();
+        self.invoke_drop_callbacks();
+        let dependents = self.dependents.seal_remaining();
+        if strong_portals > 0 {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorStillInUse,
+                self.label,
+                strong_portals,
+                &reference.traces,
+            )
+        }
+        if let Some(dependents) = dependents {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorHasDependents,
+                self.label,
+                0,
+                &dependents,
+            )
+        }
+        #[cfg(all(feature = "diagnostics", feature = "freelist"))]
+        self.registration.unregister();
+        freelist::recycle(reference);
+    }
+}
+
+impl<'a, T: ?Sized> Drop for RwAnchor<'a, T> {
+    /// Executes the destructor for this type. [Read more](https://doc.rust-lang.org/nightly/core/ops/drop/trait.Drop.html#tymethod.drop)
+    ///
+    /// # Panics
+    ///
+    /// If any associated `RwPortal`s exist or, otherwise, iff the anchor has been poisoned:
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_panic!({
+    ///     // Poison anchor.
+    ///     let _guard = portal.write();
+    ///     panic!()
+    /// });
+    ///
+    /// assert_panic!(
+    ///     drop(anchor),
+    ///     String,
+    ///     starts with "Anchor still in use (at least one portal exists)",
+    /// );
+    /// ```
+    fn drop(&mut self) {
+        self.join_bound_threads();
+        if self.reference.idle.policy() == ViolationPolicy::Block {
+            self.reference.wait_idle();
+        }
+        let reference = unsafe {
+            //SAFETY: Dropping.
+            ManuallyDrop::take(&mut self.reference)
+        };
+        let strong_portals = reference.detach();
+        let dependents = self.dependents.seal_remaining();
+        let holders = (strong_portals > 0).then(|| {
+            // Listed before waiting for them.
+            let holders = holders(&*reference);
+            // Poison RwLock, and leave only a dangling pointer behind in case that's ignored.
+            dangle_poisoned(|| {
+                reference
+                    .target
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+            });
+            holders
+        });
+        self.invoke_drop_callbacks();
+        if let Some(holders) = holders {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorStillInUse,
+                self.label,
+                strong_portals,
+                &format_args!("{}{}", reference.traces, holders),
+            )
+        }
+        if let Some(dependents) = dependents {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorHasDependents,
+                self.label,
+                0,
+                &dependents,
+            )
+        }
+        if reference.target.read().is_err() {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorPoisoned,
+                self.label,
+                0,
+                &reference.poison_origin,
+            )
+        }
+        #[cfg(all(feature = "diagnostics", feature = "freelist"))]
+        self.registration.unregister();
+        freelist::recycle(reference);
+    }
+}
+
+impl<'a, T: ?Sized> Drop for WAnchor<'a, T> {
+    /// Executes the destructor for this type. [Read more](https://doc.rust-lang.org/nightly/core/ops/drop/trait.Drop.html#tymethod.drop)
+    ///
+    /// # Panics
+    ///
+    /// If any associated `WPortal`s exist or, otherwise, iff the anchor has been poisoned:
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::WAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = WAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_panic!({
+    ///     // Poison anchor.
+    ///     let _guard = portal.lock();
+    ///     panic!()
+    /// });
+    ///
+    /// assert_panic!(
+    ///     drop(anchor),
+    ///     String,
+    ///     starts with "Anchor still in use (at least one portal exists)",
+    /// );
+    /// ```
+    fn drop(&mut self) {
+        self.join_bound_threads();
+        if self.reference.idle.policy() == ViolationPolicy::Block {
+            self.reference.wait_idle();
+        }
+        let reference = unsafe {
+            //SAFETY: Dropping.
+            ManuallyDrop::take(&mut self.reference)
+        };
+        let strong_portals = reference.detach();
+        let dependents = self.dependents.seal_remaining();
+        let holders = (strong_portals > 0).then(|| {
+            // Listed before waiting for them.
+            let holders = holders(&*reference);
+            // Poison Mutex, and leave only a dangling pointer behind in case that's ignored.
+            dangle_poisoned(|| {
+                reference
+                    .target
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+            });
+            holders
+        });
+        self.invoke_drop_callbacks();
+        if let Some(holders) = holders {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorStillInUse,
+                self.label,
+                strong_portals,
+                &format_args!("{}{}", reference.traces, holders),
+            )
+        }
+        if let Some(dependents) = dependents {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorHasDependents,
+                self.label,
+                0,
+                &dependents,
+            )
+        }
+        if reference.target.lock().is_err() {
+            anchor_drop_violation::<T>(
+                &reference.idle,
+                Violation::AnchorPoisoned,
+                self.label,
+                0,
+                &reference.poison_origin,
+            )
+        }
+        #[cfg(all(feature = "diagnostics", feature = "freelist"))]
+        self.registration.unregister();
+        freelist::recycle(reference);
+    }
+}
+
+/// # Safety:
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::sync::RwAnchor;
+///
+/// let mut x = "Scoped".to_owned();
+/// let anchor = RwAnchor::new(&mut x);
+/// let portal = anchor.portal();
+///
+/// assert_panic!(
+///     drop(anchor),
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
+/// );
+/// assert_panic!(
+///     { portal.read(); },
+///     String,
+///     starts with "Anchor poisoned:",
+/// );
+/// ```
+impl<'a, T: ?Sized> UnwindSafe for RwAnchor<'a, T> where T: RefUnwindSafe {}
+
+/// # Safety:
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::sync::WAnchor;
+///
+/// let mut x = "Scoped".to_owned();
+/// let anchor = WAnchor::new(&mut x);
+/// let portal = anchor.portal();
+///
+/// assert_panic!(
+///     drop(anchor),
+///     String,
+///     starts with "Anchor still in use (at least one portal exists)",
+/// );
+/// assert_panic!(
+///     { portal.lock(); },
+///     String,
+///     starts with "Anchor poisoned:",
+/// );
+/// ```
+impl<'a, T: ?Sized> UnwindSafe for WAnchor<'a, T> where T: RefUnwindSafe {}
+
+/// A threadsafe immutable portal.  
+/// Dereference it directly with `*` or `.deref()`.
+#[must_use]
+#[cfg_attr(not(feature = "backtrace"), repr(transparent))]
+pub struct Portal<T: ?Sized>(Arc<Shared<SSNonNull<T>>>, TraceId);
+
+/// A threadsafe mutable portal supporting concurred reads.  
+/// Acquire a guard by calling `.read()` or `.write()`.
+#[must_use]
+#[cfg_attr(not(feature = "backtrace"), repr(transparent))]
+pub struct RwPortal<T: ?Sized>(Arc<RwShared<T>>, TraceId);
+
+/// A threadsafe mutable portal with only exclusive access.  
+/// Acquire a guard by calling `.lock()`.
+#[must_use]
+#[cfg_attr(not(feature = "backtrace"), repr(transparent))]
+pub struct WPortal<T: ?Sized>(Arc<WShared<T>>, TraceId);
+
+impl<T: ?Sized> Portal<T> {
+    /// Wraps `shared`, which must already count this portal, recording where it was created.
+    #[inline]
+    fn new(shared: Arc<Shared<SSNonNull<T>>>) -> Self {
+        let trace = shared.traces.capture();
+        Self(shared, trace)
+    }
+
+    /// Creates a weak portal associated with the same anchor as `portal`.  
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    #[inline]
+    pub fn downgrade(portal: &Self) -> WeakPortal<T> {
+        WeakPortal(Arc::downgrade(&portal.0), portal.0.id)
+    }
+
+    /// Returns the unique ID of the anchor `portal` is associated with.
+    #[inline]
+    #[must_use]
+    pub fn anchor_id(portal: &Self) -> AnchorId {
+        portal.0.id
+    }
+
+    /// Returns `true` iff `portal` and `other` are associated with the same anchor.  
+    /// This doesn't dereference either portal.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(portal: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&portal.0, &other.0)
+    }
+
+    /// Returns `true` iff `portal` is associated with `anchor`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{Anchor, Portal};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    /// let weak_portal = anchor.weak_portal();
+    ///
+    /// assert!(Portal::is_from(&portal, &anchor));
+    /// assert!(weak_portal.is_from(&anchor));
+    /// assert!(weak_portal.ptr_eq(&Portal::downgrade(&portal)));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_from(portal: &Self, anchor: &Anchor<'_, T>) -> bool {
+        Arc::ptr_eq(&portal.0, &anchor.reference)
+    }
+
+    /// Returns the address of the anchored value, e.g. for identity comparisons or as map key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::{Anchor, Portal}, std::ptr};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    ///
+    /// assert!(ptr::eq(Portal::as_ptr(&portal), &x));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn as_ptr(portal: &Self) -> *const T {
+        portal.0.target.as_ptr()
+    }
+}
+
+impl<T: Clone> Portal<T> {
+    /// Clones the anchored value.
+    #[inline]
+    #[must_use]
+    pub fn cloned(portal: &Self) -> T {
+        T::clone(portal)
+    }
+}
+
+impl<T: ?Sized> Deref for Portal<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // Only fails if the anchor was dropped anyway, while panicking.
+        if !self.0.is_anchored() {
+            anchor_dropped_panic()
+        }
+        let pointer = &self.0.target;
+        unsafe {
+            //SAFETY: Valid as long as self.0 is and the anchor exists.
+            pointer.as_ref()
+        }
+    }
+}
+
+impl<T: ?Sized> Borrow<T> for Portal<T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for Portal<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Wraps `shared`, which must already count this portal, recording where it was created.
+    #[inline]
+    fn new(shared: Arc<RwShared<T>>) -> Self {
+        let trace = shared.traces.capture();
+        Self(shared, trace)
+    }
+
+    /// Clones this portal for internal use, e.g. by an owning guard, regardless of the anchor's portal limit.
+    #[inline]
+    fn clone_pin(&self) -> Self {
+        self.0.acquire_pin();
+        self.0.pipe_ref(Arc::clone).pipe(Self::new)
+    }
+
+    /// Returns the address of the anchored value without locking it, e.g. for identity comparisons or as map key.  
+    /// Dereferencing the pointer is only sound while a matching guard is held.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::RwAnchor, std::ptr};
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let expected: *mut String = &mut x;
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let _guard = portal.write();
+    /// assert!(ptr::eq(portal.data_ptr(), expected));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn data_ptr(&self) -> *mut T {
+        self.0.address.as_ptr()
+    }
+
+    /// Creates a weak portal associated with the same anchor as this one.  
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    #[inline]
+    pub fn downgrade(&self) -> WeakRwPortal<T> {
+        WeakRwPortal(Arc::downgrade(&self.0), self.0.id)
+    }
+
+    /// Returns the unique ID of the anchor this portal is associated with.
+    #[inline]
+    #[must_use]
+    pub fn anchor_id(&self) -> AnchorId {
+        self.0.id
+    }
+
+    /// Returns `true` iff this portal and `other` are associated with the same anchor.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns `true` iff this portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &RwAnchor<'_, T>) -> bool {
+        Arc::ptr_eq(&self.0, &anchor.reference)
+    }
+
+    /// Returns `true` iff the anchored value is currently borrowed through any guard.  
+    /// This is only a snapshot and may be outdated by the time it returns.
+    ///
+    /// The standard `RwLock` doesn't expose how many readers it has,
+    /// so there is no `reader_count()` here.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert!(!portal.is_locked());
+    ///
+    /// let guard = portal.read();
+    /// assert!(portal.is_locked());
+    /// assert!(!portal.is_locked_exclusive());
+    /// drop(guard);
+    ///
+    /// let guard = portal.write();
+    /// assert!(portal.is_locked_exclusive());
+    /// # drop(guard);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        matches!(self.0.target.try_write(), Err(TryLockError::WouldBlock))
+    }
+
+    /// Returns `true` iff the anchored value is currently borrowed through a write guard.  
+    /// This is only a snapshot and may be outdated by the time it returns.
+    ///
+    /// Depending on the platform, this may also return `true` while a writer is waiting for readers to finish.
+    #[inline]
+    #[must_use]
+    pub fn is_locked_exclusive(&self) -> bool {
+        matches!(self.0.target.try_read(), Err(TryLockError::WouldBlock))
+    }
+
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read<'a>(&'a self) -> impl AsRef<T> + HeldFor + ToArc<T> + 'a {
+        check_blocking("sync::RwPortal::read", &*self.0, false);
+        PortalReadGuard(
+            self.0.read(),
+            &self.0,
+            self.0.held::<T>("sync::RwPortal::read", false),
+        )
+    }
+
+    /// Acquires a read guard that can be cloned cheaply, e.g. to hand the same consistent view of the target to several consumers without copying it.  
+    /// Writers stay blocked until the last clone is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let guard = portal.read_arc();
+    /// let consumers = vec![guard.clone(), guard];
+    /// assert!(portal.try_write().is_none());
+    /// assert!(consumers.iter().all(|guard| **guard == "Scoped"));
+    ///
+    /// drop(consumers);
+    /// assert!(portal.try_write().is_some());
+    /// # drop(portal);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_arc(&self) -> ArcReadGuard<T>
+    where
+        T: 'static,
+    {
+        let portal = self.clone_pin();
+        let shared = unsafe {
+            //SAFETY: Kept alive by `portal`, which `ArcRead` drops only after the guard.
+            &*Arc::as_ptr(&portal.0)
+        };
+        check_blocking("sync::RwPortal::read_arc", shared, false);
+        let lock = ArcRead {
+            guard: PortalReadGuard(
+                shared.read(),
+                shared,
+                shared.held::<T>("sync::RwPortal::read_arc", false),
+            ),
+            _portal: portal,
+        };
+        ArcReadGuard {
+            target: NonNull::from(&*lock.guard),
+            lock: Arc::new(lock),
+        }
+    }
+
+    /// Acquires a write guard that owns a clone of this portal, so that it isn't bound to this portal's lifetime.  
+    /// Use [`ArcWriteGuard::map`] to narrow it down to the part of the target that's actually needed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::{ArcWriteGuard, RwAnchor};
+    ///
+    /// let mut x = vec!["Scoped".to_owned()];
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let first = ArcWriteGuard::try_map(portal.write_arc(), |x| x.first_mut()).unwrap();
+    /// assert_panic!({
+    ///     let _first = first;
+    ///     panic!();
+    /// });
+    ///
+    /// // Released while unwinding, so the anchor is poisoned.
+    /// assert_panic!(
+    ///     { portal.read(); },
+    ///     String,
+    ///     starts with "Anchor poisoned",
+    /// );
+    /// drop(portal);
+    /// assert_panic!(drop(anchor), String, starts with "Anchor poisoned");
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write_arc(&self) -> ArcWriteGuard<T>
+    where
+        T: 'static,
+    {
+        let portal = self.clone_pin();
+        let shared = unsafe {
+            //SAFETY: Kept alive by `portal`, which `ArcWrite` drops only after the guard.
+            &*Arc::as_ptr(&portal.0)
+        };
+        shared.check_writer("sync::RwPortal::write_arc");
+        shared.wait_for_write_grant();
+        check_blocking("sync::RwPortal::write_arc", shared, true);
+        let guard = shared
+            .counters
+            .contend(|| shared.target.try_write(), || shared.target.write())
+            .or_else(|error| {
+                drop(error);
+                shared.settle();
+                shared.target.write()
+            })
+            .unwrap_or_else(|error| shared.poisoned_panic(&error));
+        let mut lock = ArcWrite {
+            guard: PortalWriteGuard(
+                ManuallyDrop::new(guard),
+                shared,
+                shared.held::<T>("sync::RwPortal::write_arc", true),
+                false,
+            ),
+            _portal: portal,
+        };
+        ArcWriteGuard {
+            target: NonNull::from(&mut *lock.guard),
+            lock: Box::new(lock),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write<'a>(
+        &'a self,
+    ) -> impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + HandOff + 'a {
+        self.write_guard("sync::RwPortal::write")
+    }
+
+    /// Acquires a write guard on behalf of `operation`, as [`write`](#method.write) does.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn write_guard(&self, operation: &'static str) -> PortalWriteGuard<'_, T> {
+        self.0.check_writer(operation);
+        check_blocking(operation, &*self.0, true);
+        self.0.wait_for_write_grant();
+        let guard = self
+            .0
+            .counters
+            .contend(|| self.0.target.try_write(), || self.0.target.write())
+            .or_else(|error| {
+                drop(error);
+                self.0.settle();
+                self.0.target.write()
+            })
+            .unwrap_or_else(|error| self.0.poisoned_panic(&error));
+        PortalWriteGuard(
+            ManuallyDrop::new(guard),
+            &self.0,
+            self.0.held::<T>(operation, true),
+            false,
+        )
+    }
+
+    /// Replaces the target with the result of `update`, which runs on a clone of it without holding any lock.  
+    /// If another write guard was released in the meantime, `update` runs again on a fresh clone, until it wins.
+    ///
+    /// This keeps expensive computations from blocking readers, at the cost of repeating them under write contention.
+    /// A panic in `update` doesn't poison the anchor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::RwAnchor, std::thread};
+    ///
+    /// let mut config = vec!["a".to_owned()];
+    /// let anchor = RwAnchor::new(&mut config);
+    /// let portal = anchor.portal();
+    ///
+    /// thread::spawn(move || {
+    ///     portal.update_cloned(|mut config| {
+    ///         config.push("b".to_owned());
+    ///         config
+    ///     })
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// drop(anchor);
+    /// assert_eq!(config, ["a", "b"]);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn update_cloned(&self, mut update: impl FnMut(T) -> T)
+    where
+        T: Clone,
+    {
+        loop {
+            let (snapshot, writes) = {
+                let guard = self.read();
+                // Stable while the read guard is held.
+                ((*guard).clone(), self.0.writes.load(Ordering::Relaxed))
+            };
+            let updated = update(snapshot);
+            let mut guard = self.write_guard("sync::RwPortal::update_cloned");
+            if self.0.writes.load(Ordering::Relaxed) == writes {
+                *guard = updated;
+                return;
+            }
+        }
+    }
+
+    /// Acquires a read guard iff that's possible without blocking.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read<'a>(&'a self) -> Option<impl AsRef<T> + HeldFor + ToArc<T> + 'a> {
+        let guard = match self.0.target.try_read() {
+            Err(TryLockError::Poisoned(error)) => {
+                drop(error);
+                self.0.settle();
+                self.0.target.try_read()
+            }
+            result => result,
+        };
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                self.0.counters.contended();
+                return None;
+            }
+            Err(TryLockError::Poisoned(error)) => self.0.poisoned_panic(&error),
+        };
+        Some(PortalReadGuard(
+            guard,
+            &self.0,
+            self.0.held::<T>("sync::RwPortal::try_read", false),
+        ))
+    }
+
+    /// Acquires a write guard iff that's possible without blocking.  
+    /// Returns `None` also while the anchor's [`WriteThrottle`] doesn't grant one.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_write<'a>(
+        &'a self,
+    ) -> Option<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + HandOff + 'a>
+    {
+        self.try_write_guard("sync::RwPortal::try_write")
+            .ok()
+            .flatten()
+    }
+
+    /// Acquires a write guard on behalf of `operation` iff that's possible without blocking, as [`try_write`](#method.try_write) does.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn try_write_guard(
+        &self,
+        operation: &'static str,
+    ) -> Result<Option<PortalWriteGuard<'_, T>>, Throttled> {
+        self.0.check_writer(operation);
+        self.0.try_write_grant()?;
+        let guard = match self.0.target.try_write() {
+            Err(TryLockError::Poisoned(error)) => {
+                drop(error);
+                self.0.settle();
+                self.0.target.try_write()
+            }
+            result => result,
+        };
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                self.0.counters.contended();
+                self.0.refund_write_grant();
+                return Ok(None);
+            }
+            Err(TryLockError::Poisoned(error)) => self.0.poisoned_panic(&error),
+        };
+        Ok(Some(PortalWriteGuard(
+            ManuallyDrop::new(guard),
+            &self.0,
+            self.0.held::<T>(operation, true),
+            false,
+        )))
+    }
+
+    /// Acquires a read guard like [`read`](#method.read), but returns it boxed in a [`PoisonError`] instead of panicking if the anchor has been poisoned,
+    /// like [`RwLock::read`](`std::sync::RwLock::read`) does.
+    ///
+    /// This doesn't clear the poison, so dropping the anchor still panics.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been poisoned, with the guard boxed inside.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use {ref_portals::sync::RwAnchor, std::sync::PoisonError};
+    ///
+    /// let mut x = vec![1, 2, 3];
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_panic!({
+    ///     let mut guard = portal.write();
+    ///     guard.push(4);
+    ///     panic!()
+    /// });
+    ///
+    /// assert!(portal.read_poisonable().is_err());
+    /// let guard = portal
+    ///     .read_poisonable()
+    ///     .err()
+    ///     .map(PoisonError::into_inner)
+    ///     .unwrap();
+    /// assert_eq!(**guard, [1, 2, 3, 4]);
+    /// # drop(guard);
+    /// # drop(portal);
+    /// # assert_panic!(drop(anchor));
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_poisonable<'a>(
+        &'a self,
+    ) -> Result<
+        impl AsRef<T> + HeldFor + ToArc<T> + 'a,
+        PoisonError<Box<impl AsRef<T> + HeldFor + ToArc<T> + 'a>>,
+    > {
+        check_blocking("sync::RwPortal::read_poisonable", &*self.0, false);
+        let (guard, poisoned) = match self.0.read_waiting().or_else(|error| {
+            drop(error);
+            self.0.settle();
+            self.0.target.read()
+        }) {
+            Ok(guard) => (guard, false),
+            Err(error) => (error.into_inner(), true),
+        };
+        let guard = PortalReadGuard(
+            guard,
+            &self.0,
+            self.0.held::<T>("sync::RwPortal::read_poisonable", false),
+        );
+        if poisoned {
+            guard.pipe(Box::new).pipe(PoisonError::new).pipe(Err)
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// Acquires a write guard like [`write`](#method.write), but returns it boxed in a [`PoisonError`] instead of panicking if the anchor has been poisoned,
+    /// like [`RwLock::write`](`std::sync::RwLock::write`) does.
+    ///
+    /// This doesn't clear the poison, so dropping the anchor still panics.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been poisoned, with the guard boxed inside.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write_poisonable<'a>(
+        &'a self,
+    ) -> Result<
+        impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a,
+        PoisonError<
+            Box<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a>,
+        >,
+    > {
+        self.0.check_writer("sync::RwPortal::write_poisonable");
+        check_blocking("sync::RwPortal::write_poisonable", &*self.0, true);
+        self.0.wait_for_write_grant();
+        let contended = self
+            .0
+            .counters
+            .contend(|| self.0.target.try_write(), || self.0.target.write());
+        let (guard, poisoned) = match contended.or_else(|error| {
+            drop(error);
+            self.0.settle();
+            self.0.target.write()
+        }) {
+            Ok(guard) => (guard, false),
+            Err(error) => (error.into_inner(), true),
+        };
+        let guard = PortalWriteGuard(
+            ManuallyDrop::new(guard),
+            &self.0,
+            self.0.held::<T>("sync::RwPortal::write_poisonable", true),
+            false,
+        );
+        if poisoned {
+            guard.pipe(Box::new).pipe(PoisonError::new).pipe(Err)
+        } else {
+            Ok(guard)
+        }
+    }
+
+    /// Calls the anchored closure with `args` while holding the write lock, e.g. for an `RwPortal<dyn FnMut(f32) + Send + Sync>`.  
+    /// `args` is a tuple with one element per argument.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned. If the closure panics, the anchor is poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut total = 0;
+    /// let mut add = |amount: i32, times: i32| {
+    ///     assert!(times >= 0);
+    ///     total += amount * times;
+    ///     total
+    /// };
+    /// let callback: &mut (dyn FnMut(i32, i32) -> i32 + Send + Sync) = &mut add;
+    /// let anchor = RwAnchor::new(callback);
+    /// let portal = anchor.portal();
+    ///
+    /// assert_eq!(portal.call_mut((2, 3)), 6);
+    /// assert_eq!(portal.call_mut((1, 1)), 7);
+    ///
+    /// assert_panic!({ portal.call_mut((1, -1)); });
+    /// assert_panic!(
+    ///     { portal.call_mut((1, 1)); },
+    ///     String,
+    ///     starts with "Anchor poisoned",
+    /// );
+    /// # drop(portal);
+    /// # assert_panic!(drop(anchor));
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn call_mut<Args>(&self, args: Args) -> T::Output
+    where
+        T: CallMut<Args>,
+    {
+        self.write().call_with(args)
+    }
+}
+
+impl<T: ?Sized> WPortal<T> {
+    /// Wraps `shared`, which must already count this portal, recording where it was created.
+    #[inline]
+    fn new(shared: Arc<WShared<T>>) -> Self {
+        let trace = shared.traces.capture();
+        Self(shared, trace)
+    }
+
+    /// Returns the address of the anchored value without locking it, e.g. for identity comparisons or as map key.  
+    /// Dereferencing the pointer is only sound while a matching guard is held.
+    #[inline]
+    #[must_use]
+    pub fn data_ptr(&self) -> *mut T {
+        self.0.address.as_ptr()
+    }
+
+    /// Creates a weak portal associated with the same anchor as this one.  
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    #[inline]
+    pub fn downgrade(&self) -> WeakWPortal<T> {
+        WeakWPortal(Arc::downgrade(&self.0), self.0.id)
+    }
+
+    /// Returns the unique ID of the anchor this portal is associated with.
+    #[inline]
+    #[must_use]
+    pub fn anchor_id(&self) -> AnchorId {
+        self.0.id
+    }
+
+    /// Returns `true` iff this portal and `other` are associated with the same anchor.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Returns `true` iff this portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &WAnchor<'_, T>) -> bool {
+        Arc::ptr_eq(&self.0, &anchor.reference)
+    }
+
+    /// Returns `true` iff the anchored value is currently borrowed through a guard.  
+    /// This is only a snapshot and may be outdated by the time it returns.
+    #[inline]
+    #[must_use]
+    pub fn is_locked(&self) -> bool {
+        matches!(self.0.target.try_lock(), Err(TryLockError::WouldBlock))
+    }
+
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn lock<'a>(
+        &'a self,
+    ) -> impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + HandOff + 'a {
+        check_blocking("sync::WPortal::lock", &*self.0, true);
+        let guard = self
+            .0
+            .counters
+            .contend(|| self.0.target.try_lock(), || self.0.target.lock())
+            .or_else(|error| {
+                drop(error);
+                self.0.settle();
+                self.0.target.lock()
+            })
+            .unwrap_or_else(|error| self.0.poisoned_panic(&error));
+        PortalMutexGuard(
+            ManuallyDrop::new(guard),
+            &self.0,
+            self.0.held::<T>("sync::WPortal::lock", true),
+            false,
+        )
+    }
+
+    /// Acquires a guard iff that's possible without blocking.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_lock<'a>(
+        &'a self,
+    ) -> Option<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + HandOff + 'a>
+    {
+        let guard = match self.0.target.try_lock() {
+            Err(TryLockError::Poisoned(error)) => {
+                drop(error);
+                self.0.settle();
+                self.0.target.try_lock()
+            }
+            result => result,
+        };
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                self.0.counters.contended();
+                return None;
+            }
+            Err(TryLockError::Poisoned(error)) => self.0.poisoned_panic(&error),
+        };
+        Some(PortalMutexGuard(
+            ManuallyDrop::new(guard),
+            &self.0,
+            self.0.held::<T>("sync::WPortal::try_lock", true),
+            false,
+        ))
+    }
+
+    /// Acquires a guard like [`lock`](#method.lock), but returns it boxed in a [`PoisonError`] instead of panicking if the anchor has been poisoned,
+    /// like [`Mutex::lock`](`std::sync::Mutex::lock`) does.
+    ///
+    /// This doesn't clear the poison, so dropping the anchor still panics.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been poisoned, with the guard boxed inside.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn lock_poisonable<'a>(
+        &'a self,
+    ) -> Result<
+        impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a,
+        PoisonError<
+            Box<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a>,
+        >,
+    > {
+        check_blocking("sync::WPortal::lock_poisonable", &*self.0, true);
+        let contended = self
+            .0
+            .counters
+            .contend(|| self.0.target.try_lock(), || self.0.target.lock());
+        let (guard, poisoned) = match contended.or_else(|error| {
+            drop(error);
+            self.0.settle();
+            self.0.target.lock()
+        }) {
+            Ok(guard) => (guard, false),
+            Err(error) => (error.into_inner(), true),
+        };
+        let guard = PortalMutexGuard(
+            ManuallyDrop::new(guard),
+            &self.0,
+            self.0.held::<T>("sync::WPortal::lock_poisonable", true),
+            false,
+        );
+        if poisoned {
+            guard.pipe(Box::new).pipe(PoisonError::new).pipe(Err)
+        } else {
+            Ok(guard)
+        }
+    }
+}
+
+impl<T: Clone> RwPortal<T> {
+    /// Clones the anchored value out under a short-lived read guard,
+    /// so that no guard is held while the copy is processed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let snapshot: String = portal.snapshot();
+    /// portal.write().push_str(" and changed");
+    /// assert_eq!(snapshot, "Scoped");
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot(&self) -> T {
+        T::clone(&*self.read())
+    }
+}
+
+impl<T: Clone> WPortal<T> {
+    /// Clones the anchored value out under a short-lived exclusive guard,
+    /// so that no guard is held while the copy is processed.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot(&self) -> T {
+        T::clone(&*self.lock())
+    }
+}
+
+impl<T> RwPortal<T> {
+    /// Replaces the anchored value with `value`, returning the old one.  
+    /// The guard is released before this method returns.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = 1;
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// assert_eq!(portal.replace(2), 1);
+    /// portal.set(3);
+    /// assert_eq!(portal.take(), 3);
+    /// drop(portal);
+    /// drop(anchor);
+    ///
+    /// assert_eq!(x, 0);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.write(), value)
+    }
+
+    /// Replaces the anchored value with `value`.  
+    /// The old value is dropped after the guard has been released.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn set(&self, value: T) {
+        drop(self.replace(value));
+    }
+
+    /// Takes the anchored value, leaving `Default::default()` in its place.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    /// Builds a replacement through `build` and checks it with `validate`, both without holding any lock,
+    /// then swaps it in under a brief write guard and returns the old value.
+    /// A rejected candidate is dropped without locking the target, so it doesn't count as a write:
+    /// [`CachedPortal`]s and [`ArcMemo`]s keep their values, and waiting writers aren't delayed.
+    ///
+    /// # Errors
+    ///
+    /// Iff `validate` rejects the candidate, with the error it returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut port = 8080_u16;
+    /// let anchor = RwAnchor::new(&mut port);
+    /// let portal = anchor.portal();
+    ///
+    /// let validate = |port: &u16| if *port < 1024 { Err("Privileged port") } else { Ok(()) };
+    /// assert_eq!(portal.replace_validated(|| "80".parse().unwrap(), validate), Err("Privileged port"));
+    /// assert_eq!(portal.replace_validated(|| "8443".parse().unwrap(), validate), Ok(8080));
+    ///
+    /// drop(portal);
+    /// drop(anchor);
+    /// assert_eq!(port, 8443);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn replace_validated<E>(
+        &self,
+        build: impl FnOnce() -> T,
+        validate: impl FnOnce(&T) -> Result<(), E>,
+    ) -> Result<T, E> {
+        let candidate = build();
+        validate(&candidate)?;
+        Ok(mem::replace(
+            &mut *self.write_guard("sync::RwPortal::replace_validated"),
+            candidate,
+        ))
+    }
+
+    /// Swaps the anchored values of this portal and `other`.  
+    /// Does nothing if both are associated with the same anchor.
+    ///
+    /// Both locks are acquired in a consistent order across all calls,
+    /// so concurrent swaps between the same portals in opposite directions can't deadlock.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let (mut a, mut b) = (1, 2);
+    /// let (anchor_a, anchor_b) = (RwAnchor::new(&mut a), RwAnchor::new(&mut b));
+    ///
+    /// anchor_a.portal().swap_with(&anchor_b.portal());
+    /// drop((anchor_a, anchor_b));
+    ///
+    /// assert_eq!((a, b), (2, 1));
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn swap_with(&self, other: &Self) {
+        if self.ptr_eq(other) {
+            return;
+        }
+        let (first, second) = if Arc::as_ptr(&self.0) < Arc::as_ptr(&other.0) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut first = first.write();
+        let mut second = second.write();
+        mem::swap(&mut *first, &mut *second);
+    }
+}
+
+impl<T> WPortal<T> {
+    /// Replaces the anchored value with `value`, returning the old one.  
+    /// The guard is released before this method returns.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn replace(&self, value: T) -> T {
+        mem::replace(&mut *self.lock(), value)
+    }
+
+    /// Replaces the anchored value with `value`.  
+    /// The old value is dropped after the guard has been released.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn set(&self, value: T) {
+        drop(self.replace(value));
+    }
+
+    /// Takes the anchored value, leaving `Default::default()` in its place.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn take(&self) -> T
+    where
+        T: Default,
+    {
+        self.replace(T::default())
+    }
+
+    /// Swaps the anchored values of this portal and `other`.  
+    /// Does nothing if both are associated with the same anchor.
+    ///
+    /// Both locks are acquired in a consistent order across all calls,
+    /// so concurrent swaps between the same portals in opposite directions can't deadlock.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn swap_with(&self, other: &Self) {
+        if self.ptr_eq(other) {
+            return;
+        }
+        let (first, second) = if Arc::as_ptr(&self.0) < Arc::as_ptr(&other.0) {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let mut first = first.lock();
+        let mut second = second.lock();
+        mem::swap(&mut *first, &mut *second);
+    }
+}
+
+impl<T: ?Sized> Clone for Portal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.acquire();
+        self.0.pipe_ref(Arc::clone).pipe(Self::new)
+    }
+}
+
+impl<T: ?Sized> GuardState for RwPortal<T> {
+    #[inline]
+    fn is_guarded(&self) -> bool {
+        self.is_locked()
+    }
+
+    fn acquisitions(&self) -> String {
+        held_here(&*self.0)
+    }
+}
+
+impl<T: ?Sized> GuardState for WPortal<T> {
+    #[inline]
+    fn is_guarded(&self) -> bool {
+        self.is_locked()
+    }
+
+    fn acquisitions(&self) -> String {
+        held_here(&*self.0)
+    }
+}
+
+impl<T: ?Sized> Clone for RwPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.acquire();
+        self.0.pipe_ref(Arc::clone).pipe(Self::new)
+    }
+}
+
+impl<T: ?Sized> Clone for WPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.acquire();
+        self.0.pipe_ref(Arc::clone).pipe(Self::new)
+    }
+}
+
+impl<T: ?Sized> Drop for Portal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.traces.release(&self.1);
+        self.0.release();
+    }
+}
+
+impl<T: ?Sized> Drop for RwPortal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.traces.release(&self.1);
+        self.0.release();
+    }
+}
+
+impl<T: ?Sized> Drop for WPortal<T> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.traces.release(&self.1);
+        self.0.release();
+    }
+}
+
+/// Reports a violation detected while dropping an anchor of `T` whose idle state is `idle`:
+/// Panics, or aborts the process if the anchor's group has [`ViolationPolicy::Abort`].
+#[cold]
+fn anchor_drop_violation<T: ?Sized>(
+    idle: &Idle,
+    violation: Violation,
+    label: Option<&str>,
+    strong_portals: usize,
+    extra: &dyn Display,
+) -> ! {
+    if idle.policy() == ViolationPolicy::Abort {
+        anchor_drop_abort::<T>(violation, label, strong_portals, extra)
+    } else {
+        anchor_drop_panic::<T>(violation, label, strong_portals, extra)
+    }
+}
+
+/// Leaves only a dangling pointer behind in the lock returned by `lock`, then poisons and releases it
+/// by unwinding through its guard without invoking the panic hook.
+///
+/// Without unwinding, the guard is leaked instead, so that the lock stays held until the process aborts.
+/// Call this only while the target is still valid.
+#[cold]
+fn dangle_poisoned<T: ?Sized, G: DerefMut<Target = SSNonNull<T>>>(lock: impl FnOnce() -> G) {
+    #[cfg(panic = "unwind")]
+    drop(panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut guard = lock();
+        guard.dangle();
+        panic::resume_unwind(Box::new(()))
+    })));
+    #[cfg(not(panic = "unwind"))]
+    {
+        let mut guard = lock();
+        guard.dangle();
+        mem::forget(guard);
+    }
+}
+
+/// Shows the target behind a non-blocking lock attempt's `result`.
+fn peek<T: ?Sized, G: Deref<Target = SSNonNull<T>>>(result: &TryLockResult<G>) -> Peek<'_, T> {
+    match result {
+        Ok(guard) => Peek::Value(unsafe {
+            //SAFETY: Valid and not mutated while `guard` is held. Dropping the anchor in use would have poisoned the lock.
+            guard.as_ref()
+        }),
+        Err(TryLockError::WouldBlock) => Peek::Locked,
+        Err(TryLockError::Poisoned(_)) => Peek::Poisoned,
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Portal<T> {
+    /// Shows the target, or `<expired>` if the anchor was dropped anyway while panicking.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let target = if self.0.is_anchored() {
+            Peek::Value(&**self)
+        } else {
+            Peek::Expired
+        };
+        f.debug_tuple("Portal").field(&target).finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for RwPortal<T> {
+    /// Shows the target iff it can be read without blocking, otherwise `<locked>` or `<poisoned>`.  
+    /// This doesn't count as acquisition and never deadlocks, even while the current thread holds a write guard.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// assert_eq!(format!("{:?}", portal), r#"RwPortal("Scoped")"#);
+    ///
+    /// let guard = portal.write();
+    /// assert_eq!(format!("{:?}", portal), "RwPortal(<locked>)");
+    /// # drop(guard);
+    /// # drop(portal);
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let guard = self.0.target.try_read();
+        f.debug_tuple("RwPortal").field(&peek(&guard)).finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for WPortal<T> {
+    /// Shows the target iff it can be locked without blocking, otherwise `<locked>` or `<poisoned>`.  
+    /// Like `RwPortal`'s, this never deadlocks.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let guard = self.0.target.try_lock();
+        f.debug_tuple("WPortal").field(&peek(&guard)).finish()
+    }
+}
+
+#[must_use]
+pub struct WeakPortal<T: ?Sized>(Weak<Shared<SSNonNull<T>>>, AnchorId);
+
+#[must_use]
+pub struct WeakRwPortal<T: ?Sized>(Weak<RwShared<T>>, AnchorId);
+
+#[must_use]
+pub struct WeakWPortal<T: ?Sized>(Weak<WShared<T>>, AnchorId);
+
+/// Upgrades `weak` and registers an additional strong portal iff the anchor still exists and isn't sealed.
+fn acquire<L, A>(weak: &Weak<Shared<L, A>>) -> Result<Arc<Shared<L, A>>, UpgradeError> {
+    let shared = weak.upgrade().ok_or(UpgradeError::Dropped)?;
+    shared.try_acquire()?;
+    Ok(shared)
+}
+
+/// Pins the anchor behind `weak` for internal use iff it still exists and isn't sealed, regardless of its portal limit.
+fn pin<L, A>(weak: &Weak<Shared<L, A>>) -> Result<Pin<L, A>, UpgradeError> {
+    let shared = weak.upgrade().ok_or(UpgradeError::Dropped)?;
+    shared.try_pin()?;
+    Ok(Pin(shared))
+}
+
+/// Counts as strong portal until dropped, without recording a trace like one or counting against the anchor's portal limit.
+struct Pin<L, A>(Arc<Shared<L, A>>);
+
+impl<L, A> Drop for Pin<L, A> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+impl<T: ?Sized> WeakPortal<T> {
+    #[inline]
+    pub fn try_upgrade(&self) -> Option<Portal<T>> {
+        acquire(&self.0).ok().map(Portal::new)
+    }
+
+    /// Calls `f` with the target iff the anchor still exists and isn't sealed.  
+    /// Unlike upgrading first, this doesn't record where a portal was created, so it's cheaper where that's done in a hot loop.
+    /// The anchor still counts `f` as a strong portal while it runs.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been dropped or sealed, without calling `f`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::{poison::Expired, sync::Anchor};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(weak_portal.read_with(|x| x.len()), Ok(6));
+    ///
+    /// drop(anchor);
+    /// assert_eq!(weak_portal.read_with(|x| x.len()), Err(Expired));
+    /// ```
+    pub fn read_with<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, Expired> {
+        self.read_pinned(f).map_err(|_| Expired)
+    }
+
+    /// Calls `f` with the target iff the anchor still exists and isn't sealed, like [`read_with`](#method.read_with),
+    /// but reports why it didn't.
+    fn read_pinned<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, UpgradeError> {
+        let pin = pin(&self.0)?;
+        let target = unsafe {
+            //SAFETY: Valid while the anchor exists, and dropping it while pinned panics.
+            pin.0.target.as_ref()
+        };
+        Ok(f(target))
+    }
+
+    #[inline]
+    pub fn upgrade(&self) -> Portal<T> {
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Returns `true` iff the anchor still exists, so that upgrading would succeed.  
+    /// Unlike `try_upgrade`, this doesn't create a portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let weak_portal = anchor.weak_portal();
+    /// let portal = anchor.portal();
+    /// assert!(weak_portal.is_alive());
+    /// assert_eq!(weak_portal.strong_count(), 1);
+    ///
+    /// drop(portal);
+    /// drop(anchor);
+    /// assert!(!weak_portal.is_alive());
+    /// assert_eq!(weak_portal.strong_count(), 0);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.upgrade().is_some_and(|shared| shared.is_anchored())
+    }
+
+    /// Returns the number of (strong) portals currently associated with the anchor, `0` once it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        self.0
+            .upgrade()
+            .map_or(0, |shared| shared.strong_portal_count())
+    }
+
+    /// Returns the unique ID of the anchor this weak portal is associated with.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub const fn anchor_id(&self) -> AnchorId {
+        self.1
+    }
+
+    /// Returns `true` iff this weak portal and `other` are associated with the same anchor.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    /// Returns `true` iff this weak portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &Anchor<'_, T>) -> bool {
+        ptr::addr_eq(self.0.as_ptr(), Arc::as_ptr(&anchor.reference))
+    }
+}
+
+impl<T: ?Sized> WeakRwPortal<T> {
+    #[inline]
+    pub fn try_upgrade(&self) -> Option<RwPortal<T>> {
+        acquire(&self.0).ok().map(RwPortal::new)
+    }
+
+    #[inline]
+    pub fn upgrade(&self) -> RwPortal<T> {
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Like `try_upgrade`, but also fails if the anchor is poisoned, so that the first read or write through the portal can't panic because of that.  
+    /// The anchor can still be poisoned later, by a panic while it's locked for writing.
+    ///
+    /// # Errors
+    ///
+    /// [`UpgradeError::Dropped`] if the anchor has been dropped,  
+    /// [`UpgradeError::Sealed`] if it has been sealed,  
+    /// [`UpgradeError::Poisoned`] if it has been poisoned,  
+    /// [`UpgradeError::LimitExceeded`] if it has as many portals as its limit allows.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::{poison::UpgradeError, sync::RwAnchor};
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert!(weak_portal.try_upgrade_checked().is_ok());
+    ///
+    /// let portal = weak_portal.upgrade();
+    /// assert_panic!({
+    ///     let _guard = portal.write();
+    ///     panic!()
+    /// });
+    /// drop(portal);
+    /// assert_eq!(weak_portal.try_upgrade_checked().unwrap_err(), UpgradeError::Poisoned);
+    ///
+    /// assert_panic!(drop(anchor), String, starts with "Anchor poisoned");
+    /// assert_eq!(weak_portal.try_upgrade_checked().unwrap_err(), UpgradeError::Dropped);
+    /// ```
+    #[inline]
+    pub fn try_upgrade_checked(&self) -> Result<RwPortal<T>, UpgradeError> {
+        let portal = acquire(&self.0).map(RwPortal::new)?;
+        // A currently held write lock can only have been taken out while the anchor wasn't poisoned.
+        portal.0.settle();
+        if let Err(TryLockError::Poisoned(_)) = portal.0.target.try_read() {
+            return Err(UpgradeError::Poisoned);
+        }
+        Ok(portal)
+    }
+
+    /// Upgrades this weak portal temporarily and calls `f` with the target iff it can be locked for reading without blocking.  
+    /// The portal and guard are released before this method returns.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] if the anchor has been dropped,  
+    /// [`AccessError::Sealed`] if it has been sealed,  
+    /// [`AccessError::Poisoned`] if it has been poisoned,  
+    /// [`AccessError::LimitExceeded`] if it has as many portals as its limit allows,  
+    /// [`AccessError::WouldBlock`] if the target is locked for writing.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read_upgraded<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, AccessError> {
+        let portal = acquire(&self.0).map(RwPortal::new)?;
+        let guard = match portal.0.target.try_read() {
+            Err(TryLockError::Poisoned(error)) => {
+                drop(error);
+                portal.0.settle();
+                portal.0.target.try_read()
+            }
+            result => result,
+        };
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                portal.0.counters.contended();
+                return Err(AccessError::WouldBlock);
+            }
+            Err(TryLockError::Poisoned(_)) => return Err(AccessError::Poisoned),
+        };
+        let guard = PortalReadGuard(
+            guard,
+            &portal.0,
+            portal
+                .0
+                .held::<T>("sync::WeakRwPortal::try_read_upgraded", false),
+        );
+        Ok(f(&guard))
+    }
+
+    /// Upgrades this weak portal temporarily and calls `f` with the target iff it can be locked for writing without blocking.  
+    /// The portal and guard are released before this method returns.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] if the anchor has been dropped,  
+    /// [`AccessError::Sealed`] if it has been sealed,  
+    /// [`AccessError::Poisoned`] if it has been poisoned,  
+    /// [`AccessError::LimitExceeded`] if it has as many portals as its limit allows,  
+    /// [`AccessError::WouldBlock`] if the target is locked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::{poison::AccessError, sync::RwAnchor};
+    ///
+    /// let mut x = 1;
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(weak_portal.try_write_upgraded(|x| { *x += 1; *x }), Ok(2));
+    ///
+    /// let portal = anchor.portal();
+    /// let guard = portal.read();
+    /// assert_eq!(weak_portal.try_write_upgraded(|x| *x), Err(AccessError::WouldBlock));
+    /// assert_eq!(weak_portal.try_read_upgraded(|x| *x), Ok(2));
+    /// drop(guard);
+    /// drop(portal);
+    ///
+    /// drop(anchor);
+    /// assert_eq!(weak_portal.try_read_upgraded(|x| *x), Err(AccessError::Dropped));
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_write_upgraded<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R, AccessError> {
+        let portal = acquire(&self.0).map(RwPortal::new)?;
+        portal
+            .0
+            .check_writer("sync::WeakRwPortal::try_write_upgraded");
+        if portal.0.try_write_grant().is_err() {
+            return Err(AccessError::WouldBlock);
+        }
+        let guard = match portal.0.target.try_write() {
+            Err(TryLockError::Poisoned(error)) => {
+                drop(error);
+                portal.0.settle();
+                portal.0.target.try_write()
+            }
+            result => result,
+        };
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                portal.0.counters.contended();
+                portal.0.refund_write_grant();
+                return Err(AccessError::WouldBlock);
+            }
+            Err(TryLockError::Poisoned(_)) => {
+                portal.0.refund_write_grant();
+                return Err(AccessError::Poisoned);
+            }
+        };
+        let mut guard = PortalWriteGuard(
+            ManuallyDrop::new(guard),
+            &portal.0,
+            portal
+                .0
+                .held::<T>("sync::WeakRwPortal::try_write_upgraded", true),
+            false,
+        );
+        Ok(f(&mut guard))
+    }
+
+    /// Returns `true` iff the anchor still exists, so that upgrading would succeed.  
+    /// Unlike `try_upgrade`, this doesn't create a portal.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.upgrade().is_some_and(|shared| shared.is_anchored())
+    }
+
+    /// Returns the number of (strong) portals currently associated with the anchor, `0` once it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        self.0
+            .upgrade()
+            .map_or(0, |shared| shared.strong_portal_count())
+    }
+
+    /// Returns the unique ID of the anchor this weak portal is associated with.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub const fn anchor_id(&self) -> AnchorId {
+        self.1
+    }
+
+    /// Returns `true` iff this weak portal and `other` are associated with the same anchor.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    /// Returns `true` iff this weak portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &RwAnchor<'_, T>) -> bool {
+        ptr::addr_eq(self.0.as_ptr(), Arc::as_ptr(&anchor.reference))
+    }
+}
+
+impl<T: Clone> WeakRwPortal<T> {
+    /// Clones the anchored value out like [`RwPortal::snapshot`], or returns `default` if the anchor has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(weak_portal.snapshot_or("Gone".to_owned()), "Scoped");
+    ///
+    /// drop(anchor);
+    /// assert_eq!(weak_portal.snapshot_or("Gone".to_owned()), "Gone");
+    /// assert_eq!(weak_portal.snapshot_or_default(), "");
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot_or(&self, default: T) -> T {
+        self.try_upgrade()
+            .map_or(default, |portal| portal.snapshot())
+    }
+
+    /// Clones the anchored value out like [`RwPortal::snapshot`], or returns `T::default()` if the anchor has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn snapshot_or_default(&self) -> T
+    where
+        T: Default,
+    {
+        self.try_upgrade()
+            .map_or_else(T::default, |portal| portal.snapshot())
+    }
+}
+
+impl<T: ?Sized> WeakWPortal<T> {
+    #[inline]
+    pub fn try_upgrade(&self) -> Option<WPortal<T>> {
+        acquire(&self.0).ok().map(WPortal::new)
+    }
+
+    #[inline]
+    pub fn upgrade(&self) -> WPortal<T> {
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Like `try_upgrade`, but also fails if the anchor is poisoned, so that the first lock through the portal can't panic because of that.  
+    /// The anchor can still be poisoned later, by a panic while it's locked.
+    ///
+    /// # Errors
+    ///
+    /// [`UpgradeError::Dropped`] if the anchor has been dropped,  
+    /// [`UpgradeError::Sealed`] if it has been sealed,  
+    /// [`UpgradeError::Poisoned`] if it has been poisoned,  
+    /// [`UpgradeError::LimitExceeded`] if it has as many portals as its limit allows.
+    #[inline]
+    pub fn try_upgrade_checked(&self) -> Result<WPortal<T>, UpgradeError> {
+        let portal = acquire(&self.0).map(WPortal::new)?;
+        // A currently held lock can only have been taken out while the anchor wasn't poisoned.
+        portal.0.settle();
+        if let Err(TryLockError::Poisoned(_)) = portal.0.target.try_lock() {
+            return Err(UpgradeError::Poisoned);
+        }
+        Ok(portal)
+    }
+
+    /// Upgrades this weak portal temporarily and calls `f` with the target iff it can be locked without blocking.  
+    /// The portal and guard are released before this method returns.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] if the anchor has been dropped,  
+    /// [`AccessError::Sealed`] if it has been sealed,  
+    /// [`AccessError::Poisoned`] if it has been poisoned,  
+    /// [`AccessError::LimitExceeded`] if it has as many portals as its limit allows,  
+    /// [`AccessError::WouldBlock`] if the target is locked.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_lock_upgraded<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R, AccessError> {
+        let portal = acquire(&self.0).map(WPortal::new)?;
+        let guard = match portal.0.target.try_lock() {
+            Err(TryLockError::Poisoned(error)) => {
+                drop(error);
+                portal.0.settle();
+                portal.0.target.try_lock()
+            }
+            result => result,
+        };
+        let guard = match guard {
+            Ok(guard) => guard,
+            Err(TryLockError::WouldBlock) => {
+                portal.0.counters.contended();
+                return Err(AccessError::WouldBlock);
+            }
+            Err(TryLockError::Poisoned(_)) => return Err(AccessError::Poisoned),
+        };
+        let mut guard = PortalMutexGuard(
+            ManuallyDrop::new(guard),
+            &portal.0,
+            portal
+                .0
+                .held::<T>("sync::WeakWPortal::try_lock_upgraded", true),
+            false,
+        );
+        Ok(f(&mut guard))
+    }
+
+    /// Returns `true` iff the anchor still exists, so that upgrading would succeed.  
+    /// Unlike `try_upgrade`, this doesn't create a portal.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.upgrade().is_some_and(|shared| shared.is_anchored())
+    }
+
+    /// Returns the number of (strong) portals currently associated with the anchor, `0` once it has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn strong_count(&self) -> usize {
+        self.0
+            .upgrade()
+            .map_or(0, |shared| shared.strong_portal_count())
+    }
+
+    /// Returns the unique ID of the anchor this weak portal is associated with.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub const fn anchor_id(&self) -> AnchorId {
+        self.1
+    }
+
+    /// Returns `true` iff this weak portal and `other` are associated with the same anchor.  
+    /// This works even after the anchor has been dropped.
+    #[inline]
+    #[must_use]
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
+    /// Returns `true` iff this weak portal is associated with `anchor`.
+    #[inline]
+    #[must_use]
+    pub fn is_from(&self, anchor: &WAnchor<'_, T>) -> bool {
+        ptr::addr_eq(self.0.as_ptr(), Arc::as_ptr(&anchor.reference))
+    }
+}
+
+impl<T: ?Sized> Clone for WeakPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0), self.1)
+    }
+}
+
+impl<T: ?Sized> Clone for WeakRwPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0), self.1)
+    }
+}
+
+impl<T: ?Sized> Clone for WeakWPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Weak::clone(&self.0), self.1)
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for WeakPortal<T> {
+    /// Shows the target iff the anchor still exists and isn't sealed, otherwise `<expired>`.  
+    /// Like [`read_with`](`WeakPortal::read_with`), this counts as strong portal while formatting.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pin = pin(&self.0);
+        let target = match &pin {
+            Ok(pin) => Peek::Value(unsafe {
+                //SAFETY: Valid while the anchor exists, and dropping it while pinned panics.
+                pin.0.target.as_ref()
+            }),
+            Err(_) => Peek::Expired,
+        };
+        f.debug_tuple("WeakPortal").field(&target).finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for WeakRwPortal<T> {
+    /// Shows the target iff the anchor still exists and isn't sealed, and the target can be read without blocking.  
+    /// Otherwise, shows `<expired>`, `<locked>` or `<poisoned>`.
+    /// This counts as strong portal while formatting.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let weak_portal = anchor.weak_portal();
+    /// assert_eq!(format!("{:?}", weak_portal), r#"WeakRwPortal("Scoped")"#);
+    ///
+    /// drop(anchor);
+    /// assert_eq!(format!("{:?}", weak_portal), "WeakRwPortal(<expired>)");
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("WeakRwPortal");
+        match pin(&self.0) {
+            Ok(pin) => tuple.field(&peek(&pin.0.target.try_read())),
+            Err(_) => tuple.field(&Peek::<T>::Expired),
+        };
+        tuple.finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for WeakWPortal<T> {
+    /// Shows the target iff the anchor still exists and isn't sealed, and the target can be locked without blocking.  
+    /// Otherwise, shows `<expired>`, `<locked>` or `<poisoned>`.
+    /// This counts as strong portal while formatting.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("WeakWPortal");
+        match pin(&self.0) {
+            Ok(pin) => tuple.field(&peek(&pin.0.target.try_lock())),
+            Err(_) => tuple.field(&Peek::<T>::Expired),
+        };
+        tuple.finish()
+    }
+}
+
+/// A `HashMap` or `HashSet` key that hashes and compares by anchor identity, not by value.  
+/// Obtained from any portal or weak portal via `From`, without borrowing the target.
+///
+/// Keys don't keep the anchor alive, but do keep its bookkeeping allocated, so that identities aren't reused while a key exists.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{Anchor, PortalKey},
+///     std::collections::HashSet,
+/// };
+///
+/// let x = "Scoped".to_owned();
+/// let y = "Scoped".to_owned();
+/// let anchor_x = Anchor::new(&x);
+/// let anchor_y = Anchor::new(&y);
+/// let portal = anchor_x.portal();
+///
+/// let mut observers = HashSet::new();
+/// observers.insert(PortalKey::from(&portal));
+/// assert!(observers.contains(&PortalKey::from(&anchor_x.weak_portal())));
+/// assert!(!observers.contains(&PortalKey::from(&anchor_y.portal())));
+/// # drop(portal);
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct PortalKey<T: ?Sized>(KeyTarget<T>);
+
+/// The anchor a [`PortalKey`] identifies.
+#[derive(Debug)]
+enum KeyTarget<T: ?Sized> {
+    /// From an [`Anchor`].
+    Portal(WeakPortal<T>),
+    /// From an [`RwAnchor`].
+    RwPortal(WeakRwPortal<T>),
+    /// From a [`WAnchor`].
+    WPortal(WeakWPortal<T>),
+}
+
+impl<T: ?Sized> PortalKey<T> {
+    /// The address of the shared anchor state, which identifies the anchor.
+    fn address(&self) -> *const () {
+        match &self.0 {
+            KeyTarget::Portal(weak) => weak.0.as_ptr().cast(),
+            KeyTarget::RwPortal(weak) => weak.0.as_ptr().cast(),
+            KeyTarget::WPortal(weak) => weak.0.as_ptr().cast(),
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for PortalKey<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match &self.0 {
+            KeyTarget::Portal(weak) => KeyTarget::Portal(weak.clone()),
+            KeyTarget::RwPortal(weak) => KeyTarget::RwPortal(weak.clone()),
+            KeyTarget::WPortal(weak) => KeyTarget::WPortal(weak.clone()),
+        }
+        .pipe(Self)
+    }
+}
+
+impl<T: ?Sized> PartialEq for PortalKey<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self.address(), other.address())
+    }
+}
+
+impl<T: ?Sized> Eq for PortalKey<T> {}
+
+impl<T: ?Sized> Hash for PortalKey<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.address().hash(state);
+    }
+}
+
+impl<T: ?Sized> From<&Portal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(portal: &Portal<T>) -> Self {
+        Portal::downgrade(portal).pipe(KeyTarget::Portal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&RwPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(portal: &RwPortal<T>) -> Self {
+        portal.downgrade().pipe(KeyTarget::RwPortal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&WPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(portal: &WPortal<T>) -> Self {
+        portal.downgrade().pipe(KeyTarget::WPortal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&WeakPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(weak_portal: &WeakPortal<T>) -> Self {
+        weak_portal.clone().pipe(KeyTarget::Portal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&WeakRwPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(weak_portal: &WeakRwPortal<T>) -> Self {
+        weak_portal.clone().pipe(KeyTarget::RwPortal).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> From<&WeakWPortal<T>> for PortalKey<T> {
+    #[inline]
+    fn from(weak_portal: &WeakWPortal<T>) -> Self {
+        weak_portal.clone().pipe(KeyTarget::WPortal).pipe(Self)
+    }
+}
+
+/// A list of weak portals to notify, e.g. the observers of a subject.  
+/// Portals whose anchor has been dropped are pruned while iterating.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{Anchor, Observers};
+///
+/// let a = "A".to_owned();
+/// let b = "B".to_owned();
+/// let anchor_a = Anchor::new(&a);
+/// let anchor_b = Anchor::new(&b);
+///
+/// let mut observers = Observers::new();
+/// observers.push(anchor_a.weak_portal());
+/// observers.push(anchor_b.weak_portal());
+/// drop(anchor_a);
+///
+/// let mut notified = Vec::new();
+/// observers.for_each_alive(|observer| notified.push(observer.clone()));
+/// assert_eq!(notified, ["B"]);
+/// assert_eq!(observers.len(), 1);
+/// ```
+#[derive(Debug)]
+pub struct Observers<T: ?Sized>(Vec<WeakPortal<T>>);
+
+impl<T: ?Sized> Observers<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds `observer` at the end of the list.
+    #[inline]
+    pub fn push(&mut self, observer: WeakPortal<T>) {
+        self.0.push(observer);
+    }
+
+    /// Calls `f` with the target of each observer whose anchor still exists and isn't sealed, in the order they were added.  
+    /// Removes the observers whose anchor has been dropped.
+    ///
+    /// # Example
+    ///
+    /// Observers whose anchor merely can't be upgraded right now are kept:
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{Anchor, Observers},
+    ///     std::time::Instant,
+    /// };
+    ///
+    /// let a = "A".to_owned();
+    /// let b = "B".to_owned();
+    /// let limited = Anchor::with_limit(&a, 1);
+    /// let portal = limited.portal();
+    /// let sealed = Anchor::with_deadline(&b, Instant::now());
+    ///
+    /// let mut observers = Observers::new();
+    /// observers.push(limited.weak_portal());
+    /// observers.push(sealed.weak_portal());
+    ///
+    /// let mut notified = Vec::new();
+    /// observers.for_each_alive(|observer| notified.push(observer.clone()));
+    /// assert_eq!(notified, ["A"]);
+    /// assert_eq!(observers.len(), 2);
+    /// # drop(portal);
+    /// ```
+    pub fn for_each_alive(&mut self, mut f: impl FnMut(&T)) {
+        self.0
+            .retain(|observer| observer.read_pinned(&mut f) != Err(UpgradeError::Dropped));
+    }
+
+    /// Removes observers whose anchor has been dropped without iterating the others, and releases the freed capacity.  
+    /// Returns the number of observers removed.
+    ///
+    /// Call this periodically on lists that are rarely iterated but often pushed to, e.g. in long-running registries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{Anchor, Observers};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let mut observers: Observers<_> = anchor.weak_portals(3).into_iter().collect();
+    /// drop(anchor);
+    ///
+    /// assert_eq!(observers.compact(), 3);
+    /// assert!(observers.is_empty());
+    /// ```
+    pub fn compact(&mut self) -> usize {
+        let count = self.0.len();
+        self.0.retain(WeakPortal::is_alive);
+        self.0.shrink_to_fit();
+        count - self.0.len()
+    }
+
+    /// Returns the number of observers, including any whose anchor was dropped since they were last iterated.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` iff there are no observers, not even ones whose anchor was dropped since they were last iterated.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T: ?Sized> Default for Observers<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> Extend<WeakPortal<T>> for Observers<T> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = WeakPortal<T>>>(&mut self, observers: I) {
+        self.0.extend(observers);
+    }
+}
+
+impl<T: ?Sized> FromIterator<WeakPortal<T>> for Observers<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = WeakPortal<T>>>(observers: I) -> Self {
+        observers.into_iter().collect::<Vec<_>>().pipe(Self)
+    }
+}
+
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can deadlock other tasks on this thread"
+)]
+struct PortalReadGuard<'a, T: 'a + ?Sized>(
+    RwLockReadGuard<'a, SSNonNull<T>>,
+    &'a RwShared<T>,
+    Held,
+);
+
+/// Records the poison origin if released while unwinding, or clears the poison instead if defused.
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can deadlock other tasks on this thread"
+)]
+struct PortalWriteGuard<'a, T: 'a + ?Sized>(
+    ManuallyDrop<RwLockWriteGuard<'a, SSNonNull<T>>>,
+    &'a RwShared<T>,
+    Held,
+    bool,
+);
+
+/// Records the poison origin if released while unwinding, or clears the poison instead if defused.
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can deadlock other tasks on this thread"
+)]
+struct PortalMutexGuard<'a, T: 'a + ?Sized>(
+    ManuallyDrop<MutexGuard<'a, SSNonNull<T>>>,
+    &'a WShared<T>,
+    Held,
+    bool,
+);
+
+impl<'a, T: ?Sized> Deref for PortalReadGuard<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        let pointer = self.0.deref();
+        unsafe {
+            //SAFETY: Valid as long as self.0 is.
+            pointer.as_ref()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for PortalWriteGuard<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        let pointer: &SSNonNull<T> = &self.0;
+        unsafe {
+            //SAFETY: Valid as long as self.0 is.
+            pointer.as_ref()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Deref for PortalMutexGuard<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        let pointer: &SSNonNull<T> = &self.0;
+        unsafe {
+            //SAFETY: Valid as long as self.0 is.
+            pointer.as_ref()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for PortalWriteGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        let pointer: &mut SSNonNull<T> = &mut self.0;
+        unsafe {
+            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
+            pointer.as_mut()
+        }
+    }
+}
+
+impl<'a, T: ?Sized> DerefMut for PortalMutexGuard<'a, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        let pointer: &mut SSNonNull<T> = &mut self.0;
+        unsafe {
+            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
+            pointer.as_mut()
         }
-        .pipe(Arc::try_unwrap)
-        .unwrap_or_else(|reference| {
-            // Poison Mutex.
-            let _guard = reference.lock();
-            panic!(ANCHOR_STILL_IN_USE);
-        })
-        .into_inner()
-        .unwrap_or_else(|error| Err(error).expect(ANCHOR_POISONED));
     }
 }
 
-/// # Safety:
-///
-/// ```rust
-/// # use assert_panic::assert_panic;
-/// use ref_portals::sync::RwAnchor;
-///
-/// let mut x = "Scoped".to_owned();
-/// let anchor = RwAnchor::new(&mut x);
-/// let portal = anchor.portal();
+/// Anchors part of a guarded value, to re-lend it (e.g. to worker threads) while the guard is held.  
+/// Implemented by the guards returned from [`RwPortal::write`] and [`WPortal::lock`], and their `try_` variants.
 ///
-/// assert_panic!(
-///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
-/// );
-/// assert_panic!(
-///     { portal.read(); },
-///     String,
-///     starts with "Anchor poisoned:",
-/// );
-/// ```
-impl<'a, T: ?Sized> UnwindSafe for RwAnchor<'a, T> where T: RefUnwindSafe {}
-
-/// # Safety:
+/// # Example
 ///
 /// ```rust
-/// # use assert_panic::assert_panic;
-/// use ref_portals::sync::WAnchor;
+/// use {
+///     ref_portals::sync::{AnchorField, RwAnchor},
+///     std::thread,
+/// };
 ///
-/// let mut x = "Scoped".to_owned();
-/// let anchor = WAnchor::new(&mut x);
+/// struct State {
+///     counter: usize,
+///     log: Vec<String>,
+/// }
+///
+/// let mut state = State { counter: 0, log: Vec::new() };
+/// let anchor = RwAnchor::new(&mut state);
 /// let portal = anchor.portal();
+/// {
+///     let mut guard = portal.write();
+///     guard.counter += 1;
+///     let log_anchor = guard.anchor_field(|state| &mut state.log);
+///     let log_portal = log_anchor.portal();
+///     thread::spawn(move || log_portal.write().push("Worker".to_owned()))
+///         .join()
+///         .unwrap();
+/// }
+/// drop(portal);
+/// drop(anchor);
 ///
-/// assert_panic!(
-///     drop(anchor),
-///     &str,
-///     "Anchor still in use (at least one portal exists)",
-/// );
-/// assert_panic!(
-///     { portal.lock(); },
-///     String,
-///     starts with "Anchor poisoned:",
-/// );
+/// assert_eq!(state.log, ["Worker"]);
 /// ```
-impl<'a, T: ?Sized> UnwindSafe for WAnchor<'a, T> where T: RefUnwindSafe {}
-
-/// A threadsafe immutable portal.  
-/// Dereference it directly with `*` or `.deref()`.
-#[derive(Debug)]
-#[must_use]
-#[repr(transparent)]
-pub struct Portal<T: ?Sized>(Arc<SSNonNull<T>>);
-
-/// A threadsafe mutable portal supporting concurred reads.  
-/// Acquire a guard by calling `.read()` or `.write()`.
-#[derive(Debug)]
-#[must_use]
-#[repr(transparent)]
-pub struct RwPortal<T: ?Sized>(Arc<RwLock<SSNonNull<T>>>);
+pub trait AnchorField: DerefMut {
+    /// Anchors the part of the guarded value selected by `project`.  
+    /// The returned anchor borrows this guard, so it (and with it, the use check on its portals) ends before the guard can be released.
+    #[inline]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    fn anchor_field<'a, F: ?Sized>(
+        &'a mut self,
+        project: impl FnOnce(&'a mut Self::Target) -> &'a mut F,
+    ) -> RwAnchor<'a, F> {
+        RwAnchor::new(project(self))
+    }
+}
 
-/// A threadsafe mutable portal with only exclusive access.  
-/// Acquire a guard by calling `.lock()`.
-#[derive(Debug)]
-#[must_use]
-#[repr(transparent)]
-pub struct WPortal<T: ?Sized>(Arc<Mutex<SSNonNull<T>>>);
+impl<'a, T: ?Sized> AsRef<T> for PortalReadGuard<'a, T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
 
-impl<T: ?Sized> Portal<T> {
-    /// Creates a weak portal associated with the same anchor as `portal`.  
-    /// Dropping an anchor doesn't panic if only weak portals exist.
+impl<'a, T: ?Sized> AsRef<T> for PortalWriteGuard<'a, T> {
     #[inline]
-    pub fn downgrade(portal: &Self) -> WeakPortal<T> {
-        Arc::downgrade(&portal.0).pipe(WeakPortal)
+    fn as_ref(&self) -> &T {
+        self
     }
 }
 
-impl<T: ?Sized> Deref for Portal<T> {
-    type Target = T;
+impl<'a, T: ?Sized> AsMut<T> for PortalWriteGuard<'a, T> {
     #[inline]
-    fn deref(&self) -> &Self::Target {
-        let pointer = self.0.deref();
-        unsafe {
-            //SAFETY: Valid as long as self.0 is.
-            pointer.as_ref()
-        }
+    fn as_mut(&mut self) -> &mut T {
+        &mut *self
     }
 }
 
-impl<T: ?Sized> Borrow<T> for Portal<T> {
+impl<'a, T: ?Sized> AsRef<T> for PortalMutexGuard<'a, T> {
     #[inline]
-    fn borrow(&self) -> &T {
-        &*self
+    fn as_ref(&self) -> &T {
+        self
     }
 }
 
-impl<T: ?Sized> RwPortal<T> {
-    /// Creates a weak portal associated with the same anchor as this one.  
-    /// Dropping an anchor doesn't panic if only weak portals exist.
+impl<'a, T: ?Sized> AsMut<T> for PortalMutexGuard<'a, T> {
     #[inline]
-    pub fn downgrade(&self) -> WeakRwPortal<T> {
-        Arc::downgrade(&self.0).pipe(WeakRwPortal)
+    fn as_mut(&mut self) -> &mut T {
+        &mut *self
     }
+}
 
+impl<'a, T: ?Sized> HeldFor for PortalReadGuard<'a, T> {
+    #[cfg(feature = "watchdog")]
     #[inline]
-    pub fn read<'a>(&'a self) -> impl Deref<Target = T> + 'a {
-        self.0.read().expect(ANCHOR_POISONED).pipe(PortalReadGuard)
+    fn held_for(&self) -> Duration {
+        self.2.held_for()
     }
+}
 
+impl<'a, T: ?Sized> HeldFor for PortalWriteGuard<'a, T> {
+    #[cfg(feature = "watchdog")]
     #[inline]
-    pub fn write<'a>(&'a self) -> impl DerefMut<Target = T> + 'a {
-        self.0
-            .write()
-            .expect(ANCHOR_POISONED)
-            .pipe(PortalWriteGuard)
+    fn held_for(&self) -> Duration {
+        self.2.held_for()
     }
 }
 
-impl<T: ?Sized> WPortal<T> {
-    /// Creates a weak portal associated with the same anchor as this one.  
-    /// Dropping an anchor doesn't panic if only weak portals exist.
+impl<'a, T: ?Sized> HeldFor for PortalMutexGuard<'a, T> {
+    #[cfg(feature = "watchdog")]
     #[inline]
-    pub fn downgrade(&self) -> WeakWPortal<T> {
-        Arc::downgrade(&self.0).pipe(WeakWPortal)
+    fn held_for(&self) -> Duration {
+        self.2.held_for()
     }
+}
 
+impl<'a, T: ?Sized> Defuse for PortalWriteGuard<'a, T> {
     #[inline]
-    pub fn lock<'a>(&'a self) -> impl DerefMut<Target = T> + 'a {
-        self.0.lock().expect(ANCHOR_POISONED).pipe(PortalMutexGuard)
+    fn defuse(&mut self) {
+        self.3 = true;
     }
 }
 
-impl<T: ?Sized> Clone for Portal<T> {
+impl<'a, T: ?Sized> Drop for PortalWriteGuard<'a, T> {
     #[inline]
-    fn clone(&self) -> Self {
-        self.0.pipe_ref(Arc::clone).pipe(Self)
+    fn drop(&mut self) {
+        let defusing = match (thread::panicking(), self.3) {
+            (false, _) => None,
+            // Threads that see the poison before it's cleared wait for this before panicking.
+            (true, true) => self
+                .1
+                .defusing
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pipe(Some),
+            (true, false) => {
+                self.1.poison_origin.record();
+                None
+            }
+        };
+        // Counted before releasing the lock, so that readers see it along with the change.
+        self.1.writes.fetch_add(1, Ordering::Relaxed);
+        unsafe {
+            //SAFETY: Dropping.
+            ManuallyDrop::drop(&mut self.0);
+        }
+        if let Some(defusing) = defusing {
+            // Loom's locks are never poisoned.
+            #[cfg(not(loom))]
+            self.1.target.clear_poison();
+            drop(defusing);
+        }
     }
 }
 
-impl<T: ?Sized> Clone for RwPortal<T> {
+impl<'a, T: ?Sized> Defuse for PortalMutexGuard<'a, T> {
     #[inline]
-    fn clone(&self) -> Self {
-        self.0.pipe_ref(Arc::clone).pipe(Self)
+    fn defuse(&mut self) {
+        self.3 = true;
     }
 }
 
-impl<T: ?Sized> Clone for WPortal<T> {
+impl<'a, T: ?Sized> Drop for PortalMutexGuard<'a, T> {
     #[inline]
-    fn clone(&self) -> Self {
-        self.0.pipe_ref(Arc::clone).pipe(Self)
+    fn drop(&mut self) {
+        let defusing = match (thread::panicking(), self.3) {
+            (false, _) => None,
+            // Threads that see the poison before it's cleared wait for this before panicking.
+            (true, true) => self
+                .1
+                .defusing
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pipe(Some),
+            (true, false) => {
+                self.1.poison_origin.record();
+                None
+            }
+        };
+        unsafe {
+            //SAFETY: Dropping.
+            ManuallyDrop::drop(&mut self.0);
+        }
+        if let Some(defusing) = defusing {
+            // Loom's locks are never poisoned.
+            #[cfg(not(loom))]
+            self.1.target.clear_poison();
+            drop(defusing);
+        }
     }
 }
 
-#[derive(Debug)]
-#[must_use]
-#[repr(transparent)]
-pub struct WeakPortal<T: ?Sized>(Weak<SSNonNull<T>>);
+impl<'a, T: ?Sized> AnchorField for PortalWriteGuard<'a, T> {}
+impl<'a, T: ?Sized> AnchorField for PortalMutexGuard<'a, T> {}
 
-#[derive(Debug)]
+/// A read guard that can be cloned cheaply, as returned by [`RwPortal::read_arc`].  
+/// Writers are blocked until the last clone is dropped.
+///
+/// Like the other guards, it can't be sent to other threads, since the standard locks must be released on the thread that acquired them.
+/// For the same reason, it isn't `Sync` either: Another thread could clone it through a shared reference and then drop the last clone.
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can deadlock other tasks on this thread"
+)]
 #[must_use]
-#[repr(transparent)]
-pub struct WeakRwPortal<T: ?Sized>(Weak<RwLock<SSNonNull<T>>>);
+pub struct ArcReadGuard<T: ?Sized + 'static> {
+    /// Points into the target locked through `lock`, or a part of it.
+    target: NonNull<T>,
 
-#[derive(Debug)]
-#[must_use]
-#[repr(transparent)]
-pub struct WeakWPortal<T: ?Sized>(Weak<Mutex<SSNonNull<T>>>);
+    /// Shared between clones, including mapped ones.
+    lock: Arc<dyn HeldFor>,
+}
 
-impl<T: ?Sized> WeakPortal<T> {
+/// The lock shared between clones of an `ArcReadGuard`.
+struct ArcRead<T: ?Sized + 'static> {
+    /// Borrows from `_portal`'s shared state, so it must be (and, being declared first, is) dropped first.
+    guard: PortalReadGuard<'static, T>,
+
+    /// Keeps the lock alive and counts as strong portal while the guard is held.
+    _portal: RwPortal<T>,
+}
+
+impl<T: ?Sized> ArcReadGuard<T> {
+    /// Projects `guard` onto a part of its target, keeping the lock.  
+    /// This is an associated function so that it can't shadow a method of the target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{ArcReadGuard, RwAnchor};
+    ///
+    /// let mut x = ("Scoped".to_owned(), 1);
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let name: ArcReadGuard<str> = ArcReadGuard::map(portal.read_arc(), |x| x.0.as_str());
+    /// drop(portal);
+    /// assert_eq!(&*name, "Scoped");
+    /// # drop(name);
+    /// ```
     #[inline]
-    pub fn try_upgrade(&self) -> Option<Portal<T>> {
-        self.0.upgrade().map(Portal)
+    pub fn map<U: ?Sized>(guard: Self, project: impl FnOnce(&T) -> &U) -> ArcReadGuard<U> {
+        ArcReadGuard {
+            target: project(&guard).pipe(NonNull::from),
+            lock: guard.lock,
+        }
     }
 
+    /// Projects `guard` onto a part of its target iff `project` returns one, keeping the lock.
+    ///
+    /// # Errors
+    ///
+    /// Iff `project` returns `None`, with the original `guard`.
     #[inline]
-    pub fn upgrade(&self) -> Portal<T> {
-        self.try_upgrade().expect(ANCHOR_DROPPED)
+    pub fn try_map<U: ?Sized>(
+        guard: Self,
+        project: impl FnOnce(&T) -> Option<&U>,
+    ) -> Result<ArcReadGuard<U>, Self> {
+        match project(&guard).map(NonNull::from) {
+            Some(target) => Ok(ArcReadGuard {
+                target,
+                lock: guard.lock,
+            }),
+            None => Err(guard),
+        }
     }
 }
 
-impl<T: ?Sized> WeakRwPortal<T> {
+impl<T: ?Sized> Clone for ArcReadGuard<T> {
     #[inline]
-    pub fn try_upgrade(&self) -> Option<RwPortal<T>> {
-        self.0.upgrade().map(RwPortal)
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            lock: Arc::clone(&self.lock),
+        }
     }
+}
 
-    #[inline]
-    pub fn upgrade(&self) -> RwPortal<T> {
-        self.try_upgrade().expect(ANCHOR_DROPPED)
+impl<T: ?Sized> Debug for ArcReadGuard<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArcReadGuard").finish_non_exhaustive()
     }
 }
 
-impl<T: ?Sized> WeakWPortal<T> {
+impl<T: ?Sized> Deref for ArcReadGuard<T> {
+    type Target = T;
     #[inline]
-    pub fn try_upgrade(&self) -> Option<WPortal<T>> {
-        self.0.upgrade().map(WPortal)
+    fn deref(&self) -> &T {
+        unsafe {
+            //SAFETY: Valid and not mutated as long as `self.lock` is held.
+            self.target.as_ref()
+        }
     }
+}
 
+impl<T: ?Sized> AsRef<T> for ArcReadGuard<T> {
     #[inline]
-    pub fn upgrade(&self) -> WPortal<T> {
-        self.try_upgrade().expect(ANCHOR_DROPPED)
+    fn as_ref(&self) -> &T {
+        self
     }
 }
 
-impl<T: ?Sized> Clone for WeakPortal<T> {
+impl<T: ?Sized> HeldFor for ArcReadGuard<T> {
+    #[cfg(feature = "watchdog")]
     #[inline]
-    fn clone(&self) -> Self {
-        self.0.pipe_ref(Weak::clone).pipe(Self)
+    fn held_for(&self) -> Duration {
+        self.lock.held_for()
     }
 }
 
-impl<T: ?Sized> Clone for WeakRwPortal<T> {
+impl<T: ?Sized> HeldFor for ArcRead<T> {
+    #[cfg(feature = "watchdog")]
     #[inline]
-    fn clone(&self) -> Self {
-        self.0.pipe_ref(Weak::clone).pipe(Self)
+    fn held_for(&self) -> Duration {
+        self.guard.held_for()
     }
 }
 
-impl<T: ?Sized> Clone for WeakWPortal<T> {
-    #[inline]
-    fn clone(&self) -> Self {
-        self.0.pipe_ref(Weak::clone).pipe(Self)
-    }
+/// A write guard that owns its portal, as returned by [`RwPortal::write_arc`], e.g. to hold it across `.await`s in a local task.  
+/// It can be projected onto a part of its target through [`map`](#method.map) and [`try_map`](#method.try_map).
+///
+/// Releasing it (or any guard mapped from it) while unwinding poisons the anchor, unless it was [defused](`Defuse`).
+/// Like the other guards, it can't be sent to other threads.
+#[cfg_attr(
+    nightly,
+    must_not_suspend = "Holding a portal guard across a suspend point can deadlock other tasks on this thread"
+)]
+#[must_use]
+pub struct ArcWriteGuard<T: ?Sized + 'static> {
+    /// Points into the target locked through `lock`, or a part of it.
+    target: NonNull<T>,
+
+    /// Poisons the anchor if dropped while unwinding.
+    lock: Box<dyn WriteLock>,
 }
 
-#[repr(transparent)]
-struct PortalReadGuard<'a, T: 'a + ?Sized>(RwLockReadGuard<'a, SSNonNull<T>>);
+/// The lock behind an `ArcWriteGuard`.
+struct ArcWrite<T: ?Sized + 'static> {
+    /// Borrows from `_portal`'s shared state, so it must be (and, being declared first, is) dropped first.
+    guard: PortalWriteGuard<'static, T>,
 
-#[repr(transparent)]
-struct PortalWriteGuard<'a, T: 'a + ?Sized>(RwLockWriteGuard<'a, SSNonNull<T>>);
+    /// Keeps the lock alive and counts as strong portal while the guard is held.
+    _portal: RwPortal<T>,
+}
 
-#[repr(transparent)]
-struct PortalMutexGuard<'a, T: 'a + ?Sized>(MutexGuard<'a, SSNonNull<T>>);
+/// Erases the target type of an `ArcWrite`, so that mapped guards can keep it.
+trait WriteLock: Defuse + HeldFor {}
+impl<T: ?Sized> WriteLock for ArcWrite<T> {}
 
-impl<'a, T: ?Sized> Deref for PortalReadGuard<'a, T> {
-    type Target = T;
+impl<T: ?Sized> ArcWriteGuard<T> {
+    /// Projects `guard` onto a part of its target, keeping the lock.  
+    /// This is an associated function so that it can't shadow a method of the target.
+    ///
+    /// If `project` panics, the lock is released while unwinding and poisons the anchor like any other write guard would.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{ArcWriteGuard, RwAnchor};
+    ///
+    /// let mut x = ("Scoped".to_owned(), 1);
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let mut count: ArcWriteGuard<i32> = ArcWriteGuard::map(portal.write_arc(), |x| &mut x.1);
+    /// drop(portal);
+    /// *count += 1;
+    /// drop(count);
+    ///
+    /// drop(anchor);
+    /// assert_eq!(x.1, 2);
+    /// ```
     #[inline]
-    fn deref(&self) -> &T {
-        let pointer = self.0.deref();
-        unsafe {
-            //SAFETY: Valid as long as self.0 is.
-            pointer.as_ref()
+    pub fn map<U: ?Sized>(
+        mut guard: Self,
+        project: impl FnOnce(&mut T) -> &mut U,
+    ) -> ArcWriteGuard<U> {
+        ArcWriteGuard {
+            target: project(&mut guard).pipe(NonNull::from),
+            lock: guard.lock,
         }
     }
-}
 
-impl<'a, T: ?Sized> Deref for PortalWriteGuard<'a, T> {
-    type Target = T;
+    /// Projects `guard` onto a part of its target iff `project` returns one, keeping the lock.
+    ///
+    /// If `project` panics, the lock is released while unwinding and poisons the anchor like any other write guard would.
+    ///
+    /// # Errors
+    ///
+    /// Iff `project` returns `None`, with the original `guard`.
     #[inline]
-    fn deref(&self) -> &T {
-        let pointer = self.0.deref();
-        unsafe {
-            //SAFETY: Valid as long as self.0 is.
-            pointer.as_ref()
+    pub fn try_map<U: ?Sized>(
+        mut guard: Self,
+        project: impl FnOnce(&mut T) -> Option<&mut U>,
+    ) -> Result<ArcWriteGuard<U>, Self> {
+        match project(&mut guard).map(NonNull::from) {
+            Some(target) => Ok(ArcWriteGuard {
+                target,
+                lock: guard.lock,
+            }),
+            None => Err(guard),
         }
     }
 }
 
-impl<'a, T: ?Sized> Deref for PortalMutexGuard<'a, T> {
+#[cfg(feature = "ffi")]
+impl<T: ?Sized> ArcWriteGuard<T> {
+    /// Splits `guard` into a pointer to its target and an opaque pointer that keeps the lock held,
+    /// e.g. to pass both through the user data of a C callback.
+    /// Only [`from_raw_parts`](#method.from_raw_parts) releases the lock again, so not calling it leaks the guard and the lock stays held.
+    ///
+    /// Only available with the `ffi` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{ArcWriteGuard, RwAnchor},
+    ///     std::ffi::c_void,
+    /// };
+    ///
+    /// // Stands in for a C function that calls back with `user_data`.
+    /// fn call_back(callback: extern "C" fn(*mut c_void), user_data: *mut c_void) {
+    ///     callback(user_data)
+    /// }
+    ///
+    /// extern "C" fn callback(user_data: *mut c_void) {
+    ///     let (target, held) = unsafe { *Box::from_raw(user_data.cast::<(*mut i32, *mut c_void)>()) };
+    ///     let mut guard = unsafe { ArcWriteGuard::from_raw_parts(target, held) };
+    ///     *guard += 1;
+    /// }
+    ///
+    /// let mut x = 1;
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let guard = anchor.portal().write_arc();
+    ///
+    /// let parts = Box::new(ArcWriteGuard::into_raw_parts(guard));
+    /// call_back(callback, Box::into_raw(parts).cast());
+    ///
+    /// drop(anchor);
+    /// assert_eq!(x, 2);
+    /// ```
+    #[must_use]
+    pub fn into_raw_parts(guard: Self) -> (*mut T, *mut c_void) {
+        let held = guard.lock.pipe(Box::new).pipe(Box::into_raw).cast();
+        (guard.target.as_ptr(), held)
+    }
+
+    /// Reassembles a guard split by [`into_raw_parts`](#method.into_raw_parts), which continues the critical section.
+    ///
+    /// Only available with the `ffi` feature.
+    ///
+    /// # Safety
+    ///
+    /// `target` and `held` must be the parts returned by one call to `into_raw_parts` on a guard of the same type,
+    /// and this must be called at most once for them, on the thread that split the guard.
+    /// The target must not be accessed through `target` afterwards.
+    pub unsafe fn from_raw_parts(target: *mut T, held: *mut c_void) -> Self {
+        Self {
+            target: NonNull::new_unchecked(target),
+            lock: *Box::from_raw(held.cast::<Box<dyn WriteLock>>()),
+        }
+    }
+}
+
+impl<T: ?Sized> Debug for ArcWriteGuard<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArcWriteGuard").finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized> Deref for ArcWriteGuard<T> {
     type Target = T;
     #[inline]
     fn deref(&self) -> &T {
-        let pointer = self.0.deref();
         unsafe {
-            //SAFETY: Valid as long as self.0 is.
-            pointer.as_ref()
+            //SAFETY: Valid and exclusive as long as `self.lock` is held.
+            self.target.as_ref()
         }
     }
 }
 
-impl<'a, T: ?Sized> DerefMut for PortalWriteGuard<'a, T> {
+impl<T: ?Sized> DerefMut for ArcWriteGuard<T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
-        let pointer = self.0.deref_mut();
         unsafe {
-            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
-            pointer.as_mut()
+            //SAFETY: Valid and exclusive as long as `self.lock` is held.
+            self.target.as_mut()
         }
     }
 }
 
-impl<'a, T: ?Sized> DerefMut for PortalMutexGuard<'a, T> {
+impl<T: ?Sized> AsRef<T> for ArcWriteGuard<T> {
     #[inline]
-    fn deref_mut(&mut self) -> &mut T {
-        let pointer = self.0.deref_mut();
-        unsafe {
-            //SAFETY: Valid as long as self.0 is. Can't be created from a read-only anchor.
-            pointer.as_mut()
-        }
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized> AsMut<T> for ArcWriteGuard<T> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut T {
+        &mut *self
+    }
+}
+
+impl<T: ?Sized> HeldFor for ArcWriteGuard<T> {
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    fn held_for(&self) -> Duration {
+        self.lock.held_for()
+    }
+}
+
+impl<T: ?Sized> Defuse for ArcWriteGuard<T> {
+    #[inline]
+    fn defuse(&mut self) {
+        self.lock.defuse();
+    }
+}
+
+impl<T: ?Sized> AnchorField for ArcWriteGuard<T> {}
+
+impl<T: ?Sized> HeldFor for ArcWrite<T> {
+    #[cfg(feature = "watchdog")]
+    #[inline]
+    fn held_for(&self) -> Duration {
+        self.guard.held_for()
+    }
+}
+
+impl<T: ?Sized> Defuse for ArcWrite<T> {
+    #[inline]
+    fn defuse(&mut self) {
+        self.guard.defuse();
     }
 }
 
@@ -669,24 +4829,53 @@ mod tests {
         trait S: Send {}
         trait SS: Send + Sync {}
 
-        assert_impl!(!Send: WAnchor<'_, dyn Any>, WPortal<dyn Any>);
-        assert_impl!(Send: WAnchor<'_, dyn S>, WPortal<dyn S>);
+        assert_impl!(
+            !Send: WAnchor<'_, dyn Any>,
+            WPortal<dyn Any>,
+            DetachedWAnchor<dyn Any>,
+        );
+        assert_impl!(Send: WAnchor<'_, dyn S>, WPortal<dyn S>, DetachedWAnchor<dyn S>);
         assert_impl!(
             !Send: Anchor<'_, dyn S>,
             RwAnchor<'_, dyn S>,
             Portal<dyn S>,
             RwPortal<dyn S>,
+            ReadPortal<dyn S>,
+            WritePortal<dyn S>,
+            CapAnchor<'_, dyn S, true, true, true, true>,
+            CapPortal<dyn S, true, true>,
+            MappedPortal<dyn S>,
+            MappedWeakPortal<dyn S>,
+            MappedReadPortal<dyn S>,
+            MappedWritePortal<dyn S>,
+            Exchange<dyn S, dyn S>,
+            Lease<dyn S>,
+            DetachedRwAnchor<dyn S>,
         );
         assert_impl!(
             Send: Anchor<'_, dyn SS>,
             RwAnchor<'_, dyn SS>,
             Portal<dyn SS>,
             RwPortal<dyn SS>,
+            ReadPortal<dyn SS>,
+            WritePortal<dyn SS>,
+            CapAnchor<'_, dyn SS, true, true, true, true>,
+            CapPortal<dyn SS, true, true>,
+            MappedPortal<dyn SS>,
+            MappedWeakPortal<dyn SS>,
+            MappedReadPortal<dyn SS>,
+            MappedWritePortal<dyn SS>,
+            Exchange<dyn SS, dyn SS>,
+            Lease<dyn SS>,
+            PortalRegistry,
+            DetachedRwAnchor<dyn SS>,
         );
         assert_impl!(
             !Send: PortalReadGuard<'_, ()>,
             PortalWriteGuard<'_, ()>,
             PortalMutexGuard<'_, ()>,
+            ArcReadGuard<()>,
+            ArcWriteGuard<()>,
         );
 
         assert_impl!(!Sync: WPortal<dyn Any>);
@@ -697,6 +4886,16 @@ mod tests {
             WAnchor<'_, dyn S>,
             Portal<dyn S>,
             RwPortal<dyn S>,
+            ReadPortal<dyn S>,
+            WritePortal<dyn S>,
+            CapAnchor<'_, dyn S, true, true, true, true>,
+            CapPortal<dyn S, true, true>,
+            MappedPortal<dyn S>,
+            MappedWeakPortal<dyn S>,
+            MappedReadPortal<dyn S>,
+            MappedWritePortal<dyn S>,
+            Exchange<dyn S, dyn S>,
+            Lease<dyn S>,
             PortalReadGuard<'_, dyn S>,
             PortalWriteGuard<'_, dyn S>,
             PortalMutexGuard<'_, dyn S>,
@@ -707,10 +4906,22 @@ mod tests {
             WAnchor<'_, dyn SS>,
             Portal<dyn SS>,
             RwPortal<dyn SS>,
+            ReadPortal<dyn SS>,
+            WritePortal<dyn SS>,
+            CapAnchor<'_, dyn SS, true, true, true, true>,
+            CapPortal<dyn SS, true, true>,
+            MappedPortal<dyn SS>,
+            MappedWeakPortal<dyn SS>,
+            MappedReadPortal<dyn SS>,
+            MappedWritePortal<dyn SS>,
+            Exchange<dyn SS, dyn SS>,
+            Lease<dyn SS>,
+            PortalRegistry,
             PortalReadGuard<'_, dyn SS>,
             PortalWriteGuard<'_, dyn SS>,
             PortalMutexGuard<'_, dyn SS>,
         );
+        assert_impl!(!Sync: ArcReadGuard<dyn SS>, ArcWriteGuard<dyn SS>);
 
         assert_impl!(
             UnwindSafe: PortalReadGuard<'_, dyn Any>,
@@ -748,6 +4959,17 @@ mod tests {
             Portal<dyn RefUnwindSafe>,
         );
 
+        assert_impl!(
+            !Send: SignalAnchor<'_, *const ()>,
+            AsyncSignalSafePortal<*const ()>,
+        );
+        assert_impl!(Send: SignalAnchor<'_, ()>, AsyncSignalSafePortal<()>);
+        assert_impl!(
+            !Sync: SignalAnchor<'_, *const ()>,
+            AsyncSignalSafePortal<*const ()>,
+        );
+        assert_impl!(Sync: SignalAnchor<'_, ()>, AsyncSignalSafePortal<()>);
+
         assert_impl!(
             Unpin: Anchor<'_, dyn Any>,
             RwAnchor<'_, dyn Any>,
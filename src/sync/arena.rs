@@ -0,0 +1,232 @@
+//! Recycled shared state for many short-lived read-write anchors.
+
+use {
+    super::{Address, Mutex, RwAnchor, RwLock, RwShared, SSNonNull, Shared},
+    std::{
+        any::Any,
+        iter, mem,
+        panic::{self, AssertUnwindSafe},
+        ptr::NonNull,
+        sync::{Arc, PoisonError},
+    },
+};
+
+/// Hands out [`RwAnchor`]s through [`ArenaFrame`]s, recycling each anchor's shared state once it has been released
+/// without any portals or weak portals left, e.g. for engines that anchor hundreds of values per frame.
+///
+/// # Example
+///
+/// ```rust
+/// use {ref_portals::sync::AnchorArena, std::thread};
+///
+/// let arena = AnchorArena::with_capacity(2);
+/// let mut positions = [1, 2];
+///
+/// for _ in 0..3 {
+///     let mut frame = arena.frame();
+///     let workers = positions
+///         .iter_mut()
+///         .map(|position| {
+///             let portal = frame.anchor(position).portal();
+///             thread::spawn(move || *portal.write() += 1)
+///         })
+///         .collect::<Vec<_>>();
+///     frame.wait_idle();
+///     for worker in workers {
+///         worker.join().unwrap();
+///     }
+/// } // Each frame releases its anchors here.
+///
+/// assert_eq!(positions, [4, 5]);
+/// assert_eq!(arena.available(), 2);
+/// ```
+#[derive(Debug)]
+pub struct AnchorArena<T> {
+    /// Shared state ready for reuse.
+    free: Mutex<Vec<Arc<RwShared<T>>>>,
+}
+
+/// The anchors created through an [`AnchorArena`] in one frame, released together when this is dropped.
+///
+/// # Panics
+///
+/// On drop, if dropping any of the anchors panics, e.g. because portals to it remain.
+/// All anchors are released first, and the panic lists all of their panic messages.
+#[derive(Debug)]
+pub struct ArenaFrame<'arena, 'a, T> {
+    /// Receives the recycled shared state.
+    arena: &'arena AnchorArena<T>,
+
+    /// In creation order.
+    anchors: Vec<RwAnchor<'a, T>>,
+}
+
+impl<T> AnchorArena<T> {
+    /// Creates an arena without preallocated shared state.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Creates an arena with shared state preallocated for `capacity` anchors.
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        let free = iter::repeat_with(|| Arc::new(new_shared(NonNull::dangling())))
+            .take(capacity)
+            .collect();
+        Self {
+            free: Mutex::new(free),
+        }
+    }
+
+    /// Returns how many anchors can currently be created without allocating their shared state.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Starts a frame, through which anchors can be created until it's dropped.
+    pub const fn frame<'a>(&self) -> ArenaFrame<'_, 'a, T> {
+        ArenaFrame {
+            arena: self,
+            anchors: Vec::new(),
+        }
+    }
+}
+
+impl<T> Default for AnchorArena<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates anchored shared state around `pointer`.
+fn new_shared<T>(pointer: NonNull<T>) -> RwShared<T> {
+    Shared::new(RwLock::new(SSNonNull::new(pointer)), Address::new(pointer))
+}
+
+impl<'arena, 'a, T> ArenaFrame<'arena, 'a, T> {
+    /// Creates an anchor capturing `reference`, reusing shared state from the arena if any is available.
+    /// The anchor lives until this frame is dropped.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn anchor(&mut self, reference: &'a mut T) -> &RwAnchor<'a, T> {
+        let pointer = NonNull::from(reference);
+        let recycled = self
+            .arena
+            .free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .pop();
+        let shared = match recycled {
+            Some(mut shared) => {
+                // Only unique shared state is put into the arena.
+                if let Some(state) = Arc::get_mut(&mut shared) {
+                    state.recycle(RwLock::new(SSNonNull::new(pointer)), Address::new(pointer));
+                    shared
+                } else {
+                    Arc::new(new_shared(pointer))
+                }
+            }
+            None => Arc::new(new_shared(pointer)),
+        };
+        self.anchors.push(RwAnchor::from_shared(shared));
+        &self.anchors[self.anchors.len() - 1]
+    }
+
+    /// Blocks the current thread until none of this frame's anchors have (strong) portals.
+    /// See [`RwAnchor::wait_idle`].
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn wait_idle(&self) {
+        for anchor in &self.anchors {
+            anchor.wait_idle();
+        }
+    }
+
+    /// Returns the number of anchors created in this frame.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.anchors.len()
+    }
+
+    /// Returns `true` iff no anchors were created in this frame.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+}
+
+impl<'arena, 'a, T> Drop for ArenaFrame<'arena, 'a, T> {
+    /// Drops the anchors in reverse creation order and returns their shared state to the arena where that's unique.
+    ///
+    /// # Panics
+    ///
+    /// If dropping any of the anchors panicked, after dropping all of them:
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::AnchorArena;
+    ///
+    /// let arena = AnchorArena::new();
+    /// let (mut a, mut b, mut c) = (1, 2, 3);
+    /// let mut frame = arena.frame();
+    /// Box::leak(Box::new(frame.anchor(&mut a).portal()));
+    /// frame.anchor(&mut b);
+    /// Box::leak(Box::new(frame.anchor(&mut c).portal()));
+    ///
+    /// assert_panic!(
+    ///     drop(frame),
+    ///     String,
+    ///     starts with "2 of 3 arena anchors failed to release:\n\nAnchor still in use",
+    /// );
+    /// assert_eq!(arena.available(), 1);
+    /// ```
+    fn drop(&mut self) {
+        let total = self.anchors.len();
+        let mut recycled = Vec::new();
+        let mut failures = Vec::new();
+        for anchor in mem::take(&mut self.anchors).into_iter().rev() {
+            let mut shared = Arc::clone(&anchor.reference);
+            match panic::catch_unwind(AssertUnwindSafe(|| drop(anchor))) {
+                Ok(()) => {
+                    if Arc::get_mut(&mut shared).is_some() {
+                        recycled.push(shared);
+                    }
+                }
+                Err(payload) => failures.push(payload),
+            }
+        }
+        self.arena
+            .free
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .append(&mut recycled);
+
+        if !failures.is_empty() {
+            let messages = failures
+                .iter()
+                .map(|payload| panic_message(&**payload))
+                .collect::<Vec<_>>();
+            panic!(
+                "{} of {} arena anchors failed to release:\n\n{}",
+                failures.len(),
+                total,
+                messages.join("\n\n"),
+            )
+        }
+    }
+}
+
+/// Returns the message of a panic with `payload`, if it's a string.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| payload.downcast_ref::<&str>().copied())
+        .unwrap_or("(non-string panic payload)")
+}
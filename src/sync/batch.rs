@@ -0,0 +1,108 @@
+//! Several mutations applied under a single write guard.
+
+use {
+    super::RwPortal,
+    std::fmt::{self, Debug, Formatter},
+};
+
+/// A staged mutation.
+type Mutation<'a, T> = Box<dyn FnOnce(&mut T) + 'a>;
+
+/// Collects the mutations of a [`RwPortal::write_batch`], which applies them in order once it has acquired the write lock.
+pub struct BatchWriter<'a, T: ?Sized> {
+    /// In staging order.
+    mutations: Vec<Mutation<'a, T>>,
+}
+
+impl<'a, T: ?Sized> BatchWriter<'a, T> {
+    /// Stages `mutation` to run after those staged before it.
+    pub fn stage(&mut self, mutation: impl FnOnce(&mut T) + 'a) -> &mut Self {
+        self.mutations.push(Box::new(mutation));
+        self
+    }
+
+    /// Returns the number of staged mutations.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.mutations.len()
+    }
+
+    /// Returns `true` iff no mutations are staged.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.mutations.is_empty()
+    }
+
+    /// Discards all staged mutations.
+    pub fn clear(&mut self) {
+        self.mutations.clear();
+    }
+}
+
+impl<'a, T: ?Sized> Debug for BatchWriter<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BatchWriter")
+            .field("staged", &self.mutations.len())
+            .finish()
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Calls `stage` without holding any lock, then applies the mutations it staged in order under a single write guard.
+    ///
+    /// Compared to writing once per mutation, this acquires the lock once and counts as a single write,
+    /// so that e.g. [`CachedPortal`](`super::CachedPortal`)s and [`update_cloned`](#method.update_cloned) see one change instead of several,
+    /// and readers never see a partially applied batch.
+    /// If nothing was staged, the lock isn't acquired at all.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned and a mutation was staged.
+    /// If a mutation panics, the remaining ones are discarded and the anchor is poisoned.
+    /// A panic in `stage` itself doesn't poison the anchor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// #[derive(Default)]
+    /// struct Profile {
+    ///     name: String,
+    ///     email: String,
+    ///     revision: u32,
+    /// }
+    ///
+    /// let mut profile = Profile::default();
+    /// let anchor = RwAnchor::new(&mut profile);
+    /// let portal = anchor.portal();
+    ///
+    /// let staged = portal.write_batch(|batch| {
+    ///     batch
+    ///         .stage(|p| p.name = "Ferris".to_owned())
+    ///         .stage(|p| p.email = "ferris@example.com".to_owned())
+    ///         .stage(|p| p.revision += 1);
+    ///     batch.len()
+    /// });
+    /// assert_eq!(staged, 3);
+    ///
+    /// drop(portal);
+    /// drop(anchor);
+    /// assert_eq!(profile.name, "Ferris");
+    /// assert_eq!(profile.revision, 1);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write_batch<'a, R>(&self, stage: impl FnOnce(&mut BatchWriter<'a, T>) -> R) -> R {
+        let mut batch = BatchWriter {
+            mutations: Vec::new(),
+        };
+        let result = stage(&mut batch);
+        if !batch.is_empty() {
+            let mut guard = self.write_guard("sync::RwPortal::write_batch");
+            for mutation in batch.mutations {
+                mutation(&mut guard);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,114 @@
+//! Values derived from a mutable anchor's target, recomputed only after it was written to.
+
+use {
+    super::{Mutex, MutexGuard, Ordering, WeakRwPortal},
+    std::{
+        fmt::{self, Debug, Formatter},
+        sync::{Arc, PoisonError},
+    },
+};
+
+/// The latest derived value, if any, and the write count of the anchor it was derived at.
+type Cache<U> = Option<(usize, Arc<U>)>;
+
+/// A function deriving a value of type `U` from a `T`.
+type Derive<T, U> = dyn Fn(&T) -> U + Send + Sync;
+
+/// Memoizes a value derived from an [`RwAnchor`](`super::RwAnchor`)'s target through a weak portal,
+/// recomputing it only once a write guard has been released since.
+///
+/// Like a [`WeakRwPortal`], this doesn't keep the anchor in use between calls to [`get`](#method.get).
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{CachedPortal, RwAnchor};
+///
+/// let mut words = vec!["a", "b"];
+/// let anchor = RwAnchor::new(&mut words);
+/// let portal = anchor.portal();
+/// let joined = CachedPortal::new(anchor.weak_portal(), |words: &Vec<&str>| words.join(" "));
+///
+/// let first = joined.get().unwrap();
+/// assert_eq!(*first, "a b");
+/// assert!(std::sync::Arc::ptr_eq(&first, &joined.get().unwrap())); // Not recomputed.
+///
+/// portal.write().push("c");
+/// assert_eq!(*joined.get().unwrap(), "a b c");
+///
+/// drop(portal);
+/// drop(anchor);
+/// assert!(joined.get().is_none());
+/// ```
+#[must_use]
+pub struct CachedPortal<T: ?Sized, U> {
+    /// Upgraded on each access.
+    portal: WeakRwPortal<T>,
+
+    /// Applied to the target on change.
+    derive: Box<Derive<T, U>>,
+
+    /// The value derived last.
+    cache: Mutex<Cache<U>>,
+}
+
+impl<T: ?Sized, U> CachedPortal<T, U> {
+    /// Creates a cache of `derive`'s result for the target of `portal`'s anchor.
+    /// `derive` first runs on the first call to [`get`](#method.get).
+    pub fn new(portal: WeakRwPortal<T>, derive: impl Fn(&T) -> U + Send + Sync + 'static) -> Self {
+        Self {
+            portal,
+            derive: Box::new(derive),
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Returns the value derived from the current target, recomputing it (under a read guard) iff the target may have changed.
+    /// Returns `None` iff the anchor has been dropped or sealed.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor is poisoned, or `derive` panics.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    #[must_use]
+    pub fn get(&self) -> Option<Arc<U>> {
+        let portal = self.portal.try_upgrade()?;
+        let target = portal.read();
+        // Stable while the read guard is held.
+        let writes = portal.0.writes.load(Ordering::Relaxed);
+        if let Some((cached_at, value)) = &*self.lock() {
+            if *cached_at == writes {
+                return Some(Arc::clone(value));
+            }
+        }
+        let value = Arc::new((self.derive)(&target));
+        *self.lock() = Some((writes, Arc::clone(&value)));
+        Some(value)
+    }
+
+    /// Discards the cached value, so that the next [`get`](#method.get) recomputes it.
+    pub fn invalidate(&self) {
+        drop(self.lock().take());
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.portal.is_alive()
+    }
+
+    /// Locks the cache, ignoring poison since it's always consistent.
+    fn lock(&self) -> MutexGuard<'_, Cache<U>> {
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: ?Sized, U: Debug> Debug for CachedPortal<T, U> {
+    /// Shows the cached value, without recomputing it.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedPortal")
+            .field("cached", &self.lock().as_ref().map(|(_, value)| value))
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,346 @@
+//! Anchors and portals whose capabilities are fixed at compile time.
+
+use {
+    super::{AnchorField, Defuse, HeldFor, Ordering, RwAnchor, RwPortal, ToArc, WeakRwPortal},
+    crate::{poison::PortalLimitExceeded, AnchorId},
+    wyz::pipe::*,
+};
+
+/// Configures a [`CapAnchor`], as returned by [`CapAnchor::builder`].
+///
+/// Each flag is a capability that starts out granted and can only be given up:
+///
+/// - `READ`: Portals can acquire read guards.
+/// - `WRITE`: Portals can acquire write guards.
+/// - `STRONG`: The anchor can create (strong) portals directly.
+/// - `WEAK`: The anchor can create weak portals, which upgrade to portals with the same access.
+///
+/// The anchor and portal types carry these flags, so methods that were opted out of don't exist on them.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct AnchorBuilder<const READ: bool, const WRITE: bool, const STRONG: bool, const WEAK: bool>
+{
+    /// Passed to `named`.
+    label: Option<&'static str>,
+
+    /// Passed to `with_limit`.
+    limit: Option<usize>,
+}
+
+/// A threadsafe mutable anchor whose portals only have the capabilities selected through [`CapAnchor::builder`].
+///
+/// It's backed by an [`RwAnchor`], and its portals wrap [`RwPortal`]s without overhead.
+///
+/// # Panics
+///
+/// On drop, like an [`RwAnchor`].
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{CapAnchor, WeakCapPortal};
+///
+/// let mut log = vec!["started"];
+/// let anchor = CapAnchor::builder().read_only().weak_only().build(&mut log);
+///
+/// // `anchor.portal()` and `.write()` don't exist here.
+/// let observer: WeakCapPortal<Vec<&str>, true, false> = anchor.weak_portal();
+/// let portal = observer.try_upgrade().unwrap();
+/// assert_eq!(portal.read().len(), 1);
+/// drop(portal);
+///
+/// drop(anchor);
+/// assert!(observer.try_upgrade().is_none());
+/// ```
+///
+/// Methods that were given up don't exist, so each of these fails to compile:
+///
+/// ```compile_fail
+/// use ref_portals::sync::CapAnchor;
+///
+/// let mut value = 0;
+/// let anchor = CapAnchor::builder().read_only().build(&mut value);
+/// *anchor.portal().write() += 1;
+/// ```
+///
+/// ```compile_fail
+/// use ref_portals::sync::CapAnchor;
+///
+/// let mut value = 0;
+/// let anchor = CapAnchor::builder().weak_only().build(&mut value);
+/// let portal = anchor.portal();
+/// ```
+///
+/// ```compile_fail
+/// use ref_portals::sync::CapAnchor;
+///
+/// let mut value = 0;
+/// let anchor = CapAnchor::builder().strong_only().build(&mut value);
+/// let weak_portal = anchor.weak_portal();
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct CapAnchor<
+    'a,
+    T: ?Sized,
+    const READ: bool,
+    const WRITE: bool,
+    const STRONG: bool,
+    const WEAK: bool,
+>(RwAnchor<'a, T>);
+
+/// A threadsafe mutable portal to a [`CapAnchor`] that can read iff `READ` and write iff `WRITE`.
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct CapPortal<T: ?Sized, const READ: bool, const WRITE: bool>(RwPortal<T>);
+
+/// A weak portal to a [`CapAnchor`], which upgrades to a [`CapPortal`] with the same capabilities.
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct WeakCapPortal<T: ?Sized, const READ: bool, const WRITE: bool>(WeakRwPortal<T>);
+
+impl AnchorBuilder<true, true, true, true> {
+    /// Creates a new `AnchorBuilder` that grants all capabilities.
+    pub const fn new() -> Self {
+        Self {
+            label: None,
+            limit: None,
+        }
+    }
+}
+
+impl Default for AnchorBuilder<true, true, true, true> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const READ: bool, const WRITE: bool, const STRONG: bool, const WEAK: bool>
+    AnchorBuilder<READ, WRITE, STRONG, WEAK>
+{
+    /// Gives up the write capability, so portals only have `.read()`.
+    pub const fn read_only(self) -> AnchorBuilder<READ, false, STRONG, WEAK> {
+        AnchorBuilder {
+            label: self.label,
+            limit: self.limit,
+        }
+    }
+
+    /// Gives up the read capability, so portals only have `.write()`, which still lets them inspect the target.
+    pub const fn write_only(self) -> AnchorBuilder<false, WRITE, STRONG, WEAK> {
+        AnchorBuilder {
+            label: self.label,
+            limit: self.limit,
+        }
+    }
+
+    /// Gives up creating strong portals directly, so the anchor only has `.weak_portal()`.
+    pub const fn weak_only(self) -> AnchorBuilder<READ, WRITE, false, WEAK> {
+        AnchorBuilder {
+            label: self.label,
+            limit: self.limit,
+        }
+    }
+
+    /// Gives up creating weak portals, so the anchor only has `.portal()`.
+    pub const fn strong_only(self) -> AnchorBuilder<READ, WRITE, STRONG, false> {
+        AnchorBuilder {
+            label: self.label,
+            limit: self.limit,
+        }
+    }
+
+    /// Labels the anchor as `label`, like `named` does.
+    pub const fn named(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// Allows at most `limit` (strong) portals at a time, like `with_limit` does.
+    pub const fn limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Creates the anchor, capturing `reference`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn build<T: ?Sized>(
+        self,
+        reference: &mut T,
+    ) -> CapAnchor<'_, T, READ, WRITE, STRONG, WEAK> {
+        let anchor = match self.label {
+            Some(label) => RwAnchor::named(label, reference),
+            None => RwAnchor::new(reference),
+        };
+        if let Some(limit) = self.limit {
+            anchor.reference.limit.store(limit, Ordering::Relaxed);
+        }
+        CapAnchor(anchor)
+    }
+}
+
+impl CapAnchor<'static, (), true, true, true, true> {
+    /// Starts configuring a `CapAnchor`, which grants all capabilities unless they are given up.
+    pub const fn builder() -> AnchorBuilder<true, true, true, true> {
+        AnchorBuilder::new()
+    }
+}
+
+impl<'a, T: ?Sized, const READ: bool, const WRITE: bool, const STRONG: bool, const WEAK: bool>
+    CapAnchor<'a, T, READ, WRITE, STRONG, WEAK>
+{
+    /// Returns this anchor's label, if it has one.
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        self.0.label()
+    }
+
+    /// Returns this anchor's unique ID.
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        self.0.id()
+    }
+
+    /// Returns the number of (strong) portals associated with this anchor, including upgraded weak portals.
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        self.0.strong_portal_count()
+    }
+}
+
+impl<'a, T: ?Sized, const READ: bool, const WRITE: bool, const WEAK: bool>
+    CapAnchor<'a, T, READ, WRITE, true, WEAK>
+{
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    pub fn portal(&self) -> CapPortal<T, READ, WRITE> {
+        self.0.portal().pipe(CapPortal)
+    }
+
+    /// Creates a portal iff fewer (strong) portals than this anchor's limit exist.
+    ///
+    /// # Errors
+    ///
+    /// Iff this anchor was built with a [`limit`](`AnchorBuilder::limit`) and that many portals exist already.
+    #[inline]
+    pub fn try_portal(&self) -> Result<CapPortal<T, READ, WRITE>, PortalLimitExceeded> {
+        self.0.try_portal().map(CapPortal)
+    }
+}
+
+impl<'a, T: ?Sized, const READ: bool, const WRITE: bool, const STRONG: bool>
+    CapAnchor<'a, T, READ, WRITE, STRONG, true>
+{
+    /// Creates a weak portal of indefinite lifetime associated with this anchor.
+    /// Dropping an anchor doesn't panic if only weak portals exist.
+    pub fn weak_portal(&self) -> WeakCapPortal<T, READ, WRITE> {
+        self.0.weak_portal().pipe(WeakCapPortal)
+    }
+}
+
+impl<T: ?Sized, const READ: bool, const WRITE: bool> CapPortal<T, READ, WRITE> {
+    /// Returns the unique ID of the anchor this portal is associated with.
+    #[must_use]
+    pub fn anchor_id(&self) -> AnchorId {
+        self.0.anchor_id()
+    }
+}
+
+impl<T: ?Sized, const WRITE: bool> CapPortal<T, true, WRITE> {
+    /// Acquires a read guard, blocking while the target is locked for writing.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read<'a>(&'a self) -> impl AsRef<T> + HeldFor + ToArc<T> + 'a {
+        self.0.read()
+    }
+
+    /// Acquires a read guard iff that's possible without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read<'a>(&'a self) -> Option<impl AsRef<T> + HeldFor + ToArc<T> + 'a> {
+        self.0.try_read()
+    }
+}
+
+impl<T: ?Sized, const READ: bool> CapPortal<T, READ, true> {
+    /// Acquires a write guard, blocking while the target is locked.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write<'a>(
+        &'a self,
+    ) -> impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a {
+        self.0.write()
+    }
+
+    /// Acquires a write guard iff that's possible without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_write<'a>(
+        &'a self,
+    ) -> Option<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a> {
+        self.0.try_write()
+    }
+
+    /// Gives up the write capability.
+    #[inline]
+    pub fn into_read_only(self) -> CapPortal<T, READ, false> {
+        CapPortal(self.0)
+    }
+}
+
+impl<T: ?Sized, const READ: bool, const WRITE: bool> WeakCapPortal<T, READ, WRITE> {
+    /// Upgrades this weak portal iff its anchor still exists and hasn't been sealed.
+    #[inline]
+    #[must_use]
+    pub fn try_upgrade(&self) -> Option<CapPortal<T, READ, WRITE>> {
+        self.0.try_upgrade().map(CapPortal)
+    }
+
+    /// Checks whether the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.is_alive()
+    }
+}
+
+impl<T: ?Sized, const READ: bool, const WRITE: bool> Clone for CapPortal<T, READ, WRITE> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized, const READ: bool, const WRITE: bool> Clone for WeakCapPortal<T, READ, WRITE> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
@@ -0,0 +1,231 @@
+//! Sharing an anchored channel receiver through portals.
+
+use {
+    super::{ShutdownToken, WAnchor, WPortal},
+    crate::poison::RecvError,
+    std::{
+        ops::Deref,
+        sync::mpsc::{self, RecvTimeoutError, TryRecvError},
+        time::Duration,
+    },
+};
+
+/// How long a blocking [`WPortal::recv`] waits for a message before releasing its guard and checking for closure again,
+/// which bounds how long closing a [`ReceiverAnchor`] can take to be noticed.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The receiving end of a channel, which [`WPortal::recv`] and [`WPortal::try_recv`] can receive from.
+///
+/// Implemented for [`mpsc::Receiver`] and, with the `crossbeam` feature, `crossbeam_channel::Receiver`.
+pub trait ChannelReceiver {
+    /// The type of the received messages.
+    type Item;
+
+    /// Receives a message iff one is available right away.
+    ///
+    /// # Errors
+    ///
+    /// [`RecvError::Empty`] iff no message is available,
+    /// [`RecvError::Disconnected`] iff additionally all senders have been dropped.
+    fn try_receive(&self) -> Result<Self::Item, RecvError>;
+
+    /// Waits up to `timeout` for a message.
+    ///
+    /// # Errors
+    ///
+    /// [`RecvError::Empty`] iff no message arrived in time,
+    /// [`RecvError::Disconnected`] iff the channel is empty and all senders have been dropped.
+    fn receive_timeout(&self, timeout: Duration) -> Result<Self::Item, RecvError>;
+}
+
+impl<T> ChannelReceiver for mpsc::Receiver<T> {
+    type Item = T;
+
+    fn try_receive(&self) -> Result<T, RecvError> {
+        self.try_recv().map_err(|error| match error {
+            TryRecvError::Empty => RecvError::Empty,
+            TryRecvError::Disconnected => RecvError::Disconnected,
+        })
+    }
+
+    fn receive_timeout(&self, timeout: Duration) -> Result<T, RecvError> {
+        self.recv_timeout(timeout).map_err(|error| match error {
+            RecvTimeoutError::Timeout => RecvError::Empty,
+            RecvTimeoutError::Disconnected => RecvError::Disconnected,
+        })
+    }
+}
+
+#[cfg(feature = "crossbeam")]
+impl<T> ChannelReceiver for crossbeam_channel::Receiver<T> {
+    type Item = T;
+
+    fn try_receive(&self) -> Result<T, RecvError> {
+        self.try_recv().map_err(|error| match error {
+            crossbeam_channel::TryRecvError::Empty => RecvError::Empty,
+            crossbeam_channel::TryRecvError::Disconnected => RecvError::Disconnected,
+        })
+    }
+
+    fn receive_timeout(&self, timeout: Duration) -> Result<T, RecvError> {
+        self.recv_timeout(timeout).map_err(|error| match error {
+            crossbeam_channel::RecvTimeoutError::Timeout => RecvError::Empty,
+            crossbeam_channel::RecvTimeoutError::Disconnected => RecvError::Disconnected,
+        })
+    }
+}
+
+/// A [`WAnchor`] to a channel receiver that, when closed or dropped, makes blocked and later [`WPortal::recv`] calls through its portals fail.
+/// Dereferences to that anchor, so portals are created and checked as usual.
+///
+/// Dropping it closes it and then waits for its remaining strong portals to be dropped,
+/// so consumers should drop their portal once they receive [`RecvError::Closed`].
+/// Waiting on a thread that holds one of them deadlocks.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::{poison::RecvError, sync::ReceiverAnchor},
+///     std::{sync::mpsc, thread},
+/// };
+///
+/// let (sender, mut receiver) = mpsc::channel();
+/// let (acks, ack_receiver) = mpsc::channel();
+/// let anchor = ReceiverAnchor::new(&mut receiver);
+///
+/// let portal = anchor.portal();
+/// let consumer = thread::spawn(move || loop {
+///     match portal.recv() {
+///         Ok(message) => acks.send(message).unwrap(),
+///         Err(error) => break error,
+///     }
+/// });
+///
+/// sender.send("first").unwrap();
+/// assert_eq!(ack_receiver.recv(), Ok("first"));
+///
+/// drop(anchor); // Unblocks the consumer, then waits for its portal.
+/// assert_eq!(consumer.join().unwrap(), RecvError::Closed);
+///
+/// sender.send("unread").unwrap();
+/// assert_eq!(receiver.try_recv(), Ok("unread"));
+/// ```
+#[derive(Debug)]
+pub struct ReceiverAnchor<'a, R: ?Sized> {
+    /// Sealed through `token` before it's dropped.
+    anchor: WAnchor<'a, R>,
+
+    /// Bound to `anchor`, to close it.
+    token: ShutdownToken,
+}
+
+impl<'a, R: ?Sized> ReceiverAnchor<'a, R> {
+    /// Anchors `receiver`.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(receiver: &'a mut R) -> Self {
+        let anchor = WAnchor::new(receiver);
+        let token = ShutdownToken::new();
+        anchor.bind_shutdown(&token);
+        Self { anchor, token }
+    }
+
+    /// Closes the anchor, so that [`WPortal::recv`] through its portals fails with [`RecvError::Closed`]
+    /// and its weak portals can't be upgraded anymore.  
+    /// Blocked calls notice this within a few milliseconds. Calling this again has no effect.
+    pub fn close(&self) {
+        self.token.shutdown();
+    }
+
+    /// Returns `true` iff the anchor has been closed.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.token.is_shutdown()
+    }
+}
+
+impl<'a, R: ?Sized> Deref for ReceiverAnchor<'a, R> {
+    type Target = WAnchor<'a, R>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.anchor
+    }
+}
+
+impl<'a, R: ?Sized> Drop for ReceiverAnchor<'a, R> {
+    fn drop(&mut self) {
+        self.close();
+        self.anchor.wait_idle();
+    }
+}
+
+impl<R: ?Sized + ChannelReceiver> WPortal<R> {
+    /// Blocks until a message can be received from the anchored receiver.
+    ///
+    /// The guard is only held for a few milliseconds at a time, so other portals can receive (or lock the receiver) meanwhile.
+    ///
+    /// # Errors
+    ///
+    /// [`RecvError::Disconnected`] iff the channel is empty and all of its senders have been dropped,
+    /// [`RecvError::Closed`] iff the anchor is sealed, e.g. because it's a [`ReceiverAnchor`] that has been closed or is being dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn recv(&self) -> Result<R::Item, RecvError> {
+        loop {
+            if self.0.idle.is_sealed() {
+                return Err(RecvError::Closed);
+            }
+            match self.lock().receive_timeout(POLL_INTERVAL) {
+                Err(RecvError::Empty) => (),
+                result => return result,
+            }
+        }
+    }
+
+    /// Receives a message from the anchored receiver iff one is available right away.
+    ///
+    /// # Errors
+    ///
+    /// [`RecvError::Empty`] iff no message is available,
+    /// [`RecvError::Disconnected`] iff additionally all senders have been dropped,
+    /// [`RecvError::Closed`] iff the anchor is sealed.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{poison::RecvError, sync::ReceiverAnchor},
+    ///     std::sync::mpsc,
+    /// };
+    ///
+    /// let (sender, mut receiver) = mpsc::channel();
+    /// let anchor = ReceiverAnchor::new(&mut receiver);
+    /// let portal = anchor.portal();
+    ///
+    /// sender.send(1).unwrap();
+    /// assert_eq!(portal.try_recv(), Ok(1));
+    /// assert_eq!(portal.try_recv(), Err(RecvError::Empty));
+    ///
+    /// drop(sender);
+    /// assert_eq!(portal.try_recv(), Err(RecvError::Disconnected));
+    ///
+    /// anchor.close();
+    /// assert_eq!(portal.try_recv(), Err(RecvError::Closed));
+    /// # drop(portal);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_recv(&self) -> Result<R::Item, RecvError> {
+        if self.0.idle.is_sealed() {
+            return Err(RecvError::Closed);
+        }
+        self.lock().try_receive()
+    }
+}
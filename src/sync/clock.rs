@@ -0,0 +1,27 @@
+//! Time sources for anchor deadlines and shutdown grace periods.
+
+use std::{fmt::Debug, panic::RefUnwindSafe, time::Instant};
+
+/// A source of the current time, by which a threadsafe anchor or [`ShutdownToken`](`super::ShutdownToken`) measures its deadlines.  
+/// The default is [`SystemClock`]. Implement this to drive those deadlines with virtual time, e.g. in simulations or deterministic tests.
+///
+/// Anchors only read their clock when checking whether they're sealed and when they start or continue waiting for portals,
+/// so a blocking `wait_idle` sleeps in real time for what was left when it last checked.  
+/// Tokens and the anchors bound to them should use the same clock.
+///
+/// Clocks must be [`RefUnwindSafe`] so that anchors using them stay so, as the standard locks are.
+pub trait Clock: Debug + Send + Sync + RefUnwindSafe {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// The system's monotonic clock, i.e. [`Instant::now`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
@@ -0,0 +1,381 @@
+//! Dropping threadsafe anchors only if that wouldn't panic, and reporting why otherwise.
+
+use {
+    super::{Anchor, Ordering, RwAnchor, Shared, WAnchor, ANCHORED},
+    crate::messages::{Message, Report, Violation},
+    std::{
+        error::Error,
+        fmt::{self, Debug, Display, Formatter},
+        sync::{TryLockError, TryLockResult},
+        thread,
+    },
+    wyz::pipe::*,
+};
+
+/// Returned by the threadsafe anchors' `close` methods if dropping the anchor would have panicked.
+///
+/// This hands the anchor back, so that shutdown code can log the report and then decide what to do with it:
+/// Wait for its portals through `.wait_idle()` and close it again, drop it to panic after all,
+/// or abort the process.
+///
+/// The counts and lock state are a snapshot taken while closing and may be outdated by the time they are read.
+pub struct CloseReport<A> {
+    /// Still open, boxed to keep results small.
+    anchor: Box<A>,
+
+    /// The number of surviving (strong) portals.
+    strong_portals: usize,
+
+    /// The number of dependent anchors that haven't been dropped yet.
+    dependents: usize,
+
+    /// Whether the anchor is poisoned.
+    poisoned: bool,
+
+    /// Whether any guard was held through the portals.
+    locked: bool,
+
+    /// Whether a write (or mutex) guard was held through the portals.
+    locked_exclusive: bool,
+
+    /// The message dropping the anchor would have panicked with.
+    message: String,
+}
+
+impl<A> CloseReport<A> {
+    /// Returns the number of (strong) portals that still existed.
+    #[must_use]
+    pub const fn strong_portals(&self) -> usize {
+        self.strong_portals
+    }
+
+    /// Returns the number of anchors that [depend on](struct.Anchor.html#method.depends_on) this one and still existed.
+    #[must_use]
+    pub const fn dependents(&self) -> usize {
+        self.dependents
+    }
+
+    /// Returns `true` iff the anchor is poisoned.
+    #[must_use]
+    pub const fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Returns `true` iff a guard was held through any of the portals.
+    /// Always `false` for [`Anchor`]s, whose portals don't lock.
+    ///
+    /// Guards aren't counted individually, so this is found out by trying to lock the target, like [`RwPortal::is_locked`](`super::RwPortal::is_locked`).
+    #[must_use]
+    pub const fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Returns `true` iff a write (or mutex) guard was held through any of the portals.
+    ///
+    /// Depending on the platform, this may also be `true` for an [`RwAnchor`] while a writer was waiting for readers to finish.
+    #[must_use]
+    pub const fn is_locked_exclusive(&self) -> bool {
+        self.locked_exclusive
+    }
+
+    /// Returns the anchor that couldn't be closed.
+    #[must_use]
+    pub const fn anchor(&self) -> &A {
+        &self.anchor
+    }
+
+    /// Returns the anchor that couldn't be closed, e.g. to wait for its portals before closing it again.
+    #[must_use]
+    pub fn into_anchor(self) -> A {
+        *self.anchor
+    }
+}
+
+impl<A> Debug for CloseReport<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloseReport")
+            .field("strong_portals", &self.strong_portals)
+            .field("dependents", &self.dependents)
+            .field("poisoned", &self.poisoned)
+            .field("locked", &self.locked)
+            .field("locked_exclusive", &self.locked_exclusive)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A> Display for CloseReport<A> {
+    /// Formats the message dropping the anchor would have panicked with,
+    /// including where the surviving portals were created with the `backtrace` feature.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl<A> Error for CloseReport<A> {}
+
+impl<L, A> Shared<L, A> {
+    /// Marks the anchor as dropped iff no strong portals exist, without discarding idle callbacks yet.
+    /// Returns the number of strong portals otherwise.
+    fn try_detach(&self) -> Result<(), usize> {
+        self.state
+            .compare_exchange(ANCHORED, 0, Ordering::AcqRel, Ordering::Acquire)
+            .map(drop)
+            .map_err(|state| state & !ANCHORED)
+    }
+
+    /// Undoes a successful `try_detach`, for an anchor that turned out to be poisoned.
+    /// Weak portals can't be upgraded in between, as if the anchor had been dropped.
+    fn reattach(&self) {
+        self.state.fetch_or(ANCHORED, Ordering::AcqRel);
+    }
+}
+
+/// What closing an anchor found, before the anchor is handed back.
+struct Findings {
+    /// See [`CloseReport::strong_portals`].
+    strong_portals: usize,
+
+    /// See [`CloseReport::dependents`].
+    dependents: usize,
+
+    /// See [`CloseReport::is_poisoned`].
+    poisoned: bool,
+
+    /// See [`CloseReport::is_locked`].
+    locked: bool,
+
+    /// See [`CloseReport::is_locked_exclusive`].
+    locked_exclusive: bool,
+}
+
+impl Findings {
+    /// Finds `target`'s lock state without blocking, through its `try_lock` (or `try_write`) and `try_read` results.
+    /// Poison can't be detected while the target is locked exclusively.
+    fn probe<G, H>(
+        strong_portals: usize,
+        dependents: usize,
+        try_lock: &TryLockResult<G>,
+        try_read: impl FnOnce() -> TryLockResult<H>,
+    ) -> Self {
+        let (locked, poisoned) = match try_lock {
+            Ok(_) => (false, false),
+            Err(TryLockError::Poisoned(_)) => (false, true),
+            Err(TryLockError::WouldBlock) => (true, false),
+        };
+        let (locked_exclusive, poisoned) = if locked {
+            match &try_read() {
+                Ok(_) => (false, false),
+                Err(TryLockError::Poisoned(_)) => (false, true),
+                Err(TryLockError::WouldBlock) => (true, false),
+            }
+        } else {
+            (false, poisoned)
+        };
+        Self {
+            strong_portals,
+            dependents,
+            poisoned,
+            locked,
+            locked_exclusive,
+        }
+    }
+
+    /// Reports these findings for `anchor` of `T`, labelled `label`.
+    /// `traces` or `poison_origin` are appended to the message as details.
+    fn report<T: ?Sized, A>(
+        self,
+        anchor: A,
+        label: Option<&str>,
+        traces: &dyn Display,
+        poison_origin: &dyn Display,
+    ) -> CloseReport<A> {
+        let no_details: &dyn Display = &"";
+        let (violation, details) = if self.strong_portals > 0 {
+            (Violation::AnchorStillInUse, traces)
+        } else if self.dependents > 0 {
+            (Violation::AnchorHasDependents, no_details)
+        } else {
+            (Violation::AnchorPoisoned, poison_origin)
+        };
+        let message = Report::new(violation)
+            .dropping::<T>(label, self.strong_portals, thread::current().name())
+            .with_details(details)
+            .pipe(Message)
+            .to_string();
+        CloseReport {
+            anchor: Box::new(anchor),
+            strong_portals: self.strong_portals,
+            dependents: self.dependents,
+            poisoned: self.poisoned,
+            locked: self.locked,
+            locked_exclusive: self.locked_exclusive,
+            message,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Drops this anchor iff that wouldn't panic, i.e. iff no (strong) portals or [dependent](#method.depends_on) anchors exist anymore,
+    /// after joining bound threads and revoking leases like dropping it does.
+    /// Unlike dropping, this ignores the [`ViolationPolicy`](`super::ViolationPolicy`) of the anchor's group, if any.
+    ///
+    /// # Errors
+    ///
+    /// Iff portals or dependent anchors still exist, with a report that hands the anchor back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::named("config", &x);
+    /// let portal = anchor.portal();
+    ///
+    /// let report = anchor.close().unwrap_err();
+    /// assert_eq!(report.strong_portals(), 1);
+    /// assert!(report.to_string().starts_with("Anchor still in use"));
+    ///
+    /// drop(portal);
+    /// report.into_anchor().close().unwrap();
+    /// ```
+    pub fn close(mut self) -> Result<(), CloseReport<Self>> {
+        self.join_bound_threads();
+        self.leases.revoke_all();
+        let dependents = self.dependents.remaining();
+        if dependents > 0 {
+            let strong_portals = self.reference.strong_portal_count();
+            return Err(self.report(strong_portals, dependents));
+        }
+        match self.reference.try_detach() {
+            Ok(()) => {
+                drop(self);
+                Ok(())
+            }
+            Err(strong_portals) => Err(self.report(strong_portals, 0)),
+        }
+    }
+
+    /// Reports this anchor as still in use.
+    fn report(self, strong_portals: usize, dependents: usize) -> CloseReport<Self> {
+        let findings = Findings {
+            strong_portals,
+            dependents,
+            poisoned: false,
+            locked: false,
+            locked_exclusive: false,
+        };
+        let (label, traces) = (self.label, self.reference.traces.to_string());
+        findings.report::<T, _>(self, label, &traces, &"")
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Drops this anchor iff that wouldn't panic, i.e. iff no (strong) portals or [dependent](#method.depends_on) anchors exist anymore
+    /// and it isn't poisoned, after joining bound threads like dropping it does.
+    /// Unlike dropping, this ignores the [`ViolationPolicy`](`super::ViolationPolicy`) of the anchor's group, if any.
+    ///
+    /// # Errors
+    ///
+    /// Iff portals or dependent anchors still exist or the anchor is poisoned, with a report that hands the anchor back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// let guard = portal.read();
+    ///
+    /// let report = anchor.close().unwrap_err();
+    /// assert_eq!(report.strong_portals(), 1);
+    /// assert!(report.is_locked() && !report.is_locked_exclusive());
+    ///
+    /// // Shutdown code would log the report here, then wait for the portals.
+    /// drop(guard);
+    /// drop(portal);
+    /// let anchor = report.into_anchor();
+    /// anchor.wait_idle();
+    /// anchor.close().unwrap();
+    /// ```
+    pub fn close(mut self) -> Result<(), CloseReport<Self>> {
+        self.join_bound_threads();
+        let dependents = self.dependents.remaining();
+        if dependents > 0 {
+            let strong_portals = self.reference.strong_portal_count();
+            return Err(self.report(strong_portals, dependents));
+        }
+        match self.reference.try_detach() {
+            // No guards can be acquired anymore, so this is final.
+            Ok(()) if self.reference.target.read().is_err() => {
+                self.reference.reattach();
+                Err(self.report(0, 0))
+            }
+            Ok(()) => {
+                drop(self);
+                Ok(())
+            }
+            Err(strong_portals) => Err(self.report(strong_portals, 0)),
+        }
+    }
+
+    /// Reports this anchor as still in use or poisoned.
+    fn report(self, strong_portals: usize, dependents: usize) -> CloseReport<Self> {
+        let target = &self.reference.target;
+        let findings = Findings::probe(strong_portals, dependents, &target.try_write(), || {
+            target.try_read()
+        });
+        let (label, traces, poison_origin) = (
+            self.label,
+            self.reference.traces.to_string(),
+            self.reference.poison_origin.to_string(),
+        );
+        findings.report::<T, _>(self, label, &traces, &poison_origin)
+    }
+}
+
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Drops this anchor iff that wouldn't panic, i.e. iff no (strong) portals or [dependent](#method.depends_on) anchors exist anymore
+    /// and it isn't poisoned, after joining bound threads like dropping it does.
+    /// Unlike dropping, this ignores the [`ViolationPolicy`](`super::ViolationPolicy`) of the anchor's group, if any.
+    ///
+    /// # Errors
+    ///
+    /// Iff portals or dependent anchors still exist or the anchor is poisoned, with a report that hands the anchor back.
+    pub fn close(mut self) -> Result<(), CloseReport<Self>> {
+        self.join_bound_threads();
+        let dependents = self.dependents.remaining();
+        if dependents > 0 {
+            let strong_portals = self.reference.strong_portal_count();
+            return Err(self.report(strong_portals, dependents));
+        }
+        match self.reference.try_detach() {
+            // No guards can be acquired anymore, so this is final.
+            Ok(()) if self.reference.target.lock().is_err() => {
+                self.reference.reattach();
+                Err(self.report(0, 0))
+            }
+            Ok(()) => {
+                drop(self);
+                Ok(())
+            }
+            Err(strong_portals) => Err(self.report(strong_portals, 0)),
+        }
+    }
+
+    /// Reports this anchor as still in use or poisoned.
+    fn report(self, strong_portals: usize, dependents: usize) -> CloseReport<Self> {
+        let target = &self.reference.target;
+        let findings = Findings::probe(strong_portals, dependents, &target.try_lock(), || {
+            target.try_lock()
+        });
+        let (label, traces, poison_origin) = (
+            self.label,
+            self.reference.traces.to_string(),
+            self.reference.poison_origin.to_string(),
+        );
+        findings.report::<T, _>(self, label, &traces, &poison_origin)
+    }
+}
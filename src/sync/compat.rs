@@ -0,0 +1,64 @@
+//! Guard traits shared with the standard locks, to migrate code from them to portals incrementally.
+
+use std::ops::{Deref, DerefMut};
+
+/// Implemented by all read guards, including [`std::sync::RwLockReadGuard`] and this crate's, as well as all write guards.
+///
+/// Replace a parameter of type `RwLockReadGuard<'_, T>` with `impl ReadGuard<T>` to accept either while migrating.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{ReadGuard, RwAnchor},
+///     std::sync::RwLock,
+/// };
+///
+/// fn len(names: impl ReadGuard<Vec<&'static str>>) -> usize {
+///     names.len()
+/// }
+///
+/// let lock = RwLock::new(vec!["Scoped"]);
+/// assert_eq!(len(lock.read().unwrap()), 1);
+///
+/// let mut names = vec!["Scoped", "portal"];
+/// let anchor = RwAnchor::new(&mut names);
+/// let portal = anchor.portal();
+/// assert_eq!(len(portal.read()), 2);
+/// # drop(portal);
+/// ```
+pub trait ReadGuard<T: ?Sized>: Deref<Target = T> {}
+
+impl<T: ?Sized, G: ?Sized + Deref<Target = T>> ReadGuard<T> for G {}
+
+/// Implemented by all write guards, including [`std::sync::RwLockWriteGuard`], [`std::sync::MutexGuard`] and this crate's.
+///
+/// Replace a parameter of type `RwLockWriteGuard<'_, T>` or `MutexGuard<'_, T>` with `impl WriteGuard<T>` to accept either while migrating.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{WAnchor, WriteGuard},
+///     std::sync::Mutex,
+/// };
+///
+/// fn push(mut names: impl WriteGuard<Vec<&'static str>>) {
+///     names.push("portal");
+/// }
+///
+/// let lock = Mutex::new(vec!["Scoped"]);
+/// push(lock.lock().unwrap());
+/// assert_eq!(*lock.lock().unwrap(), ["Scoped", "portal"]);
+///
+/// let mut names = vec!["Scoped"];
+/// let anchor = WAnchor::new(&mut names);
+/// let portal = anchor.portal();
+/// push(portal.lock());
+/// drop(portal);
+/// drop(anchor);
+/// assert_eq!(names, ["Scoped", "portal"]);
+/// ```
+pub trait WriteGuard<T: ?Sized>: DerefMut<Target = T> {}
+
+impl<T: ?Sized, G: ?Sized + DerefMut<Target = T>> WriteGuard<T> for G {}
@@ -0,0 +1,185 @@
+//! Views of an immutable anchor's target that outlive it as owned snapshots.
+
+use {
+    super::{acquire, Anchor, Portal, WeakPortal},
+    std::{
+        borrow::Borrow,
+        fmt::{self, Debug, Formatter},
+        ops::Deref,
+        sync::{Arc, OnceLock},
+    },
+};
+
+/// Either a live portal or an owned snapshot of the target, as returned by [`WeakPortal::to_cow`] and [`CowPortal::to_cow`].
+/// Dereferences to the target either way, so that e.g. reporting code can handle live and expired anchors uniformly.
+#[must_use]
+pub enum PortalCow<T: ?Sized + ToOwned> {
+    /// The anchor still exists. This counts as strong portal.
+    Borrowed(Portal<T>),
+
+    /// The anchor has been dropped. This is the snapshot taken as it was.
+    Owned(Arc<T::Owned>),
+}
+
+/// A weak portal that falls back to a snapshot of the target taken as the anchor is dropped, as returned by [`Anchor::cow_portal`].
+/// Only the target's last state is cloned, and only once.
+#[must_use]
+pub struct CowPortal<T: ?Sized + ToOwned> {
+    /// Upgraded while the anchor exists.
+    weak: WeakPortal<T>,
+
+    /// Set as the anchor is dropped.
+    snapshot: Arc<OnceLock<Arc<T::Owned>>>,
+}
+
+impl<'a, T: ?Sized + ToOwned + Send + Sync + 'static> Anchor<'a, T>
+where
+    T::Owned: Send + Sync,
+{
+    /// Creates a weak portal that, once this anchor has been dropped, reads an owned snapshot of the target instead.
+    /// The snapshot is taken as the anchor is dropped, and shared by all clones of the returned portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{Anchor, PortalCow};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new_str(&x);
+    /// let cow_portal = anchor.cow_portal();
+    ///
+    /// let view = cow_portal.to_cow().unwrap();
+    /// assert!(matches!(view, PortalCow::Borrowed(_)));
+    /// assert_eq!(&*view, "Scoped");
+    /// drop(view);
+    ///
+    /// drop(anchor);
+    /// let view = cow_portal.to_cow().unwrap();
+    /// assert!(matches!(view, PortalCow::Owned(_)));
+    /// assert_eq!(&*view, "Scoped");
+    /// ```
+    pub fn cow_portal(&self) -> CowPortal<T> {
+        let snapshot = Arc::new(OnceLock::new());
+        self.on_drop({
+            let weak = Arc::downgrade(&self.reference);
+            let snapshot = Arc::clone(&snapshot);
+            move || {
+                // Still allocated, since the anchor holds its reference while dropping.
+                if let Some(shared) = weak.upgrade() {
+                    let target = unsafe {
+                        //SAFETY: Still valid while the anchor is being dropped, and immutable.
+                        shared.target.as_ref()
+                    };
+                    drop(snapshot.set(Arc::new(target.to_owned())));
+                }
+            }
+        });
+        CowPortal {
+            weak: self.weak_portal(),
+            snapshot,
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned> WeakPortal<T> {
+    /// Returns a view of the target that borrows it through a portal, iff the anchor still exists and isn't sealed.
+    /// Use a [`CowPortal`] to fall back to an owned snapshot instead.
+    #[must_use]
+    pub fn to_cow(&self) -> Option<PortalCow<T>> {
+        acquire(&self.0)
+            .ok()
+            .map(|shared| PortalCow::Borrowed(Portal::new(shared)))
+    }
+}
+
+impl<T: ?Sized + ToOwned> CowPortal<T> {
+    /// Returns a view of the target that borrows it through a portal while the anchor exists,
+    /// or the snapshot taken as it was dropped.
+    /// Returns `None` only while the anchor is sealed.
+    #[must_use]
+    pub fn to_cow(&self) -> Option<PortalCow<T>> {
+        self.weak.to_cow().or_else(|| {
+            self.snapshot
+                .get()
+                .map(|snapshot| PortalCow::Owned(Arc::clone(snapshot)))
+        })
+    }
+
+    /// Returns the weak portal this reads through while the anchor exists.
+    pub const fn weak_portal(&self) -> &WeakPortal<T> {
+        &self.weak
+    }
+}
+
+impl<T: ?Sized + ToOwned> PortalCow<T> {
+    /// Returns `true` iff this borrows the target through a live portal.
+    #[must_use]
+    pub const fn is_borrowed(&self) -> bool {
+        matches!(self, Self::Borrowed(_))
+    }
+
+    /// Clones the target out, or unwraps the snapshot if it's not shared.
+    #[must_use]
+    pub fn into_owned(self) -> T::Owned {
+        match self {
+            Self::Borrowed(portal) => (*portal).to_owned(),
+            Self::Owned(snapshot) => {
+                Arc::try_unwrap(snapshot).unwrap_or_else(|snapshot| (*snapshot).borrow().to_owned())
+            }
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned> Deref for PortalCow<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            Self::Borrowed(portal) => portal,
+            Self::Owned(snapshot) => (**snapshot).borrow(),
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned> AsRef<T> for PortalCow<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T: ?Sized + ToOwned> Clone for PortalCow<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        match self {
+            Self::Borrowed(portal) => Self::Borrowed(portal.clone()),
+            Self::Owned(snapshot) => Self::Owned(Arc::clone(snapshot)),
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned + Debug> Debug for PortalCow<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Borrowed(portal) => f.debug_tuple("Borrowed").field(portal).finish(),
+            Self::Owned(_) => f.debug_tuple("Owned").field(&&**self).finish(),
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned> Clone for CowPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            weak: self.weak.clone(),
+            snapshot: Arc::clone(&self.snapshot),
+        }
+    }
+}
+
+impl<T: ?Sized + ToOwned + Debug> Debug for CowPortal<T> {
+    /// Shows the target like [`PortalCow`] does, or `None` while the anchor is sealed.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CowPortal").field(&self.to_cow()).finish()
+    }
+}
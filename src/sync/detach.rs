@@ -0,0 +1,196 @@
+//! Detaching mutable anchors from their target and re-attaching them to a new one.
+
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::Registration;
+use {
+    super::{
+        Dependents, Mutex, Ordering, RwAnchor, RwShared, SSNonNull, WAnchor, WShared, WeakRwPortal,
+        WeakWPortal, ANCHORED,
+    },
+    crate::Callbacks,
+    std::{
+        marker::PhantomData,
+        mem::ManuallyDrop,
+        ptr::NonNull,
+        sync::{Arc, PoisonError},
+    },
+    wyz::pipe::*,
+};
+
+/// An `RwAnchor` without target, as returned by [`RwAnchor::downgrade`].  
+/// Its weak portals can't be upgraded until it's attached to a new target.
+#[derive(Debug)]
+#[must_use]
+pub struct DetachedRwAnchor<T: ?Sized> {
+    /// Shared with the weak portals.
+    reference: Arc<RwShared<T>>,
+
+    /// The label of the anchor this was detached from.
+    label: Option<&'static str>,
+}
+
+/// A `WAnchor` without target, as returned by [`WAnchor::downgrade`].  
+/// Its weak portals can't be upgraded until it's attached to a new target.
+#[derive(Debug)]
+#[must_use]
+pub struct DetachedWAnchor<T: ?Sized> {
+    /// Shared with the weak portals.
+    reference: Arc<WShared<T>>,
+
+    /// The label of the anchor this was detached from.
+    label: Option<&'static str>,
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Releases the captured reference like dropping this anchor would, but keeps its weak portals associated with the returned [`DetachedRwAnchor`].  
+    /// Attaching that to a new target revives them, e.g. to resume after recreating a resource, without handing out new weak portals.
+    ///
+    /// Drop and idle callbacks don't carry over.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping this anchor, if any associated `RwPortal`s exist or it has been poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut device = "Device 1".to_owned();
+    /// let anchor = RwAnchor::new(&mut device);
+    /// let weak_portal = anchor.weak_portal();
+    ///
+    /// let detached = anchor.downgrade();
+    /// assert!(weak_portal.try_upgrade().is_none());
+    ///
+    /// let mut recreated = "Device 2".to_owned();
+    /// let anchor = detached.attach(&mut recreated);
+    /// weak_portal.upgrade().write().push_str(" (resumed)");
+    /// drop(anchor);
+    ///
+    /// assert_eq!(recreated, "Device 2 (resumed)");
+    /// ```
+    pub fn downgrade(self) -> DetachedRwAnchor<T> {
+        let reference = self.reference.pipe_deref(Arc::clone);
+        let label = self.label;
+        drop(self);
+        // No portal can access the target anymore, but the pointer is overwritten anyway.
+        reference
+            .target
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .dangle();
+        DetachedRwAnchor { reference, label }
+    }
+}
+
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Releases the captured reference like dropping this anchor would, but keeps its weak portals associated with the returned [`DetachedWAnchor`].  
+    /// Attaching that to a new target revives them, e.g. to resume after recreating a resource, without handing out new weak portals.
+    ///
+    /// Drop and idle callbacks don't carry over.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping this anchor, if any associated `WPortal`s exist or it has been poisoned.
+    pub fn downgrade(self) -> DetachedWAnchor<T> {
+        let reference = self.reference.pipe_deref(Arc::clone);
+        let label = self.label;
+        drop(self);
+        // No portal can access the target anymore, but the pointer is overwritten anyway.
+        reference
+            .target
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .dangle();
+        DetachedWAnchor { reference, label }
+    }
+}
+
+impl<T: ?Sized> DetachedRwAnchor<T> {
+    /// Creates a weak portal that can be upgraded once this is attached again.
+    #[inline]
+    pub fn weak_portal(&self) -> WeakRwPortal<T> {
+        WeakRwPortal(Arc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Captures `reference` as new target, reviving the weak portals associated with this.  
+    /// The returned anchor keeps the label of the one this was detached from.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn attach(self, reference: &mut T) -> RwAnchor<'_, T> {
+        let pointer = NonNull::from(reference);
+        *self
+            .reference
+            .target
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = SSNonNull::new(pointer);
+        unsafe {
+            //SAFETY: Detached, so no strong portals exist.
+            self.reference.address.set(pointer);
+        }
+        // Counts as write, so that values derived from the previous target are recomputed.
+        self.reference.writes.fetch_add(1, Ordering::Relaxed);
+        // Publishes the new target to weak portal upgrades.
+        self.reference.state.fetch_or(ANCHORED, Ordering::Release);
+
+        #[cfg(feature = "diagnostics")]
+        let registration = Registration::sync::<T, _>("sync::RwAnchor", &self.reference);
+        #[cfg(feature = "diagnostics")]
+        if let Some(label) = self.label {
+            registration.set_label(label);
+        }
+        RwAnchor {
+            #[cfg(feature = "diagnostics")]
+            registration,
+            reference: ManuallyDrop::new(self.reference),
+            label: self.label,
+            drop_callbacks: Mutex::new(Callbacks::new()),
+            bound_threads: Mutex::new(Callbacks::new()),
+            dependents: Dependents::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> DetachedWAnchor<T> {
+    /// Creates a weak portal that can be upgraded once this is attached again.
+    #[inline]
+    pub fn weak_portal(&self) -> WeakWPortal<T> {
+        WeakWPortal(Arc::downgrade(&self.reference), self.reference.id)
+    }
+
+    /// Captures `reference` as new target, reviving the weak portals associated with this.  
+    /// The returned anchor keeps the label of the one this was detached from.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn attach(self, reference: &mut T) -> WAnchor<'_, T> {
+        let pointer = NonNull::from(reference);
+        *self
+            .reference
+            .target
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = SSNonNull::new(pointer);
+        unsafe {
+            //SAFETY: Detached, so no strong portals exist.
+            self.reference.address.set(pointer);
+        }
+        // Publishes the new target to weak portal upgrades.
+        self.reference.state.fetch_or(ANCHORED, Ordering::Release);
+
+        #[cfg(feature = "diagnostics")]
+        let registration = Registration::sync::<T, _>("sync::WAnchor", &self.reference);
+        #[cfg(feature = "diagnostics")]
+        if let Some(label) = self.label {
+            registration.set_label(label);
+        }
+        WAnchor {
+            #[cfg(feature = "diagnostics")]
+            registration,
+            reference: ManuallyDrop::new(self.reference),
+            label: self.label,
+            drop_callbacks: Mutex::new(Callbacks::new()),
+            bound_threads: Mutex::new(Callbacks::new()),
+            dependents: Dependents::new(),
+            _phantom: PhantomData,
+        }
+    }
+}
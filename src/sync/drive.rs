@@ -0,0 +1,324 @@
+//! Running loops against anchored state until its owner tears it down.
+
+use {
+    super::{ChannelReceiver, RwPortal},
+    crate::poison::{Expired, RecvError},
+    std::{
+        io, mem,
+        ops::ControlFlow,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        thread::{self, Builder, JoinHandle, Thread},
+        time::{Duration, Instant},
+    },
+};
+
+/// How long a [`Driver`] waits for a tick before checking whether it should stop,
+/// which bounds how long sealing the anchor or calling [`Driver::stop`] can take to be noticed.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Runs one step of a state machine against the target under a write guard, unless the anchor has been sealed.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been sealed, e.g. through a [`ShutdownToken`](`super::ShutdownToken`) it's bound to, in which case `step` isn't called.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned. If `step` panics, the anchor is poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{poison::Expired, sync::{RwAnchor, ShutdownToken}},
+    ///     std::ops::ControlFlow,
+    /// };
+    ///
+    /// let mut countdown = 2;
+    /// let anchor = RwAnchor::new(&mut countdown);
+    /// let token = ShutdownToken::new();
+    /// anchor.bind_shutdown(&token);
+    /// let portal = anchor.portal();
+    ///
+    /// let step = |countdown: &mut i32| {
+    ///     *countdown -= 1;
+    ///     if *countdown > 0 {
+    ///         ControlFlow::Continue(())
+    ///     } else {
+    ///         ControlFlow::Break("Liftoff")
+    ///     }
+    /// };
+    /// assert_eq!(portal.tick(step), Ok(ControlFlow::Continue(())));
+    /// assert_eq!(portal.tick(step), Ok(ControlFlow::Break("Liftoff")));
+    ///
+    /// token.shutdown();
+    /// assert_eq!(portal.tick(step), Err(Expired));
+    /// # drop(portal);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn tick<B>(
+        &self,
+        step: impl FnOnce(&mut T) -> ControlFlow<B>,
+    ) -> Result<ControlFlow<B>, Expired> {
+        if self.0.idle.is_sealed() {
+            return Err(Expired);
+        }
+        Ok(step(&mut self.write_guard("sync::RwPortal::tick")))
+    }
+}
+
+/// The outcome of waiting on a [`TickSource`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tick {
+    /// The next step is due.
+    Ready,
+
+    /// The timeout passed without a tick.
+    Pending,
+
+    /// No more ticks will come.
+    Ended,
+}
+
+/// Paces the steps of a [`Driver`], e.g. a timer or an event source.
+///
+/// Implemented by [`Interval`] and all [`ChannelReceiver`]s, for which each received message is a tick and disconnection ends them.
+pub trait TickSource {
+    /// Waits up to about `timeout` for the next tick.
+    fn wait(&mut self, timeout: Duration) -> Tick;
+}
+
+impl<R: ChannelReceiver> TickSource for R {
+    fn wait(&mut self, timeout: Duration) -> Tick {
+        match self.receive_timeout(timeout) {
+            Ok(_) => Tick::Ready,
+            Err(RecvError::Empty) => Tick::Pending,
+            Err(RecvError::Disconnected | RecvError::Closed) => Tick::Ended,
+        }
+    }
+}
+
+/// A [`TickSource`] that ticks once per period, skipping ticks that were missed because a step took too long.
+#[derive(Debug, Clone)]
+pub struct Interval {
+    /// The time between two ticks.
+    period: Duration,
+
+    /// When the next tick is due.
+    next: Instant,
+}
+
+impl Interval {
+    /// Creates a timer that first ticks after `period` has passed.
+    #[must_use]
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            next: Instant::now() + period,
+        }
+    }
+
+    /// Returns the time between two ticks.
+    #[must_use]
+    pub const fn period(&self) -> Duration {
+        self.period
+    }
+}
+
+impl TickSource for Interval {
+    fn wait(&mut self, timeout: Duration) -> Tick {
+        let remaining = self.next.saturating_duration_since(Instant::now());
+        if remaining > timeout {
+            thread::sleep(timeout);
+            return Tick::Pending;
+        }
+        thread::sleep(remaining);
+        let now = Instant::now();
+        self.next += self.period;
+        if self.next <= now {
+            self.next = now + self.period;
+        }
+        Tick::Ready
+    }
+}
+
+/// Why a [`Driver`] stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DriverStop<B> {
+    /// The step broke with this value.
+    Break(B),
+
+    /// The anchor was sealed, e.g. because its owner signalled shutdown.
+    Sealed,
+
+    /// The tick source ended.
+    SourceEnded,
+
+    /// [`Driver::stop`] was called, or the driver was dropped.
+    Requested,
+}
+
+/// Steps anchored state on its own thread whenever its [`TickSource`] ticks, until the step breaks,
+/// the anchor is sealed, the source ends or the driver's owner stops it.
+///
+/// Dropping the driver stops it and joins its thread, so its portal is gone afterwards.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{Driver, DriverStop, Interval, RwAnchor, ShutdownToken},
+///     std::{ops::ControlFlow, thread::Builder, time::Duration},
+/// };
+///
+/// let mut frames = 0_u64;
+/// let anchor = RwAnchor::new(&mut frames);
+/// let token = ShutdownToken::new();
+/// anchor.bind_shutdown(&token);
+///
+/// let driver = Driver::spawn(
+///     Builder::new().name("game loop".to_owned()),
+///     anchor.portal(),
+///     Interval::new(Duration::from_millis(1)),
+///     |frames: &mut u64| {
+///         *frames += 1;
+///         ControlFlow::<()>::Continue(())
+///     },
+/// )
+/// .unwrap();
+///
+/// token.shutdown(); // Tears the loop down.
+/// assert_eq!(driver.join().unwrap(), DriverStop::Sealed);
+/// drop(anchor);
+/// ```
+#[derive(Debug)]
+pub struct Driver<B> {
+    /// Set to request a stop.
+    stop: Arc<AtomicBool>,
+
+    /// `None` only once joined.
+    handle: Option<JoinHandle<DriverStop<B>>>,
+
+    /// The driving thread.
+    thread: Thread,
+}
+
+impl<B: Send + 'static> Driver<B> {
+    /// Spawns a thread through `builder` that calls [`portal.tick(step)`](`RwPortal::tick`) on each tick of `source`.
+    ///
+    /// Sealing and stop requests are noticed within a few milliseconds, even while `source` doesn't tick.
+    ///
+    /// # Errors
+    ///
+    /// Iff `builder` fails to spawn the thread.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{Driver, DriverStop, RwAnchor},
+    ///     std::{ops::ControlFlow, sync::mpsc, thread::Builder},
+    /// };
+    ///
+    /// let mut pending = vec!["c", "b", "a"];
+    /// let anchor = RwAnchor::new(&mut pending);
+    /// let (events, event_receiver) = mpsc::channel();
+    ///
+    /// let driver = Driver::spawn(Builder::new(), anchor.portal(), event_receiver, |pending: &mut Vec<&str>| {
+    ///     pending.pop();
+    ///     if pending.is_empty() {
+    ///         ControlFlow::Break("Drained")
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// })
+    /// .unwrap();
+    ///
+    /// events.send(()).unwrap();
+    /// drop(events);
+    /// assert_eq!(driver.join().unwrap(), DriverStop::SourceEnded);
+    ///
+    /// drop(anchor);
+    /// assert_eq!(pending, ["c", "b"]);
+    /// ```
+    pub fn spawn<T: ?Sized + 'static>(
+        builder: Builder,
+        portal: RwPortal<T>,
+        mut source: impl TickSource + Send + 'static,
+        mut step: impl FnMut(&mut T) -> ControlFlow<B> + Send + 'static,
+    ) -> io::Result<Self>
+    where
+        RwPortal<T>: Send,
+    {
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let handle = builder.spawn({
+            let stop_requested = Arc::clone(&stop_requested);
+            move || loop {
+                if stop_requested.load(Ordering::Acquire) {
+                    return DriverStop::Requested;
+                }
+                match source.wait(POLL_INTERVAL) {
+                    Tick::Ready => match portal.tick(&mut step) {
+                        Ok(ControlFlow::Continue(())) => (),
+                        Ok(ControlFlow::Break(value)) => return DriverStop::Break(value),
+                        Err(Expired) => return DriverStop::Sealed,
+                    },
+                    Tick::Pending if portal.0.idle.is_sealed() => return DriverStop::Sealed,
+                    Tick::Pending => (),
+                    Tick::Ended => return DriverStop::SourceEnded,
+                }
+            }
+        })?;
+        Ok(Self {
+            stop: stop_requested,
+            thread: handle.thread().clone(),
+            handle: Some(handle),
+        })
+    }
+}
+
+impl<B> Driver<B> {
+    /// Requests the driver to stop before its next step.
+    /// A step that is running already is completed first.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` iff the driver has stopped, so that [`join`](#method.join) won't block.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().map_or(true, JoinHandle::is_finished)
+    }
+
+    /// Waits for the driver to stop, and returns why it did.
+    ///
+    /// # Errors
+    ///
+    /// Iff the step panicked, with the panic payload. The anchor is poisoned in that case.
+    pub fn join(mut self) -> thread::Result<DriverStop<B>> {
+        match self.handle.take() {
+            Some(handle) => handle.join(),
+            None => unreachable!("Only taken here or on drop"),
+        }
+    }
+
+    /// Returns a handle to the driving thread.
+    #[inline]
+    #[must_use]
+    pub const fn thread(&self) -> &Thread {
+        &self.thread
+    }
+}
+
+impl<B> Drop for Driver<B> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.stop();
+            mem::drop(handle.join());
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! Lifecycle events of threadsafe anchors, published only with the `events` feature.
+//! Without it, `Emitter` is zero-sized and all operations are no-ops.
+
+#[cfg(feature = "events")]
+use {
+    super::{Anchor, AtomicBool, Condvar, Mutex, MutexGuard, Ordering, RwAnchor, WAnchor},
+    std::{
+        collections::VecDeque,
+        mem,
+        num::NonZeroUsize,
+        sync::{Arc, PoisonError, Weak},
+    },
+};
+#[cfg(all(feature = "events", feature = "stream"))]
+use {
+    futures_core::Stream,
+    std::{
+        pin::Pin,
+        task::{Context, Poll, Waker},
+    },
+};
+
+/// Publishes an anchor's lifecycle events to its [`AnchorEvents`] subscriptions.
+#[derive(Debug)]
+pub(super) struct Emitter {
+    /// Set once the first subscription is created, so that events are only assembled for anchors that have any.
+    #[cfg(feature = "events")]
+    subscribed: AtomicBool,
+
+    /// Locked to publish or subscribe.
+    #[cfg(feature = "events")]
+    subscribers: Mutex<Subscribers>,
+}
+
+/// A lifecycle transition of a threadsafe anchor, as received through [`AnchorEvents`].
+///
+/// Only available with the `events` feature.
+#[cfg(feature = "events")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum AnchorEvent {
+    /// Stands in for the anchor's creation as first event of each subscription, so that each describes a whole lifecycle.
+    Created,
+
+    /// A strong portal was created (also by upgrading a weak one), after which `strong` exist.
+    PortalMinted {
+        /// The number of strong portals, including the new one.
+        strong: usize,
+    },
+
+    /// A guard was acquired through a portal.
+    GuardAcquired {
+        /// The method that acquired it, e.g. `"sync::RwPortal::read"`.
+        operation: &'static str,
+
+        /// Whether it's a write (or mutex) guard.
+        exclusive: bool,
+    },
+
+    /// The anchor was sealed, through a [`ShutdownToken`](`super::ShutdownToken`), its group or an anchor it depends on.
+    /// Sealing through an expiry isn't reported, since that only takes effect as it's checked.
+    Sealed,
+
+    /// The last strong portal was dropped.
+    Idle,
+
+    /// The anchor was dropped. This is the last event of each subscription.
+    Dropped,
+}
+
+/// The live subscriptions of an [`Emitter`].
+#[cfg(feature = "events")]
+#[derive(Debug)]
+struct Subscribers {
+    /// Dropped subscriptions are removed on the next event.
+    queues: Vec<Weak<Queue>>,
+
+    /// Set once the anchor has been dropped, after which no more events are published.
+    closed: bool,
+}
+
+/// The events buffered for one [`AnchorEvents`] subscription.
+#[cfg(feature = "events")]
+#[derive(Debug)]
+struct Queue {
+    /// Locked to wait on or notify `condvar`.
+    state: Mutex<QueueState>,
+
+    /// Notified whenever an event is buffered, and once the emitter is closed.
+    condvar: Condvar,
+
+    /// How many events are buffered before dropping the oldest.
+    capacity: NonZeroUsize,
+}
+
+/// The state behind a [`Queue`].
+#[cfg(feature = "events")]
+#[derive(Debug)]
+struct QueueState {
+    /// Oldest first.
+    events: VecDeque<AnchorEvent>,
+
+    /// How many events were dropped because the buffer was full, since this was last reset.
+    missed: usize,
+
+    /// Whether the anchor has been dropped.
+    closed: bool,
+
+    /// Woken along with `condvar`, if the subscription was last polled as stream.
+    #[cfg(feature = "stream")]
+    waker: Option<Waker>,
+}
+
+/// Receives an anchor's lifecycle events, oldest first, as created through the threadsafe anchors' `events` methods.
+/// Doesn't count as portal, so it can outlive the anchor. Once the anchor is dropped, the remaining events can still be received.
+///
+/// Consume it blocking through [`recv`](#method.recv) or as [`Iterator`],
+/// or, with the `stream` feature, as [`Stream`](https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html).
+///
+/// Only available with the `events` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{AnchorEvent, RwAnchor},
+///     std::num::NonZeroUsize,
+/// };
+///
+/// let mut x = "Scoped".to_owned();
+/// let anchor = RwAnchor::new(&mut x);
+/// let events = anchor.events(NonZeroUsize::new(8).unwrap());
+///
+/// let portal = anchor.portal();
+/// drop(portal.read());
+/// drop(portal);
+/// drop(anchor);
+///
+/// assert_eq!(
+///     events.collect::<Vec<_>>(),
+///     [
+///         AnchorEvent::Created,
+///         AnchorEvent::PortalMinted { strong: 1 },
+///         AnchorEvent::GuardAcquired {
+///             operation: "sync::RwPortal::read",
+///             exclusive: false,
+///         },
+///         AnchorEvent::Idle,
+///         AnchorEvent::Dropped,
+///     ],
+/// );
+/// ```
+#[cfg(feature = "events")]
+#[derive(Debug)]
+#[must_use]
+pub struct AnchorEvents(Arc<Queue>);
+
+#[cfg(feature = "events")]
+impl Emitter {
+    /// Creates an instance without subscriptions.
+    #[allow(clippy::missing_const_for_fn)] // Not with loom.
+    pub(super) fn new() -> Self {
+        Self {
+            subscribed: AtomicBool::new(false),
+            subscribers: Mutex::new(Subscribers {
+                queues: Vec::new(),
+                closed: false,
+            }),
+        }
+    }
+
+    /// Locks the subscribers, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, Subscribers> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Subscribes to the events published from now on, buffering up to `capacity` of them.
+    fn subscribe(&self, capacity: NonZeroUsize) -> AnchorEvents {
+        let mut subscribers = self.lock();
+        let mut events = VecDeque::with_capacity(capacity.get());
+        events.push_back(AnchorEvent::Created);
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                events,
+                missed: 0,
+                closed: subscribers.closed,
+                #[cfg(feature = "stream")]
+                waker: None,
+            }),
+            condvar: Condvar::new(),
+            capacity,
+        });
+        subscribers.queues.push(Arc::downgrade(&queue));
+        self.subscribed.store(true, Ordering::Release);
+        AnchorEvents(queue)
+    }
+
+    /// Publishes `event` to all subscriptions, dropping their oldest one where full.
+    fn emit(&self, event: AnchorEvent) {
+        if !self.subscribed.load(Ordering::Acquire) {
+            return;
+        }
+        self.lock().queues.retain(|queue| {
+            if let Some(queue) = queue.upgrade() {
+                let mut state = queue.lock();
+                if state.events.len() == queue.capacity.get() {
+                    state.events.pop_front();
+                    state.missed += 1;
+                }
+                state.events.push_back(event);
+                queue.wake(state);
+                true
+            } else {
+                false
+            }
+        });
+    }
+
+    /// Publishes [`AnchorEvent::PortalMinted`].
+    #[inline]
+    pub(super) fn minted(&self, strong: usize) {
+        self.emit(AnchorEvent::PortalMinted { strong });
+    }
+
+    /// Publishes [`AnchorEvent::GuardAcquired`].
+    #[inline]
+    pub(super) fn acquired(&self, operation: &'static str, exclusive: bool) {
+        self.emit(AnchorEvent::GuardAcquired {
+            operation,
+            exclusive,
+        });
+    }
+
+    /// Publishes [`AnchorEvent::Sealed`].
+    #[inline]
+    pub(super) fn sealed(&self) {
+        self.emit(AnchorEvent::Sealed);
+    }
+
+    /// Publishes [`AnchorEvent::Idle`].
+    #[inline]
+    pub(super) fn idle(&self) {
+        self.emit(AnchorEvent::Idle);
+    }
+
+    /// Publishes [`AnchorEvent::Dropped`], then closes all subscriptions once they are drained.
+    pub(super) fn dropped(&self) {
+        self.emit(AnchorEvent::Dropped);
+        let mut subscribers = self.lock();
+        subscribers.closed = true;
+        for queue in mem::take(&mut subscribers.queues)
+            .iter()
+            .filter_map(Weak::upgrade)
+        {
+            let mut state = queue.lock();
+            state.closed = true;
+            queue.wake(state);
+        }
+    }
+}
+
+#[cfg(not(feature = "events"))]
+impl Emitter {
+    /// Creates an instance without subscriptions.
+    #[allow(clippy::missing_const_for_fn)] // Not with the `events` feature.
+    pub(super) fn new() -> Self {
+        Self {}
+    }
+
+    /// Publishes a minted portal.
+    /// Only with the `events` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn minted(&self, _: usize) {}
+
+    /// Publishes an acquired guard.
+    /// Only with the `events` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn acquired(&self, _: &'static str, _: bool) {}
+
+    /// Publishes that the anchor was sealed.
+    /// Only with the `events` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn sealed(&self) {}
+
+    /// Publishes that the anchor became idle.
+    /// Only with the `events` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn idle(&self) {}
+
+    /// Publishes that the anchor was dropped.
+    /// Only with the `events` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn dropped(&self) {}
+}
+
+#[cfg(feature = "events")]
+impl Queue {
+    /// Locks the queue state, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Releases `state`, then wakes whoever waits for it to change.
+    #[allow(unused_mut)] // Only mutated with the `stream` feature.
+    fn wake(&self, mut state: MutexGuard<'_, QueueState>) {
+        #[cfg(feature = "stream")]
+        let waker = state.waker.take();
+        drop(state);
+        self.condvar.notify_all();
+        #[cfg(feature = "stream")]
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(feature = "events")]
+impl AnchorEvents {
+    /// Removes and returns the oldest buffered event, if any, without blocking.
+    #[must_use]
+    pub fn try_recv(&self) -> Option<AnchorEvent> {
+        self.0.lock().events.pop_front()
+    }
+
+    /// Removes and returns the oldest buffered event, blocking until there is one.
+    /// Returns `None` once the anchor has been dropped and all events were received.
+    #[must_use]
+    pub fn recv(&self) -> Option<AnchorEvent> {
+        let mut state = self.0.lock();
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                return Some(event);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .0
+                .condvar
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Returns how many events were dropped because the buffer was full since the last call, and resets the count.
+    #[must_use]
+    pub fn take_missed(&self) -> usize {
+        mem::take(&mut self.0.lock().missed)
+    }
+}
+
+#[cfg(feature = "events")]
+impl Iterator for AnchorEvents {
+    type Item = AnchorEvent;
+
+    /// Like [`recv`](#method.recv).
+    #[inline]
+    fn next(&mut self) -> Option<AnchorEvent> {
+        self.recv()
+    }
+}
+
+#[cfg(all(feature = "events", feature = "stream"))]
+impl Stream for AnchorEvents {
+    type Item = AnchorEvent;
+
+    /// Like [`try_recv`](#method.try_recv), but pending until there is an event or the anchor has been dropped.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<AnchorEvent>> {
+        let mut state = self.0.lock();
+        if let Some(event) = state.events.pop_front() {
+            Poll::Ready(Some(event))
+        } else if state.closed {
+            Poll::Ready(None)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(feature = "events")]
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Subscribes to this anchor's lifecycle events from now on, buffering up to `capacity` of them.
+    /// Full subscriptions drop their oldest event to make room, and count it as [missed](`AnchorEvents::take_missed`).
+    ///
+    /// Only available with the `events` feature.
+    pub fn events(&self, capacity: NonZeroUsize) -> AnchorEvents {
+        self.reference.idle.events.subscribe(capacity)
+    }
+}
+
+#[cfg(feature = "events")]
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Subscribes to this anchor's lifecycle events from now on, buffering up to `capacity` of them.
+    /// Full subscriptions drop their oldest event to make room, and count it as [missed](`AnchorEvents::take_missed`).
+    ///
+    /// Only available with the `events` feature.
+    pub fn events(&self, capacity: NonZeroUsize) -> AnchorEvents {
+        self.reference.idle.events.subscribe(capacity)
+    }
+}
+
+#[cfg(feature = "events")]
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Subscribes to this anchor's lifecycle events from now on, buffering up to `capacity` of them.
+    /// Full subscriptions drop their oldest event to make room, and count it as [missed](`AnchorEvents::take_missed`).
+    ///
+    /// Only available with the `events` feature.
+    pub fn events(&self, capacity: NonZeroUsize) -> AnchorEvents {
+        self.reference.idle.events.subscribe(capacity)
+    }
+}
@@ -0,0 +1,203 @@
+//! Lending mutable references to each other across two threads.
+
+use {
+    super::{Condvar, Mutex, MutexGuard, RwAnchor, RwPortal},
+    std::{
+        fmt::{self, Debug, Formatter},
+        sync::PoisonError,
+    },
+};
+
+/// A rendezvous at which a thread on the left and one on the right each lend a mutable reference
+/// and receive an [`RwPortal`] to the other's value, e.g. for symmetric pipeline stages that trade buffers.
+///
+/// Each side's anchor is dropped only once both closures have completed, so neither anchor can outlive the other's use of it.
+/// The exchange can be used for any number of rounds, and each side blocks until the other arrives.
+///
+/// If a closure panics, the other side still completes its round, but a panic while writing through a portal poisons that portal's anchor,
+/// which then panics in turn when its side drops it.
+///
+/// # Example
+///
+/// ```rust
+/// use {ref_portals::sync::Exchange, std::thread};
+///
+/// let exchange = Exchange::<Vec<i32>, Vec<i32>>::new();
+/// let mut produced = vec![1, 2, 3];
+/// let mut consumed = vec![];
+///
+/// thread::scope(|scope| {
+///     scope.spawn(|| exchange.left(&mut produced, |consumed| consumed.write().push(0)));
+///     exchange.right(&mut consumed, |produced| {
+///         let mut produced = produced.write();
+///         produced.reverse();
+///     });
+/// });
+///
+/// assert_eq!(produced, [3, 2, 1]);
+/// assert_eq!(consumed, [0]);
+/// ```
+pub struct Exchange<A: ?Sized, B: ?Sized> {
+    /// The current round.
+    state: Mutex<Round<A, B>>,
+
+    /// Notified whenever `state` changes.
+    changed: Condvar,
+}
+
+/// The state of an [`Exchange`] round.
+struct Round<A: ?Sized, B: ?Sized> {
+    /// The left side's deposit.
+    left: Side<A>,
+
+    /// The right side's deposit.
+    right: Side<B>,
+
+    /// How many sides have completed their closure in the current round.
+    finished: u8,
+
+    /// Incremented (wrapping) whenever a round is complete.
+    count: u64,
+}
+
+/// One side of an [`Exchange`] round.
+struct Side<T: ?Sized> {
+    /// Whether a thread is taking part on this side in the current round.
+    busy: bool,
+
+    /// The portal lent by this side, until the other side takes it.
+    lent: Option<RwPortal<T>>,
+}
+
+/// Selects the calling side's deposit and the other's from a [`Round`].
+type Sides<A, B, M, O> = fn(&mut Round<A, B>) -> (&mut Side<M>, &mut Side<O>);
+
+impl<A: ?Sized, B: ?Sized> Exchange<A, B> {
+    /// Creates a new `Exchange` instance that's waiting for both sides.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // Not with loom.
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(Round {
+                left: Side::new(),
+                right: Side::new(),
+                finished: 0,
+                count: 0,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Lends `value` to the right side and calls `f` with a portal to the right side's value, once that has been lent.
+    /// Returns only after the right side's closure has completed, too.
+    ///
+    /// Blocks while another thread takes part on the left side, until that round is complete.
+    pub fn left<R>(&self, value: &mut A, f: impl FnOnce(&RwPortal<B>) -> R) -> R {
+        self.meet(value, |round| (&mut round.left, &mut round.right), f)
+    }
+
+    /// Lends `value` to the left side and calls `f` with a portal to the left side's value, once that has been lent.
+    /// Returns only after the left side's closure has completed, too.
+    ///
+    /// Blocks while another thread takes part on the right side, until that round is complete.
+    pub fn right<R>(&self, value: &mut B, f: impl FnOnce(&RwPortal<A>) -> R) -> R {
+        self.meet(value, |round| (&mut round.right, &mut round.left), f)
+    }
+
+    /// Takes part in a round on the side selected by `sides`.
+    fn meet<M: ?Sized, O: ?Sized, R>(
+        &self,
+        value: &mut M,
+        sides: Sides<A, B, M, O>,
+        f: impl FnOnce(&RwPortal<O>) -> R,
+    ) -> R {
+        let anchor = RwAnchor::new(value);
+
+        let mut round = self.lock();
+        while sides(&mut round).0.busy {
+            round = self.wait(round);
+        }
+        let mine = sides(&mut round).0;
+        mine.busy = true;
+        mine.lent = Some(anchor.portal());
+        self.changed.notify_all();
+        let theirs = loop {
+            if let Some(theirs) = sides(&mut round).1.lent.take() {
+                break theirs;
+            }
+            round = self.wait(round);
+        };
+        drop(round);
+
+        let _finish = Finish {
+            exchange: self,
+            sides,
+        };
+        // Declared after `_finish`, so that it's dropped first, even while unwinding.
+        let theirs = theirs;
+        f(&theirs)
+    }
+
+    /// Locks the current round, ignoring poison since it's never left inconsistent.
+    fn lock(&self) -> MutexGuard<'_, Round<A, B>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for a change to the current round.
+    fn wait<'a>(&self, round: MutexGuard<'a, Round<A, B>>) -> MutexGuard<'a, Round<A, B>> {
+        self.changed
+            .wait(round)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Completes a side's part in a round when dropped, even during a panic,
+/// then waits for the other side so that the anchor can be dropped afterwards.
+struct Finish<'a, A: ?Sized, B: ?Sized, M: ?Sized, O: ?Sized> {
+    /// The exchange the round belongs to.
+    exchange: &'a Exchange<A, B>,
+
+    /// Selects this side's deposit.
+    sides: Sides<A, B, M, O>,
+}
+
+impl<'a, A: ?Sized, B: ?Sized, M: ?Sized, O: ?Sized> Drop for Finish<'a, A, B, M, O> {
+    fn drop(&mut self) {
+        let mut round = self.exchange.lock();
+        round.finished += 1;
+        if round.finished == 2 {
+            round.finished = 0;
+            round.count = round.count.wrapping_add(1);
+        } else {
+            let count = round.count;
+            while round.count == count {
+                round = self.exchange.wait(round);
+            }
+        }
+        (self.sides)(&mut round).0.busy = false;
+        self.exchange.changed.notify_all();
+    }
+}
+
+impl<T: ?Sized> Side<T> {
+    /// Creates a new idle side.
+    const fn new() -> Self {
+        Self {
+            busy: false,
+            lent: None,
+        }
+    }
+}
+
+impl<A: ?Sized, B: ?Sized> Default for Exchange<A, B> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: ?Sized, B: ?Sized> Debug for Exchange<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Exchange").finish_non_exhaustive()
+    }
+}
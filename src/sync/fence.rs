@@ -0,0 +1,289 @@
+//! Keeping buffers anchored until hardware or foreign code signals that it's done with them.
+
+#[cfg(feature = "ffi")]
+use std::ffi::c_void;
+use {
+    super::{RwAnchor, RwPortal},
+    std::{
+        fmt::{self, Debug, Formatter},
+        ops::Deref,
+        sync::{Arc, Condvar, Mutex, PoisonError},
+    },
+};
+
+/// The outstanding fences of a [`FenceAnchor`].
+#[derive(Debug)]
+struct Fences {
+    /// The number of fences that haven't been signalled yet. Locked to wait on or notify `signalled`.
+    outstanding: Mutex<usize>,
+
+    /// Notified when the last outstanding fence is signalled.
+    signalled: Condvar,
+}
+
+impl Fences {
+    /// Counts a new fence.
+    fn fence(self: &Arc<Self>) -> Fence {
+        *self
+            .outstanding
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) += 1;
+        Fence(Arc::clone(self))
+    }
+}
+
+/// An [`RwAnchor`] that, when dropped, first waits until all [`Fence`]s it handed out have been signalled,
+/// e.g. by the completion callback of a GPU readback or an audio stream.
+/// Dereferences to that anchor, so portals are created and checked as usual.
+///
+/// Dropping it while a fence is leaked or held on the current thread deadlocks.
+/// Portals that remain once all fences have been signalled make it panic as usual.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::FenceAnchor,
+///     std::{thread, time::Duration},
+/// };
+///
+/// let mut samples = [0_i16; 4];
+/// let anchor = FenceAnchor::new(&mut samples[..]);
+///
+/// // Stands in for a callback registered with an audio driver.
+/// let fenced = anchor.fenced();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(10));
+///     fenced.portal().write().copy_from_slice(&[1, -1, 1, -1]);
+///     fenced.signal();
+/// });
+///
+/// drop(anchor); // Waits for the fence.
+/// assert_eq!(samples, [1, -1, 1, -1]);
+/// ```
+pub struct FenceAnchor<'a, T: ?Sized> {
+    /// Dropped only after all fences have been signalled.
+    anchor: RwAnchor<'a, T>,
+
+    /// Shared with the fences.
+    fences: Arc<Fences>,
+}
+
+impl<'a, T: ?Sized> FenceAnchor<'a, T> {
+    /// Anchors `buffer`.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(buffer: &'a mut T) -> Self {
+        Self {
+            anchor: RwAnchor::new(buffer),
+            fences: Arc::new(Fences {
+                outstanding: Mutex::new(0),
+                signalled: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Creates a fence this anchor waits for before it's dropped.
+    pub fn fence(&self) -> Fence {
+        self.fences.fence()
+    }
+
+    /// Creates a portal together with a fence, e.g. to hand both to a callback registration at once.
+    pub fn fenced(&self) -> Fenced<T> {
+        Fenced {
+            portal: self.anchor.portal(),
+            fence: self.fence(),
+        }
+    }
+
+    /// Returns the number of fences that haven't been signalled yet.  
+    /// This is only a snapshot and may be outdated by the time it returns.
+    #[must_use]
+    pub fn outstanding_fences(&self) -> usize {
+        *self
+            .fences
+            .outstanding
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Blocks the current thread until all fences created so far have been signalled.
+    pub fn wait_fences(&self) {
+        drop(
+            self.fences
+                .signalled
+                .wait_while(
+                    self.fences
+                        .outstanding
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner),
+                    |outstanding| *outstanding > 0,
+                )
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+    }
+}
+
+impl<'a, T: ?Sized> Deref for FenceAnchor<'a, T> {
+    type Target = RwAnchor<'a, T>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.anchor
+    }
+}
+
+impl<'a, T: ?Sized> Drop for FenceAnchor<'a, T> {
+    fn drop(&mut self) {
+        self.wait_fences();
+    }
+}
+
+impl<'a, T: ?Sized> Debug for FenceAnchor<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FenceAnchor")
+            .field("outstanding_fences", &self.outstanding_fences())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keeps a [`FenceAnchor`] from being dropped until it's signalled.
+/// Dropping it signals it too, including during a panic.
+#[must_use]
+pub struct Fence(Arc<Fences>);
+
+impl Fence {
+    /// Signals the fence, so that the anchor doesn't wait for it anymore.
+    #[inline]
+    pub fn signal(self) {
+        drop(self);
+    }
+
+    /// Converts the fence into an opaque pointer, e.g. to pass it through the user data of a C callback.
+    /// Only [`from_raw`](#method.from_raw) can signal it again, so not calling it makes dropping the anchor deadlock.
+    ///
+    /// Only available with the `ffi` feature.
+    #[cfg(feature = "ffi")]
+    #[must_use]
+    pub fn into_raw(self) -> *mut c_void {
+        Box::into_raw(Box::new(self)).cast()
+    }
+
+    /// Reassembles a fence converted by [`into_raw`](#method.into_raw).
+    ///
+    /// Only available with the `ffi` feature.
+    ///
+    /// # Safety
+    ///
+    /// `fence` must have been returned by `into_raw`, and this must be called at most once for it.
+    #[cfg(feature = "ffi")]
+    pub unsafe fn from_raw(fence: *mut c_void) -> Self {
+        *Box::from_raw(fence.cast::<Self>())
+    }
+}
+
+impl Drop for Fence {
+    fn drop(&mut self) {
+        let mut outstanding = self
+            .0
+            .outstanding
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        *outstanding -= 1;
+        if *outstanding == 0 {
+            drop(outstanding);
+            self.0.signalled.notify_all();
+        }
+    }
+}
+
+impl Debug for Fence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fence").finish_non_exhaustive()
+    }
+}
+
+/// A portal to the buffer of a [`FenceAnchor`] together with a [`Fence`],
+/// which is signalled after the portal has been dropped.
+#[must_use]
+pub struct Fenced<T: ?Sized> {
+    /// Dropped before `fence`.
+    portal: RwPortal<T>,
+
+    /// Keeps the anchor from being dropped.
+    fence: Fence,
+}
+
+impl<T: ?Sized> Fenced<T> {
+    /// Returns the portal to the buffer.
+    #[inline]
+    pub const fn portal(&self) -> &RwPortal<T> {
+        &self.portal
+    }
+
+    /// Drops the portal, then signals the fence.
+    #[inline]
+    pub fn signal(self) {
+        drop(self);
+    }
+
+    /// Splits this into the portal and the fence, e.g. to signal the fence only after hardware that was given the portal's target is done with it.
+    #[inline]
+    pub fn into_parts(self) -> (RwPortal<T>, Fence) {
+        (self.portal, self.fence)
+    }
+
+    /// Converts this into an opaque pointer, e.g. to pass it through the user data of a C callback.
+    /// Only [`from_raw`](#method.from_raw) can signal the fence again, so not calling it makes dropping the anchor deadlock.
+    ///
+    /// Only available with the `ffi` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{FenceAnchor, Fenced},
+    ///     std::{ffi::c_void, thread},
+    /// };
+    ///
+    /// // Stands in for a C function that reads back a GPU buffer and calls back from a driver thread.
+    /// fn read_back(callback: extern "C" fn(*mut c_void), user_data: *mut c_void) {
+    ///     let user_data = user_data as usize;
+    ///     thread::spawn(move || callback(user_data as *mut c_void));
+    /// }
+    ///
+    /// extern "C" fn on_read_back(user_data: *mut c_void) {
+    ///     let fenced = unsafe { Fenced::<[u8]>::from_raw(user_data) };
+    ///     fenced.portal().write().copy_from_slice(b"pixels");
+    /// }
+    ///
+    /// let mut buffer = [0; 6];
+    /// let anchor = FenceAnchor::new(&mut buffer[..]);
+    /// read_back(on_read_back, anchor.fenced().into_raw());
+    ///
+    /// drop(anchor); // Waits for the callback.
+    /// assert_eq!(&buffer, b"pixels");
+    /// ```
+    #[cfg(feature = "ffi")]
+    #[must_use]
+    pub fn into_raw(self) -> *mut c_void {
+        Box::into_raw(Box::new(self)).cast()
+    }
+
+    /// Reassembles a [`Fenced`] converted by [`into_raw`](#method.into_raw).
+    ///
+    /// Only available with the `ffi` feature.
+    ///
+    /// # Safety
+    ///
+    /// `fenced` must have been returned by `into_raw` on an instance of the same type, and this must be called at most once for it.
+    #[cfg(feature = "ffi")]
+    pub unsafe fn from_raw(fenced: *mut c_void) -> Self {
+        *Box::from_raw(fenced.cast::<Self>())
+    }
+}
+
+impl<T: ?Sized> Debug for Fenced<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fenced").finish_non_exhaustive()
+    }
+}
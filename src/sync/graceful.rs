@@ -0,0 +1,159 @@
+//! Dropping threadsafe anchors only once their portals are gone, instead of panicking while they're in use.
+
+use {
+    super::{Anchor, CloseReport, RwAnchor, WAnchor},
+    std::time::Duration,
+};
+
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Creates a new `Anchor` instance, capturing `reference`, that waits for its remaining (strong) portals when dropped
+    /// instead of panicking, like anchors of an [`AnchorGroup`](`super::AnchorGroup`) with [`ViolationPolicy::Block`](`super::ViolationPolicy::Block`).
+    ///
+    /// Waiting returns early at the deadline of a [`ShutdownToken`](`super::ShutdownToken`) the anchor is bound to, if any,
+    /// after which any remaining portals cause a panic as usual.
+    /// Dropping the anchor on a thread that holds one of its portals deadlocks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::Anchor,
+    ///     std::{thread, time::Duration},
+    /// };
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new_blocking(&x);
+    /// let portal = anchor.portal();
+    /// thread::spawn(move || {
+    ///     thread::sleep(Duration::from_millis(100));
+    ///     println!("{}", *portal);
+    /// });
+    ///
+    /// drop(anchor); // Waits for the portal instead of panicking.
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_blocking(reference: &'a T) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.block_on_drop();
+        anchor
+    }
+
+    /// Waits for the remaining (strong) portals like [`wait_idle`](#method.wait_idle), then drops this anchor.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping the anchor, iff portals still exist after the shutdown deadline or were upgraded meanwhile,
+    /// or if [dependent](#method.depends_on) anchors still exist.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn drop_blocking(self) {
+        self.wait_idle();
+        drop(self);
+    }
+
+    /// Waits up to `timeout` for the remaining (strong) portals, then [closes](#method.close) this anchor.
+    ///
+    /// # Errors
+    ///
+    /// Like [`close`](#method.close), iff portals or dependent anchors still exist, with a report that hands the anchor back.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::Anchor, std::time::Duration};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let portal = anchor.portal();
+    ///
+    /// let report = anchor.try_drop(Duration::from_millis(10)).unwrap_err();
+    /// assert_eq!(report.strong_portals(), 1);
+    ///
+    /// drop(portal);
+    /// report.into_anchor().try_drop(Duration::from_millis(10)).unwrap();
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_drop(self, timeout: Duration) -> Result<(), CloseReport<Self>> {
+        self.reference.wait_idle_within(timeout);
+        self.close()
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Creates a new `RwAnchor` instance, capturing `reference`, that waits for its remaining (strong) portals when dropped
+    /// instead of panicking, like anchors of an [`AnchorGroup`](`super::AnchorGroup`) with [`ViolationPolicy::Block`](`super::ViolationPolicy::Block`).
+    /// Poison still causes a panic.
+    ///
+    /// Waiting returns early at the deadline of a [`ShutdownToken`](`super::ShutdownToken`) the anchor is bound to, if any,
+    /// after which any remaining portals cause a panic as usual.
+    /// Dropping the anchor on a thread that holds one of its portals deadlocks.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_blocking(reference: &'a mut T) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.block_on_drop();
+        anchor
+    }
+
+    /// Waits for the remaining (strong) portals like [`wait_idle`](#method.wait_idle), then drops this anchor.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping the anchor, iff portals still exist after the shutdown deadline or were upgraded meanwhile,
+    /// if [dependent](#method.depends_on) anchors still exist, or if the anchor is poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn drop_blocking(self) {
+        self.wait_idle();
+        drop(self);
+    }
+
+    /// Waits up to `timeout` for the remaining (strong) portals, then [closes](#method.close) this anchor.
+    ///
+    /// # Errors
+    ///
+    /// Like [`close`](#method.close), iff portals or dependent anchors still exist or the anchor is poisoned,
+    /// with a report that hands the anchor back.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_drop(self, timeout: Duration) -> Result<(), CloseReport<Self>> {
+        self.reference.wait_idle_within(timeout);
+        self.close()
+    }
+}
+
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Creates a new `WAnchor` instance, capturing `reference`, that waits for its remaining (strong) portals when dropped
+    /// instead of panicking, like anchors of an [`AnchorGroup`](`super::AnchorGroup`) with [`ViolationPolicy::Block`](`super::ViolationPolicy::Block`).
+    /// Poison still causes a panic.
+    ///
+    /// Waiting returns early at the deadline of a [`ShutdownToken`](`super::ShutdownToken`) the anchor is bound to, if any,
+    /// after which any remaining portals cause a panic as usual.
+    /// Dropping the anchor on a thread that holds one of its portals deadlocks.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_blocking(reference: &'a mut T) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.idle.block_on_drop();
+        anchor
+    }
+
+    /// Waits for the remaining (strong) portals like [`wait_idle`](#method.wait_idle), then drops this anchor.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping the anchor, iff portals still exist after the shutdown deadline or were upgraded meanwhile,
+    /// if [dependent](#method.depends_on) anchors still exist, or if the anchor is poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn drop_blocking(self) {
+        self.wait_idle();
+        drop(self);
+    }
+
+    /// Waits up to `timeout` for the remaining (strong) portals, then [closes](#method.close) this anchor.
+    ///
+    /// # Errors
+    ///
+    /// Like [`close`](#method.close), iff portals or dependent anchors still exist or the anchor is poisoned,
+    /// with a report that hands the anchor back.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_drop(self, timeout: Duration) -> Result<(), CloseReport<Self>> {
+        self.reference.wait_idle_within(timeout);
+        self.close()
+    }
+}
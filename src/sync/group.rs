@@ -0,0 +1,314 @@
+//! Anchors that share a label, a violation policy and sealing.
+
+#[cfg(feature = "stats")]
+use super::AnchorStats;
+use {
+    super::{
+        Anchor, Mutex, MutexGuard, RwAnchor, RwShared, SSNonNull, Shared, ShutdownToken, WAnchor,
+        WShared,
+    },
+    std::{
+        fmt::{self, Debug, Formatter},
+        sync::{Arc, PoisonError, Weak},
+    },
+    wyz::pipe::*,
+};
+
+/// What an anchor created through an [`AnchorGroup`] does if it's dropped while portals to it still exist,
+/// or while it's poisoned.  
+/// Anchors created through `new_blocking` outside of a group follow [`Block`](`ViolationPolicy::Block`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ViolationPolicy {
+    /// Panic, like anchors that don't belong to a group.
+    #[default]
+    Panic,
+
+    /// Wait for the remaining (strong) portals to be dropped first, like `wait_idle`.
+    /// This returns early at the group's shutdown deadline, if any, after which any remaining portals cause a panic.
+    /// Poison still causes a panic.
+    ///
+    /// Dropping the anchor on a thread that holds one of its portals deadlocks.
+    Block,
+
+    /// Print the panic message to standard error and abort the process.
+    Abort,
+}
+
+/// An anchor's shared state, with its target type erased.
+trait Member {
+    /// Returns `true` iff the anchor hasn't been dropped yet.
+    fn is_anchored(&self) -> bool;
+
+    /// Blocks until no strong portals exist, or the shutdown deadline passes.
+    fn wait_idle(&self);
+
+    /// Takes a snapshot of the anchor's statistics.
+    #[cfg(feature = "stats")]
+    fn stats(self: Arc<Self>) -> AnchorStats;
+}
+
+impl<T: ?Sized> Member for Shared<SSNonNull<T>> {
+    fn is_anchored(&self) -> bool {
+        Self::is_anchored(self)
+    }
+
+    fn wait_idle(&self) {
+        Self::wait_idle(self);
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(self: Arc<Self>) -> AnchorStats {
+        Self::stats(&self, false)
+    }
+}
+
+impl<T: ?Sized> Member for RwShared<T> {
+    fn is_anchored(&self) -> bool {
+        Self::is_anchored(self)
+    }
+
+    fn wait_idle(&self) {
+        Self::wait_idle(self);
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(self: Arc<Self>) -> AnchorStats {
+        Self::stats(&self, self.target.is_poisoned())
+    }
+}
+
+impl<T: ?Sized> Member for WShared<T> {
+    fn is_anchored(&self) -> bool {
+        Self::is_anchored(self)
+    }
+
+    fn wait_idle(&self) {
+        Self::wait_idle(self);
+    }
+
+    #[cfg(feature = "stats")]
+    fn stats(self: Arc<Self>) -> AnchorStats {
+        Self::stats(&self, self.target.is_poisoned())
+    }
+}
+
+/// The state behind an [`AnchorGroup`].
+struct Group {
+    /// Given to each anchor created through the group.
+    label: &'static str,
+
+    /// Followed by each anchor created through the group.
+    policy: ViolationPolicy,
+
+    /// Bound to each anchor created through the group.
+    token: ShutdownToken,
+
+    /// The anchors created through the group.
+    members: Mutex<Vec<Weak<dyn Member>>>,
+}
+
+unsafe impl Send for Group {
+    //SAFETY: Only the members' portal counts, locks and idle states are accessed, which are threadsafe regardless of the target.
+}
+unsafe impl Sync for Group {
+    //SAFETY: Only the members' portal counts, locks and idle states are accessed, which are threadsafe regardless of the target.
+}
+
+/// Creates threadsafe anchors that share a label and a [`ViolationPolicy`], and that can be sealed and waited for together.
+/// Clones refer to the same group.
+///
+/// Sealing works like signalling shutdown through a [`ShutdownToken`] bound to each member.
+/// Anchors keep their own portal counts, so this only refers to the ones created through it until they are dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{AnchorGroup, ViolationPolicy},
+///     std::{thread, time::Duration},
+/// };
+///
+/// let group = AnchorGroup::new("session", ViolationPolicy::Block);
+///
+/// let name = "Ferris".to_owned();
+/// let mut visits = 0;
+/// let name_anchor = group.anchor(&name);
+/// let visits_anchor = group.rw_anchor(&mut visits);
+/// assert_eq!(name_anchor.label(), Some("session"));
+///
+/// let (name_portal, visits_portal) = (name_anchor.portal(), visits_anchor.portal());
+/// let weak_portal = name_anchor.weak_portal();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(10));
+///     *visits_portal.write() += name_portal.len();
+/// });
+///
+/// group.seal();
+/// assert!(weak_portal.try_upgrade().is_none());
+///
+/// drop(visits_anchor); // Waits for the thread instead of panicking.
+/// group.wait_idle();
+/// drop(name_anchor);
+/// assert_eq!(visits, 6);
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct AnchorGroup(Arc<Group>);
+
+impl AnchorGroup {
+    /// Creates a group whose anchors are labelled `label` and follow `policy`.
+    pub fn new(label: &'static str, policy: ViolationPolicy) -> Self {
+        Group {
+            label,
+            policy,
+            token: ShutdownToken::new(),
+            members: Mutex::new(Vec::new()),
+        }
+        .pipe(Arc::new)
+        .pipe(Self)
+    }
+
+    /// Returns the label of this group's anchors.
+    #[must_use]
+    pub fn label(&self) -> &'static str {
+        self.0.label
+    }
+
+    /// Returns the policy this group's anchors follow.
+    #[must_use]
+    pub fn policy(&self) -> ViolationPolicy {
+        self.0.policy
+    }
+
+    /// Returns the token this group's anchors are bound to, e.g. to register shutdown callbacks or to bind further anchors.
+    #[must_use]
+    pub fn shutdown_token(&self) -> &ShutdownToken {
+        &self.0.token
+    }
+
+    /// Creates a new `Anchor` in this group, capturing `reference`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn anchor<'a, T: ?Sized + 'static>(&self, reference: &'a T) -> Anchor<'a, T> {
+        let anchor = Anchor::named(self.0.label, reference);
+        self.join(&anchor.reference);
+        anchor
+    }
+
+    /// Creates a new `RwAnchor` in this group, capturing `reference`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn rw_anchor<'a, T: ?Sized + 'static>(&self, reference: &'a mut T) -> RwAnchor<'a, T> {
+        let anchor = RwAnchor::named(self.0.label, reference);
+        self.join(&anchor.reference);
+        anchor
+    }
+
+    /// Creates a new `WAnchor` in this group, capturing `reference`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn w_anchor<'a, T: ?Sized + 'static>(&self, reference: &'a mut T) -> WAnchor<'a, T> {
+        let anchor = WAnchor::named(self.0.label, reference);
+        self.join(&anchor.reference);
+        anchor
+    }
+
+    /// Seals all of this group's anchors, including ones created later, so that their weak portals can't be upgraded anymore.
+    /// Calling this again has no effect.
+    pub fn seal(&self) {
+        self.0.token.shutdown();
+    }
+
+    /// Returns `true` iff this group has been sealed.
+    #[must_use]
+    pub fn is_sealed(&self) -> bool {
+        self.0.token.is_shutdown()
+    }
+
+    /// Blocks until each of this group's anchors has no strong portals, in turn.
+    /// Portals created meanwhile to anchors that were waited for already aren't waited for.
+    /// If the group has been sealed with a deadline through its [`shutdown_token`](#method.shutdown_token), this returns at that deadline.
+    ///
+    /// Waiting on a thread that holds a portal of the group deadlocks.
+    pub fn wait_idle(&self) {
+        for member in self.members() {
+            member.wait_idle();
+        }
+    }
+
+    /// Returns the number of this group's anchors that haven't been dropped yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.members().len()
+    }
+
+    /// Returns `true` iff all of this group's anchors have been dropped.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sums the statistics of this group's anchors, e.g. for a dashboard.
+    /// `poisoned` is `true` iff any of them is poisoned.
+    ///
+    /// Only available with the `stats` feature.
+    #[cfg(feature = "stats")]
+    #[must_use]
+    pub fn stats(&self) -> AnchorStats {
+        self.members().into_iter().map(Member::stats).fold(
+            AnchorStats {
+                strong: 0,
+                weak: 0,
+                reads_acquired: 0,
+                writes_acquired: 0,
+                contended_acquires: 0,
+                poisoned: false,
+            },
+            |total, stats| AnchorStats {
+                strong: total.strong + stats.strong,
+                weak: total.weak + stats.weak,
+                reads_acquired: total.reads_acquired + stats.reads_acquired,
+                writes_acquired: total.writes_acquired + stats.writes_acquired,
+                contended_acquires: total.contended_acquires + stats.contended_acquires,
+                poisoned: total.poisoned || stats.poisoned,
+            },
+        )
+    }
+
+    /// Makes the anchor with `shared` state follow this group.
+    fn join<L: 'static, A: 'static>(&self, shared: &Arc<Shared<L, A>>)
+    where
+        Shared<L, A>: Member,
+    {
+        shared.idle.join_group(self.0.policy);
+        self.0.token.bind(&shared.idle);
+        let mut members = self.lock();
+        members.retain(|member| member.strong_count() > 0);
+        let member = Arc::downgrade(shared);
+        members.push(member);
+    }
+
+    /// Returns the anchors that haven't been dropped yet, without keeping the members locked.
+    fn members(&self) -> Vec<Arc<dyn Member>> {
+        self.lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|member| member.is_anchored())
+            .collect()
+    }
+
+    /// Locks the members, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, Vec<Weak<dyn Member>>> {
+        self.0
+            .members
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Debug for AnchorGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnchorGroup")
+            .field("label", &self.0.label)
+            .field("policy", &self.0.policy)
+            .field("sealed", &self.is_sealed())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,139 @@
+//! Handing exclusive guards to scoped threads without releasing them in between.
+
+use {
+    super::{PortalMutexGuard, PortalWriteGuard, SSNonNull},
+    std::{
+        fmt::{self, Debug, Formatter},
+        ops::DerefMut,
+        panic,
+        ptr::NonNull,
+        thread::{self, Scope, ScopedJoinHandle},
+    },
+};
+
+/// Implemented by exclusive guards, so that a closure on another thread can continue working under them.
+///
+/// Guards can't be sent to other threads, since some platforms require locks to be released on the thread that acquired them.
+/// Handing one off instead keeps it on the current thread while a scoped thread accesses its target,
+/// so that a multi-step protocol stays atomic across the thread boundary without a gap in which other threads could lock the target.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{HandOff, WAnchor},
+///     std::thread,
+/// };
+///
+/// let mut ledger = vec![];
+/// let anchor = WAnchor::new(&mut ledger);
+/// let portal = anchor.portal();
+///
+/// thread::scope(|scope| {
+///     let mut guard = portal.lock();
+///     guard.push("prepared");
+///     let handed_off = guard.hand_off(scope, |ledger| {
+///         ledger.push("committed");
+///         ledger.len()
+///     });
+///     let (guard, len) = handed_off.join();
+///     assert_eq!(len, 2);
+///     assert_eq!(*guard, ["prepared", "committed"]);
+/// });
+/// # drop(portal);
+/// ```
+pub trait HandOff: DerefMut + Sized {
+    /// Runs `f` on a thread spawned in `scope`, with exclusive access to this guard's target,
+    /// and keeps the guard held on the current thread until `f` has returned and the [`HandedOff`] is joined or dropped.
+    ///
+    /// If `f` panics, joining (or dropping) the `HandedOff` resumes the panic while releasing the guard, which poisons the anchor unless the guard was defused.
+    #[must_use = "Dropping the `HandedOff` immediately blocks until `f` has returned"]
+    fn hand_off<'scope, 'env, R: Send + 'scope>(
+        mut self,
+        scope: &'scope Scope<'scope, 'env>,
+        f: impl FnOnce(&mut Self::Target) -> R + Send + 'scope,
+    ) -> HandedOff<'scope, Self, R>
+    where
+        Self::Target: Send + 'scope,
+    {
+        let target = SSNonNull::new(NonNull::from(&mut *self));
+        let handle = scope.spawn(move || {
+            let mut target = target;
+            f(unsafe {
+                //SAFETY: The guard stays held and unused until this thread has been joined, or forever if the `HandedOff` is leaked.
+                target.as_mut()
+            })
+        });
+        HandedOff {
+            handle: Some(handle),
+            guard: Some(self),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> HandOff for PortalMutexGuard<'a, T> {}
+impl<'a, T: ?Sized> HandOff for PortalWriteGuard<'a, T> {}
+
+/// A guard handed off to a scoped thread through [`HandOff::hand_off`], which gives it back once joined.
+///
+/// Dropping this joins the thread, and then releases the guard.
+#[must_use = "Dropping this blocks until the thread has finished"]
+pub struct HandedOff<'scope, G, R> {
+    /// Taken when joining.
+    handle: Option<ScopedJoinHandle<'scope, R>>,
+
+    /// Not accessed before `handle` is joined, and dropped only after that.
+    guard: Option<G>,
+}
+
+impl<'scope, G, R> HandedOff<'scope, G, R> {
+    /// Waits for the thread to finish, then returns the guard and the closure's result.
+    ///
+    /// # Panics
+    ///
+    /// Iff the closure panicked, with its payload, after releasing the guard while unwinding.
+    pub fn join(mut self) -> (G, R) {
+        let result = self.wait();
+        let guard = self
+            .guard
+            .take()
+            .unwrap_or_else(|| unreachable!("Only taken here"));
+        match result {
+            Some(Ok(result)) => (guard, result),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => unreachable!("Only joined here or on drop"),
+        }
+    }
+
+    /// Returns `true` iff the closure has returned or panicked, so that joining won't block.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.handle
+            .as_ref()
+            .map_or(true, ScopedJoinHandle::is_finished)
+    }
+
+    /// Joins the thread, unless that happened already.
+    fn wait(&mut self) -> Option<thread::Result<R>> {
+        self.handle.take().map(ScopedJoinHandle::join)
+    }
+}
+
+impl<'scope, G, R> Drop for HandedOff<'scope, G, R> {
+    /// Joins the thread, then resumes its panic (if any, and unless already unwinding) while the guard is released.
+    fn drop(&mut self) {
+        if let Some(Err(payload)) = self.wait() {
+            if !thread::panicking() {
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+}
+
+impl<'scope, G, R> Debug for HandedOff<'scope, G, R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HandedOff")
+            .field("finished", &self.is_finished())
+            .finish_non_exhaustive()
+    }
+}
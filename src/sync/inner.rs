@@ -0,0 +1,69 @@
+//! Access to the contents of anchored locks in one step.
+
+use {
+    super::Portal,
+    crate::poison::AccessError,
+    // Not swapped for Loom's, since these are the types of user-provided targets.
+    std::sync::{Mutex, MutexGuard, TryLockError},
+};
+
+impl<U: ?Sized> Portal<Mutex<U>> {
+    /// Returns the anchored mutex, or [`AccessError::Dropped`] instead of panicking if its anchor is gone.
+    fn inner(portal: &Self) -> Result<&Mutex<U>, AccessError> {
+        if portal.0.is_anchored() {
+            Ok(portal)
+        } else {
+            Err(AccessError::Dropped)
+        }
+    }
+
+    /// Locks the anchored mutex, blocking the current thread until that's possible.
+    ///
+    /// This is an associated function because `Portal` dereferences to its target.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] if the anchor has been dropped,  
+    /// [`AccessError::Poisoned`] if the mutex is poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{Anchor, Portal},
+    ///     std::{sync::Mutex, thread},
+    /// };
+    ///
+    /// let counter = Mutex::new(0);
+    /// let anchor = Anchor::new(&counter);
+    /// let portal = anchor.portal();
+    ///
+    /// thread::spawn(move || *Portal::lock_inner(&portal).unwrap() += 1)
+    ///     .join()
+    ///     .unwrap();
+    ///
+    /// drop(anchor);
+    /// assert_eq!(*counter.lock().unwrap(), 1);
+    /// ```
+    pub fn lock_inner(portal: &Self) -> Result<MutexGuard<'_, U>, AccessError> {
+        Self::inner(portal)?
+            .lock()
+            .map_err(|_| AccessError::Poisoned)
+    }
+
+    /// Locks the anchored mutex iff that's possible without blocking.
+    ///
+    /// # Errors
+    ///
+    /// [`AccessError::Dropped`] if the anchor has been dropped,  
+    /// [`AccessError::Poisoned`] if the mutex is poisoned,  
+    /// [`AccessError::WouldBlock`] if it's locked.
+    pub fn try_lock_inner(portal: &Self) -> Result<MutexGuard<'_, U>, AccessError> {
+        Self::inner(portal)?
+            .try_lock()
+            .map_err(|error| match error {
+                TryLockError::Poisoned(_) => AccessError::Poisoned,
+                TryLockError::WouldBlock => AccessError::WouldBlock,
+            })
+    }
+}
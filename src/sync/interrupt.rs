@@ -0,0 +1,151 @@
+//! Blocking acquisition that gives up on request or on shutdown.
+
+use {
+    super::{shutdown::Idle, AnchorField, Defuse, HeldFor, RwPortal, ToArc, WPortal},
+    crate::{poison::Cancelled, watchdog::check_blocking},
+    std::{
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+        time::Duration,
+    },
+};
+
+/// The first pause between attempts to acquire a contended lock.
+const MIN_BACKOFF: Duration = Duration::from_micros(50);
+
+/// The longest pause between attempts, which bounds how long cancellation can take to be noticed.
+const MAX_BACKOFF: Duration = Duration::from_millis(10);
+
+/// Paces the attempts of an interruptible acquisition.
+/// The standard locks can't be woken from outside, so these poll instead.
+struct Backoff(Duration);
+
+impl Backoff {
+    /// Starts with the shortest pause.
+    const fn new() -> Self {
+        Self(MIN_BACKOFF)
+    }
+
+    /// Fails iff `stop` is set or the anchor is sealed.
+    fn check(idle: &Idle, stop: &AtomicBool) -> Result<(), Cancelled> {
+        if stop.load(Ordering::Acquire) || idle.is_sealed() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sleeps, then doubles the next pause up to `MAX_BACKOFF`.
+    fn wait(&mut self) {
+        thread::sleep(self.0);
+        self.0 = (self.0 * 2).min(MAX_BACKOFF);
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Acquires a read guard like [`read`](#method.read), but gives up once `stop` is set or the anchor is sealed,
+    /// e.g. through a [`ShutdownToken`](`super::ShutdownToken`) it's bound to.
+    /// Cancellation is noticed within a few milliseconds while the target is locked for writing.
+    ///
+    /// # Errors
+    ///
+    /// Iff cancelled before the guard could be acquired, including if `stop` was set already.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{poison::Cancelled, sync::RwAnchor},
+    ///     std::{sync::atomic::{AtomicBool, Ordering}, thread},
+    /// };
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// let stop = AtomicBool::new(false);
+    ///
+    /// let guard = portal.write();
+    /// thread::scope(|scope| {
+    ///     let reader = scope.spawn(|| portal.read_interruptible(&stop).map(|x| x.len()));
+    ///     stop.store(true, Ordering::Release);
+    ///     assert_eq!(reader.join().unwrap(), Err(Cancelled));
+    /// });
+    /// drop(guard);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_interruptible<'a>(
+        &'a self,
+        stop: &AtomicBool,
+    ) -> Result<impl AsRef<T> + HeldFor + ToArc<T> + 'a, Cancelled> {
+        check_blocking("sync::RwPortal::read_interruptible", &*self.0, false);
+        let mut backoff = Backoff::new();
+        loop {
+            Backoff::check(&self.0.idle, stop)?;
+            if let Some(guard) = self.try_read() {
+                return Ok(guard);
+            }
+            backoff.wait();
+        }
+    }
+
+    /// Acquires a write guard like [`write`](#method.write), but gives up once `stop` is set or the anchor is sealed.
+    /// See [`read_interruptible`](#method.read_interruptible).
+    ///
+    /// # Errors
+    ///
+    /// Iff cancelled before the guard could be acquired, including if `stop` was set already.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write_interruptible<'a>(
+        &'a self,
+        stop: &AtomicBool,
+    ) -> Result<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a, Cancelled>
+    {
+        check_blocking("sync::RwPortal::write_interruptible", &*self.0, true);
+        let mut backoff = Backoff::new();
+        loop {
+            Backoff::check(&self.0.idle, stop)?;
+            if let Some(guard) = self.try_write() {
+                return Ok(guard);
+            }
+            backoff.wait();
+        }
+    }
+}
+
+impl<T: ?Sized> WPortal<T> {
+    /// Acquires a guard like [`lock`](#method.lock), but gives up once `stop` is set or the anchor is sealed,
+    /// e.g. through a [`ShutdownToken`](`super::ShutdownToken`) it's bound to.
+    /// Cancellation is noticed within a few milliseconds while the target is locked.
+    ///
+    /// # Errors
+    ///
+    /// Iff cancelled before the guard could be acquired, including if `stop` was set already.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn lock_interruptible<'a>(
+        &'a self,
+        stop: &AtomicBool,
+    ) -> Result<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a, Cancelled>
+    {
+        check_blocking("sync::WPortal::lock_interruptible", &*self.0, true);
+        let mut backoff = Backoff::new();
+        loop {
+            Backoff::check(&self.0.idle, stop)?;
+            if let Some(guard) = self.try_lock() {
+                return Ok(guard);
+            }
+            backoff.wait();
+        }
+    }
+}
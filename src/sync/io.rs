@@ -0,0 +1,166 @@
+//! Zero-copy I/O views of anchored byte buffers.
+
+use {
+    super::Portal,
+    std::{io::IoSlice, num::NonZeroUsize},
+};
+
+#[cfg(feature = "buf")]
+use {bytes::Buf, wyz::pipe::*};
+
+impl Portal<[u8]> {
+    /// Borrows the anchored bytes as [`IoSlice`], e.g. for a vectored write on another thread than the anchor's.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::Anchor,
+    ///     std::{io::Write, thread},
+    /// };
+    ///
+    /// let header = *b"Scoped ";
+    /// let body = *b"buffers";
+    /// let (header_anchor, body_anchor) = (Anchor::new_slice(&header), Anchor::new_slice(&body));
+    /// let (header_portal, body_portal) = (header_anchor.portal(), body_anchor.portal());
+    ///
+    /// let written = thread::spawn(move || {
+    ///     let mut sink = Vec::new();
+    ///     sink.write_vectored(&[header_portal.as_io_slice(), body_portal.as_io_slice()])
+    ///         .unwrap();
+    ///     sink
+    /// })
+    /// .join()
+    /// .unwrap();
+    /// assert_eq!(written, b"Scoped buffers");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn as_io_slice(&self) -> IoSlice<'_> {
+        IoSlice::new(self)
+    }
+
+    /// Splits the anchored bytes into [`IoSlice`]s of at most `max_len` bytes each,
+    /// e.g. to stay within a platform's limit on the size of a single vectored write buffer.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::Anchor, std::num::NonZeroUsize};
+    ///
+    /// let buffer = [0_u8; 10];
+    /// let anchor = Anchor::new_slice(&buffer);
+    /// let portal = anchor.portal();
+    ///
+    /// let lengths: Vec<_> = portal
+    ///     .io_slices(NonZeroUsize::new(4).unwrap())
+    ///     .map(|slice| slice.len())
+    ///     .collect();
+    /// assert_eq!(lengths, [4, 4, 2]);
+    /// # drop(portal);
+    /// ```
+    #[inline]
+    pub fn io_slices(&self, max_len: NonZeroUsize) -> impl Iterator<Item = IoSlice<'_>> {
+        self.chunks(max_len.get()).map(IoSlice::new)
+    }
+}
+
+/// Reads the anchored bytes of a [`Portal<[u8]>`](`Portal`) as [`bytes::Buf`](https://docs.rs/bytes/1/bytes/trait.Buf.html),
+/// so that they can be consumed by I/O code on another thread without copying them.
+/// Only available with the `buf` feature.
+///
+/// # Panics
+///
+/// The `Buf` methods panic iff the anchor has been dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     bytes::Buf,
+///     ref_portals::sync::{Anchor, BufPortal},
+///     std::thread,
+/// };
+///
+/// let buffer = *b"Scoped";
+/// let anchor = Anchor::new_slice(&buffer);
+/// let mut buf = BufPortal::new(anchor.portal());
+///
+/// thread::spawn(move || {
+///     assert_eq!(buf.get_u8(), b'S');
+///     assert_eq!(buf.chunk(), b"coped");
+///     buf.advance(5);
+///     assert!(!buf.has_remaining());
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+#[cfg(feature = "buf")]
+#[derive(Debug)]
+#[must_use]
+pub struct BufPortal {
+    /// To the buffer.
+    portal: Portal<[u8]>,
+
+    /// The number of bytes consumed so far.
+    position: usize,
+}
+
+#[cfg(feature = "buf")]
+impl BufPortal {
+    /// Wraps `portal`, starting at its first byte.
+    #[inline]
+    pub const fn new(portal: Portal<[u8]>) -> Self {
+        Self {
+            portal,
+            position: 0,
+        }
+    }
+
+    /// Returns the wrapped portal.
+    #[inline]
+    pub fn into_inner(self) -> Portal<[u8]> {
+        self.portal
+    }
+}
+
+#[cfg(feature = "buf")]
+impl From<Portal<[u8]>> for BufPortal {
+    #[inline]
+    fn from(portal: Portal<[u8]>) -> Self {
+        portal.pipe(Self::new)
+    }
+}
+
+#[cfg(feature = "buf")]
+impl Buf for BufPortal {
+    #[inline]
+    fn remaining(&self) -> usize {
+        self.portal.len() - self.position
+    }
+
+    #[inline]
+    fn chunk(&self) -> &[u8] {
+        &self.portal[self.position..]
+    }
+
+    /// # Panics
+    ///
+    /// Iff `cnt` is greater than [`remaining()`](#method.remaining).
+    #[inline]
+    fn advance(&mut self, cnt: usize) {
+        assert!(
+            cnt <= self.remaining(),
+            "Advanced past the end of the buffer"
+        );
+        self.position += cnt;
+    }
+}
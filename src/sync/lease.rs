@@ -0,0 +1,225 @@
+//! Time-limited access to an immutable anchor's target, which the anchor can revoke.
+
+use {
+    super::{Anchor, Mutex, RwLock, RwLockReadGuard},
+    crate::{poison::LeaseError, Peek},
+    std::{
+        fmt::{self, Debug, Formatter},
+        mem,
+        ops::Deref,
+        ptr::NonNull,
+        sync::{Arc, PoisonError, Weak},
+        time::{Duration, Instant},
+    },
+};
+
+/// Whether a lease has been revoked.
+/// Read-locked while the lease is used, so that revoking it waits for that.
+#[derive(Debug)]
+struct Revocation(RwLock<bool>);
+
+/// The leases handed out by an [`Anchor`], which are revoked at the latest when it's dropped.
+#[derive(Debug)]
+pub struct Leases(Mutex<Vec<Weak<Revocation>>>);
+
+impl Leases {
+    /// Creates an instance without leases.
+    #[allow(clippy::missing_const_for_fn)] // Not with loom.
+    pub(super) fn new() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+
+    /// Revokes all leases, waiting for each to be released by whoever is using it.
+    pub(super) fn revoke_all(&self) {
+        let leases = mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner));
+        for revocation in leases.iter().filter_map(Weak::upgrade) {
+            *revocation.0.write().unwrap_or_else(PoisonError::into_inner) = true;
+        }
+    }
+}
+
+/// Revocable, time-limited access to a threadsafe immutable anchor's target, as created by [`Anchor::lease`].
+///
+/// Unlike a [`Portal`](`super::Portal`), a lease doesn't count as (strong) portal.
+/// Instead, the anchor revokes it when dropped, or earlier through [`Anchor::revoke_leases`],
+/// so the lease's holder can't delay the anchor's teardown beyond releasing its current guard.
+#[must_use]
+pub struct Lease<T: ?Sized> {
+    /// Only dereferenced while `revocation` is read-locked and not set.
+    target: NonNull<T>,
+
+    /// Shared with the clones of this lease.
+    revocation: Arc<Revocation>,
+
+    /// `None` if the duration was too long to represent.
+    expires: Option<Instant>,
+}
+
+unsafe impl<T: ?Sized + Sync> Send for Lease<T> {
+    //SAFETY: Only gives out shared references to the target.
+}
+unsafe impl<T: ?Sized + Sync> Sync for Lease<T> {
+    //SAFETY: Only gives out shared references to the target.
+}
+
+/// A read guard of a [`Lease`].
+struct LeaseGuard<'a, T: ?Sized> {
+    /// Valid while `_revocation` is held.
+    target: &'a T,
+
+    /// Makes revoking the lease wait for this guard.
+    _revocation: RwLockReadGuard<'a, bool>,
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Leases out access to the target for `duration`.
+    /// The lease and its clones can be revoked together with all other leases of this anchor through [`revoke_leases`](#method.revoke_leases),
+    /// and are revoked at the latest when this anchor is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{poison::LeaseError, sync::Anchor},
+    ///     std::{thread, time::Duration},
+    /// };
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    /// let lease = anchor.lease(Duration::from_secs(60));
+    ///
+    /// thread::spawn({
+    ///     let lease = lease.clone();
+    ///     move || assert_eq!(*lease.read().unwrap(), "Scoped")
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// anchor.revoke_leases();
+    /// assert_eq!(lease.read().err(), Some(LeaseError::Revoked));
+    ///
+    /// let lease = anchor.lease(Duration::from_secs(60));
+    /// drop(anchor); // Doesn't panic, since leases aren't portals.
+    /// assert_eq!(lease.read().err(), Some(LeaseError::Revoked));
+    /// ```
+    pub fn lease(&self, duration: Duration) -> Lease<T> {
+        let revocation = Arc::new(Revocation(RwLock::new(false)));
+        {
+            let mut leases = self.leases.0.lock().unwrap_or_else(PoisonError::into_inner);
+            leases.retain(|lease| lease.strong_count() > 0);
+            leases.push(Arc::downgrade(&revocation));
+        }
+        Lease {
+            target: *self.reference.target,
+            revocation,
+            expires: Instant::now().checked_add(duration),
+        }
+    }
+
+    /// Revokes all leases of this anchor.
+    /// Blocks until each lease's current guards (if any) have been released.
+    ///
+    /// Revoking a lease the current thread holds a guard of deadlocks.
+    pub fn revoke_leases(&self) {
+        self.leases.revoke_all();
+    }
+}
+
+impl<T: ?Sized> Lease<T> {
+    /// Acquires a read guard iff the lease hasn't been revoked and hasn't expired.
+    /// The guard stays valid after the lease expires, and revoking the lease waits for it to be released.
+    ///
+    /// # Errors
+    ///
+    /// Iff the lease has been revoked or has expired.
+    pub fn read<'a>(&'a self) -> Result<impl Deref<Target = T> + AsRef<T> + 'a, LeaseError> {
+        let revocation = self
+            .revocation
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        if *revocation {
+            return Err(LeaseError::Revoked);
+        }
+        if self.is_expired() {
+            return Err(LeaseError::Expired);
+        }
+        Ok(LeaseGuard {
+            target: unsafe {
+                //SAFETY: The anchor exists while the lease isn't revoked, and waits for this guard before revoking it.
+                self.target.as_ref()
+            },
+            _revocation: revocation,
+        })
+    }
+
+    /// Returns `true` iff the lease hasn't been revoked and hasn't expired.
+    /// This may change right after the call.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        !*self
+            .revocation
+            .0
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            && !self.is_expired()
+    }
+
+    /// Returns when the lease expires, or `None` if its duration was too long to represent.
+    #[must_use]
+    pub const fn expires_at(&self) -> Option<Instant> {
+        self.expires
+    }
+
+    /// Returns the time left until the lease expires, regardless of whether it has been revoked.
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        self.expires
+            .map(|expires| expires.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns `true` iff the lease's duration has passed.
+    fn is_expired(&self) -> bool {
+        self.expires
+            .is_some_and(|expires| Instant::now() >= expires)
+    }
+}
+
+impl<T: ?Sized> Clone for Lease<T> {
+    /// Creates another handle to the same lease, which is revoked and expires along with it.
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            revocation: Arc::clone(&self.revocation),
+            expires: self.expires,
+        }
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Lease<T> {
+    /// Shows the target, or `<expired>` if the lease has been revoked or has expired.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let guard = self.read();
+        let target = guard.as_deref().map_or(Peek::Expired, Peek::Value);
+        f.debug_struct("Lease")
+            .field("target", &target)
+            .field("expires_at", &self.expires)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T: ?Sized> Deref for LeaseGuard<'a, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        self.target
+    }
+}
+
+impl<'a, T: ?Sized> AsRef<T> for LeaseGuard<'a, T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self.target
+    }
+}
@@ -0,0 +1,154 @@
+//! Lending portals to jobs on existing thread pools.
+
+use {
+    super::{Anchor, Condvar, Mutex, Portal, RwAnchor, RwPortal, WAnchor, WPortal},
+    std::sync::{Arc, PoisonError},
+    wyz::pipe::*,
+};
+
+/// A job for a thread pool, as submitted by the `lend` methods of threadsafe anchors.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// Completion state shared between a [`Ticket`] and its job.
+#[derive(Debug)]
+struct Completion {
+    /// Whether the job is complete. Locked to wait on or notify `condvar`.
+    done: Mutex<bool>,
+
+    /// Notified once the job is complete.
+    condvar: Condvar,
+}
+
+/// Tracks a job submitted through one of the `lend` methods.
+///
+/// The job counts as complete once it returned, panicked or was dropped without running,
+/// at which point the portal it was lent has been dropped.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Ticket(Arc<Completion>);
+
+impl Ticket {
+    /// Returns `true` iff the job is complete.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        *self.0.done.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Blocks the current thread until the job is complete.
+    pub fn wait(&self) {
+        let mut done = self.0.done.lock().unwrap_or_else(PoisonError::into_inner);
+        while !*done {
+            done = self
+                .0
+                .condvar
+                .wait(done)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+/// Moved into a lent job. Drops the portal, then completes the ticket.
+struct Loan<P> {
+    /// Lent to the job.
+    portal: P,
+
+    /// Completed when this is dropped.
+    _completion: Complete,
+}
+
+/// Completes a ticket when dropped, even during a panic.
+struct Complete(Arc<Completion>);
+
+impl Drop for Complete {
+    fn drop(&mut self) {
+        *self.0.done.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        self.0.condvar.notify_all();
+    }
+}
+
+/// Wraps `job` so that it runs with `portal` and completes the returned ticket afterwards, then passes it to `submit`.
+fn lend<P: Send + 'static>(
+    portal: P,
+    submit: impl FnOnce(Job),
+    job: impl FnOnce(&P) + Send + 'static,
+) -> Ticket {
+    let completion = Arc::new(Completion {
+        done: Mutex::new(false),
+        condvar: Condvar::new(),
+    });
+    let loan = Loan {
+        portal,
+        _completion: completion.pipe_ref(Arc::clone).pipe(Complete),
+    };
+    submit(Box::new(move || {
+        let loan = loan;
+        job(&loan.portal);
+    }));
+    Ticket(completion)
+}
+
+impl<'a, T: ?Sized + 'static> Anchor<'a, T>
+where
+    Portal<T>: Send,
+{
+    /// Submits `job` through `submit` together with a new portal, e.g. to a thread pool.
+    /// The portal counts towards [`wait_idle`](#method.wait_idle) until the job is complete,
+    /// so dropping the anchor before that panics like with any other remaining portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::Anchor, std::thread};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    ///
+    /// // E.g. `|job| pool.execute(job)` for a `threadpool::ThreadPool`
+    /// // or `|job| pool.spawn(job)` for a `rayon::ThreadPool`.
+    /// let ticket = anchor.lend(
+    ///     |job| drop(thread::spawn(job)),
+    ///     |portal| assert_eq!(**portal, "Scoped"),
+    /// );
+    ///
+    /// ticket.wait();
+    /// assert!(ticket.is_complete());
+    /// drop(anchor); // Doesn't panic.
+    /// ```
+    pub fn lend(
+        &self,
+        submit: impl FnOnce(Job),
+        job: impl FnOnce(&Portal<T>) + Send + 'static,
+    ) -> Ticket {
+        lend(self.portal(), submit, job)
+    }
+}
+
+impl<'a, T: ?Sized + 'static> RwAnchor<'a, T>
+where
+    RwPortal<T>: Send,
+{
+    /// Submits `job` through `submit` together with a new portal, e.g. to a thread pool.
+    /// The portal counts towards [`wait_idle`](#method.wait_idle) until the job is complete.
+    pub fn lend(
+        &self,
+        submit: impl FnOnce(Job),
+        job: impl FnOnce(&RwPortal<T>) + Send + 'static,
+    ) -> Ticket {
+        lend(self.portal(), submit, job)
+    }
+}
+
+impl<'a, T: ?Sized + 'static> WAnchor<'a, T>
+where
+    WPortal<T>: Send,
+{
+    /// Submits `job` through `submit` together with a new portal, e.g. to a thread pool.
+    /// The portal counts towards [`wait_idle`](#method.wait_idle) until the job is complete.
+    pub fn lend(
+        &self,
+        submit: impl FnOnce(Job),
+        job: impl FnOnce(&WPortal<T>) + Send + 'static,
+    ) -> Ticket {
+        lend(self.portal(), submit, job)
+    }
+}
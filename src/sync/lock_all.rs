@@ -0,0 +1,339 @@
+//! Acquiring guards across several portals, either in a globally consistent order or all or nothing without blocking.
+
+use {
+    super::{RwPortal, ToArc, WPortal},
+    crate::{
+        messages::{self, Report, Violation},
+        watchdog::HeldFor,
+    },
+    std::{
+        error::Error,
+        fmt::{self, Display, Formatter},
+        sync::Arc,
+    },
+};
+
+/// Acquires guards across several threadsafe portals in a globally consistent order,
+/// so that concurrent calls can't deadlock each other through lock-order inversion.
+/// Evaluates to a tuple of the guards, in argument order.
+///
+/// Each argument is a portal (an identifier or a parenthesised expression) followed by a method call without arguments,
+/// usually `.read()`, `.write()` or `.lock()`.
+/// Up to eight guards can be acquired at once. The arguments may also be wrapped in an additional pair of parentheses.
+///
+/// # Panics
+///
+/// Iff two of the portals are associated with the same anchor, since that could deadlock.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::{lock_all, sync::{RwAnchor, WAnchor}};
+///
+/// let (mut a, mut b, mut c) = (1, 2, 0);
+/// let (anchor_a, anchor_b, anchor_c) = (RwAnchor::new(&mut a), RwAnchor::new(&mut b), WAnchor::new(&mut c));
+/// let (p1, p2, p3) = (anchor_a.portal(), anchor_b.portal(), anchor_c.portal());
+///
+/// {
+///     let (a, mut b, mut c) = lock_all!(p1.read(), p2.write(), p3.lock());
+///     *c = *a + *b;
+///     *b = 0;
+/// }
+/// drop((p1, p2, p3));
+/// drop((anchor_a, anchor_b, anchor_c));
+///
+/// assert_eq!((a, b, c), (1, 0, 3));
+/// ```
+#[macro_export]
+macro_rules! lock_all {
+    (($($inner:tt)*)) => {
+        $crate::lock_all!($($inner)*)
+    };
+    ($($portal:tt . $method:ident ()),+ $(,)?) => {
+        $crate::sync::LockAll::lock_all((
+            $($crate::sync::LockRequest::new(&$portal, |portal| portal.$method()),)+
+        ))
+    };
+}
+
+/// Tries to acquire guards across several threadsafe portals without blocking, all or nothing.
+/// Evaluates to a `Result` of a tuple of the guards, in argument order,
+/// or [`WouldBlock`](sync/struct.WouldBlock.html) with the index of the first argument that couldn't be acquired.
+/// In the latter case, all guards acquired up to that point are released again.
+///
+/// The arguments are the same as for [`lock_all!`](macro.lock_all.html),
+/// except that the methods must return an `Option`, usually `.try_read()`, `.try_write()` or `.try_lock()`.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::{sync::{RwAnchor, WouldBlock}, try_lock_all};
+///
+/// let (mut a, mut b) = (1, 2);
+/// let (anchor_a, anchor_b) = (RwAnchor::new(&mut a), RwAnchor::new(&mut b));
+/// let (p1, p2) = (anchor_a.portal(), anchor_b.portal());
+///
+/// let reading = p2.read();
+/// let blocked = try_lock_all!(p1.try_write(), p2.try_write()).map(|_| ());
+/// assert_eq!(blocked, Err(WouldBlock { index: 1 }));
+/// assert!(p1.try_write().is_some()); // Released again.
+///
+/// drop(reading);
+/// let (mut a, mut b) = try_lock_all!(p1.try_write(), p2.try_write()).unwrap();
+/// std::mem::swap(&mut *a, &mut *b);
+/// ```
+#[macro_export]
+macro_rules! try_lock_all {
+    (($($inner:tt)*)) => {
+        $crate::try_lock_all!($($inner)*)
+    };
+    ($($portal:tt . $method:ident ()),+ $(,)?) => {
+        $crate::sync::TryLockAll::try_lock_all((
+            $($crate::sync::LockRequest::new(&$portal, |portal| portal.$method()),)+
+        ))
+    };
+}
+
+/// Returned by [`try_lock_all!`](../macro.try_lock_all.html) if one of the guards couldn't be acquired without blocking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WouldBlock {
+    /// The position of the blocking request among the arguments, starting at 0.
+    pub index: usize,
+}
+
+impl Display for WouldBlock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Request {} of try_lock_all! would block", self.index)
+    }
+}
+
+impl Error for WouldBlock {}
+
+/// Portals that can take part in [`lock_all!`](../macro.lock_all.html).
+/// This trait is sealed.
+pub trait LockOrder: sealed::Sealed {
+    /// Returns a key identifying the anchor, by which guards are ordered.
+    fn lock_order(&self) -> *const ();
+}
+
+/// Prevents outside implementations of `LockOrder`.
+mod sealed {
+    /// Implemented only by the portals in this crate.
+    pub trait Sealed {}
+}
+
+impl<T: ?Sized> sealed::Sealed for RwPortal<T> {}
+impl<T: ?Sized> LockOrder for RwPortal<T> {
+    #[inline]
+    fn lock_order(&self) -> *const () {
+        Arc::as_ptr(&self.0).cast()
+    }
+}
+
+impl<T: ?Sized> sealed::Sealed for WPortal<T> {}
+impl<T: ?Sized> LockOrder for WPortal<T> {
+    #[inline]
+    fn lock_order(&self) -> *const () {
+        Arc::as_ptr(&self.0).cast()
+    }
+}
+
+/// A deferred guard acquisition, as created by [`lock_all!`](../macro.lock_all.html).
+#[derive(Debug)]
+#[must_use]
+pub struct LockRequest<'a, P: ?Sized, G> {
+    /// The portal to acquire the guard through.
+    portal: &'a P,
+
+    /// Acquires the guard.
+    acquire: fn(&'a P) -> G,
+}
+
+impl<'a, P: ?Sized + LockOrder, G> LockRequest<'a, P, G> {
+    /// Prepares to acquire a guard through `portal` by calling `acquire`.
+    #[inline]
+    pub fn new(portal: &'a P, acquire: fn(&'a P) -> G) -> Self {
+        Self { portal, acquire }
+    }
+}
+
+/// Tuples of [`LockRequest`]s, which [`lock_all!`](../macro.lock_all.html) acquires in a consistent order.
+pub trait LockAll {
+    /// A tuple of the acquired guards.
+    type Guards;
+
+    /// Acquires all guards, ordered by anchor.
+    ///
+    /// # Panics
+    ///
+    /// Iff two of the portals are associated with the same anchor.
+    fn lock_all(self) -> Self::Guards;
+}
+
+/// Tuples of [`LockRequest`]s that may fail, which [`try_lock_all!`](../macro.try_lock_all.html) acquires all or nothing.
+pub trait TryLockAll {
+    /// A tuple of the acquired guards.
+    type Guards;
+
+    /// Tries to acquire all guards in order, releasing them again if one of them can't be acquired.
+    ///
+    /// # Errors
+    ///
+    /// Iff a request returns `None`, with that request's index.
+    fn try_lock_all(self) -> Result<Self::Guards, WouldBlock>;
+}
+
+/// Implements `LockAll` and `TryLockAll` for one tuple arity.
+macro_rules! impl_lock_all {
+    ($($request:ident: $portal:ident => $guard:ident @ $index:tt),+) => {
+        impl<'a, $($portal: ?Sized + LockOrder, $guard),+> LockAll for ($(LockRequest<'a, $portal, $guard>,)+) {
+            type Guards = ($($guard,)+);
+
+            fn lock_all(self) -> Self::Guards {
+                let ($($request,)+) = self;
+                let mut order = [$(($request.portal.lock_order(), $index)),+];
+                order.sort_unstable_by_key(|&(key, _)| key);
+                if order.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                    messages::panic(Report::new(Violation::LockAllDuplicate))
+                }
+
+                let mut guards = ($(None::<$guard>,)+);
+                for &(_, index) in &order {
+                    match index {
+                        $($index => guards.$index = Some(($request.acquire)($request.portal)),)+
+                        _ => unreachable!(),
+                    }
+                }
+                ($(guards.$index.expect("Acquired above"),)+)
+            }
+        }
+
+        impl<'a, $($portal: ?Sized + LockOrder, $guard),+> TryLockAll for ($(LockRequest<'a, $portal, Option<$guard>>,)+) {
+            type Guards = ($($guard,)+);
+
+            fn try_lock_all(self) -> Result<Self::Guards, WouldBlock> {
+                let ($($request,)+) = self;
+                // Guards acquired so far are dropped on early return.
+                Ok(($(($request.acquire)($request.portal).ok_or(WouldBlock { index: $index })?,)+))
+            }
+        }
+    };
+}
+
+impl_lock_all!(r0: P0 => G0 @ 0);
+impl_lock_all!(r0: P0 => G0 @ 0, r1: P1 => G1 @ 1);
+impl_lock_all!(r0: P0 => G0 @ 0, r1: P1 => G1 @ 1, r2: P2 => G2 @ 2);
+impl_lock_all!(r0: P0 => G0 @ 0, r1: P1 => G1 @ 1, r2: P2 => G2 @ 2, r3: P3 => G3 @ 3);
+impl_lock_all!(r0: P0 => G0 @ 0, r1: P1 => G1 @ 1, r2: P2 => G2 @ 2, r3: P3 => G3 @ 3, r4: P4 => G4 @ 4);
+impl_lock_all!(
+    r0: P0 => G0 @ 0, r1: P1 => G1 @ 1, r2: P2 => G2 @ 2, r3: P3 => G3 @ 3, r4: P4 => G4 @ 4,
+    r5: P5 => G5 @ 5
+);
+impl_lock_all!(
+    r0: P0 => G0 @ 0, r1: P1 => G1 @ 1, r2: P2 => G2 @ 2, r3: P3 => G3 @ 3, r4: P4 => G4 @ 4,
+    r5: P5 => G5 @ 5, r6: P6 => G6 @ 6
+);
+impl_lock_all!(
+    r0: P0 => G0 @ 0, r1: P1 => G1 @ 1, r2: P2 => G2 @ 2, r3: P3 => G3 @ 3, r4: P4 => G4 @ 4,
+    r5: P5 => G5 @ 5, r6: P6 => G6 @ 6, r7: P7 => G7 @ 7
+);
+
+/// A set of [`RwPortal`]s that can be read from consistently.
+///
+/// [`.read()`](#method.read) acquires read guards for all of them in the same global order as [`lock_all!`](../macro.lock_all.html),
+/// and holds them at the same time, so no writer can change any of the values in between.
+/// Invariants spanning several anchored values can therefore be checked on the result.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{ReadSet, RwAnchor};
+///
+/// let (mut checking, mut savings) = (70, 30);
+/// let anchor_checking = RwAnchor::new(&mut checking);
+/// let anchor_savings = RwAnchor::new(&mut savings);
+/// let (portal_checking, portal_savings) = (anchor_checking.portal(), anchor_savings.portal());
+///
+/// let mut accounts = ReadSet::new();
+/// assert!(accounts.insert(&portal_checking));
+/// assert!(accounts.insert(&portal_savings));
+/// assert!(!accounts.insert(&portal_checking));
+///
+/// let balances = accounts.read();
+/// assert_eq!(balances.iter().map(|balance| **balance).sum::<i32>(), 100);
+/// ```
+#[derive(Debug)]
+pub struct ReadSet<'a, T: ?Sized> {
+    /// The registered portals, in insertion order.
+    portals: Vec<&'a RwPortal<T>>,
+
+    /// Indices into `portals`, in lock order.
+    order: Vec<usize>,
+}
+
+impl<'a, T: ?Sized> ReadSet<'a, T> {
+    /// Creates an empty `ReadSet`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            portals: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    /// Adds `portal` to the set.
+    /// Returns `false` iff a portal associated with the same anchor was already present, in which case the set is unchanged.
+    pub fn insert(&mut self, portal: &'a RwPortal<T>) -> bool {
+        let key = portal.lock_order();
+        match self
+            .order
+            .binary_search_by_key(&key, |&index| self.portals[index].lock_order())
+        {
+            Ok(_) => false,
+            Err(position) => {
+                self.order.insert(position, self.portals.len());
+                self.portals.push(portal);
+                true
+            }
+        }
+    }
+
+    /// Returns the number of portals in the set.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.portals.len()
+    }
+
+    /// Returns `true` iff the set contains no portals.
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.portals.is_empty()
+    }
+
+    /// Acquires read guards for all portals in the set, returned in insertion order.
+    /// All guards are held at once, so the values they point to are consistent with each other.
+    ///
+    /// # Panics
+    ///
+    /// Iff any of the anchors has been poisoned.
+    #[must_use]
+    pub fn read(&self) -> Vec<impl AsRef<T> + HeldFor + ToArc<T> + 'a> {
+        let mut guards: Vec<_> = self.portals.iter().map(|_| None).collect();
+        for &index in &self.order {
+            guards[index] = Some(self.portals[index].read());
+        }
+        guards
+            .into_iter()
+            .map(|guard| guard.expect("Acquired above"))
+            .collect()
+    }
+}
+
+impl<'a, T: ?Sized> Default for ReadSet<'a, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,296 @@
+//! Portals projected onto a part of an immutable anchor's target.
+
+use {
+    super::{acquire, Portal, SSNonNull, Shared, WeakPortal},
+    crate::{anchor_dropped_panic, trace::TraceId, Peek},
+    std::{
+        fmt::{self, Debug, Formatter},
+        marker::PhantomData,
+        ops::Deref,
+        ptr::NonNull,
+        sync::{Arc, Weak},
+    },
+};
+
+/// An anchor's shared state, with its target type erased.
+trait Counted {
+    /// Registers an additional strong portal and records where it was created.
+    /// Only call this while the anchor or another strong portal exists.
+    fn acquire_traced(&self) -> TraceId;
+
+    /// Registers an additional strong portal regardless of the anchor's portal limit, and records where it was created.
+    /// Only call this to replace a strong portal that's released right after.
+    fn pin_traced(&self) -> TraceId;
+
+    /// Unregisters a strong portal created at `trace`.
+    fn release_traced(&self, trace: &TraceId);
+
+    /// Returns `true` iff the anchor hasn't been dropped yet.
+    fn is_anchored(&self) -> bool;
+}
+
+impl<L, A> Counted for Shared<L, A> {
+    fn acquire_traced(&self) -> TraceId {
+        self.acquire();
+        self.traces.capture()
+    }
+
+    fn pin_traced(&self) -> TraceId {
+        self.acquire_pin();
+        self.traces.capture()
+    }
+
+    fn release_traced(&self, trace: &TraceId) {
+        self.traces.release(trace);
+        self.release();
+    }
+
+    fn is_anchored(&self) -> bool {
+        Self::is_anchored(self)
+    }
+}
+
+/// A threadsafe immutable portal to a part of the anchored value, as returned by [`Portal::map`] and [`MappedWeakPortal::upgrade`].
+/// It counts as (strong) portal of the anchor. Dereference it directly with `*` or `.deref()`.
+#[must_use]
+pub struct MappedPortal<U: ?Sized> {
+    /// Points into the anchored value, or to a part of it.
+    target: NonNull<U>,
+
+    /// Counts this as strong portal.
+    shared: Arc<dyn Counted>,
+
+    /// Where this was created.
+    trace: TraceId,
+}
+
+unsafe impl<U: ?Sized + Sync> Send for MappedPortal<U> {
+    //SAFETY: Only `U` is accessed through this, and the anchor's shared state is threadsafe regardless of its target.
+}
+unsafe impl<U: ?Sized + Sync> Sync for MappedPortal<U> {
+    //SAFETY: Only `U` is accessed through this, and the anchor's shared state is threadsafe regardless of its target.
+}
+
+/// A projection of a weak portal, as returned by [`WeakPortal::map`].
+trait Project<U: ?Sized> {
+    /// Upgrades and projects the weak portal iff the anchor still exists and isn't sealed.
+    fn try_upgrade(&self) -> Option<MappedPortal<U>>;
+
+    /// Returns `true` iff the anchor still exists.
+    fn is_alive(&self) -> bool;
+}
+
+/// Implements [`Project`] through `project`.
+struct Projection<T: ?Sized, F> {
+    /// The anchor's shared state, as in a weak portal.
+    weak: Weak<Shared<SSNonNull<T>>>,
+
+    /// Applied to the target on each upgrade.
+    project: F,
+}
+
+impl<T: ?Sized + 'static, U: ?Sized, F: Fn(&T) -> &U> Project<U> for Projection<T, F> {
+    fn try_upgrade(&self) -> Option<MappedPortal<U>> {
+        let shared = acquire(&self.weak).ok()?;
+        let target = unsafe {
+            //SAFETY: Valid while the anchor exists, which it does while counting a strong portal.
+            shared.target.as_ref()
+        };
+        Some(MappedPortal {
+            target: (self.project)(target).into(),
+            trace: shared.traces.capture(),
+            shared,
+        })
+    }
+
+    fn is_alive(&self) -> bool {
+        self.weak
+            .upgrade()
+            .is_some_and(|shared| shared.is_anchored())
+    }
+}
+
+/// A threadsafe weak portal that's projected onto a part of the anchored value whenever it's upgraded, as returned by [`WeakPortal::map`].
+/// Like a [`WeakPortal`], it doesn't keep the anchor in use.
+#[must_use]
+pub struct MappedWeakPortal<U: ?Sized> {
+    /// Shared between clones.
+    projection: Arc<dyn Project<U> + Send + Sync>,
+
+    /// Upgrades only to a portal that's `Send` and `Sync` iff `U` is `Sync`.
+    _phantom: PhantomData<MappedPortal<U>>,
+}
+
+impl<T: ?Sized + 'static> Portal<T> {
+    /// Projects `portal` onto a part of its target, e.g. a field.
+    /// The mapped portal counts as strong portal instead.
+    /// This is an associated function so that it can't shadow a method of the target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{Anchor, MappedPortal, Portal};
+    ///
+    /// let x = ("Scoped".to_owned(), 1);
+    /// let anchor = Anchor::new(&x);
+    /// let name: MappedPortal<str> = Portal::map(anchor.portal(), |x| x.0.as_str());
+    /// assert_eq!(&*name, "Scoped");
+    /// # drop(name);
+    /// ```
+    pub fn map<U: ?Sized>(portal: Self, project: impl FnOnce(&T) -> &U) -> MappedPortal<U> {
+        let shared = Arc::clone(&portal.0);
+        let mapped = MappedPortal {
+            target: project(&portal).into(),
+            trace: shared.pin_traced(),
+            shared,
+        };
+        drop(portal);
+        mapped
+    }
+}
+
+impl<T: ?Sized + Send + Sync + 'static> WeakPortal<T> {
+    /// Projects this weak portal onto a part of its target, e.g. a field, by calling `project` on each upgrade.
+    /// This way, observer registries can hold only the part they need without keeping the anchor in use.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::Anchor;
+    ///
+    /// let x = ("Scoped".to_owned(), 1);
+    /// let anchor = Anchor::new(&x);
+    /// let name = anchor.weak_portal().map(|x| x.0.as_str());
+    ///
+    /// assert_eq!(&*name.upgrade(), "Scoped");
+    /// drop(anchor);
+    /// assert!(name.try_upgrade().is_none());
+    /// ```
+    pub fn map<U: ?Sized>(
+        &self,
+        project: impl Fn(&T) -> &U + Send + Sync + 'static,
+    ) -> MappedWeakPortal<U> {
+        MappedWeakPortal {
+            projection: Arc::new(Projection {
+                weak: Weak::clone(&self.0),
+                project,
+            }),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized> MappedPortal<U> {
+    /// Projects `portal` further onto a part of its target.
+    /// This is an associated function so that it can't shadow a method of the target.
+    pub fn map<V: ?Sized>(portal: Self, project: impl FnOnce(&U) -> &V) -> MappedPortal<V> {
+        let target = project(&portal).into();
+        let shared = Arc::clone(&portal.shared);
+        let trace = shared.pin_traced();
+        drop(portal);
+        MappedPortal {
+            target,
+            shared,
+            trace,
+        }
+    }
+}
+
+impl<U: ?Sized> Deref for MappedPortal<U> {
+    type Target = U;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // Only fails if the anchor was dropped anyway, while panicking.
+        if !self.shared.is_anchored() {
+            anchor_dropped_panic()
+        }
+        unsafe {
+            //SAFETY: Valid as long as the anchor exists.
+            self.target.as_ref()
+        }
+    }
+}
+
+impl<U: ?Sized> AsRef<U> for MappedPortal<U> {
+    #[inline]
+    fn as_ref(&self) -> &U {
+        self
+    }
+}
+
+impl<U: ?Sized> Clone for MappedPortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            target: self.target,
+            trace: self.shared.acquire_traced(),
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<U: ?Sized> Drop for MappedPortal<U> {
+    #[inline]
+    fn drop(&mut self) {
+        self.shared.release_traced(&self.trace);
+    }
+}
+
+impl<U: ?Sized + Debug> Debug for MappedPortal<U> {
+    /// Shows the target, or `<expired>` if the anchor was dropped anyway while panicking.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let target = if self.shared.is_anchored() {
+            Peek::Value(&**self)
+        } else {
+            Peek::Expired
+        };
+        f.debug_tuple("MappedPortal").field(&target).finish()
+    }
+}
+
+impl<U: ?Sized> MappedWeakPortal<U> {
+    /// Upgrades and projects this weak portal iff the anchor still exists and isn't sealed.
+    #[inline]
+    #[must_use]
+    pub fn try_upgrade(&self) -> Option<MappedPortal<U>> {
+        self.projection.try_upgrade()
+    }
+
+    /// Upgrades and projects this weak portal.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped or sealed.
+    #[inline]
+    pub fn upgrade(&self) -> MappedPortal<U> {
+        self.try_upgrade().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    /// Unlike `try_upgrade`, this doesn't create a portal.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.projection.is_alive()
+    }
+}
+
+impl<U: ?Sized> Clone for MappedWeakPortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            projection: Arc::clone(&self.projection),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized + Debug> Debug for MappedWeakPortal<U> {
+    /// Shows the projected target iff the anchor still exists and isn't sealed, otherwise `<expired>`.
+    /// This counts as strong portal while formatting.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let portal = self.try_upgrade();
+        let target = portal.as_deref().map_or(Peek::Expired, Peek::Value);
+        f.debug_tuple("MappedWeakPortal").field(&target).finish()
+    }
+}
@@ -0,0 +1,144 @@
+//! Anchoring memory-mapped files, so that they aren't unmapped while their portals still read from them.
+
+use {
+    super::{Anchor, MappedPortal, Portal, ShutdownToken},
+    memmap2::Mmap,
+    std::{
+        mem::ManuallyDrop,
+        ops::Deref,
+        ptr::{self, NonNull},
+        slice::SliceIndex,
+        time::Duration,
+    },
+};
+
+/// An [`Anchor`] to the bytes of a memory-mapped file it owns, which it only unmaps once all of its portals are gone.
+/// Dereferences to that anchor, so portals to the whole mapping are created and checked as usual.
+///
+/// Unmapping (on drop, or through [`unmap_within`](#method.unmap_within) or [`into_inner`](#method.into_inner))
+/// first seals the anchor, so that its weak portals can't be upgraded anymore,
+/// and then waits for its remaining strong portals to be dropped.
+/// Waiting on a thread that holds one of them deadlocks.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     memmap2::MmapOptions,
+///     ref_portals::sync::MapAnchor,
+///     std::{thread, time::Duration},
+/// };
+///
+/// let mut map = MmapOptions::new().len(6).map_anon().unwrap();
+/// map.copy_from_slice(b"header");
+/// let anchor = MapAnchor::new(map.make_read_only().unwrap());
+///
+/// let magic = anchor.slice(..4);
+/// let weak = anchor.weak_portal();
+/// let worker = thread::spawn(move || {
+///     thread::sleep(Duration::from_millis(10));
+///     assert_eq!(&*magic, b"head");
+/// });
+///
+/// drop(anchor); // Waits for the worker's portal, then unmaps.
+/// assert!(weak.try_upgrade().is_none());
+/// worker.join().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MapAnchor {
+    /// Anchors the bytes of `map`, so it must be (and, being dropped manually, is) dropped first.
+    anchor: ManuallyDrop<Anchor<'static, [u8]>>,
+
+    /// The mapping, whose address doesn't change when it's moved.
+    map: ManuallyDrop<Mmap>,
+
+    /// Bound to `anchor`, to seal it before waiting for its portals.
+    token: ShutdownToken,
+}
+
+impl MapAnchor {
+    /// Anchors the bytes of `map`.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(map: Mmap) -> Self {
+        let anchor = unsafe {
+            //SAFETY: Mapped until `map` is dropped after the anchor has been dropped without panicking.
+            // `Mmap` is read-only, so the bytes are only shared.
+            Anchor::from_raw(NonNull::from(&*map))
+        };
+        let token = ShutdownToken::new();
+        anchor.bind_shutdown(&token);
+        Self {
+            anchor: ManuallyDrop::new(anchor),
+            map: ManuallyDrop::new(map),
+            token,
+        }
+    }
+
+    /// Creates a portal to the bytes at `index`, e.g. a range.
+    ///
+    /// # Panics
+    ///
+    /// Iff `index` is out of bounds.
+    pub fn slice(&self, index: impl SliceIndex<[u8], Output = [u8]>) -> MappedPortal<[u8]> {
+        Portal::map(self.portal(), |bytes| &bytes[index])
+    }
+
+    /// Seals the anchor, so that its weak portals can't be upgraded anymore.
+    /// Calling this again has no effect.
+    pub fn seal(&self) {
+        self.token.shutdown();
+    }
+
+    /// Seals the anchor, waits up to `grace` for its remaining strong portals to be dropped, then unmaps the file.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping an [`Anchor`], iff any associated portals still exist after `grace`.
+    /// The mapping is leaked in that case.
+    pub fn unmap_within(self, grace: Duration) {
+        self.token.shutdown_within(grace);
+        drop(self);
+    }
+
+    /// Seals the anchor, waits for its remaining strong portals to be dropped, then returns the mapping.
+    pub fn into_inner(self) -> Mmap {
+        let mut this = ManuallyDrop::new(self);
+        unsafe {
+            //SAFETY: Not used again, since `this` isn't dropped.
+            this.release();
+            drop(ptr::read(ptr::addr_of!(this.token)));
+            ManuallyDrop::take(&mut this.map)
+        }
+    }
+
+    /// Seals the anchor, waits for its portals (within the deadline, if any) and then drops it.
+    ///
+    /// # Safety
+    ///
+    /// Must be called only once, since it drops `anchor`.
+    unsafe fn release(&mut self) {
+        self.seal();
+        self.anchor.wait_idle();
+        //SAFETY: Panics if portals remain, in which case `map` isn't dropped.
+        ManuallyDrop::drop(&mut self.anchor);
+    }
+}
+
+impl Deref for MapAnchor {
+    type Target = Anchor<'static, [u8]>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.anchor
+    }
+}
+
+impl Drop for MapAnchor {
+    fn drop(&mut self) {
+        unsafe {
+            //SAFETY: Dropping.
+            self.release();
+            ManuallyDrop::drop(&mut self.map);
+        }
+    }
+}
@@ -0,0 +1,369 @@
+//! Mutable anchors whose synchronization strategy is chosen at construction.
+
+use {
+    super::{Ordering, RwAnchor, RwPortal, WAnchor, WPortal},
+    crate::AnchorId,
+    std::ops::{Deref, DerefMut},
+};
+
+/// The anchor behind a [`MutAnchor`].
+#[derive(Debug)]
+enum AnchorBackend<'a, T: ?Sized> {
+    /// Concurrent reads, exclusive writes.
+    Rw(RwAnchor<'a, T>),
+
+    /// Exclusive access only.
+    W(WAnchor<'a, T>),
+}
+
+/// The portal behind a [`MutPortal`].
+#[derive(Debug)]
+enum PortalBackend<T: ?Sized> {
+    /// Concurrent reads, exclusive writes.
+    Rw(RwPortal<T>),
+
+    /// Exclusive access only.
+    W(WPortal<T>),
+}
+
+/// A guard from either backend.
+enum Guard<R, W> {
+    /// From an [`RwPortal`].
+    Rw(R),
+
+    /// From a [`WPortal`].
+    W(W),
+}
+
+/// A threadsafe mutable anchor backed by either an [`RwAnchor`] or a [`WAnchor`], as chosen through [`MutAnchor::builder`].
+///
+/// This lets call sites pick the synchronization strategy with a flag, and lets generic code accept a single mutable anchor and portal type.
+/// [`RwAnchor`] and [`WAnchor`] stay available as they are and convert into this through `From`.
+///
+/// # Panics
+///
+/// On drop, like its backend.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{MutAnchor, MutPortal};
+///
+/// fn count(portal: &MutPortal<Vec<u8>>) -> usize {
+///     portal.write().push(0);
+///     portal.read().len()
+/// }
+///
+/// for exclusive_only in [false, true] {
+///     let mut x = vec![1, 2];
+///     let anchor = MutAnchor::builder()
+///         .exclusive_only(exclusive_only)
+///         .build(&mut x);
+///     assert_eq!(anchor.is_exclusive_only(), exclusive_only);
+///     assert_eq!(count(&anchor.portal()), 3);
+/// }
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct MutAnchor<'a, T: ?Sized>(AnchorBackend<'a, T>);
+
+/// A threadsafe mutable portal to a [`MutAnchor`].
+/// Acquire a guard by calling `.read()` or `.write()`.
+///
+/// With an exclusive-only backend, read guards are exclusive too.
+#[derive(Debug)]
+#[must_use]
+pub struct MutPortal<T: ?Sized>(PortalBackend<T>);
+
+/// Configures a [`MutAnchor`], as returned by [`MutAnchor::builder`].
+#[derive(Debug, Clone, Copy, Default)]
+#[must_use]
+pub struct MutAnchorBuilder {
+    /// Whether to back the anchor with a [`WAnchor`] rather than an [`RwAnchor`].
+    exclusive_only: bool,
+
+    /// Passed to `named`.
+    label: Option<&'static str>,
+
+    /// Passed to `with_limit`.
+    limit: Option<usize>,
+}
+
+impl MutAnchorBuilder {
+    /// Backs the anchor with a [`WAnchor`] iff `exclusive_only` is `true`, and with an [`RwAnchor`] otherwise (the default).
+    ///
+    /// Exclusive-only access suits targets that aren't `Sync` or are rarely read concurrently.
+    pub const fn exclusive_only(self, exclusive_only: bool) -> Self {
+        Self {
+            exclusive_only,
+            ..self
+        }
+    }
+
+    /// Labels the anchor as `label`, like `named` does.
+    pub const fn named(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// Allows at most `limit` (strong) portals at a time, like `with_limit` does.
+    pub const fn limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Creates the anchor, capturing `reference`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn build<T: ?Sized>(self, reference: &mut T) -> MutAnchor<'_, T> {
+        let backend = if self.exclusive_only {
+            let anchor = match self.label {
+                Some(label) => WAnchor::named(label, reference),
+                None => WAnchor::new(reference),
+            };
+            if let Some(limit) = self.limit {
+                anchor.reference.limit.store(limit, Ordering::Relaxed);
+            }
+            AnchorBackend::W(anchor)
+        } else {
+            let anchor = match self.label {
+                Some(label) => RwAnchor::named(label, reference),
+                None => RwAnchor::new(reference),
+            };
+            if let Some(limit) = self.limit {
+                anchor.reference.limit.store(limit, Ordering::Relaxed);
+            }
+            AnchorBackend::Rw(anchor)
+        };
+        MutAnchor(backend)
+    }
+}
+
+impl MutAnchor<'static, ()> {
+    /// Starts configuring a `MutAnchor`, which is backed by an [`RwAnchor`] unless [`exclusive_only`](`MutAnchorBuilder::exclusive_only`) is set.
+    pub const fn builder() -> MutAnchorBuilder {
+        MutAnchorBuilder {
+            exclusive_only: false,
+            label: None,
+            limit: None,
+        }
+    }
+}
+
+impl<'a, T: ?Sized> MutAnchor<'a, T> {
+    /// Returns `true` iff this anchor is backed by a [`WAnchor`].
+    #[must_use]
+    pub const fn is_exclusive_only(&self) -> bool {
+        matches!(self.0, AnchorBackend::W(_))
+    }
+
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    pub fn portal(&self) -> MutPortal<T> {
+        MutPortal(match &self.0 {
+            AnchorBackend::Rw(anchor) => PortalBackend::Rw(anchor.portal()),
+            AnchorBackend::W(anchor) => PortalBackend::W(anchor.portal()),
+        })
+    }
+
+    /// Returns this anchor's label, if it has one.
+    #[must_use]
+    pub const fn label(&self) -> Option<&'static str> {
+        match &self.0 {
+            AnchorBackend::Rw(anchor) => anchor.label(),
+            AnchorBackend::W(anchor) => anchor.label(),
+        }
+    }
+
+    /// Returns this anchor's unique ID, like its backend's `id`.
+    #[must_use]
+    pub fn id(&self) -> AnchorId {
+        match &self.0 {
+            AnchorBackend::Rw(anchor) => anchor.id(),
+            AnchorBackend::W(anchor) => anchor.id(),
+        }
+    }
+
+    /// Returns the number of (strong) portals associated with this anchor.
+    #[must_use]
+    pub fn strong_portal_count(&self) -> usize {
+        match &self.0 {
+            AnchorBackend::Rw(anchor) => anchor.strong_portal_count(),
+            AnchorBackend::W(anchor) => anchor.strong_portal_count(),
+        }
+    }
+
+    /// Blocks the current thread until no (strong) portals exist, like the backend's `wait_idle`.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn wait_idle(&self) {
+        match &self.0 {
+            AnchorBackend::Rw(anchor) => anchor.wait_idle(),
+            AnchorBackend::W(anchor) => anchor.wait_idle(),
+        }
+    }
+
+    /// Returns the backing [`RwAnchor`], if this anchor isn't exclusive-only.
+    #[must_use]
+    pub const fn as_rw_anchor(&self) -> Option<&RwAnchor<'a, T>> {
+        match &self.0 {
+            AnchorBackend::Rw(anchor) => Some(anchor),
+            AnchorBackend::W(_) => None,
+        }
+    }
+
+    /// Returns the backing [`WAnchor`], if this anchor is exclusive-only.
+    #[must_use]
+    pub const fn as_w_anchor(&self) -> Option<&WAnchor<'a, T>> {
+        match &self.0 {
+            AnchorBackend::Rw(_) => None,
+            AnchorBackend::W(anchor) => Some(anchor),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> From<RwAnchor<'a, T>> for MutAnchor<'a, T> {
+    fn from(anchor: RwAnchor<'a, T>) -> Self {
+        Self(AnchorBackend::Rw(anchor))
+    }
+}
+
+impl<'a, T: ?Sized> From<WAnchor<'a, T>> for MutAnchor<'a, T> {
+    fn from(anchor: WAnchor<'a, T>) -> Self {
+        Self(AnchorBackend::W(anchor))
+    }
+}
+
+impl<T: ?Sized> MutPortal<T> {
+    /// Returns `true` iff this portal's anchor is backed by a [`WAnchor`].
+    #[must_use]
+    pub const fn is_exclusive_only(&self) -> bool {
+        matches!(self.0, PortalBackend::W(_))
+    }
+
+    /// Returns the unique ID of the anchor this portal is associated with.
+    #[must_use]
+    pub fn anchor_id(&self) -> AnchorId {
+        match &self.0 {
+            PortalBackend::Rw(portal) => portal.anchor_id(),
+            PortalBackend::W(portal) => portal.anchor_id(),
+        }
+    }
+
+    /// Acquires a read guard, which is exclusive with an exclusive-only backend.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read<'a>(&'a self) -> impl Deref<Target = T> + 'a {
+        match &self.0 {
+            PortalBackend::Rw(portal) => Guard::Rw(portal.read()),
+            PortalBackend::W(portal) => Guard::W(portal.lock()),
+        }
+    }
+
+    /// Acquires a write guard.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write<'a>(&'a self) -> impl DerefMut<Target = T> + 'a {
+        match &self.0 {
+            PortalBackend::Rw(portal) => Guard::Rw(portal.write()),
+            PortalBackend::W(portal) => Guard::W(portal.lock()),
+        }
+    }
+
+    /// Acquires a read guard iff that's possible without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read<'a>(&'a self) -> Option<impl Deref<Target = T> + 'a> {
+        match &self.0 {
+            PortalBackend::Rw(portal) => portal.try_read().map(Guard::Rw),
+            PortalBackend::W(portal) => portal.try_lock().map(Guard::W),
+        }
+    }
+
+    /// Acquires a write guard iff that's possible without blocking.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_write<'a>(&'a self) -> Option<impl DerefMut<Target = T> + 'a> {
+        match &self.0 {
+            PortalBackend::Rw(portal) => portal.try_write().map(Guard::Rw),
+            PortalBackend::W(portal) => portal.try_lock().map(Guard::W),
+        }
+    }
+
+    /// Returns the backing [`RwPortal`], if the anchor isn't exclusive-only.
+    #[must_use]
+    pub const fn as_rw_portal(&self) -> Option<&RwPortal<T>> {
+        match &self.0 {
+            PortalBackend::Rw(portal) => Some(portal),
+            PortalBackend::W(_) => None,
+        }
+    }
+
+    /// Returns the backing [`WPortal`], if the anchor is exclusive-only.
+    #[must_use]
+    pub const fn as_w_portal(&self) -> Option<&WPortal<T>> {
+        match &self.0 {
+            PortalBackend::Rw(_) => None,
+            PortalBackend::W(portal) => Some(portal),
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for MutPortal<T> {
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    fn clone(&self) -> Self {
+        Self(match &self.0 {
+            PortalBackend::Rw(portal) => PortalBackend::Rw(portal.clone()),
+            PortalBackend::W(portal) => PortalBackend::W(portal.clone()),
+        })
+    }
+}
+
+impl<T: ?Sized> From<RwPortal<T>> for MutPortal<T> {
+    fn from(portal: RwPortal<T>) -> Self {
+        Self(PortalBackend::Rw(portal))
+    }
+}
+
+impl<T: ?Sized> From<WPortal<T>> for MutPortal<T> {
+    fn from(portal: WPortal<T>) -> Self {
+        Self(PortalBackend::W(portal))
+    }
+}
+
+impl<T: ?Sized, R: Deref<Target = T>, W: Deref<Target = T>> Deref for Guard<R, W> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            Self::Rw(guard) => guard,
+            Self::W(guard) => guard,
+        }
+    }
+}
+
+impl<T: ?Sized, R: DerefMut<Target = T>, W: DerefMut<Target = T>> DerefMut for Guard<R, W> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        match self {
+            Self::Rw(guard) => guard,
+            Self::W(guard) => guard,
+        }
+    }
+}
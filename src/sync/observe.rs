@@ -0,0 +1,279 @@
+//! Typed change events published through read-write portals.
+
+use {
+    super::{Condvar, Mutex, MutexGuard, RwAnchor, RwPortal},
+    std::{
+        collections::VecDeque,
+        mem,
+        num::NonZeroUsize,
+        ops::Deref,
+        sync::{Arc, PoisonError, Weak},
+    },
+};
+
+/// The subscriptions to changes published through the same [`ObservingPortal`]s.
+#[derive(Debug)]
+struct Feed<C> {
+    /// Locked to publish or subscribe.
+    subscribers: Mutex<Subscribers<C>>,
+
+    /// How many changes each subscription buffers before dropping the oldest.
+    capacity: NonZeroUsize,
+}
+
+/// The live subscriptions of a [`Feed`].
+#[derive(Debug)]
+struct Subscribers<C> {
+    /// Dropped subscriptions are removed on the next publish.
+    queues: Vec<Weak<Queue<C>>>,
+
+    /// Set once the anchor has been dropped, after which no more changes can be published.
+    closed: bool,
+}
+
+/// The changes buffered for one [`Subscription`].
+#[derive(Debug)]
+struct Queue<C> {
+    /// Locked to wait on or notify `condvar`.
+    state: Mutex<QueueState<C>>,
+
+    /// Notified whenever a change is buffered, and once the feed is closed.
+    condvar: Condvar,
+}
+
+/// The state behind a [`Queue`].
+#[derive(Debug)]
+struct QueueState<C> {
+    /// Oldest first.
+    changes: VecDeque<C>,
+
+    /// How many changes were dropped because the buffer was full, since this was last reset.
+    missed: usize,
+
+    /// Whether the feed has been closed.
+    closed: bool,
+}
+
+impl<C> Feed<C> {
+    /// Locks the subscribers, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, Subscribers<C>> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Closes all subscriptions once they are drained.
+    fn close(&self) {
+        let mut subscribers = self.lock();
+        subscribers.closed = true;
+        for queue in mem::take(&mut subscribers.queues)
+            .iter()
+            .filter_map(Weak::upgrade)
+        {
+            queue.lock().closed = true;
+            queue.condvar.notify_all();
+        }
+    }
+}
+
+impl<C> Queue<C> {
+    /// Locks the queue state, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, QueueState<C>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A read-write portal that publishes a typed change event for each write through [`publish`](#method.publish).
+/// Clones publish to the same subscriptions. It dereferences to its [`RwPortal`], through which writes aren't observed.
+///
+/// Only available with the `observe` feature.
+#[derive(Debug)]
+#[must_use]
+pub struct ObservingPortal<T: ?Sized, C> {
+    /// Locked for writing while publishing.
+    portal: RwPortal<T>,
+
+    /// Receives the published changes.
+    feed: Arc<Feed<C>>,
+}
+
+/// Receives the changes published through an [`ObservingPortal`] and its clones, oldest first.
+/// Doesn't count as portal, so it can outlive the anchor. Once the anchor is dropped, the remaining changes can still be received.
+///
+/// Only available with the `observe` feature.
+#[derive(Debug)]
+#[must_use]
+pub struct Subscription<C>(Arc<Queue<C>>);
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Creates a portal that publishes a change event of type `C` for each write through it,
+    /// buffering up to `capacity` changes per [`Subscription`].
+    /// Each call creates a separate feed. Clone the returned portal to publish to the same subscriptions.
+    ///
+    /// Only available with the `observe` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::RwAnchor,
+    ///     std::{num::NonZeroUsize, thread},
+    /// };
+    ///
+    /// let mut words = Vec::new();
+    /// let anchor = RwAnchor::new(&mut words);
+    /// let portal = anchor.observed(NonZeroUsize::new(8).unwrap());
+    /// let subscription = portal.subscribe();
+    ///
+    /// // Ends once the anchor has been dropped and all changes were received.
+    /// let reader = thread::spawn(move || subscription.collect::<Vec<_>>());
+    /// thread::spawn(move || {
+    ///     for word in ["Scoped", "changes"] {
+    ///         portal.publish(|words| {
+    ///             words.push(word);
+    ///             words.len()
+    ///         });
+    ///     }
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// drop(anchor);
+    /// assert_eq!(reader.join().unwrap(), [1, 2]);
+    /// assert_eq!(words, ["Scoped", "changes"]);
+    /// ```
+    pub fn observed<C: Clone + Send + 'static>(
+        &self,
+        capacity: NonZeroUsize,
+    ) -> ObservingPortal<T, C> {
+        let feed = Arc::new(Feed {
+            subscribers: Mutex::new(Subscribers {
+                queues: Vec::new(),
+                closed: false,
+            }),
+            capacity,
+        });
+        self.on_drop({
+            // Strong, since the portals may all be gone before the anchor is.
+            let feed = Arc::clone(&feed);
+            move || feed.close()
+        });
+        ObservingPortal {
+            portal: self.portal(),
+            feed,
+        }
+    }
+}
+
+impl<T: ?Sized, C: Clone> ObservingPortal<T, C> {
+    /// Locks the target for writing, calls `change` with it and publishes the returned change event to all subscriptions.
+    /// The lock is held until the event is buffered, so events are received in the order in which the writes happened.
+    ///
+    /// Subscriptions that are full drop their oldest change to make room, and count it as [missed](`Subscription::take_missed`).
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned. If `change` panics, nothing is published and the anchor is poisoned.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn publish(&self, change: impl FnOnce(&mut T) -> C) {
+        let mut guard = self.portal.write();
+        let change = change(&mut guard);
+        let mut subscribers = self.feed.lock();
+        subscribers.queues.retain(|queue| {
+            if let Some(queue) = queue.upgrade() {
+                let mut state = queue.lock();
+                if state.changes.len() == self.feed.capacity.get() {
+                    state.changes.pop_front();
+                    state.missed += 1;
+                }
+                state.changes.push_back(change.clone());
+                drop(state);
+                queue.condvar.notify_all();
+                true
+            } else {
+                false
+            }
+        });
+        drop(subscribers);
+        drop(guard);
+    }
+}
+
+impl<T: ?Sized, C> ObservingPortal<T, C> {
+    /// Subscribes to the changes published from now on.
+    pub fn subscribe(&self) -> Subscription<C> {
+        let mut subscribers = self.feed.lock();
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                changes: VecDeque::with_capacity(self.feed.capacity.get()),
+                missed: 0,
+                closed: subscribers.closed,
+            }),
+            condvar: Condvar::new(),
+        });
+        subscribers.queues.push(Arc::downgrade(&queue));
+        Subscription(queue)
+    }
+}
+
+impl<T: ?Sized, C> Clone for ObservingPortal<T, C> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            portal: self.portal.clone(),
+            feed: Arc::clone(&self.feed),
+        }
+    }
+}
+
+impl<T: ?Sized, C> Deref for ObservingPortal<T, C> {
+    type Target = RwPortal<T>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.portal
+    }
+}
+
+impl<C> Subscription<C> {
+    /// Removes and returns the oldest buffered change, if any, without blocking.
+    #[must_use]
+    pub fn try_recv(&self) -> Option<C> {
+        self.0.lock().changes.pop_front()
+    }
+
+    /// Removes and returns the oldest buffered change, blocking until there is one.
+    /// Returns `None` once the anchor has been dropped and all changes were received.
+    #[must_use]
+    pub fn recv(&self) -> Option<C> {
+        let mut state = self.0.lock();
+        loop {
+            if let Some(change) = state.changes.pop_front() {
+                return Some(change);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .0
+                .condvar
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Returns how many changes were dropped because the buffer was full since the last call, and resets the count.
+    #[must_use]
+    pub fn take_missed(&self) -> usize {
+        mem::take(&mut self.0.lock().missed)
+    }
+}
+
+impl<C> Iterator for Subscription<C> {
+    type Item = C;
+
+    /// Like [`recv`](#method.recv).
+    #[inline]
+    fn next(&mut self) -> Option<C> {
+        self.recv()
+    }
+}
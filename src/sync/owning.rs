@@ -0,0 +1,123 @@
+//! Anchors that own their target, as a migration path off borrowed anchors.
+
+use {
+    super::{Anchor, SSNonNull},
+    std::{mem::ManuallyDrop, ops::Deref, ptr::NonNull},
+    wyz::pipe::*,
+};
+
+/// An [`Anchor`] to a value it owns, so that it isn't bound to a borrow.
+/// Dereferences to that anchor, so portals are created and checked as usual, and they have the same type as the borrowed anchor's.
+///
+/// # Panics
+///
+/// On drop, like an [`Anchor`], if any associated portals exist.
+/// The value is leaked in that case.
+#[derive(Debug)]
+pub struct OwningAnchor<T: 'static> {
+    /// Anchors the value behind `value`, so it must be (and, being dropped manually, is) dropped first.
+    anchor: ManuallyDrop<Anchor<'static, T>>,
+
+    /// The owned value, from `Box::into_raw`.
+    value: SSNonNull<T>,
+}
+
+impl<T: 'static> OwningAnchor<T> {
+    /// Moves `value` onto the heap and anchors it there.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(value: T) -> Self {
+        let value = value
+            .pipe(Box::new)
+            .pipe(Box::into_raw)
+            .pipe(|pointer| unsafe {
+                //SAFETY: From `Box::into_raw`.
+                NonNull::new_unchecked(pointer)
+            });
+        let anchor = unsafe {
+            //SAFETY: Valid until freed in `drop`, after the anchor has been dropped without panicking.
+            // Only shared through the anchor meanwhile.
+            Anchor::from_raw(value)
+        };
+        Self {
+            anchor: ManuallyDrop::new(anchor),
+            value: SSNonNull::new(value),
+        }
+    }
+
+    /// Drops the anchor and returns the owned value.
+    ///
+    /// # Panics
+    ///
+    /// Like dropping an [`Anchor`], if any associated portals exist.
+    /// The value is leaked in that case.
+    pub fn into_inner(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        unsafe {
+            //SAFETY: Not used again, since `this` isn't dropped.
+            ManuallyDrop::drop(&mut this.anchor);
+        }
+        let value = unsafe {
+            //SAFETY: From `Box::into_raw`, and no longer anchored.
+            Box::from_raw(this.value.as_ptr())
+        };
+        *value
+    }
+}
+
+impl<T: 'static> Deref for OwningAnchor<T> {
+    type Target = Anchor<'static, T>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.anchor
+    }
+}
+
+impl<T: 'static> Drop for OwningAnchor<T> {
+    fn drop(&mut self) {
+        unsafe {
+            //SAFETY: Dropping. Panics if portals remain, in which case the value is leaked.
+            ManuallyDrop::drop(&mut self.anchor);
+        }
+        unsafe {
+            //SAFETY: From `Box::into_raw`, and no longer anchored.
+            drop(Box::from_raw(self.value.as_ptr()));
+        }
+    }
+}
+
+impl<'a, T: Clone + 'static> Anchor<'a, T> {
+    /// Clones the target into a new [`OwningAnchor`], whose portals have the same type as this anchor's.
+    /// Long-lived consumers can then be migrated onto the owned copy one by one, before this anchor must be dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{Anchor, Portal};
+    ///
+    /// let mut consumers: Vec<Portal<String>>;
+    /// let owned = {
+    ///     let x = "Scoped".to_owned();
+    ///     let anchor = Anchor::new(&x);
+    ///     consumers = vec![anchor.portal(), anchor.portal()];
+    ///
+    ///     let owned = anchor.fork_owned();
+    ///     for consumer in &mut consumers {
+    ///         *consumer = owned.portal();
+    ///     }
+    ///     owned
+    /// }; // `anchor` is dropped here without panicking.
+    ///
+    /// assert!(consumers.iter().all(|consumer| **consumer == "Scoped"));
+    /// drop(consumers);
+    /// assert_eq!(owned.into_inner(), "Scoped");
+    /// ```
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn fork_owned(&self) -> OwningAnchor<T> {
+        let target = unsafe {
+            //SAFETY: Valid and shared while this anchor exists.
+            self.reference.target.as_ref()
+        };
+        target.clone().pipe(OwningAnchor::new)
+    }
+}
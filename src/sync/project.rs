@@ -0,0 +1,193 @@
+//! Portals projected onto a part of a mutable anchor's target.
+
+use {
+    super::{ArcReadGuard, ArcWriteGuard, RwPortal},
+    std::{
+        fmt::{self, Debug, Formatter},
+        marker::PhantomData,
+        sync::Arc,
+    },
+};
+
+/// A projection of a read-write portal for reading, as created by [`RwPortal::map_read`].
+trait ReadLens<U: ?Sized> {
+    /// Acquires a read guard and projects it.
+    fn read(&self) -> ArcReadGuard<U>;
+}
+
+/// A projection of a read-write portal for writing, as created by [`RwPortal::map_write`].
+trait WriteLens<U: ?Sized> {
+    /// Acquires a write guard and projects it.
+    fn write(&self) -> ArcWriteGuard<U>;
+}
+
+/// Implements [`ReadLens`] and [`WriteLens`] through `project`.
+struct Lens<T: ?Sized, F> {
+    /// Counts as strong portal of the anchor.
+    portal: RwPortal<T>,
+
+    /// Applied to the target each time it's locked.
+    project: F,
+}
+
+impl<T: ?Sized + 'static, U: ?Sized, F: Fn(&T) -> &U> ReadLens<U> for Lens<T, F> {
+    fn read(&self) -> ArcReadGuard<U> {
+        ArcReadGuard::map(self.portal.read_arc(), &self.project)
+    }
+}
+
+impl<T: ?Sized + 'static, U: ?Sized, F: Fn(&mut T) -> &mut U> WriteLens<U> for Lens<T, F> {
+    fn write(&self) -> ArcWriteGuard<U> {
+        ArcWriteGuard::map(self.portal.write_arc(), &self.project)
+    }
+}
+
+/// A threadsafe portal that can only read a part of the anchored value, as created by [`RwPortal::map_read`].
+/// Together with its clones, it counts as one (strong) portal of the anchor. Its guards lock all of the target.
+#[must_use]
+pub struct MappedReadPortal<U: ?Sized> {
+    /// Shared between clones.
+    lens: Arc<dyn ReadLens<U> + Send + Sync>,
+
+    /// `Send` and `Sync` iff `U` is both, like an `RwPortal<U>`.
+    _phantom: PhantomData<RwPortal<U>>,
+}
+
+/// A threadsafe portal that can only write a part of the anchored value, as created by [`RwPortal::map_write`].
+/// Together with its clones, it counts as one (strong) portal of the anchor. Its guards lock all of the target.
+#[must_use]
+pub struct MappedWritePortal<U: ?Sized> {
+    /// Shared between clones.
+    lens: Arc<dyn WriteLens<U> + Send + Sync>,
+
+    /// `Send` and `Sync` iff `U` is both, like an `RwPortal<U>`.
+    _phantom: PhantomData<RwPortal<U>>,
+}
+
+impl<T: ?Sized + Send + Sync + 'static> RwPortal<T> {
+    /// Creates a portal that can only read the part of the target selected by `project`, e.g. a field.
+    /// `project` is called each time a guard is acquired, so it can follow changes of the target.
+    ///
+    /// Use [`Portal::map`](`super::Portal::map`) to project immutable portals instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{MappedReadPortal, RwAnchor};
+    ///
+    /// let mut state = ("Scoped".to_owned(), 1);
+    /// let anchor = RwAnchor::new(&mut state);
+    /// let portal = anchor.portal();
+    ///
+    /// let name: MappedReadPortal<str> = portal.map_read(|state| state.0.as_str());
+    /// portal.write().0.push_str(" value");
+    /// assert_eq!(&*name.read(), "Scoped value");
+    /// # drop(name);
+    /// # drop(portal);
+    /// ```
+    pub fn map_read<U: ?Sized>(
+        &self,
+        project: impl Fn(&T) -> &U + Send + Sync + 'static,
+    ) -> MappedReadPortal<U> {
+        MappedReadPortal {
+            lens: Arc::new(Lens {
+                portal: self.clone(),
+                project,
+            }),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a portal that can only write the part of the target selected by `project`, e.g. a field.
+    /// `project` is called each time a guard is acquired, so it can follow changes of the target.
+    ///
+    /// Releasing its guards while unwinding poisons the anchor, as with the guards of this portal.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{MappedWritePortal, RwAnchor};
+    ///
+    /// let mut state = ("Scoped".to_owned(), 1);
+    /// let anchor = RwAnchor::new(&mut state);
+    /// let portal = anchor.portal();
+    ///
+    /// let count: MappedWritePortal<i32> = portal.map_write(|state| &mut state.1);
+    /// *count.write() += 1;
+    /// drop(count);
+    ///
+    /// # drop(portal);
+    /// drop(anchor);
+    /// assert_eq!(state.1, 2);
+    /// ```
+    pub fn map_write<U: ?Sized>(
+        &self,
+        project: impl Fn(&mut T) -> &mut U + Send + Sync + 'static,
+    ) -> MappedWritePortal<U> {
+        MappedWritePortal {
+            lens: Arc::new(Lens {
+                portal: self.clone(),
+                project,
+            }),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized> MappedReadPortal<U> {
+    /// Acquires a read guard, blocking while the target is locked for writing.
+    /// It can be projected further through [`ArcReadGuard::map`].
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    pub fn read(&self) -> ArcReadGuard<U> {
+        self.lens.read()
+    }
+}
+
+impl<U: ?Sized> MappedWritePortal<U> {
+    /// Acquires a write guard, blocking while the target is locked.
+    /// It can be projected further through [`ArcWriteGuard::map`].
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    pub fn write(&self) -> ArcWriteGuard<U> {
+        self.lens.write()
+    }
+}
+
+impl<U: ?Sized> Clone for MappedReadPortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            lens: Arc::clone(&self.lens),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized> Clone for MappedWritePortal<U> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            lens: Arc::clone(&self.lens),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<U: ?Sized> Debug for MappedReadPortal<U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedReadPortal").finish_non_exhaustive()
+    }
+}
+
+impl<U: ?Sized> Debug for MappedWritePortal<U> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedWritePortal").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,157 @@
+//! Portals to anchored singletons, looked up by target type.
+
+use {
+    super::Portal,
+    std::{
+        any::{self, Any, TypeId},
+        collections::HashMap,
+        fmt::{self, Debug, Formatter},
+    },
+};
+
+/// A portal stored in a [`PortalRegistry`], with its target type erased.
+trait DynPortal: Send + Sync {
+    /// Returns `self` as `Any`, to downcast it to the portal.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns `self` as `Any`, to downcast it to the portal.
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    /// Returns the name of the target type, for `Debug` output.
+    fn type_name(&self) -> &'static str;
+}
+
+impl<T: ?Sized + Send + Sync + 'static> DynPortal for Portal<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn type_name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+}
+
+/// Holds at most one threadsafe immutable portal per target type, e.g. to share multiple anchored singletons with dependency-injection style lookup.
+/// Targets can be trait objects, so that services are looked up by their interface.
+///
+/// The stored portals count as strong portals, so the registry must be dropped (or the entries removed) before their anchors.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{Anchor, PortalRegistry};
+///
+/// trait Greeter: Send + Sync {
+///     fn greet(&self) -> String;
+/// }
+///
+/// struct English;
+/// impl Greeter for English {
+///     fn greet(&self) -> String {
+///         "Hello".to_owned()
+///     }
+/// }
+///
+/// let greeter: &(dyn Greeter + 'static) = &English;
+/// let greeter_anchor = Anchor::new(greeter);
+/// let name = "Scoped".to_owned();
+/// let name_anchor = Anchor::new(&name);
+///
+/// let mut registry = PortalRegistry::new();
+/// registry.insert(greeter_anchor.portal());
+/// registry.insert(name_anchor.portal());
+///
+/// let greeter = registry.get::<dyn Greeter>().unwrap();
+/// let name = registry.get::<String>().unwrap();
+/// assert_eq!(format!("{}, {}!", greeter.greet(), *name), "Hello, Scoped!");
+/// assert!(registry.get::<str>().is_none());
+/// # drop((greeter, name));
+/// # drop(registry);
+/// ```
+#[derive(Default)]
+pub struct PortalRegistry {
+    /// Each keyed by its target type.
+    portals: HashMap<TypeId, Box<dyn DynPortal>>,
+}
+
+impl PortalRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `portal` as the one for its target type.
+    /// Returns the portal it replaced, if any.
+    pub fn insert<T: ?Sized + Send + Sync + 'static>(
+        &mut self,
+        portal: Portal<T>,
+    ) -> Option<Portal<T>> {
+        self.portals
+            .insert(TypeId::of::<T>(), Box::new(portal))
+            .map(downcast)
+    }
+
+    /// Returns a clone of the portal stored for target type `T`, if any.
+    #[must_use]
+    pub fn get<T: ?Sized + Send + Sync + 'static>(&self) -> Option<Portal<T>> {
+        self.portals
+            .get(&TypeId::of::<T>())
+            .and_then(|portal| portal.as_any().downcast_ref::<Portal<T>>())
+            .cloned()
+    }
+
+    /// Returns `true` iff a portal is stored for target type `T`.
+    #[must_use]
+    pub fn contains<T: ?Sized + 'static>(&self) -> bool {
+        self.portals.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes and returns the portal stored for target type `T`, if any.
+    pub fn remove<T: ?Sized + Send + Sync + 'static>(&mut self) -> Option<Portal<T>> {
+        self.portals.remove(&TypeId::of::<T>()).map(downcast)
+    }
+
+    /// Returns the number of stored portals.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.portals.len()
+    }
+
+    /// Returns `true` iff no portals are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.portals.is_empty()
+    }
+
+    /// Removes all portals.
+    pub fn clear(&mut self) {
+        self.portals.clear();
+    }
+}
+
+/// Recovers a portal stored under the `TypeId` of `T`.
+fn downcast<T: ?Sized + 'static>(portal: Box<dyn DynPortal>) -> Portal<T> {
+    *portal
+        .into_any()
+        .downcast::<Portal<T>>()
+        .expect("Keyed by its target type")
+}
+
+impl Debug for PortalRegistry {
+    /// Lists the target types, without reading any targets.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let types = self
+            .portals
+            .values()
+            .map(|portal| portal.type_name())
+            .collect::<Vec<_>>();
+        f.debug_struct("PortalRegistry")
+            .field("types", &types)
+            .finish()
+    }
+}
@@ -0,0 +1,115 @@
+//! Minting clones of portals with fewer capabilities than the original.
+
+use {
+    super::{
+        Portal, ReadPortal, RwPortal, WPortal, WeakPortal, WeakReadPortal, WeakRwPortal,
+        WeakWPortal,
+    },
+    wyz::pipe::*,
+};
+
+/// A clone of a portal that is about to give up capabilities, as started through the portals' `restrict` methods.
+/// Each step only removes capabilities, so a chain reads as the set of rights it hands on.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{RwAnchor, WeakReadPortal};
+///
+/// let mut config = "v1".to_owned();
+/// let anchor = RwAnchor::new(&mut config);
+/// let portal = anchor.portal();
+///
+/// // Handed to a subsystem that may only observe the config while it's still around.
+/// let observer: WeakReadPortal<String> = portal.restrict().read_only().weak();
+///
+/// let reader = observer.try_upgrade().unwrap();
+/// assert_eq!(*reader.read(), "v1");
+/// drop(reader);
+///
+/// # drop(portal);
+/// drop(anchor);
+/// assert!(observer.try_upgrade().is_none());
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct Restrict<P>(P);
+
+impl<P> Restrict<P> {
+    /// Stops restricting, returning the portal with the capabilities it has left.
+    #[inline]
+    pub fn into_inner(self) -> P {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Restrict<RwPortal<T>> {
+    /// Gives up the write capability.
+    #[inline]
+    pub fn read_only(self) -> Restrict<ReadPortal<T>> {
+        self.0.pipe(ReadPortal::from).pipe(Restrict)
+    }
+
+    /// Gives up keeping the anchor alive, so the result has to be upgraded before each use.
+    #[inline]
+    pub fn weak(self) -> WeakRwPortal<T> {
+        self.0.downgrade()
+    }
+}
+
+impl<T: ?Sized> Restrict<ReadPortal<T>> {
+    /// Gives up keeping the anchor alive, so the result has to be upgraded before each use.
+    #[inline]
+    pub fn weak(self) -> WeakReadPortal<T> {
+        self.0.downgrade()
+    }
+}
+
+impl<T: ?Sized> Restrict<WPortal<T>> {
+    /// Gives up keeping the anchor alive, so the result has to be upgraded before each use.
+    #[inline]
+    pub fn weak(self) -> WeakWPortal<T> {
+        self.0.downgrade()
+    }
+}
+
+impl<T: ?Sized> Restrict<Portal<T>> {
+    /// Gives up keeping the anchor alive, so the result has to be upgraded before each use.
+    #[inline]
+    pub fn weak(self) -> WeakPortal<T> {
+        Portal::downgrade(&self.0)
+    }
+}
+
+impl<T: ?Sized> Portal<T> {
+    /// Starts minting a clone of `portal` with fewer capabilities.
+    /// This is an associated function because `Portal` dereferences to its target.
+    #[inline]
+    pub fn restrict(portal: &Self) -> Restrict<Self> {
+        portal.clone().pipe(Restrict)
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Starts minting a clone of this portal with fewer capabilities.
+    #[inline]
+    pub fn restrict(&self) -> Restrict<Self> {
+        self.clone().pipe(Restrict)
+    }
+}
+
+impl<T: ?Sized> ReadPortal<T> {
+    /// Starts minting a clone of this portal with fewer capabilities.
+    #[inline]
+    pub fn restrict(&self) -> Restrict<Self> {
+        self.clone().pipe(Restrict)
+    }
+}
+
+impl<T: ?Sized> WPortal<T> {
+    /// Starts minting a clone of this portal with fewer capabilities.
+    #[inline]
+    pub fn restrict(&self) -> Restrict<Self> {
+        self.clone().pipe(Restrict)
+    }
+}
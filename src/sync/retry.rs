@@ -0,0 +1,229 @@
+//! Non-blocking guard acquisition, retried with backoff.
+
+use {
+    super::{AnchorField, Defuse, HeldFor, RwPortal, ToArc},
+    crate::watchdog::check_blocking,
+    std::{hint, thread, time::Duration},
+};
+
+/// How [`RwPortal::read_retry`] and [`RwPortal::write_retry`] wait between attempts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backoff {
+    /// Busy-waits through [`spin_loop`](`std::hint::spin_loop`), for locks that are only ever held very briefly.
+    Spin,
+
+    /// Yields the rest of the thread's time slice.
+    Yield,
+
+    /// Sleeps for `initial`, then twice as long after each further failed attempt, but never longer than `max`.
+    Exponential {
+        /// The first sleep.
+        initial: Duration,
+
+        /// The cap on each sleep.
+        max: Duration,
+    },
+}
+
+/// A [`Backoff`] and an optional limit on attempts, as taken by [`RwPortal::read_retry`] and [`RwPortal::write_retry`].
+/// Without a limit, retrying only stops once the guard has been acquired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    /// Applied after each failed attempt.
+    backoff: Backoff,
+
+    /// Including the first one.
+    max_attempts: Option<u32>,
+}
+
+/// The state of a retry loop.
+struct Retry {
+    /// What to do after each failed attempt.
+    policy: RetryPolicy,
+
+    /// Made so far.
+    attempts: u32,
+
+    /// The next sleep, for [`Backoff::Exponential`].
+    delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Retries indefinitely with `backoff`.
+    #[must_use]
+    pub const fn new(backoff: Backoff) -> Self {
+        Self {
+            backoff,
+            max_attempts: None,
+        }
+    }
+
+    /// Retries indefinitely, busy-waiting in between.
+    #[must_use]
+    pub const fn spin() -> Self {
+        Self::new(Backoff::Spin)
+    }
+
+    /// Retries indefinitely, yielding in between.
+    #[must_use]
+    pub const fn yielding() -> Self {
+        Self::new(Backoff::Yield)
+    }
+
+    /// Retries indefinitely, sleeping exponentially longer in between.
+    #[must_use]
+    pub const fn exponential(initial: Duration, max: Duration) -> Self {
+        Self::new(Backoff::Exponential { initial, max })
+    }
+
+    /// Gives up after `max_attempts` failed attempts, including the first one.
+    /// With `0`, no attempt is made at all.
+    #[must_use]
+    pub const fn with_max_attempts(self, max_attempts: u32) -> Self {
+        Self {
+            max_attempts: Some(max_attempts),
+            ..self
+        }
+    }
+
+    /// Returns how this policy waits between attempts.
+    #[must_use]
+    pub const fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Returns the limit on attempts, if any.
+    #[must_use]
+    pub const fn max_attempts(&self) -> Option<u32> {
+        self.max_attempts
+    }
+
+    /// Starts a retry loop.
+    const fn start(self) -> Retry {
+        Retry {
+            delay: match self.backoff {
+                Backoff::Exponential { initial, .. } => initial,
+                Backoff::Spin | Backoff::Yield => Duration::from_secs(0),
+            },
+            policy: self,
+            attempts: 0,
+        }
+    }
+}
+
+impl Retry {
+    /// Calls `attempt` until it succeeds, backing off in between.
+    /// Returns `None` iff the attempts ran out.
+    fn run<G>(mut self, mut attempt: impl FnMut() -> Option<G>) -> Option<G> {
+        loop {
+            if self
+                .policy
+                .max_attempts
+                .is_some_and(|max| self.attempts >= max)
+            {
+                return None;
+            }
+            if self.attempts > 0 {
+                self.back_off();
+            }
+            self.attempts += 1;
+            if let Some(guard) = attempt() {
+                return Some(guard);
+            }
+        }
+    }
+
+    /// Waits as per the policy's [`Backoff`].
+    fn back_off(&mut self) {
+        match self.policy.backoff {
+            Backoff::Spin => hint::spin_loop(),
+            Backoff::Yield => thread::yield_now(),
+            Backoff::Exponential { max, .. } => {
+                let delay = self.delay.min(max);
+                thread::sleep(delay);
+                self.delay = delay.saturating_mul(2);
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Acquires a read guard through [`try_read`](#method.try_read), retrying as per `policy` while that would block.
+    /// Returns `None` iff the policy's attempts ran out.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    ///
+    /// With the `watchdog` feature, also iff `policy` has no limit on attempts and this thread holds a write guard on the anchor,
+    /// which would retry forever.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{RetryPolicy, RwAnchor},
+    ///     std::time::Duration,
+    /// };
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let policy = RetryPolicy::exponential(Duration::from_micros(10), Duration::from_millis(1));
+    /// assert_eq!(*portal.read_retry(policy).unwrap(), "Scoped");
+    ///
+    /// let guard = portal.write();
+    /// assert!(portal.read_retry(RetryPolicy::spin().with_max_attempts(3)).is_none());
+    /// # drop(guard);
+    /// ```
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read_retry<'a>(
+        &'a self,
+        policy: RetryPolicy,
+    ) -> Option<impl AsRef<T> + HeldFor + ToArc<T> + 'a> {
+        if policy.max_attempts.is_none() {
+            check_blocking("sync::RwPortal::read_retry", &*self.0, false);
+        }
+        policy.start().run(|| self.try_read())
+    }
+
+    /// Acquires a write guard through [`try_write`](#method.try_write), retrying as per `policy` while that would block.
+    /// Returns `None` iff the policy's attempts ran out.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    ///
+    /// With the `watchdog` feature, also iff `policy` has no limit on attempts and this thread holds a guard on the anchor,
+    /// which would retry forever.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::{RetryPolicy, RwAnchor};
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// portal.write_retry(RetryPolicy::yielding()).unwrap().push_str("!");
+    ///
+    /// let guard = portal.read();
+    /// assert!(portal.write_retry(RetryPolicy::yielding().with_max_attempts(3)).is_none());
+    /// assert_eq!(*guard, "Scoped!");
+    /// # drop(guard);
+    /// ```
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write_retry<'a>(
+        &'a self,
+        policy: RetryPolicy,
+    ) -> Option<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a> {
+        if policy.max_attempts.is_none() {
+            check_blocking("sync::RwPortal::write_retry", &*self.0, true);
+        }
+        policy.start().run(|| self.try_write())
+    }
+}
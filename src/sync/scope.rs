@@ -0,0 +1,124 @@
+//! Scoped worker threads with read-only or read-write portals.
+
+use {
+    super::{ReadPortal, RwAnchor, RwPortal},
+    std::{
+        num::NonZeroUsize,
+        sync::{Mutex, PoisonError},
+        thread::{self, Scope, ScopedJoinHandle},
+    },
+    wyz::pipe::*,
+};
+
+/// Spawns worker threads for [`RwAnchor::scope`], each with its own portal.
+#[derive(Debug)]
+pub struct RwScope<'scope, 'env, T: ?Sized> {
+    /// Joins the workers before `RwAnchor::scope` returns.
+    scope: &'scope Scope<'scope, 'env>,
+
+    /// Cloned for each worker.
+    portal: RwPortal<T>,
+}
+
+impl<'scope, 'env, T: ?Sized> RwScope<'scope, 'env, T>
+where
+    RwPortal<T>: Send + 'scope,
+{
+    /// Spawns a scoped thread that runs `reader` with a portal that can only read.
+    pub fn spawn_reader<R: Send + 'scope>(
+        &self,
+        reader: impl FnOnce(ReadPortal<T>) -> R + Send + 'scope,
+    ) -> ScopedJoinHandle<'scope, R> {
+        let portal = self.portal.clone().pipe(ReadPortal::from);
+        self.scope.spawn(move || reader(portal))
+    }
+
+    /// Spawns a scoped thread that runs `writer` with a portal that can also write.
+    pub fn spawn_writer<R: Send + 'scope>(
+        &self,
+        writer: impl FnOnce(RwPortal<T>) -> R + Send + 'scope,
+    ) -> ScopedJoinHandle<'scope, R> {
+        let portal = self.portal.clone();
+        self.scope.spawn(move || writer(portal))
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Runs `f` with an [`RwScope`] to spawn reader and writer threads through, then joins all of them before returning.  
+    /// Since they are joined, their portals are gone once this returns and the anchor can be dropped without panicking.
+    ///
+    /// # Panics
+    ///
+    /// Like [`std::thread::scope`], if any spawned thread panicked and wasn't joined manually.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut counter = 0;
+    /// let anchor = RwAnchor::new(&mut counter);
+    /// anchor.scope(|s| {
+    ///     s.spawn_writer(|portal| *portal.write() += 1);
+    ///     s.spawn_reader(|portal| assert!(*portal.read() <= 1));
+    /// });
+    /// drop(anchor);
+    ///
+    /// assert_eq!(counter, 1);
+    /// ```
+    pub fn scope<'env, R>(
+        &'env self,
+        f: impl for<'scope> FnOnce(&RwScope<'scope, 'env, T>) -> R,
+    ) -> R {
+        let portal = self.portal();
+        thread::scope(|scope| f(&RwScope { scope, portal }))
+    }
+}
+
+impl<T: Send> RwPortal<[T]> {
+    /// Calls `f` on each chunk of at most `chunk_len` elements of the anchored slice, in parallel on scoped threads,
+    /// and returns once all chunks have been processed.  
+    /// The slice stays locked for writing meanwhile. Idle threads take the next unprocessed chunk, so uneven work is balanced.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned, or after all threads finished if `f` panicked, which poisons the anchor.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::RwAnchor, std::num::NonZeroUsize};
+    ///
+    /// let mut buffer: Vec<u32> = (0..1000).collect();
+    /// let anchor = RwAnchor::new(buffer.as_mut_slice());
+    /// let portal = anchor.portal();
+    ///
+    /// portal.for_each_parallel(NonZeroUsize::new(64).unwrap(), |chunk| {
+    ///     chunk.iter_mut().for_each(|x| *x *= 2);
+    /// });
+    /// drop(portal);
+    /// drop(anchor);
+    ///
+    /// assert!(buffer.iter().enumerate().all(|(i, x)| *x as usize == i * 2));
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn for_each_parallel(&self, chunk_len: NonZeroUsize, f: impl Fn(&mut [T]) + Sync) {
+        let mut guard = self.write();
+        let chunk_count = guard.len().div_ceil(chunk_len.get());
+        let workers = thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(chunk_count);
+        let chunks = guard.chunks_mut(chunk_len.get()).pipe(Mutex::new);
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let chunk = chunks.lock().unwrap_or_else(PoisonError::into_inner).next();
+                    match chunk {
+                        Some(chunk) => f(chunk),
+                        None => break,
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,389 @@
+//! Bump allocation of portal-accessible values in an anchored byte buffer.
+
+use {
+    super::{Mutex, MutexGuard, RwLock, RwLockReadGuard, SSNonNull},
+    crate::{
+        messages::{self, Report, Violation},
+        poison::Expired,
+        Peek,
+    },
+    std::{
+        fmt::{self, Debug, Formatter},
+        marker::PhantomData,
+        mem,
+        ops::{Deref, DerefMut},
+        ptr::{self, NonNull},
+        sync::{Arc, PoisonError},
+    },
+};
+
+/// State shared between a [`ScratchAnchor`] and its portals.
+#[derive(Debug)]
+struct Scratch {
+    /// The generation of the current allocations, or `None` once the anchor has been dropped.
+    /// Read-locked while a guard is held, and write-locked to reset.
+    generation: RwLock<Option<usize>>,
+
+    /// Where and what was allocated in the current generation.
+    allocations: Mutex<Allocations>,
+}
+
+/// The allocations of a [`ScratchAnchor`]'s current generation.
+#[derive(Debug, Default)]
+struct Allocations {
+    /// The number of bytes used from the start of the buffer, including padding.
+    used: usize,
+
+    /// Dropped on reset, in reverse order.
+    slots: Vec<Slot>,
+}
+
+/// An allocated value, with its type erased.
+#[derive(Debug)]
+struct Slot {
+    /// Points to a `RwLock<T>` in the buffer.
+    pointer: NonNull<u8>,
+
+    /// Drops the `RwLock<T>` at `pointer`.
+    drop: unsafe fn(NonNull<u8>),
+}
+
+unsafe impl Send for Slot {
+    //SAFETY: Only dropped while the buffer is locked exclusively, and the value is `Send`.
+}
+
+/// Drops the `RwLock<T>` at `pointer`.
+///
+/// # Safety
+///
+/// `pointer` must point to a valid `RwLock<T>` that's not used afterwards.
+unsafe fn drop_slot<T>(pointer: NonNull<u8>) {
+    ptr::drop_in_place(pointer.cast::<RwLock<T>>().as_ptr());
+}
+
+/// A threadsafe anchor of a byte buffer that's used as bump allocator.
+/// Each allocation is accessed through a [`ScratchPortal`], and all of them are invalidated together when the anchor is reset or dropped.
+///
+/// Unlike with other anchors, portals don't keep this in use. Resetting or dropping it waits only for guards that are currently held,
+/// after which the allocated values are dropped and the used bytes are zeroed.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::{poison::Expired, sync::ScratchAnchor},
+///     std::thread,
+/// };
+///
+/// let mut buffer = [0; 256];
+/// let mut scratch = ScratchAnchor::new(&mut buffer);
+///
+/// for frame in 0..2 {
+///     let positions = scratch.alloc(vec![frame; 4]).unwrap();
+///     let total = scratch.alloc(0).unwrap();
+///     thread::spawn({
+///         let (positions, total) = (positions.clone(), total.clone());
+///         move || *total.write().unwrap() = positions.read().unwrap().iter().sum()
+///     })
+///     .join()
+///     .unwrap();
+///     assert_eq!(*total.read().unwrap(), frame * 4);
+///
+///     scratch.reset();
+///     assert_eq!(total.read().err(), Some(Expired));
+/// }
+/// ```
+#[must_use]
+pub struct ScratchAnchor<'a> {
+    /// The start of the buffer, and its length.
+    buffer: SSNonNull<[u8]>,
+
+    /// Shared with the portals.
+    scratch: Arc<Scratch>,
+
+    /// Act as exclusive borrower.
+    _phantom: PhantomData<&'a mut [u8]>,
+}
+
+/// A threadsafe mutable portal to a value allocated through a [`ScratchAnchor`].
+/// Acquire a guard by calling `.read()` or `.write()`, which fail once the anchor has been reset or dropped.
+#[must_use]
+pub struct ScratchPortal<T> {
+    /// Valid while `scratch` is at `generation`.
+    slot: NonNull<RwLock<T>>,
+
+    /// Shared with the anchor.
+    scratch: Arc<Scratch>,
+
+    /// The generation `slot` was allocated in.
+    generation: usize,
+}
+
+unsafe impl<T: Send + Sync> Send for ScratchPortal<T> {
+    //SAFETY: Gives access to the value only through its lock, like an `Arc<RwLock<T>>`.
+}
+unsafe impl<T: Send + Sync> Sync for ScratchPortal<T> {
+    //SAFETY: Gives access to the value only through its lock, like an `Arc<RwLock<T>>`.
+}
+
+/// A guard of a [`ScratchPortal`], which keeps the anchor from being reset while it's held.
+struct ScratchGuard<'a, G> {
+    /// Released first.
+    guard: G,
+
+    /// Keeps the value allocated.
+    _generation: RwLockReadGuard<'a, Option<usize>>,
+}
+
+impl<'a> ScratchAnchor<'a> {
+    /// Creates a new `ScratchAnchor` instance, allocating from the start of `buffer`.
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer: SSNonNull::new(buffer.into()),
+            scratch: Arc::new(Scratch {
+                generation: RwLock::new(Some(0)),
+                allocations: Mutex::new(Allocations::default()),
+            }),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Moves `value` into the buffer and returns a portal to it.
+    /// `value` has to be `Send`, since [`reset`](`ScratchAnchor::reset`) and dropping the anchor may drop it on another thread.
+    ///
+    /// # Errors
+    ///
+    /// Iff `value` doesn't fit into the rest of the buffer, in which case it's returned back.
+    pub fn alloc<T: Send + 'a>(&self, value: T) -> Result<ScratchPortal<T>, T> {
+        let generation = self
+            .scratch
+            .generation
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .unwrap_or_else(|| unreachable!("Only cleared on drop"));
+        let mut allocations = self.lock();
+        let start = self.start();
+        let capacity = self.capacity();
+        let padding = unsafe {
+            //SAFETY: At most one past the end of the buffer.
+            start.as_ptr().add(allocations.used)
+        }
+        .align_offset(mem::align_of::<RwLock<T>>());
+        // Can't saturate within the buffer, as a slice is at most `isize::MAX` bytes long.
+        let offset = allocations.used.saturating_add(padding);
+        let end = offset.saturating_add(mem::size_of::<RwLock<T>>());
+        if end > capacity {
+            return Err(value);
+        }
+        let slot = unsafe {
+            //SAFETY: In bounds and aligned, as checked above, and not allocated yet in this generation.
+            let slot = start.as_ptr().add(offset).cast::<RwLock<T>>();
+            slot.write(RwLock::new(value));
+            NonNull::new_unchecked(slot)
+        };
+        allocations.used = end;
+        allocations.slots.push(Slot {
+            pointer: slot.cast(),
+            drop: drop_slot::<T>,
+        });
+        Ok(ScratchPortal {
+            slot,
+            scratch: Arc::clone(&self.scratch),
+            generation,
+        })
+    }
+
+    /// Invalidates all portals to values allocated so far and drops those values, so that the whole buffer can be allocated again.
+    /// Blocks until all guards of those portals have been released.
+    ///
+    /// Resetting on a thread that holds such a guard deadlocks.
+    pub fn reset(&mut self) {
+        let mut generation = self
+            .scratch
+            .generation
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.release();
+        *generation = generation.map(|generation| generation.wrapping_add(1));
+    }
+
+    /// Returns the buffer's length in bytes.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        unsafe {
+            //SAFETY: Valid for this anchor's lifetime.
+            self.buffer.as_ref()
+        }
+        .len()
+    }
+
+    /// Returns the number of bytes allocated since the last reset, including padding.
+    #[must_use]
+    pub fn used(&self) -> usize {
+        self.lock().used
+    }
+
+    /// Drops all allocated values and zeroes the used bytes.
+    /// Only call this while `generation` is write-locked.
+    fn release(&self) {
+        let mut allocations = self.lock();
+        for slot in allocations.slots.drain(..).rev() {
+            unsafe {
+                //SAFETY: No guards are held and the portals are invalidated right after.
+                (slot.drop)(slot.pointer);
+            }
+        }
+        unsafe {
+            //SAFETY: In bounds, and nothing is allocated there anymore.
+            // Zeroing restores the bytes that were padding or uninitialised inside the values.
+            ptr::write_bytes(self.start().as_ptr(), 0, allocations.used);
+        }
+        allocations.used = 0;
+    }
+
+    /// Returns the start of the buffer.
+    fn start(&self) -> NonNull<u8> {
+        let buffer: NonNull<[u8]> = *self.buffer;
+        buffer.cast()
+    }
+
+    /// Locks the allocations, ignoring poison since they are always consistent.
+    fn lock(&self) -> MutexGuard<'_, Allocations> {
+        self.scratch
+            .allocations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<'a> Drop for ScratchAnchor<'a> {
+    /// Invalidates all portals and drops the allocated values, like `reset`.
+    fn drop(&mut self) {
+        let mut generation = self
+            .scratch
+            .generation
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.release();
+        *generation = None;
+    }
+}
+
+impl<'a> Debug for ScratchAnchor<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScratchAnchor")
+            .field("used", &self.used())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl<T> ScratchPortal<T> {
+    /// Locks the anchor's generation iff it's still the one the value was allocated in.
+    fn current(&self) -> Result<RwLockReadGuard<'_, Option<usize>>, Expired> {
+        let generation = self
+            .scratch
+            .generation
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        if *generation == Some(self.generation) {
+            Ok(generation)
+        } else {
+            Err(Expired)
+        }
+    }
+
+    /// Returns the value's lock.
+    /// Only call this while holding a guard from `current`.
+    const fn slot(&self) -> &RwLock<T> {
+        unsafe {
+            //SAFETY: Allocated while the generation is current.
+            self.slot.as_ref()
+        }
+    }
+
+    /// Acquires a read guard iff the anchor hasn't been reset or dropped since the value was allocated.
+    /// Resetting or dropping the anchor waits for the guard to be released.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been reset or dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff a write guard of the value was released while panicking.
+    pub fn read<'a>(&'a self) -> Result<impl Deref<Target = T> + 'a, Expired> {
+        let generation = self.current()?;
+        let guard = self
+            .slot()
+            .read()
+            .unwrap_or_else(|_| messages::panic(Report::new(Violation::AnchorPoisoned)));
+        Ok(ScratchGuard {
+            guard,
+            _generation: generation,
+        })
+    }
+
+    /// Acquires a write guard iff the anchor hasn't been reset or dropped since the value was allocated.
+    /// Resetting or dropping the anchor waits for the guard to be released.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor has been reset or dropped.
+    ///
+    /// # Panics
+    ///
+    /// Iff a write guard of the value was released while panicking.
+    pub fn write<'a>(&'a self) -> Result<impl DerefMut<Target = T> + 'a, Expired> {
+        let generation = self.current()?;
+        let guard = self
+            .slot()
+            .write()
+            .unwrap_or_else(|_| messages::panic(Report::new(Violation::AnchorPoisoned)));
+        Ok(ScratchGuard {
+            guard,
+            _generation: generation,
+        })
+    }
+
+    /// Returns `true` iff the anchor hasn't been reset or dropped since the value was allocated.
+    /// This may change right after the call.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.current().is_ok()
+    }
+}
+
+impl<T> Clone for ScratchPortal<T> {
+    fn clone(&self) -> Self {
+        Self {
+            slot: self.slot,
+            scratch: Arc::clone(&self.scratch),
+            generation: self.generation,
+        }
+    }
+}
+
+impl<T: Debug> Debug for ScratchPortal<T> {
+    /// Shows the value, or `<expired>` if the anchor has been reset or dropped.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let guard = self.read();
+        let target = guard.as_deref().map_or(Peek::Expired, Peek::Value);
+        f.debug_tuple("ScratchPortal").field(&target).finish()
+    }
+}
+
+impl<'a, G: Deref> Deref for ScratchGuard<'a, G> {
+    type Target = G::Target;
+    #[inline]
+    fn deref(&self) -> &G::Target {
+        &self.guard
+    }
+}
+
+impl<'a, G: DerefMut> DerefMut for ScratchGuard<'a, G> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut G::Target {
+        &mut self.guard
+    }
+}
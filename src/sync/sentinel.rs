@@ -0,0 +1,185 @@
+//! Standing in for expired or poisoned targets in debug builds, so that QA runs can go on collecting context after a lifecycle bug.
+
+use {
+    super::RwPortal,
+    crate::messages::{Message, Report, Violation},
+    log::error,
+    std::{
+        ops::{Deref, DerefMut},
+        panic::Location,
+        sync::PoisonError,
+    },
+    wyz::pipe::*,
+};
+
+/// Creates a value that [`SentinelPortal`]s hand out in place of a target they can't access.
+pub trait Sentinel: Sized {
+    /// Creates the stand-in value, which should be easy to recognise as not real.
+    fn sentinel() -> Self;
+}
+
+/// An [`RwPortal`] whose guards dereference to a fresh [`Sentinel`] instead of panicking
+/// if the anchor has been poisoned or dropped, in builds with `debug_assertions`.
+/// Each such access is logged as error, along with its location. Writes to a sentinel are discarded.
+///
+/// Without `debug_assertions`, access panics like it does through the portal.
+/// It dereferences to that portal, through which access always panics on these lifecycle bugs.
+///
+/// # Example
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::sync::{RwAnchor, Sentinel, SentinelPortal};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Stock(i64);
+///
+/// impl Sentinel for Stock {
+///     fn sentinel() -> Self {
+///         Self(-999)
+///     }
+/// }
+///
+/// let mut stock = Stock(3);
+/// let anchor = RwAnchor::new(&mut stock);
+/// let portal = SentinelPortal::new(anchor.portal());
+///
+/// assert_panic!({
+///     // Poison anchor.
+///     let _guard = portal.write();
+///     panic!()
+/// });
+///
+/// if cfg!(debug_assertions) {
+///     assert_eq!(*portal.read(), Stock(-999)); // Logged as error.
+///     portal.write().0 = 5; // Discarded.
+/// }
+/// # drop(portal);
+/// # assert_panic!(drop(anchor));
+/// ```
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct SentinelPortal<T: ?Sized>(RwPortal<T>);
+
+/// A guard to the target, or a sentinel standing in for it.
+enum Access<G, T> {
+    /// The target is accessible.
+    Live(G),
+
+    /// The target was expired or poisoned.
+    Sentinel(T),
+}
+
+impl<G: Deref<Target = T>, T> Deref for Access<G, T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Live(guard) => guard,
+            Self::Sentinel(sentinel) => sentinel,
+        }
+    }
+}
+
+impl<G: DerefMut<Target = T>, T> DerefMut for Access<G, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::Live(guard) => guard,
+            Self::Sentinel(sentinel) => sentinel,
+        }
+    }
+}
+
+impl<T: ?Sized> SentinelPortal<T> {
+    /// Wraps `portal`.
+    #[inline]
+    pub const fn new(portal: RwPortal<T>) -> Self {
+        Self(portal)
+    }
+
+    /// Returns the wrapped portal.
+    #[inline]
+    pub fn into_inner(self) -> RwPortal<T> {
+        self.0
+    }
+}
+
+impl<T: Sentinel> SentinelPortal<T> {
+    /// Acquires a read guard, blocking while the target is locked for writing.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or dropped, unless `debug_assertions` are enabled.
+    #[track_caller]
+    pub fn read<'a>(&'a self) -> impl Deref<Target = T> + 'a {
+        match self.0.read_poisonable() {
+            Ok(guard) => Access::Live(guard),
+            Err(error) => {
+                // Never dereferenced, since the target may be gone.
+                drop(error);
+                self.stand_in("sync::SentinelPortal::read")
+                    .pipe(Access::Sentinel)
+            }
+        }
+    }
+
+    /// Acquires a write guard, blocking while the target is locked.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned or dropped, unless `debug_assertions` are enabled.
+    #[track_caller]
+    pub fn write<'a>(&'a self) -> impl DerefMut<Target = T> + 'a {
+        match self.0.write_poisonable() {
+            Ok(guard) => Access::Live(guard),
+            Err(error) => {
+                // Never dereferenced, since the target may be gone.
+                drop(error);
+                self.stand_in("sync::SentinelPortal::write")
+                    .pipe(Access::Sentinel)
+            }
+        }
+    }
+
+    /// Logs that `operation` continues with a sentinel, naming the caller's location, and creates that sentinel.
+    ///
+    /// # Panics
+    ///
+    /// Instead, unless `debug_assertions` are enabled.
+    #[cold]
+    #[track_caller]
+    fn stand_in(&self, operation: &'static str) -> T {
+        if !cfg!(debug_assertions) {
+            self.0 .0.poisoned_panic(&PoisonError::new(()))
+        }
+        let violation = if self.0 .0.is_anchored() {
+            Violation::AnchorPoisoned
+        } else {
+            Violation::AnchorDropped
+        };
+        error!(
+            "{} (at {}), so {} continues with a sentinel value for debugging",
+            Message(Report::new(violation)),
+            Location::caller(),
+            operation,
+        );
+        T::sentinel()
+    }
+}
+
+impl<T: ?Sized> Clone for SentinelPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> Deref for SentinelPortal<T> {
+    type Target = RwPortal<T>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -0,0 +1,239 @@
+//! Hash maps split into stripes that are anchored and locked separately.
+
+use {
+    super::{RwAnchor, RwPortal},
+    std::{
+        borrow::Borrow,
+        collections::{hash_map::RandomState, HashMap},
+        convert::TryFrom,
+        hash::{BuildHasher, Hash},
+        mem,
+        panic::{self, AssertUnwindSafe},
+        sync::Arc,
+    },
+};
+
+/// A hash map split into stripes, each holding the keys that hash to it.
+/// Anchor it through [`ShardedAnchor`], so that writes to one stripe don't block access to the others.
+#[derive(Debug, Clone)]
+pub struct ShardedMap<K, V, S = RandomState> {
+    /// Not empty.
+    stripes: Box<[HashMap<K, V, S>]>,
+
+    /// Picks the stripe for each key.
+    hasher: S,
+}
+
+/// Selects the stripe for `key` among `count`.
+fn stripe_index<Q: ?Sized + Hash>(hasher: &impl BuildHasher, count: usize, key: &Q) -> usize {
+    // The stripes' maps pick buckets by the low bits of the same hash and control bytes by the top seven.
+    let hash = hasher.hash_one(key) >> 32;
+    let count = u64::try_from(count).unwrap_or(u64::MAX);
+    usize::try_from(hash % count).unwrap_or_else(|_| unreachable!("Less than the stripe count"))
+}
+
+impl<K: Hash + Eq, V> ShardedMap<K, V> {
+    /// Creates an empty map with `stripes` stripes.
+    ///
+    /// # Panics
+    ///
+    /// Iff `stripes` is zero.
+    #[must_use]
+    pub fn new(stripes: usize) -> Self {
+        Self::with_hasher(stripes, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Clone> ShardedMap<K, V, S> {
+    /// Creates an empty map with `stripes` stripes, which uses `hasher` both to pick stripes and within them.
+    ///
+    /// # Panics
+    ///
+    /// Iff `stripes` is zero.
+    #[must_use]
+    pub fn with_hasher(stripes: usize, hasher: S) -> Self {
+        assert!(stripes > 0, "A `ShardedMap` needs at least one stripe");
+        Self {
+            stripes: (0..stripes)
+                .map(|_| HashMap::with_hasher(hasher.clone()))
+                .collect(),
+            hasher,
+        }
+    }
+
+    /// Returns the stripe `key` belongs to.
+    fn stripe<Q: ?Sized + Hash>(&self, key: &Q) -> &HashMap<K, V, S> {
+        &self.stripes[stripe_index(&self.hasher, self.stripes.len(), key)]
+    }
+
+    /// Returns a reference to the value for `key`, if any.
+    #[must_use]
+    pub fn get<Q: ?Sized + Hash + Eq>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        self.stripe(key).get(key)
+    }
+
+    /// Inserts `value` for `key`, returning the previous value for it, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let index = stripe_index(&self.hasher, self.stripes.len(), &key);
+        self.stripes[index].insert(key, value)
+    }
+
+    /// Removes the value for `key` and returns it, if any.
+    pub fn remove<Q: ?Sized + Hash + Eq>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let index = stripe_index(&self.hasher, self.stripes.len(), key);
+        self.stripes[index].remove(key)
+    }
+
+    /// Returns the number of entries across all stripes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stripes.iter().map(HashMap::len).sum()
+    }
+
+    /// Returns `true` iff no stripe has entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stripes.iter().all(HashMap::is_empty)
+    }
+
+    /// Iterates over all entries, stripe by stripe.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.stripes.iter().flat_map(HashMap::iter)
+    }
+}
+
+/// A threadsafe mutable anchor of a [`ShardedMap`], which anchors each of its stripes separately.
+/// Its portals lock only the stripe of the key they access.
+///
+/// # Panics
+///
+/// On drop, if any associated `ShardedPortal`s exist, like an [`RwAnchor`] would.
+/// All stripes are released first.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{ShardedAnchor, ShardedMap},
+///     std::thread,
+/// };
+///
+/// let mut sessions = ShardedMap::new(8);
+/// let anchor = ShardedAnchor::new(&mut sessions);
+///
+/// let workers = (0..4)
+///     .map(|user| {
+///         let portal = anchor.portal();
+///         thread::spawn(move || {
+///             // Only locks the stripe `user` belongs to.
+///             portal.entry_portal(&user).write().insert(user, user * 10);
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for worker in workers {
+///     worker.join().unwrap();
+/// }
+///
+/// drop(anchor);
+/// assert_eq!(sessions.len(), 4);
+/// assert_eq!(sessions.get(&3), Some(&30));
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct ShardedAnchor<'a, K, V, S = RandomState> {
+    /// One per stripe, in order.
+    stripes: Box<[RwAnchor<'a, HashMap<K, V, S>>]>,
+
+    /// Shared with the portals.
+    hasher: Arc<S>,
+}
+
+/// A threadsafe mutable portal to a [`ShardedAnchor`]'s map.
+/// Select the stripe portal for a key through `.entry_portal(…)`.
+#[derive(Debug)]
+#[must_use]
+pub struct ShardedPortal<K, V, S = RandomState> {
+    /// One per stripe, in order.
+    stripes: Box<[RwPortal<HashMap<K, V, S>>]>,
+
+    /// Picks the stripe for each key.
+    hasher: Arc<S>,
+}
+
+impl<'a, K, V, S: Clone> ShardedAnchor<'a, K, V, S> {
+    /// Creates a new `ShardedAnchor` instance, capturing each stripe of `map`.
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(map: &'a mut ShardedMap<K, V, S>) -> Self {
+        Self {
+            stripes: map.stripes.iter_mut().map(RwAnchor::new).collect(),
+            hasher: Arc::new(map.hasher.clone()),
+        }
+    }
+}
+
+impl<'a, K, V, S> ShardedAnchor<'a, K, V, S> {
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    /// It counts as (strong) portal of each stripe.
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    pub fn portal(&self) -> ShardedPortal<K, V, S> {
+        ShardedPortal {
+            stripes: self.stripes.iter().map(RwAnchor::portal).collect(),
+            hasher: Arc::clone(&self.hasher),
+        }
+    }
+
+    /// Returns the number of stripes.
+    #[must_use]
+    pub fn stripe_count(&self) -> usize {
+        self.stripes.len()
+    }
+}
+
+impl<'a, K, V, S> Drop for ShardedAnchor<'a, K, V, S> {
+    /// Drops each stripe's anchor, then resumes the first panic that caused, if any.
+    fn drop(&mut self) {
+        let mut failure = None;
+        for stripe in mem::take(&mut self.stripes).into_vec() {
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| drop(stripe))) {
+                failure.get_or_insert(payload);
+            }
+        }
+        if let Some(payload) = failure {
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+impl<K, V, S: BuildHasher> ShardedPortal<K, V, S> {
+    /// Returns the portal to the stripe `key` belongs to.
+    /// Locking it doesn't block access to other stripes.
+    pub fn entry_portal<Q: ?Sized + Hash>(&self, key: &Q) -> &RwPortal<HashMap<K, V, S>>
+    where
+        K: Borrow<Q>,
+    {
+        &self.stripes[stripe_index(&*self.hasher, self.stripes.len(), key)]
+    }
+}
+
+impl<K, V, S> ShardedPortal<K, V, S> {
+    /// Returns the portals to all stripes, in order, e.g. to visit every entry.
+    pub fn stripes(&self) -> &[RwPortal<HashMap<K, V, S>>] {
+        &self.stripes
+    }
+}
+
+impl<K, V, S> Clone for ShardedPortal<K, V, S> {
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    fn clone(&self) -> Self {
+        Self {
+            stripes: self.stripes.iter().map(RwPortal::clone).collect(),
+            hasher: Arc::clone(&self.hasher),
+        }
+    }
+}
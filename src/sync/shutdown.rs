@@ -0,0 +1,411 @@
+//! Coordinated teardown of many threadsafe anchors.
+
+use {
+    super::{
+        events::Emitter, AtomicBool, Clock, Condvar, Mutex, MutexGuard, Ordering, ReadPortal,
+        RwAnchor, RwPortal, ViolationPolicy, WeakRwPortal,
+    },
+    crate::{poison::UpgradeError, Callbacks},
+    std::{
+        mem,
+        sync::{Arc, OnceLock, PoisonError, Weak},
+        time::{Duration, Instant},
+    },
+    wyz::pipe::*,
+};
+
+/// The idle and shutdown state of a threadsafe anchor.
+/// Separate from the rest of the shared state so that [`ShutdownToken`]s can refer to it regardless of the anchored type.
+#[derive(Debug)]
+pub(super) struct Idle {
+    /// The deadline for `wait_while`, if shutdown was signalled with one.
+    /// Locked to wait on or notify `condvar`.
+    deadline: Mutex<Option<Instant>>,
+
+    /// Notified whenever the last strong portal is dropped, and on shutdown.
+    condvar: Condvar,
+
+    /// Set on shutdown, after which weak portals can't be upgraded.
+    sealed: AtomicBool,
+
+    /// When the anchor seals itself, if it was created with a deadline.
+    /// Only set before the anchor is shared.
+    expiry: OnceLock<Instant>,
+
+    /// Whether weak portals can still be upgraded to read-only ones once sealed, as per [`SealPolicy::ReadOnly`].
+    read_only_when_sealed: AtomicBool,
+
+    /// Measures `expiry` and `deadline`, if not the system clock.
+    /// Only set before the anchor is shared.
+    clock: OnceLock<Arc<dyn Clock>>,
+
+    /// The policy of the [`AnchorGroup`](`super::AnchorGroup`) the anchor was created through, if any.
+    /// Only set before the anchor is shared.
+    group_policy: OnceLock<ViolationPolicy>,
+
+    /// Whether the anchor waits for its portals when dropped outside of a group, as created through `new_blocking`.
+    /// Only set before the anchor is shared.
+    blocking: AtomicBool,
+
+    /// Publishes the anchor's lifecycle events, with the `events` feature.
+    pub(super) events: Emitter,
+}
+
+impl Idle {
+    /// Creates an unsealed instance without deadline.
+    #[allow(clippy::missing_const_for_fn)] // Not with loom.
+    pub(super) fn new() -> Self {
+        Self {
+            deadline: Mutex::new(None),
+            condvar: Condvar::new(),
+            sealed: AtomicBool::new(false),
+            expiry: OnceLock::new(),
+            read_only_when_sealed: AtomicBool::new(false),
+            clock: OnceLock::new(),
+            group_policy: OnceLock::new(),
+            blocking: AtomicBool::new(false),
+            events: Emitter::new(),
+        }
+    }
+
+    /// Returns the current time, as measured by this instance's clock.
+    fn now(&self) -> Instant {
+        self.clock
+            .get()
+            .map_or_else(Instant::now, |clock| clock.now())
+    }
+
+    /// Makes this measure time with `clock`. Call this only once, while creating the anchor.
+    pub(super) fn use_clock(&self, clock: Arc<dyn Clock>) {
+        self.clock.set(clock).ok();
+    }
+
+    /// Makes the anchor follow its group's `policy`. Call this only once, while creating the anchor.
+    pub(super) fn join_group(&self, policy: ViolationPolicy) {
+        self.group_policy.set(policy).ok();
+    }
+
+    /// Returns `true` iff the anchor was created through an `AnchorGroup`.
+    #[cfg(feature = "stats")]
+    pub(super) fn is_grouped(&self) -> bool {
+        self.group_policy.get().is_some()
+    }
+
+    /// Makes the anchor wait for its portals when dropped, unless it joins a group. Call this only while creating the anchor.
+    pub(super) fn block_on_drop(&self) {
+        self.blocking.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns what the anchor does if it's dropped while in use or poisoned.
+    pub(super) fn policy(&self) -> ViolationPolicy {
+        match self.group_policy.get() {
+            Some(&policy) => policy,
+            None if self.blocking.load(Ordering::Relaxed) => ViolationPolicy::Block,
+            None => ViolationPolicy::default(),
+        }
+    }
+
+    /// Returns `true` iff a token this is bound to has signalled shutdown or the expiry has passed.
+    #[inline]
+    pub(super) fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::Acquire)
+            || self
+                .expiry
+                .get()
+                .is_some_and(|expiry| self.now() >= *expiry)
+    }
+
+    /// Returns `true` iff weak portals can be upgraded to read-only ones even while this is sealed.
+    fn admits_readers(&self) -> bool {
+        self.read_only_when_sealed.load(Ordering::Acquire)
+    }
+
+    /// Makes this seal itself at `expiry`. Call this only once, while creating the anchor.
+    pub(super) fn expire_at(&self, expiry: Instant) {
+        self.expiry.set(expiry).ok();
+    }
+
+    /// Returns the time left until this seals itself, if it has an expiry.
+    pub(super) fn remaining(&self) -> Option<Duration> {
+        self.expiry
+            .get()
+            .map(|expiry| expiry.saturating_duration_since(self.now()))
+    }
+
+    /// Wakes all `.wait_while(…)` callers, so that they check their condition again.
+    pub(super) fn notify(&self) {
+        drop(self.deadline.lock().unwrap_or_else(PoisonError::into_inner));
+        self.condvar.notify_all();
+    }
+
+    /// Blocks the current thread while `condition` returns `true`, checking it again after each `.notify()`.
+    /// Returns early once the shutdown deadline has passed.
+    pub(super) fn wait_while(&self, condition: impl FnMut() -> bool) {
+        self.wait_while_until(None, condition);
+    }
+
+    /// Like `wait_while`, but also returns once `timeout` has passed.
+    pub(super) fn wait_while_within(&self, timeout: Duration, condition: impl FnMut() -> bool) {
+        // `None` if too far ahead to ever pass, so that this then waits like `wait_while`.
+        self.wait_while_until(self.now().checked_add(timeout), condition);
+    }
+
+    /// Like `wait_while`, but also returns once `until` (if any) has passed.
+    fn wait_while_until(&self, until: Option<Instant>, mut condition: impl FnMut() -> bool) {
+        let mut deadline = self.deadline.lock().unwrap_or_else(PoisonError::into_inner);
+        while condition() {
+            let limit = match (*deadline, until) {
+                (Some(deadline), Some(until)) => Some(deadline.min(until)),
+                (deadline, until) => deadline.or(until),
+            };
+            deadline = match limit {
+                None => self
+                    .condvar
+                    .wait(deadline)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(limit) => {
+                    let now = self.now();
+                    if now >= limit {
+                        return;
+                    }
+                    self.condvar
+                        .wait_timeout(deadline, limit - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        }
+    }
+
+    /// Prevents further weak portal upgrades and limits waiting to `deadline`, keeping an earlier one.
+    pub(super) fn seal(&self, deadline: Option<Instant>) {
+        let mut current = self.deadline.lock().unwrap_or_else(PoisonError::into_inner);
+        let was_sealed = self.sealed.swap(true, Ordering::AcqRel);
+        *current = match (*current, deadline) {
+            (Some(current), Some(deadline)) => Some(current.min(deadline)),
+            (current, deadline) => current.or(deadline),
+        };
+        drop(current);
+        self.condvar.notify_all();
+        if !was_sealed {
+            self.events.sealed();
+        }
+    }
+}
+
+/// What weak portals to an [`RwAnchor`] can still be upgraded to once it has been sealed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SealPolicy {
+    /// Upgrades fail with [`UpgradeError::Sealed`].
+    #[default]
+    Reject,
+
+    /// [`WeakRwPortal::try_upgrade_read`] keeps succeeding while the anchor exists, so that readers can finish up during shutdown.  
+    /// Other upgrades fail with [`UpgradeError::Sealed`].
+    ReadOnly,
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Sets what weak portals can still be upgraded to once this anchor has been sealed, through a [`ShutdownToken`] or its deadline.  
+    /// The default is [`SealPolicy::Reject`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::{
+    ///     poison::UpgradeError,
+    ///     sync::{RwAnchor, SealPolicy, ShutdownToken},
+    /// };
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// anchor.set_seal_policy(SealPolicy::ReadOnly);
+    /// let token = ShutdownToken::new();
+    /// anchor.bind_shutdown(&token);
+    /// let weak_portal = anchor.weak_portal();
+    ///
+    /// token.shutdown();
+    /// assert_eq!(weak_portal.try_upgrade_checked().unwrap_err(), UpgradeError::Sealed);
+    /// let reader = weak_portal.try_upgrade_read().unwrap();
+    /// assert_eq!(*reader.read(), "Scoped");
+    ///
+    /// drop(reader);
+    /// drop(anchor);
+    /// assert_eq!(weak_portal.try_upgrade_read().unwrap_err(), UpgradeError::Dropped);
+    /// ```
+    pub fn set_seal_policy(&self, policy: SealPolicy) {
+        self.reference
+            .idle
+            .read_only_when_sealed
+            .store(policy == SealPolicy::ReadOnly, Ordering::Release);
+    }
+}
+
+impl<T: ?Sized> WeakRwPortal<T> {
+    /// Upgrades this weak portal to one that can only read.  
+    /// Unlike `try_upgrade`, this still succeeds after the anchor has been sealed if its [`SealPolicy`] is [`ReadOnly`](`SealPolicy::ReadOnly`).
+    ///
+    /// # Errors
+    ///
+    /// [`UpgradeError::Dropped`] if the anchor has been dropped,  
+    /// [`UpgradeError::Sealed`] if it has been sealed and its policy is [`SealPolicy::Reject`],  
+    /// [`UpgradeError::LimitExceeded`] if it has as many portals as its limit allows.
+    pub fn try_upgrade_read(&self) -> Result<ReadPortal<T>, UpgradeError> {
+        let shared = self.0.upgrade().ok_or(UpgradeError::Dropped)?;
+        if shared.idle.admits_readers() {
+            shared.try_acquire_sealed()?;
+        } else {
+            shared.try_acquire()?;
+        }
+        Ok(shared.pipe(RwPortal::new).pipe(ReadPortal::from))
+    }
+}
+
+/// Signals shutdown to any number of threadsafe anchors at once.
+/// Clones refer to the same token.
+///
+/// Once shutdown is signalled, bound anchors are sealed so that their weak portals can't be upgraded anymore,
+/// and their `wait_idle` returns at the shutdown deadline, if any, even if portals remain.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{Anchor, ShutdownToken},
+///     std::time::Duration,
+/// };
+///
+/// let token = ShutdownToken::new();
+/// token.on_shutdown(|| println!("Shutting down…"));
+///
+/// let x = "Scoped".to_owned();
+/// let anchor = Anchor::new(&x);
+/// anchor.bind_shutdown(&token);
+/// let weak_portal = anchor.weak_portal();
+/// let stuck_portal = anchor.portal();
+///
+/// token.shutdown_within(Duration::from_millis(10));
+/// assert!(weak_portal.try_upgrade().is_none());
+/// anchor.wait_idle(); // Returns after 10ms.
+/// assert_eq!(anchor.strong_portal_count(), 1);
+/// # drop(stuck_portal);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShutdownToken(Arc<Mutex<Token>>);
+
+/// The state behind a [`ShutdownToken`].
+#[derive(Debug, Default)]
+struct Token {
+    /// Whether shutdown has been signalled.
+    shutdown: bool,
+
+    /// The deadline shutdown was signalled with, if any.
+    deadline: Option<Instant>,
+
+    /// Measures the grace period of `shutdown_within`, if not the system clock.
+    clock: Option<Arc<dyn Clock>>,
+
+    /// Bound anchors, while shutdown hasn't been signalled yet.
+    anchors: Vec<Weak<Idle>>,
+
+    /// Invoked once shutdown is signalled.
+    watchers: Callbacks<dyn FnOnce() + Send>,
+}
+
+impl ShutdownToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Token::default().pipe(Mutex::new).pipe(Arc::new).pipe(Self)
+    }
+
+    /// Creates a token whose `shutdown_within` measures the grace period with `clock`.
+    /// Bind it to anchors that use the same clock, through their `with_deadline_on`.
+    #[must_use]
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Token {
+            clock: Some(clock),
+            ..Token::default()
+        }
+        .pipe(Mutex::new)
+        .pipe(Arc::new)
+        .pipe(Self)
+    }
+
+    /// Signals shutdown without deadline.
+    /// Calling this or `shutdown_within` again has no effect.
+    pub fn shutdown(&self) {
+        self.signal(None);
+    }
+
+    /// Signals shutdown, limiting how long `wait_idle` on bound anchors blocks to `grace` from now.
+    /// Calling this or `shutdown` again has no effect.
+    pub fn shutdown_within(&self, grace: Duration) {
+        let now = self
+            .lock()
+            .clock
+            .as_ref()
+            .map_or_else(Instant::now, |clock| clock.now());
+        self.signal(Some(now + grace));
+    }
+
+    /// Returns `true` iff shutdown has been signalled.
+    #[must_use]
+    pub fn is_shutdown(&self) -> bool {
+        self.lock().shutdown
+    }
+
+    /// Registers `callback` to be invoked when shutdown is signalled, on the signalling thread after bound anchors were sealed.
+    /// If that happened already, `callback` is invoked immediately.
+    pub fn on_shutdown(&self, callback: impl FnOnce() + Send + 'static) {
+        let mut token = self.lock();
+        if token.shutdown {
+            drop(token);
+            callback();
+        } else {
+            token.watchers.push(Box::new(callback));
+        }
+    }
+
+    /// Binds an anchor's `idle` state to this token, sealing it immediately if shutdown was signalled already.
+    pub(super) fn bind(&self, idle: &Arc<Idle>) {
+        let mut token = self.lock();
+        if token.shutdown {
+            let deadline = token.deadline;
+            drop(token);
+            idle.seal(deadline);
+        } else {
+            token.anchors.retain(|anchor| anchor.strong_count() > 0);
+            token.anchors.push(Arc::downgrade(idle));
+        }
+    }
+
+    /// Seals all bound anchors with `deadline`, then notifies watchers, unless that happened already.
+    fn signal(&self, deadline: Option<Instant>) {
+        let mut token = self.lock();
+        if token.shutdown {
+            return;
+        }
+        token.shutdown = true;
+        token.deadline = deadline;
+        let anchors = mem::take(&mut token.anchors);
+        let mut watchers = mem::take(&mut token.watchers);
+        drop(token);
+
+        for idle in anchors.iter().filter_map(Weak::upgrade) {
+            idle.seal(deadline);
+        }
+        watchers.invoke_once();
+    }
+
+    /// Locks the token state, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, Token> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for ShutdownToken {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,226 @@
+//! Sampling `Copy` values from POSIX signal handlers.
+
+use {
+    super::SSNonNull,
+    std::{
+        hint,
+        marker::PhantomData,
+        mem::{ManuallyDrop, MaybeUninit},
+        panic::{RefUnwindSafe, UnwindSafe},
+        ptr,
+        sync::{
+            atomic::{self, AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+    },
+    wyz::pipe::*,
+};
+
+/// How often [`AsyncSignalSafePortal::read`] checks again for a write in progress to finish before giving up.
+const WRITE_SPINS: u32 = 1000;
+
+/// State shared between a `SignalAnchor` and its portals.
+#[derive(Debug)]
+struct SignalShared<T> {
+    /// The anchored value.
+    target: SSNonNull<T>,
+
+    /// Sequence counter of the seqlock guarding `target`. Odd while it's being written.
+    sequence: AtomicUsize,
+
+    /// Whether the anchor exists.
+    anchored: AtomicBool,
+
+    /// The number of reads in progress.
+    readers: AtomicUsize,
+}
+
+/// A threadsafe anchor for `Copy` values that can be sampled from signal handlers through [`AsyncSignalSafePortal`]s.
+/// Writes go through the anchor, which must be the only writer.
+///
+/// Portals don't keep the target alive. Instead, dropping the anchor waits only for reads that are in progress,
+/// after which reading through its portals returns `None`. It never panics.
+///
+/// # Example
+///
+/// ```rust
+/// use {ref_portals::sync::SignalAnchor, std::thread};
+///
+/// let mut progress = 0_u64;
+/// let mut anchor = SignalAnchor::new(&mut progress);
+/// let portal = anchor.portal();
+///
+/// // In a real program, this would be read from a signal handler instead.
+/// let sampler = thread::spawn(move || {
+///     let mut last = 0;
+///     loop {
+///         match portal.read() {
+///             Some(sample) => {
+///                 assert!(sample >= last);
+///                 last = sample;
+///             }
+///             // A write took too long.
+///             None if portal.is_alive() => (),
+///             None => break,
+///         }
+///     }
+/// });
+///
+/// for i in 1..=1000 {
+///     anchor.set(i);
+/// }
+/// drop(anchor);
+/// sampler.join().unwrap();
+/// assert_eq!(progress, 1000);
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct SignalAnchor<'a, T: Copy> {
+    /// Shared with the portals.
+    reference: ManuallyDrop<Arc<SignalShared<T>>>,
+    /// Ties the anchor to the captured reference.
+    _phantom: PhantomData<&'a mut T>,
+}
+
+/// A threadsafe portal whose [`read`](#method.read) is async-signal-safe:
+/// It doesn't lock, allocate or panic, and only uses lock-free atomics.
+///
+/// Only `read` has these guarantees. Create, clone and drop portals outside of signal handlers.
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct AsyncSignalSafePortal<T: Copy>(Arc<SignalShared<T>>);
+
+impl<'a, T: Copy> SignalAnchor<'a, T> {
+    /// Creates a new `SignalAnchor` instance, capturing `reference`.
+    #[inline]
+    pub fn new(reference: &'a mut T) -> Self {
+        Self {
+            reference: SignalShared {
+                target: SSNonNull::new(reference.into()),
+                sequence: AtomicUsize::new(0),
+                anchored: AtomicBool::new(true),
+                readers: AtomicUsize::new(0),
+            }
+            .pipe(Arc::new)
+            .pipe(ManuallyDrop::new),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    #[inline]
+    pub fn portal(&self) -> AsyncSignalSafePortal<T> {
+        self.reference
+            .pipe_deref(Arc::clone)
+            .pipe(AsyncSignalSafePortal)
+    }
+
+    /// Returns the current value.
+    #[inline]
+    #[must_use]
+    pub fn get(&self) -> T {
+        unsafe {
+            //SAFETY: Only written through `&mut self`.
+            *self.reference.target.as_ptr()
+        }
+    }
+
+    /// Replaces the value, so that later reads through portals return `value`.
+    ///
+    /// A signal handler interrupting this on the same thread can't read the target, since it can't wait for the write to finish.
+    #[inline]
+    pub fn set(&mut self, value: T) {
+        let shared = &**self.reference;
+        let sequence = shared.sequence.load(Ordering::Relaxed);
+        shared
+            .sequence
+            .store(sequence.wrapping_add(1), Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
+        unsafe {
+            //SAFETY: Exclusive through `&mut self`. Concurrent readers discard what they read meanwhile.
+            ptr::write_volatile(shared.target.as_ptr(), value);
+        }
+        shared
+            .sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+}
+
+impl<'a, T: Copy> Drop for SignalAnchor<'a, T> {
+    /// Waits for reads through associated portals that are in progress.
+    fn drop(&mut self) {
+        let reference = unsafe {
+            //SAFETY: Dropping.
+            ManuallyDrop::take(&mut self.reference)
+        };
+        reference.anchored.store(false, Ordering::SeqCst);
+        while reference.readers.load(Ordering::SeqCst) > 0 {
+            hint::spin_loop();
+        }
+    }
+}
+
+impl<T: Copy> AsyncSignalSafePortal<T> {
+    /// Reads the current value.
+    /// This is async-signal-safe.
+    ///
+    /// Waits briefly for a write in progress on another thread to finish.
+    /// Returns `None` iff the anchor has been dropped or a write didn't finish in time,
+    /// which is always the case if this interrupted a write on the same thread.
+    #[inline]
+    #[must_use]
+    pub fn read(&self) -> Option<T> {
+        let shared = &*self.0;
+        shared.readers.fetch_add(1, Ordering::SeqCst);
+        let value = if shared.anchored.load(Ordering::SeqCst) {
+            let mut spins = 0;
+            loop {
+                let sequence = shared.sequence.load(Ordering::Acquire);
+                if sequence % 2 == 1 {
+                    // Spinning indefinitely would never end if this interrupted the writer.
+                    if spins == WRITE_SPINS {
+                        break None;
+                    }
+                    spins += 1;
+                    hint::spin_loop();
+                    continue;
+                }
+                // Possibly torn, so not assumed to be a valid `T` yet.
+                let value = unsafe {
+                    //SAFETY: Anchored, so the target is valid for reads.
+                    ptr::read_volatile(shared.target.as_ptr().cast::<MaybeUninit<T>>())
+                };
+                atomic::fence(Ordering::Acquire);
+                if shared.sequence.load(Ordering::Relaxed) == sequence {
+                    break Some(unsafe {
+                        //SAFETY: Not written meanwhile, so this is a complete copy of the target.
+                        value.assume_init()
+                    });
+                }
+            }
+        } else {
+            None
+        };
+        shared.readers.fetch_sub(1, Ordering::SeqCst);
+        value
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.anchored.load(Ordering::SeqCst)
+    }
+}
+
+impl<T: Copy> Clone for AsyncSignalSafePortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.pipe_ref(Arc::clone).pipe(Self)
+    }
+}
+
+impl<'a, T: Copy> UnwindSafe for SignalAnchor<'a, T> where T: RefUnwindSafe {}
+impl<T: Copy> RefUnwindSafe for AsyncSignalSafePortal<T> {}
+impl<T: Copy> UnwindSafe for AsyncSignalSafePortal<T> {}
@@ -0,0 +1,121 @@
+//! Copying a read guard's target into an `Arc`, so that it can be kept around without holding the guard.
+
+use {
+    super::{Ordering, PortalReadGuard},
+    crate::AnchorId,
+    std::{
+        fmt::{self, Debug, Formatter},
+        ops::Deref,
+        sync::{Arc, Mutex, MutexGuard, PoisonError},
+    },
+};
+
+/// The latest snapshot, if any, along with the anchor it was taken from and that anchor's write count at the time.
+type Memo<T> = Option<(AnchorId, usize, Arc<T>)>;
+
+/// Implemented by the read guards of [`RwPortal`](`super::RwPortal`)s, to snapshot their target into an `Arc` that can outlive them.
+///
+/// This lets consumers that need a `'static` view release the guard right away instead of holding it for a long time.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::sync::{ArcMemo, RwAnchor, ToArc},
+///     std::{sync::Arc, thread},
+/// };
+///
+/// let mut config = vec!["a".to_owned()];
+/// let anchor = RwAnchor::new(&mut config);
+/// let portal = anchor.portal();
+/// let memo = ArcMemo::new();
+///
+/// let first = portal.read().to_arc_memoized(&memo);
+/// assert!(Arc::ptr_eq(&first, &portal.read().to_arc_memoized(&memo))); // Not cloned again.
+///
+/// portal.write().push("b".to_owned());
+/// let second = portal.read().to_arc_memoized(&memo);
+/// assert_eq!((first.len(), second.len()), (1, 2));
+///
+/// drop(portal);
+/// drop(anchor);
+/// thread::spawn(move || assert_eq!(*second, ["a", "b"]))
+///     .join()
+///     .unwrap();
+/// ```
+pub trait ToArc<T: ?Sized>: Deref<Target = T> {
+    /// Clones the target into a new `Arc`.
+    #[must_use]
+    fn to_arc(&self) -> Arc<T>
+    where
+        T: Clone,
+    {
+        Arc::new((**self).clone())
+    }
+
+    /// Like [`to_arc`](#method.to_arc), but returns the `Arc` stored in `memo` instead of cloning the target again
+    /// if it was taken from the same anchor and no write guard has been released since.
+    /// Otherwise, the new `Arc` replaces it.
+    #[must_use]
+    fn to_arc_memoized(&self, memo: &ArcMemo<T>) -> Arc<T>
+    where
+        T: Clone;
+}
+
+impl<'a, T: ?Sized> ToArc<T> for PortalReadGuard<'a, T> {
+    fn to_arc_memoized(&self, memo: &ArcMemo<T>) -> Arc<T>
+    where
+        T: Clone,
+    {
+        let id = self.1.id;
+        // Stable while the read guard is held.
+        let writes = self.1.writes.load(Ordering::Relaxed);
+        let mut memo = memo.lock();
+        match &*memo {
+            Some((memo_id, memo_writes, snapshot)) if (*memo_id, *memo_writes) == (id, writes) => {
+                Arc::clone(snapshot)
+            }
+            _ => {
+                let snapshot = self.to_arc();
+                *memo = Some((id, writes, Arc::clone(&snapshot)));
+                snapshot
+            }
+        }
+    }
+}
+
+/// Stores the latest snapshot taken through [`ToArc::to_arc_memoized`], to reuse it while it's current.
+pub struct ArcMemo<T>(Mutex<Memo<T>>);
+
+impl<T> ArcMemo<T> {
+    /// Creates an empty memo.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    /// Discards the stored snapshot, so that the next [`ToArc::to_arc_memoized`] clones the target again.
+    pub fn clear(&self) {
+        drop(self.lock().take());
+    }
+
+    /// Locks the memo, ignoring poison since it's always consistent.
+    fn lock(&self) -> MutexGuard<'_, Memo<T>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Default for ArcMemo<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Debug> Debug for ArcMemo<T> {
+    /// Shows the stored snapshot.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArcMemo")
+            .field(&self.lock().as_ref().map(|(_, _, snapshot)| snapshot))
+            .finish()
+    }
+}
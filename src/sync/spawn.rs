@@ -0,0 +1,190 @@
+//! Spawning threads with portals, joined by their anchor before it releases its reference.
+
+use {
+    super::{Anchor, Mutex, Portal, RwAnchor, RwPortal, WAnchor, WPortal},
+    std::{
+        io, mem,
+        sync::{Arc, PoisonError},
+        thread::{self, Builder, JoinHandle, Thread},
+    },
+    wyz::pipe::*,
+};
+
+/// The state of a thread spawned through one of the `spawn_with_portal` methods.
+#[derive(Debug)]
+enum Slot<R> {
+    /// Neither the handle's owner nor the anchor has joined the thread yet.
+    Running(JoinHandle<R>),
+
+    /// The anchor joined the thread and kept its result for the handle's owner.
+    Joined(thread::Result<R>),
+
+    /// The handle's owner took the result.
+    Taken,
+}
+
+/// An owned permission to join a thread spawned through one of the `spawn_with_portal` methods.
+///
+/// Unlike for a [`JoinHandle`], dropping this doesn't detach the thread:
+/// The anchor joins it before releasing its reference either way, keeping the result for this handle if it still exists.
+#[derive(Debug)]
+#[must_use]
+pub struct PortalJoinHandle<R> {
+    /// Shared with the anchor.
+    slot: Arc<Mutex<Slot<R>>>,
+
+    /// The spawned thread.
+    thread: Thread,
+}
+
+impl<R> PortalJoinHandle<R> {
+    /// Waits for the thread to finish, unless the anchor joined it already, and returns its result.
+    ///
+    /// # Errors
+    ///
+    /// Iff the thread panicked, with the panic payload.
+    pub fn join(self) -> thread::Result<R> {
+        let mut slot = self.slot.lock().unwrap_or_else(PoisonError::into_inner);
+        match mem::replace(&mut *slot, Slot::Taken) {
+            Slot::Running(handle) => handle.join(),
+            Slot::Joined(result) => result,
+            Slot::Taken => unreachable!(),
+        }
+    }
+
+    /// Returns `true` iff the thread has finished running.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        match &*self.slot.lock().unwrap_or_else(PoisonError::into_inner) {
+            Slot::Running(handle) => handle.is_finished(),
+            Slot::Joined(_) | Slot::Taken => true,
+        }
+    }
+
+    /// Returns a handle to the spawned thread.
+    #[inline]
+    #[must_use]
+    pub const fn thread(&self) -> &Thread {
+        &self.thread
+    }
+}
+
+/// Spawns `f` with `portal` through `builder`, and returns the handle together with the callback that joins the thread for the anchor.
+fn spawn<P: Send + 'static, R: Send + 'static>(
+    builder: Builder,
+    portal: P,
+    f: impl FnOnce(P) -> R + Send + 'static,
+) -> io::Result<(PortalJoinHandle<R>, impl FnOnce() + Send + 'static)> {
+    let handle = builder.spawn(move || f(portal))?;
+    let thread = handle.thread().clone();
+    let slot = handle.pipe(Slot::Running).pipe(Mutex::new).pipe(Arc::new);
+    let join = {
+        let slot = Arc::clone(&slot);
+        move || {
+            let mut slot = slot.lock().unwrap_or_else(PoisonError::into_inner);
+            // Only the anchor stores `Joined`, so anything else means the owner took the result already.
+            if let Slot::Running(handle) = mem::replace(&mut *slot, Slot::Taken) {
+                *slot = handle.join().pipe(Slot::Joined);
+            }
+        }
+    };
+    Ok((PortalJoinHandle { slot, thread }, join))
+}
+
+impl<'a, T: ?Sized + 'static> Anchor<'a, T>
+where
+    Portal<T>: Send,
+{
+    /// Spawns a thread through `builder` that runs `f` with a new portal, and binds it to this anchor like [`bind_join`](#method.bind_join).
+    /// This is a lighter alternative to scoped threads where the anchor outlives a few threads anyway.
+    ///
+    /// The returned handle can join the thread early to get its result.
+    /// Otherwise, dropping the anchor joins the thread first, so its portal is gone before the anchor checks for remaining ones.
+    ///
+    /// Dropping the anchor on the spawned thread deadlocks.
+    ///
+    /// # Errors
+    ///
+    /// Iff `builder` fails to spawn the thread.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::Anchor, std::thread::Builder};
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let anchor = Anchor::new(&x);
+    ///
+    /// let handle = anchor
+    ///     .spawn_with_portal(Builder::new().name("worker".to_owned()), |portal| portal.len())
+    ///     .unwrap();
+    /// let unjoined = anchor
+    ///     .spawn_with_portal(Builder::new(), |portal| assert_eq!(*portal, "Scoped"))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(handle.thread().name(), Some("worker"));
+    /// assert_eq!(handle.join().unwrap(), 6);
+    /// drop(anchor); // Joins the other thread first, so this doesn't panic.
+    /// assert!(unjoined.join().is_ok());
+    /// ```
+    pub fn spawn_with_portal<R: Send + 'static>(
+        &self,
+        builder: Builder,
+        f: impl FnOnce(Portal<T>) -> R + Send + 'static,
+    ) -> io::Result<PortalJoinHandle<R>> {
+        let (handle, join) = spawn(builder, self.portal(), f)?;
+        self.bound_threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(join));
+        Ok(handle)
+    }
+}
+
+impl<'a, T: ?Sized + 'static> RwAnchor<'a, T>
+where
+    RwPortal<T>: Send,
+{
+    /// Spawns a thread through `builder` that runs `f` with a new portal, and binds it to this anchor like [`bind_join`](#method.bind_join).
+    /// See [`Anchor::spawn_with_portal`].
+    ///
+    /// # Errors
+    ///
+    /// Iff `builder` fails to spawn the thread.
+    pub fn spawn_with_portal<R: Send + 'static>(
+        &self,
+        builder: Builder,
+        f: impl FnOnce(RwPortal<T>) -> R + Send + 'static,
+    ) -> io::Result<PortalJoinHandle<R>> {
+        let (handle, join) = spawn(builder, self.portal(), f)?;
+        self.bound_threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(join));
+        Ok(handle)
+    }
+}
+
+impl<'a, T: ?Sized + 'static> WAnchor<'a, T>
+where
+    WPortal<T>: Send,
+{
+    /// Spawns a thread through `builder` that runs `f` with a new portal, and binds it to this anchor like [`bind_join`](#method.bind_join).
+    /// See [`Anchor::spawn_with_portal`].
+    ///
+    /// # Errors
+    ///
+    /// Iff `builder` fails to spawn the thread.
+    pub fn spawn_with_portal<R: Send + 'static>(
+        &self,
+        builder: Builder,
+        f: impl FnOnce(WPortal<T>) -> R + Send + 'static,
+    ) -> io::Result<PortalJoinHandle<R>> {
+        let (handle, join) = spawn(builder, self.portal(), f)?;
+        self.bound_threads
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::new(join));
+        Ok(handle)
+    }
+}
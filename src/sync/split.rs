@@ -0,0 +1,184 @@
+//! Read and write capabilities split off read-write portals.
+
+use {
+    super::{AnchorField, Defuse, HeldFor, RwPortal, ToArc, WeakRwPortal},
+    crate::poison::UpgradeError,
+    wyz::pipe::*,
+};
+
+/// A portal that can only read, e.g. the read half of [`RwPortal::split`]
+/// or as handed to the readers spawned through [`RwScope::spawn_reader`](`super::RwScope::spawn_reader`).
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct ReadPortal<T: ?Sized>(RwPortal<T>);
+
+impl<T: ?Sized> ReadPortal<T> {
+    /// Acquires a read guard, blocking while the target is locked for writing.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read<'a>(&'a self) -> impl AsRef<T> + HeldFor + ToArc<T> + 'a {
+        self.0.read()
+    }
+
+    /// Acquires a read guard iff that's possible without blocking.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read<'a>(&'a self) -> Option<impl AsRef<T> + HeldFor + ToArc<T> + 'a> {
+        self.0.try_read()
+    }
+
+    /// Creates a weak portal that can only be upgraded to another read-only portal.
+    #[inline]
+    pub fn downgrade(&self) -> WeakReadPortal<T> {
+        self.0.downgrade().pipe(WeakReadPortal)
+    }
+}
+
+impl<T: ?Sized> Clone for ReadPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> From<RwPortal<T>> for ReadPortal<T> {
+    /// Gives up the write capability of `portal`.
+    #[inline]
+    fn from(portal: RwPortal<T>) -> Self {
+        portal.pipe(Self)
+    }
+}
+
+/// A weak portal that can only be upgraded to a [`ReadPortal`],
+/// as created through [`ReadPortal::downgrade`] or [`Restrict::weak`](`super::Restrict::weak`).
+///
+/// Upgrading follows [`WeakRwPortal::try_upgrade_read`], so it still succeeds after the anchor has been sealed
+/// if its [`SealPolicy`](`super::SealPolicy`) is [`ReadOnly`](`super::SealPolicy::ReadOnly`).
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct WeakReadPortal<T: ?Sized>(WeakRwPortal<T>);
+
+impl<T: ?Sized> WeakReadPortal<T> {
+    /// Upgrades this weak portal iff the anchor still admits readers.
+    #[inline]
+    #[must_use]
+    pub fn try_upgrade(&self) -> Option<ReadPortal<T>> {
+        self.0.try_upgrade_read().ok()
+    }
+
+    /// Upgrades this weak portal, reporting why that isn't possible otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Like [`WeakRwPortal::try_upgrade_read`].
+    #[inline]
+    pub fn try_upgrade_checked(&self) -> Result<ReadPortal<T>, UpgradeError> {
+        self.0.try_upgrade_read()
+    }
+
+    /// Checks whether the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.is_alive()
+    }
+}
+
+impl<T: ?Sized> Clone for WeakReadPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: ?Sized> From<WeakRwPortal<T>> for WeakReadPortal<T> {
+    /// Gives up the write capability of `portal`.
+    #[inline]
+    fn from(portal: WeakRwPortal<T>) -> Self {
+        portal.pipe(Self)
+    }
+}
+
+/// The only portal that can write, as split off through [`RwPortal::split`].
+/// Not `Clone`, so that the write capability can be handed to exactly one owner.
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct WritePortal<T: ?Sized>(RwPortal<T>);
+
+impl<T: ?Sized> WritePortal<T> {
+    /// Acquires a read guard, blocking while the target is locked for writing.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read<'a>(&'a self) -> impl AsRef<T> + HeldFor + ToArc<T> + 'a {
+        self.0.read()
+    }
+
+    /// Acquires a write guard, blocking while the target is locked.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write<'a>(
+        &'a self,
+    ) -> impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a {
+        self.0.write()
+    }
+
+    /// Acquires a read guard iff that's possible without blocking.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_read<'a>(&'a self) -> Option<impl AsRef<T> + HeldFor + ToArc<T> + 'a> {
+        self.0.try_read()
+    }
+
+    /// Acquires a write guard iff that's possible without blocking.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_write<'a>(
+        &'a self,
+    ) -> Option<impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + 'a> {
+        self.0.try_write()
+    }
+
+    /// Creates another read-only portal associated with the same anchor.
+    #[inline]
+    pub fn reader(&self) -> ReadPortal<T> {
+        self.0.clone().pipe(ReadPortal)
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Splits this portal into a read half that can be cloned freely and a write half that can't,
+    /// so that only the owner of the [`WritePortal`] can write through it.
+    ///
+    /// Other `RwPortal`s to the same anchor can still write, so split the first one and don't keep the anchor around to create more.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::RwAnchor, std::thread};
+    ///
+    /// let mut log = Vec::new();
+    /// let anchor = RwAnchor::new(&mut log);
+    /// let (reader, writer) = anchor.portal().split();
+    ///
+    /// thread::spawn(move || writer.write().push("written")).join().unwrap();
+    /// let readers = vec![reader.clone(), reader];
+    /// assert!(readers.iter().all(|reader| reader.read().len() == 1));
+    ///
+    /// drop(readers);
+    /// drop(anchor);
+    /// assert_eq!(log, ["written"]);
+    /// ```
+    #[inline]
+    pub fn split(self) -> (ReadPortal<T>, WritePortal<T>) {
+        (self.clone().pipe(ReadPortal), self.pipe(WritePortal))
+    }
+}
@@ -0,0 +1,186 @@
+//! Per-anchor acquisition counters, kept only with the `stats` feature.
+//! Without it, `Counters` is zero-sized and all operations are no-ops.
+
+use std::sync::{LockResult, TryLockResult};
+#[cfg(feature = "stats")]
+use {
+    super::{Anchor, AtomicUsize, Ordering, RwAnchor, Shared, WAnchor},
+    std::sync::{Arc, TryLockError},
+};
+
+/// Counts the guards acquired through an anchor's portals.
+#[derive(Debug, Default)]
+pub(super) struct Counters {
+    /// Shared guards acquired.
+    #[cfg(feature = "stats")]
+    reads: AtomicUsize,
+
+    /// Exclusive guards acquired.
+    #[cfg(feature = "stats")]
+    writes: AtomicUsize,
+
+    /// Acquisition attempts that found the target locked.
+    #[cfg(feature = "stats")]
+    contended: AtomicUsize,
+}
+
+/// A snapshot of an anchor's portal counts and acquisition statistics, as returned by the threadsafe anchors' `stats` methods.
+/// The fields are read separately, so they may be slightly inconsistent with each other while the anchor is in use.
+///
+/// Only available with the `stats` feature.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct AnchorStats {
+    /// The number of (strong) portals.
+    pub strong: usize,
+
+    /// The number of weak portals.
+    pub weak: usize,
+
+    /// How many read guards were acquired through the anchor's portals so far.
+    pub reads_acquired: usize,
+
+    /// How many write (or mutex) guards were acquired through the anchor's portals so far.
+    pub writes_acquired: usize,
+
+    /// How many acquisitions found the target locked, whether they then waited or, like each failed `try_…` call, gave up.
+    pub contended_acquires: usize,
+
+    /// Whether the anchor is currently poisoned.
+    pub poisoned: bool,
+}
+
+#[cfg(feature = "stats")]
+impl Counters {
+    /// Counts a guard that was just acquired.
+    #[inline]
+    pub(super) fn acquired(&self, exclusive: bool) {
+        if exclusive { &self.writes } else { &self.reads }.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts an attempt that found the target locked.
+    #[inline]
+    pub(super) fn contended(&self) {
+        self.contended.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Tries `try_lock` first to count contention, then falls back to `lock` if that would block.
+    pub(super) fn contend<G>(
+        &self,
+        try_lock: impl FnOnce() -> TryLockResult<G>,
+        lock: impl FnOnce() -> LockResult<G>,
+    ) -> LockResult<G> {
+        match try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(error)) => Err(error),
+            Err(TryLockError::WouldBlock) => {
+                self.contended();
+                lock()
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+impl Counters {
+    /// Counts a guard that was just acquired.
+    /// Only with the `stats` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn acquired(&self, _: bool) {}
+
+    /// Counts an attempt that found the target locked.
+    /// Only with the `stats` feature.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) const fn contended(&self) {}
+
+    /// Calls `lock`.
+    /// With the `stats` feature, tries `try_lock` first to count contention.
+    #[inline]
+    #[allow(clippy::unused_self)]
+    pub(super) fn contend<G>(
+        &self,
+        _: impl FnOnce() -> TryLockResult<G>,
+        lock: impl FnOnce() -> LockResult<G>,
+    ) -> LockResult<G> {
+        lock()
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<L, A> Shared<L, A> {
+    /// Takes a snapshot of `shared`'s counts, which is poisoned as given.
+    pub(super) fn stats(shared: &Arc<Self>, poisoned: bool) -> AnchorStats {
+        AnchorStats {
+            strong: shared.strong_portal_count(),
+            // Excluding the diagnostics registry's and the anchor group's.
+            weak: Arc::weak_count(shared).saturating_sub(
+                usize::from(cfg!(feature = "diagnostics")) + usize::from(shared.idle.is_grouped()),
+            ),
+            reads_acquired: shared.counters.reads.load(Ordering::Relaxed),
+            writes_acquired: shared.counters.writes.load(Ordering::Relaxed),
+            contended_acquires: shared.counters.contended.load(Ordering::Relaxed),
+            poisoned,
+        }
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Takes a snapshot of this anchor's portal counts and acquisition statistics, e.g. for a dashboard.
+    /// Portals to immutable anchors don't acquire guards, so only the portal counts change here.
+    ///
+    /// Only available with the `stats` feature.
+    #[must_use]
+    pub fn stats(&self) -> AnchorStats {
+        Shared::stats(&self.reference, false)
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Takes a snapshot of this anchor's portal counts and acquisition statistics, e.g. for a dashboard.
+    ///
+    /// Only available with the `stats` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut x = "Scoped".to_owned();
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    /// let weak_portal = anchor.weak_portal();
+    ///
+    /// portal.write().push('!');
+    /// let guard = portal.read();
+    /// assert!(portal.try_write().is_none());
+    /// drop(guard);
+    ///
+    /// let stats = anchor.stats();
+    /// assert_eq!((stats.strong, stats.weak), (1, 1));
+    /// assert_eq!((stats.reads_acquired, stats.writes_acquired), (1, 1));
+    /// assert_eq!(stats.contended_acquires, 1);
+    /// assert!(!stats.poisoned);
+    /// # drop(portal);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> AnchorStats {
+        Shared::stats(&self.reference, self.reference.target.is_poisoned())
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Takes a snapshot of this anchor's portal counts and acquisition statistics, e.g. for a dashboard.
+    /// See [`RwAnchor::stats`].
+    ///
+    /// Only available with the `stats` feature.
+    #[must_use]
+    pub fn stats(&self) -> AnchorStats {
+        Shared::stats(&self.reference, self.reference.target.is_poisoned())
+    }
+}
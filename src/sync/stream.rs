@@ -0,0 +1,99 @@
+//! Consuming anchored streams through portals.
+//! Only available with the `stream` feature.
+
+use {
+    super::WPortal,
+    futures_core::Stream,
+    std::{
+        pin::Pin,
+        task::{Context, Poll},
+    },
+    wyz::pipe::*,
+};
+
+/// Adapts a [`WPortal`] to an anchored [`Stream`](https://docs.rs/futures-core/0.3/futures_core/stream/trait.Stream.html) into a stream itself,
+/// so that it can be consumed by combinators in another task.
+///
+/// Each `poll_next` locks the portal only for the duration of the call, so the anchored stream remains accessible between polls.
+/// The target must be `Unpin`, since it's released again when the anchor is dropped.
+///
+/// # Panics
+///
+/// Polling panics if the anchor is poisoned.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     futures_core::Stream,
+///     ref_portals::sync::{StreamPortal, WAnchor},
+///     std::{
+///         pin::Pin,
+///         task::{Context, Poll, Waker},
+///     },
+/// };
+///
+/// struct Countdown(u8);
+/// impl Stream for Countdown {
+///     type Item = u8;
+///     fn poll_next(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<u8>> {
+///         let next = self.0.checked_sub(1);
+///         if let Some(next) = next {
+///             self.0 = next;
+///         }
+///         Poll::Ready(next)
+///     }
+/// }
+///
+/// let mut countdown = Countdown(2);
+/// let anchor = WAnchor::new(&mut countdown);
+///
+/// // This could be moved into a task instead.
+/// let mut stream = StreamPortal::new(anchor.portal());
+/// let mut cx = Context::from_waker(Waker::noop());
+/// assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(1)));
+/// assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(Some(0)));
+/// assert_eq!(Pin::new(&mut stream).poll_next(&mut cx), Poll::Ready(None));
+/// drop(stream);
+///
+/// drop(anchor);
+/// assert_eq!(countdown.0, 0);
+/// ```
+#[derive(Debug)]
+#[must_use]
+#[repr(transparent)]
+pub struct StreamPortal<T: ?Sized>(WPortal<T>);
+
+impl<T: ?Sized> StreamPortal<T> {
+    #[inline]
+    pub const fn new(portal: WPortal<T>) -> Self {
+        Self(portal)
+    }
+
+    /// Returns the wrapped portal.
+    #[inline]
+    pub fn into_inner(self) -> WPortal<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> From<WPortal<T>> for StreamPortal<T> {
+    #[inline]
+    fn from(portal: WPortal<T>) -> Self {
+        portal.pipe(Self)
+    }
+}
+
+impl<T: ?Sized + Stream + Unpin> Stream for StreamPortal<T> {
+    type Item = T::Item;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut *self.0.lock()).poll_next(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.lock().size_hint()
+    }
+}
@@ -0,0 +1,256 @@
+//! Lock-free, read-mostly anchors backed by [`arc-swap`](https://crates.io/crates/arc-swap).
+
+use {
+    super::{Condvar, Mutex, MutexGuard, SSNonNull},
+    crate::{anchor_dropped_panic, Peek},
+    arc_swap::{ArcSwapOption, Guard},
+    std::{
+        fmt::{self, Debug, Formatter},
+        marker::PhantomData,
+        ops::Deref,
+        sync::{Arc, PoisonError},
+    },
+    wyz::pipe::*,
+};
+
+/// State shared between a [`SwapAnchor`] and its portals.
+struct SwapShared<T: ?Sized> {
+    /// The currently stored target, or `None` once the anchor has been dropped.
+    current: ArcSwapOption<Stored<T>>,
+
+    /// Tracks the stored targets that are still referenced.
+    stored: Arc<Live>,
+}
+
+/// A target stored in a [`SwapAnchor`].
+/// Dropped once it has been replaced and the last [`Snapshot`] of it has been released.
+struct Stored<T: ?Sized> {
+    /// Valid until the anchor has been dropped, which waits for this.
+    target: SSNonNull<T>,
+
+    /// Notified when this is dropped.
+    live: Arc<Live>,
+}
+
+/// Counts the [`Stored`] targets that haven't been dropped yet.
+#[derive(Debug)]
+struct Live {
+    /// The number of targets.
+    count: Mutex<usize>,
+
+    /// Notified whenever `count` reaches zero.
+    released: Condvar,
+}
+
+/// A threadsafe immutable anchor whose target can be replaced, and whose portals read it without locking.
+/// This suits configuration that is read at high rates and updated rarely.
+///
+/// Portals don't keep the target alive. Instead, dropping the anchor waits for [`Snapshot`]s that are still held,
+/// after which loading through its portals panics.
+///
+/// Only available with the `swap` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use {ref_portals::sync::SwapAnchor, std::thread};
+///
+/// let defaults = "defaults".to_owned();
+/// let updated = "updated".to_owned();
+///
+/// let anchor = SwapAnchor::new(&defaults);
+/// let portal = anchor.portal();
+/// let snapshot = portal.load();
+///
+/// anchor.store(&updated);
+/// assert_eq!(*snapshot, "defaults"); // Snapshots don't change.
+/// drop(snapshot);
+///
+/// thread::spawn(move || assert_eq!(*portal.load(), "updated"))
+///     .join()
+///     .unwrap();
+/// ```
+#[must_use]
+pub struct SwapAnchor<'a, T: ?Sized> {
+    /// Shared with the portals.
+    reference: Arc<SwapShared<T>>,
+
+    /// Act as sharing borrower.
+    _phantom: PhantomData<&'a T>,
+}
+
+/// A threadsafe portal that reads a [`SwapAnchor`]'s current target without locking.
+///
+/// Only available with the `swap` feature.
+#[must_use]
+#[repr(transparent)]
+pub struct SwapPortal<T: ?Sized>(Arc<SwapShared<T>>);
+
+/// A read guard of the target a [`SwapAnchor`] stored when it was loaded through a [`SwapPortal`].
+/// It doesn't change when the anchor stores another target.
+///
+/// Dropping the anchor waits for this to be released, so don't hold on to it for long.
+///
+/// Only available with the `swap` feature.
+#[must_use]
+pub struct Snapshot<T: ?Sized>(Guard<Option<Arc<Stored<T>>>>);
+
+impl Live {
+    /// Locks the count, ignoring poison.
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        self.count.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: ?Sized> Stored<T> {
+    /// Creates a stored target and counts it as live.
+    fn new(reference: &T, live: &Arc<Live>) -> Arc<Self> {
+        *live.lock() += 1;
+        Arc::new(Self {
+            target: SSNonNull::new(reference.into()),
+            live: Arc::clone(live),
+        })
+    }
+}
+
+impl<T: ?Sized> Drop for Stored<T> {
+    fn drop(&mut self) {
+        let mut count = self.live.lock();
+        *count -= 1;
+        if *count == 0 {
+            self.live.released.notify_all();
+        }
+    }
+}
+
+impl<'a, T: ?Sized> SwapAnchor<'a, T> {
+    #[inline]
+    pub fn new(reference: &'a T) -> Self {
+        let live = Arc::new(Live {
+            count: Mutex::new(0),
+            released: Condvar::new(),
+        });
+        Self {
+            reference: SwapShared {
+                current: ArcSwapOption::new(Some(Stored::new(reference, &live))),
+                stored: live,
+            }
+            .pipe(Arc::new),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    #[inline]
+    pub fn portal(&self) -> SwapPortal<T> {
+        self.reference.pipe_ref(Arc::clone).pipe(SwapPortal)
+    }
+
+    /// Replaces the target, so that later loads through portals return `reference`.
+    /// Snapshots of the previous target stay valid until they are released.
+    pub fn store(&self, reference: &'a T) {
+        self.reference
+            .current
+            .store(Some(Stored::new(reference, &self.reference.stored)));
+    }
+
+    /// Returns the current target.
+    pub fn load(&self) -> Snapshot<T> {
+        Snapshot(self.reference.current.load())
+    }
+}
+
+impl<'a, T: ?Sized> Drop for SwapAnchor<'a, T> {
+    /// Waits until all [`Snapshot`]s loaded through associated portals have been released.
+    ///
+    /// Dropping the anchor on a thread that holds a snapshot deadlocks.
+    fn drop(&mut self) {
+        // Returning the previous target settles the references that snapshots borrowed without counting them.
+        drop(self.reference.current.swap(None));
+        let live = &self.reference.stored;
+        let mut count = live.lock();
+        while *count > 0 {
+            count = live
+                .released
+                .wait(count)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+}
+
+impl<T: ?Sized> SwapPortal<T> {
+    /// Loads the anchor's current target without locking.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been dropped.
+    #[inline]
+    #[track_caller]
+    pub fn load(&self) -> Snapshot<T> {
+        self.try_load().unwrap_or_else(|| anchor_dropped_panic())
+    }
+
+    /// Loads the anchor's current target without locking, iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn try_load(&self) -> Option<Snapshot<T>> {
+        let guard = self.0.current.load();
+        guard.is_some().then(|| Snapshot(guard))
+    }
+
+    /// Returns `true` iff the anchor still exists.
+    #[inline]
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.0.current.load().is_some()
+    }
+}
+
+impl<T: ?Sized> Clone for SwapPortal<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        self.0.pipe_ref(Arc::clone).pipe(Self)
+    }
+}
+
+impl<T: ?Sized> Deref for Snapshot<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        let stored = self
+            .0
+            .as_ref()
+            .unwrap_or_else(|| unreachable!("Only loaded while anchored"));
+        unsafe {
+            //SAFETY: The anchor is only dropped once this has been released.
+            stored.target.as_ref()
+        }
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for Snapshot<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<'a, T: ?Sized + Debug> Debug for SwapAnchor<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SwapAnchor").field(&&*self.load()).finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for SwapPortal<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let snapshot = self.try_load();
+        let target = snapshot.as_deref().map_or(Peek::Expired, Peek::Value);
+        f.debug_tuple("SwapPortal").field(&target).finish()
+    }
+}
+
+impl<T: ?Sized + Debug> Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Snapshot").field(&&**self).finish()
+    }
+}
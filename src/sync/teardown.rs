@@ -0,0 +1,193 @@
+//! Teardown ordering between threadsafe anchors.
+
+use {
+    super::{Anchor, AtomicBool, Idle, Mutex, Ordering, RwAnchor, WAnchor},
+    std::{
+        any,
+        fmt::{self, Display, Formatter},
+        mem,
+        sync::{Arc, PoisonError},
+    },
+};
+
+/// Tracks an anchor for the anchor it depends on.
+#[derive(Debug)]
+struct Dependent {
+    /// Cleared once the dependent anchor is dropped.
+    anchored: AtomicBool,
+
+    /// The dependent anchor's idle state, sealed if the anchor it depends on is dropped first.
+    idle: Arc<Idle>,
+
+    /// The dependent anchor's label, if any.
+    label: Option<&'static str>,
+
+    /// The dependent anchor's target type.
+    type_name: &'static str,
+}
+
+/// The anchors that depend on a threadsafe anchor.
+#[derive(Debug)]
+pub struct Dependents(Mutex<Vec<Arc<Dependent>>>);
+
+impl Dependents {
+    /// Creates an instance without dependents.
+    #[allow(clippy::missing_const_for_fn)] // Not with loom.
+    pub(super) fn new() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+
+    /// Seals the dependents that haven't been dropped yet, so that their weak portals can't be upgraded anymore.
+    /// Returns them iff there are any.
+    pub(super) fn seal_remaining(&self) -> Option<Remaining> {
+        let remaining = mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+            .into_iter()
+            .filter(|dependent| dependent.anchored.load(Ordering::Acquire))
+            .collect::<Vec<_>>();
+        for dependent in &remaining {
+            dependent.idle.seal(None);
+        }
+        if remaining.is_empty() {
+            None
+        } else {
+            Some(Remaining(remaining))
+        }
+    }
+
+    /// Returns the number of dependents that haven't been dropped yet.
+    pub(super) fn remaining(&self) -> usize {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|dependent| dependent.anchored.load(Ordering::Acquire))
+            .count()
+    }
+
+    /// Adds `dependent`, dropping those that are gone already.
+    fn push(&self, dependent: Arc<Dependent>) {
+        let mut dependents = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        dependents.retain(|dependent| dependent.anchored.load(Ordering::Acquire));
+        dependents.push(dependent);
+    }
+}
+
+/// Dependents that were still anchored when the anchor they depend on was dropped.
+/// Lists them when displayed, to be appended to the panic message.
+pub(super) struct Remaining(Vec<Arc<Dependent>>);
+
+impl Display for Remaining {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for dependent in &self.0 {
+            write!(f, "\n\nDependent anchor still exists: [")?;
+            if let Some(label) = dependent.label {
+                write!(f, "label: {label:?}, ")?;
+            }
+            write!(f, "anchored type: `{}`]", dependent.type_name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Implemented by the threadsafe anchors, so that other anchors can [depend on](struct.Anchor.html#method.depends_on) them.
+pub trait TeardownParent {
+    /// The anchors that depend on this one.
+    #[doc(hidden)]
+    fn dependents(&self) -> &Dependents;
+}
+
+impl<'a, T: ?Sized> TeardownParent for Anchor<'a, T> {
+    #[inline]
+    fn dependents(&self) -> &Dependents {
+        &self.dependents
+    }
+}
+
+impl<'a, T: ?Sized> TeardownParent for RwAnchor<'a, T> {
+    #[inline]
+    fn dependents(&self) -> &Dependents {
+        &self.dependents
+    }
+}
+
+impl<'a, T: ?Sized> TeardownParent for WAnchor<'a, T> {
+    #[inline]
+    fn dependents(&self) -> &Dependents {
+        &self.dependents
+    }
+}
+
+/// Registers a dependent anchor with `idle` state, `label` and target type `T` with `parent`.
+/// Returns the callback to invoke once the dependent anchor is dropped.
+fn depend<T: ?Sized>(
+    idle: &Arc<Idle>,
+    label: Option<&'static str>,
+    parent: &impl TeardownParent,
+) -> impl FnOnce() + Send + 'static {
+    let dependent = Arc::new(Dependent {
+        anchored: AtomicBool::new(true),
+        idle: Arc::clone(idle),
+        label,
+        type_name: any::type_name::<T>(),
+    });
+    parent.dependents().push(Arc::clone(&dependent));
+    move || dependent.anchored.store(false, Ordering::Release)
+}
+
+impl<'a, T: ?Sized> Anchor<'a, T> {
+    /// Declares that this anchor must be dropped before `parent`, e.g. because its target is owned by `parent`'s.
+    ///
+    /// # Panics
+    ///
+    /// Dropping `parent` while this anchor still exists panics, naming this anchor, after sealing it so that its weak portals can't be upgraded anymore.
+    /// This happens after `parent` has released its reference, like its other panics on drop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::Anchor;
+    ///
+    /// let x = "Scoped".to_owned();
+    /// let y = 1;
+    /// let parent = Anchor::new(&x);
+    /// let dependent = Anchor::named("dependent", &y);
+    /// dependent.depends_on(&parent);
+    /// let weak_portal = dependent.weak_portal();
+    ///
+    /// assert_panic!(
+    ///     drop(parent),
+    ///     String,
+    ///     starts with "Anchor dropped before its dependents",
+    /// );
+    /// assert!(weak_portal.try_upgrade().is_none());
+    /// # drop(dependent);
+    /// ```
+    pub fn depends_on(&self, parent: &impl TeardownParent) {
+        self.on_drop(depend::<T>(&self.reference.idle, self.label, parent));
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Declares that this anchor must be dropped before `parent`, e.g. because its target is owned by `parent`'s.
+    ///
+    /// # Panics
+    ///
+    /// Dropping `parent` while this anchor still exists panics, naming this anchor, after sealing it.
+    /// See [`Anchor::depends_on`].
+    pub fn depends_on(&self, parent: &impl TeardownParent) {
+        self.on_drop(depend::<T>(&self.reference.idle, self.label, parent));
+    }
+}
+
+impl<'a, T: ?Sized> WAnchor<'a, T> {
+    /// Declares that this anchor must be dropped before `parent`, e.g. because its target is owned by `parent`'s.
+    ///
+    /// # Panics
+    ///
+    /// Dropping `parent` while this anchor still exists panics, naming this anchor, after sealing it.
+    /// See [`Anchor::depends_on`].
+    pub fn depends_on(&self, parent: &impl TeardownParent) {
+        self.on_drop(depend::<T>(&self.reference.idle, self.label, parent));
+    }
+}
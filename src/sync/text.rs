@@ -0,0 +1,147 @@
+//! Passing anchored text to logging and formatting APIs, which often require `'static` values or must not block.
+
+use {
+    super::{peek, Portal, RwPortal, WPortal},
+    crate::Peek,
+    std::{
+        fmt::{self, Debug, Display, Formatter},
+        sync::Arc,
+    },
+};
+
+/// Displays the target of a portal without blocking, as returned by the portals' `display` methods.
+///
+/// If the target can't be read without blocking, this shows `<locked>`, `<poisoned>` or `<expired>` instead,
+/// so that formatting it in a log statement never deadlocks, even while the current thread holds a conflicting guard.
+/// This doesn't count as acquisition.
+#[must_use]
+pub struct PortalDisplay<'a, T: ?Sized>(Source<'a, T>);
+
+/// The portal a [`PortalDisplay`] reads from.
+enum Source<'a, T: ?Sized> {
+    /// Never blocks.
+    Portal(&'a Portal<T>),
+
+    /// Tried for reading.
+    Rw(&'a RwPortal<T>),
+
+    /// Tried for locking.
+    W(&'a WPortal<T>),
+}
+
+impl<'a, T: ?Sized + Display> Display for PortalDisplay<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Source::Portal(portal) => {
+                let target = if portal.0.is_anchored() {
+                    Peek::Value(&**portal)
+                } else {
+                    Peek::Expired
+                };
+                target.fmt(f)
+            }
+            Source::Rw(portal) => peek(&portal.0.target.try_read()).fmt(f),
+            Source::W(portal) => peek(&portal.0.target.try_lock()).fmt(f),
+        }
+    }
+}
+
+impl<'a, T: ?Sized> Debug for PortalDisplay<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PortalDisplay").finish_non_exhaustive()
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> Portal<T> {
+    /// Copies the anchored text into an `Arc<str>`, e.g. to pass it into a logging pipeline that requires `'static` values.
+    ///
+    /// This is an associated function because `Portal` dereferences to its target.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{Anchor, Portal},
+    ///     std::{sync::Arc, thread},
+    /// };
+    ///
+    /// let request_id = "req-42".to_owned();
+    /// let anchor = Anchor::new(&request_id);
+    /// let portal = anchor.portal();
+    ///
+    /// let snapshot: Arc<str> = Portal::to_arc_str(&portal);
+    /// drop(portal);
+    /// drop(anchor);
+    /// thread::spawn(move || assert_eq!(&*snapshot, "req-42")).join().unwrap();
+    /// ```
+    #[must_use]
+    pub fn to_arc_str(portal: &Self) -> Arc<str> {
+        Arc::from((**portal).as_ref())
+    }
+}
+
+impl<T: ?Sized + Display> Portal<T> {
+    /// Returns a [`PortalDisplay`] of the target.
+    ///
+    /// Unlike the target itself, this shows `<expired>` if the anchor was dropped anyway while panicking.
+    pub const fn display(portal: &Self) -> PortalDisplay<'_, T> {
+        PortalDisplay(Source::Portal(portal))
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> RwPortal<T> {
+    /// Copies the anchored text into an `Arc<str>` under a read guard, e.g. to pass it into a logging pipeline that requires `'static` values.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn to_arc_str(&self) -> Arc<str> {
+        Arc::from((*self.read()).as_ref())
+    }
+}
+
+impl<T: ?Sized + Display> RwPortal<T> {
+    /// Returns a [`PortalDisplay`] of the target, which tries to read it whenever it's formatted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// let mut status = "idle".to_owned();
+    /// let anchor = RwAnchor::new(&mut status);
+    /// let portal = anchor.portal();
+    /// assert_eq!(format!("status: {:>6}", portal.display()), "status:   idle");
+    ///
+    /// let mut guard = portal.write();
+    /// *guard = "busy".to_owned();
+    /// assert_eq!(format!("status: {}", portal.display()), "status: <locked>");
+    /// # drop(guard);
+    /// # drop(portal);
+    /// ```
+    pub const fn display(&self) -> PortalDisplay<'_, T> {
+        PortalDisplay(Source::Rw(self))
+    }
+}
+
+impl<T: ?Sized + AsRef<str>> WPortal<T> {
+    /// Copies the anchored text into an `Arc<str>` under a guard, e.g. to pass it into a logging pipeline that requires `'static` values.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn to_arc_str(&self) -> Arc<str> {
+        Arc::from((*self.lock()).as_ref())
+    }
+}
+
+impl<T: ?Sized + Display> WPortal<T> {
+    /// Returns a [`PortalDisplay`] of the target, which tries to lock it whenever it's formatted.
+    pub const fn display(&self) -> PortalDisplay<'_, T> {
+        PortalDisplay(Source::W(self))
+    }
+}
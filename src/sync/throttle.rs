@@ -0,0 +1,196 @@
+//! Limiting how often write guards are granted, so that bursty writers can't starve readers.
+
+use {
+    super::{AnchorField, Defuse, HandOff, HeldFor, RwAnchor, RwPortal, Shared},
+    crate::poison::Throttled,
+    std::{
+        sync::{Mutex, PoisonError},
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// How often an [`RwAnchor`] created through [`with_write_throttle`](`RwAnchor::with_write_throttle`) grants write guards.
+///
+/// Grants are counted when a write guard is acquired (or, for blocking acquisition, once the throttle let it through),
+/// so contended `try_write` attempts don't use them up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WriteThrottle {
+    /// The time it takes to regain one grant.
+    interval: Duration,
+
+    /// How many grants can be used in quick succession, at least 1.
+    burst: u32,
+}
+
+impl WriteThrottle {
+    /// Grants a write guard at most once per `interval`.
+    #[must_use]
+    pub const fn min_interval(interval: Duration) -> Self {
+        Self { interval, burst: 1 }
+    }
+
+    /// Grants up to `burst` write guards in quick succession, regaining one each `refill_every`.  
+    /// A `burst` of 0 is treated as 1.
+    #[must_use]
+    pub const fn token_bucket(burst: u32, refill_every: Duration) -> Self {
+        Self {
+            interval: refill_every,
+            burst: if burst == 0 { 1 } else { burst },
+        }
+    }
+
+    /// Returns the time it takes to regain one grant.
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns how many grants can be used in quick succession.
+    #[must_use]
+    pub const fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+/// The throttle state of an anchor, as generic cell rate algorithm.
+#[derive(Debug)]
+pub(super) struct Throttle {
+    /// The configuration.
+    policy: WriteThrottle,
+
+    /// When all grants will have been regained, or `None` before the first grant.
+    full_at: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    /// Creates a throttle with all grants available.
+    pub(super) const fn new(policy: WriteThrottle) -> Self {
+        Self {
+            policy,
+            full_at: Mutex::new(None),
+        }
+    }
+
+    /// Uses up one grant, or returns how long it will take until one is available.
+    fn try_grant(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut full_at = self.full_at.lock().unwrap_or_else(PoisonError::into_inner);
+        let current = full_at.map_or(now, |full_at| full_at.max(now));
+        let tolerance = self.policy.interval * (self.policy.burst - 1);
+        if current > now + tolerance {
+            return Err(current.duration_since(now).saturating_sub(tolerance));
+        }
+        *full_at = Some(current + self.policy.interval);
+        Ok(())
+    }
+
+    /// Returns a grant that couldn't be used after all.
+    fn refund(&self) {
+        if let Some(full_at) = &mut *self.full_at.lock().unwrap_or_else(PoisonError::into_inner) {
+            *full_at = full_at
+                .checked_sub(self.policy.interval)
+                .unwrap_or(*full_at);
+        }
+    }
+}
+
+impl<L, A> Shared<L, A> {
+    /// Blocks until the throttle, if any, grants a write guard.
+    pub(super) fn wait_for_write_grant(&self) {
+        if let Some(throttle) = self.throttle.get() {
+            while let Err(retry_after) = throttle.try_grant() {
+                thread::sleep(retry_after);
+            }
+        }
+    }
+
+    /// Uses up a write grant of the throttle, if any.
+    pub(super) fn try_write_grant(&self) -> Result<(), Throttled> {
+        self.throttle
+            .get()
+            .map_or(Ok(()), Throttle::try_grant)
+            .map_err(Throttled::new)
+    }
+
+    /// Returns a write grant taken through [`try_write_grant`](`Self::try_write_grant`) if no guard could be acquired with it.
+    pub(super) fn refund_write_grant(&self) {
+        if let Some(throttle) = self.throttle.get() {
+            throttle.refund();
+        }
+    }
+}
+
+impl<'a, T: ?Sized> RwAnchor<'a, T> {
+    /// Creates a new `RwAnchor` instance, capturing `reference` and granting write guards through its portals only as often as `throttle` allows,
+    /// e.g. to keep a bursty background job from starving readers of UI state.
+    ///
+    /// Blocking acquisition of write guards sleeps until the throttle lets it through.
+    /// `try_write` returns `None` while throttled, and [`RwPortal::try_write_throttled`] tells throttling apart from contention.
+    /// Read guards aren't affected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::{RwAnchor, WriteThrottle},
+    ///     std::time::Duration,
+    /// };
+    ///
+    /// let mut progress = 0;
+    /// let anchor = RwAnchor::with_write_throttle(
+    ///     &mut progress,
+    ///     WriteThrottle::min_interval(Duration::from_secs(60)),
+    /// );
+    /// let portal = anchor.portal();
+    ///
+    /// *portal.write() += 1;
+    /// assert!(portal.try_write().is_none());
+    /// let throttled = portal.try_write_throttled().err().unwrap();
+    /// assert!(throttled.retry_after() > Duration::from_secs(59));
+    ///
+    /// assert_eq!(*portal.read(), 1);
+    /// # drop(portal);
+    /// ```
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn with_write_throttle(reference: &'a mut T, throttle: WriteThrottle) -> Self {
+        let anchor = Self::new(reference);
+        anchor.reference.throttle.set(Throttle::new(throttle)).ok();
+        anchor
+    }
+
+    /// Returns the throttle of write guards, if this anchor was created through `with_write_throttle`.
+    #[inline]
+    #[must_use]
+    pub fn write_throttle(&self) -> Option<WriteThrottle> {
+        self.reference
+            .throttle
+            .get()
+            .map(|throttle| throttle.policy)
+    }
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Acquires a write guard iff that's possible without blocking, like [`try_write`](#method.try_write),
+    /// but returns an error instead of `None` if the anchor's [`WriteThrottle`] doesn't grant one right now.
+    ///
+    /// # Errors
+    ///
+    /// Iff the anchor was created through [`RwAnchor::with_write_throttle`] and the throttle doesn't currently grant a write guard.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn try_write_throttled<'a>(
+        &'a self,
+    ) -> Result<
+        Option<
+            impl AnchorField<Target = T> + AsRef<T> + AsMut<T> + Defuse + HeldFor + HandOff + 'a,
+        >,
+        Throttled,
+    > {
+        self.try_write_guard("sync::RwPortal::try_write_throttled")
+    }
+}
@@ -0,0 +1,172 @@
+//! Mutations that are rolled back if they fail or panic.
+
+use {
+    super::RwPortal,
+    crate::poison::Defuse,
+    std::panic::{self, AssertUnwindSafe},
+};
+
+/// Snapshots a value cheaper than cloning it, so that [`RwPortal::transact_with_backup`] can roll back failed mutations.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::sync::{Backup, RwAnchor};
+///
+/// /// An append-only log, which rolls back by truncating.
+/// struct Log(Vec<String>);
+///
+/// impl Backup for Log {
+///     type Snapshot = usize;
+///     fn backup(&self) -> usize {
+///         self.0.len()
+///     }
+///     fn restore(&mut self, len: usize) {
+///         self.0.truncate(len);
+///     }
+/// }
+///
+/// let mut log = Log(vec!["started".to_owned()]);
+/// let anchor = RwAnchor::new(&mut log);
+/// let portal = anchor.portal();
+///
+/// let result: Result<(), &str> = portal.transact_with_backup(|log| {
+///     log.0.push("step 1".to_owned());
+///     Err("step 2 failed")
+/// });
+/// assert_eq!(result, Err("step 2 failed"));
+///
+/// drop(portal);
+/// drop(anchor);
+/// assert_eq!(log.0, ["started"]);
+/// ```
+pub trait Backup {
+    /// What's needed to restore the current state.
+    type Snapshot;
+
+    /// Captures the current state.
+    fn backup(&self) -> Self::Snapshot;
+
+    /// Returns to the state captured in `snapshot`.
+    fn restore(&mut self, snapshot: Self::Snapshot);
+}
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Runs `mutate` under a write guard and keeps its changes only if it returns `Ok`.
+    /// The target is cloned beforehand and restored from that clone if `mutate` returns `Err` or panics.
+    ///
+    /// A panic in `mutate` is resumed after the rollback, but doesn't poison the anchor.
+    ///
+    /// # Errors
+    ///
+    /// Iff `mutate` returns an error, which is passed through.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned, or `mutate` panics.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use assert_panic::assert_panic;
+    /// use ref_portals::sync::RwAnchor;
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum Door {
+    ///     Closed,
+    ///     Open { visitors: u32 },
+    /// }
+    ///
+    /// let mut door = Door::Closed;
+    /// let anchor = RwAnchor::new(&mut door);
+    /// let portal = anchor.portal();
+    ///
+    /// let admitted = portal.transact(|door| {
+    ///     *door = Door::Open { visitors: 0 };
+    ///     match door {
+    ///         Door::Open { visitors } if *visitors < 10 => {
+    ///             *visitors += 1;
+    ///             Ok(*visitors)
+    ///         }
+    ///         _ => Err("Full"),
+    ///     }
+    /// });
+    /// assert_eq!(admitted, Ok(1));
+    ///
+    /// assert_panic!({
+    ///     let _ = portal.transact(|door| -> Result<(), ()> {
+    ///         *door = Door::Closed;
+    ///         panic!("Jammed")
+    ///     });
+    /// });
+    /// assert_eq!(*portal.read(), Door::Open { visitors: 1 });
+    ///
+    /// drop(portal);
+    /// drop(anchor); // Doesn't panic.
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn transact<R, E>(&self, mutate: impl FnOnce(&mut T) -> Result<R, E>) -> Result<R, E>
+    where
+        T: Clone,
+    {
+        self.transact_from(
+            "sync::RwPortal::transact",
+            mutate,
+            T::clone,
+            |target, backup| *target = backup,
+        )
+    }
+
+    /// Like [`transact`](#method.transact), but rolls back through the target's [`Backup`] implementation instead of cloning it.
+    ///
+    /// # Errors
+    ///
+    /// Iff `mutate` returns an error, which is passed through.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned, or `mutate` panics.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn transact_with_backup<R, E>(
+        &self,
+        mutate: impl FnOnce(&mut T) -> Result<R, E>,
+    ) -> Result<R, E>
+    where
+        T: Backup,
+    {
+        self.transact_from(
+            "sync::RwPortal::transact_with_backup",
+            mutate,
+            T::backup,
+            T::restore,
+        )
+    }
+
+    /// Runs `mutate` under a write guard, and calls `restore` with what `backup` returned beforehand unless it succeeds.
+    ///
+    /// If `restore` panics, the anchor is poisoned as usual.
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    fn transact_from<S, R, E>(
+        &self,
+        operation: &'static str,
+        mutate: impl FnOnce(&mut T) -> Result<R, E>,
+        backup: impl FnOnce(&T) -> S,
+        restore: impl FnOnce(&mut T, S),
+    ) -> Result<R, E> {
+        let mut guard = self.write_guard(operation);
+        let snapshot = backup(&guard);
+        match panic::catch_unwind(AssertUnwindSafe(|| mutate(&mut guard))) {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(error)) => {
+                restore(&mut guard, snapshot);
+                Err(error)
+            }
+            Err(payload) => {
+                restore(&mut guard, snapshot);
+                guard.defuse();
+                drop(guard);
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! Lending uninitialised buffers, which portals initialise element-wise before they can be read as initialised slice.
+
+use {
+    super::{peek, AtomicBool, AtomicUsize, Ordering, PortalWriteGuard, RwAnchor, RwPortal},
+    crate::{poison::Uninitialized, Peek},
+    std::{
+        fmt::{self, Debug, Formatter},
+        mem::{self, MaybeUninit},
+        ops::{Deref, DerefMut},
+        ptr,
+        sync::Arc,
+    },
+    wyz::pipe::*,
+};
+
+/// Which elements of an [`UninitAnchor`]'s buffer have been initialised.
+/// Only marked while the buffer is write-locked, and never unmarked.
+#[derive(Debug)]
+struct Initialized {
+    /// One flag per element.
+    elements: Box<[AtomicBool]>,
+
+    /// The number of set flags in `elements`.
+    count: AtomicUsize,
+}
+
+impl Initialized {
+    /// Creates an instance for a buffer of `len` uninitialised elements.
+    fn new(len: usize) -> Self {
+        Self {
+            elements: (0..len).map(|_| AtomicBool::new(false)).collect(),
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns `true` iff the element at `index` has been initialised.
+    ///
+    /// # Panics
+    ///
+    /// Iff `index` is out of bounds.
+    fn is_init(&self, index: usize) -> bool {
+        // Relaxed is enough since the buffer's lock orders this with the element's initialisation.
+        self.elements[index].load(Ordering::Relaxed)
+    }
+
+    /// Marks the element at `index` as initialised.
+    fn mark(&self, index: usize) {
+        if !self.elements[index].swap(true, Ordering::Relaxed) {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of initialised elements.
+    fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Checks that all elements have been initialised.
+    fn check(&self) -> Result<(), Uninitialized> {
+        if self.count() == self.elements.len() {
+            return Ok(());
+        }
+        self.elements
+            .iter()
+            .position(|element| !element.load(Ordering::Relaxed))
+            .map_or(Ok(()), |index| Err(Uninitialized::new(index)))
+    }
+}
+
+/// Reinterprets a fully initialised buffer.
+///
+/// # Safety
+///
+/// All elements of `buffer` must be initialised.
+const unsafe fn assume_init<T>(buffer: &[MaybeUninit<T>]) -> &[T] {
+    //SAFETY: `MaybeUninit<T>` has the same layout as `T`, and the caller vouches for initialisation.
+    &*ptr::slice_from_raw_parts(buffer.as_ptr().cast::<T>(), buffer.len())
+}
+
+/// Reinterprets a fully initialised buffer mutably.
+///
+/// # Safety
+///
+/// All elements of `buffer` must be initialised.
+const unsafe fn assume_init_mut<T>(buffer: &mut [MaybeUninit<T>]) -> &mut [T] {
+    //SAFETY: As in `assume_init`. Elements can't be de-initialised through `&mut T`.
+    &mut *ptr::slice_from_raw_parts_mut(buffer.as_mut_ptr().cast::<T>(), buffer.len())
+}
+
+/// A threadsafe mutable anchor of an uninitialised buffer, as created by [`RwAnchor::new_uninit_slice`].
+/// Its portals can only initialise elements (or overwrite initialised ones), so that it can check when the buffer is ready to be read.
+///
+/// Like with any `MaybeUninit`, initialised elements aren't dropped along with the anchor.
+#[derive(Debug)]
+#[must_use]
+pub struct UninitAnchor<'a, T> {
+    /// Checks the portals like any other `RwAnchor`.
+    anchor: RwAnchor<'a, [MaybeUninit<T>]>,
+
+    /// Shared with the portals.
+    initialized: Arc<Initialized>,
+}
+
+/// A threadsafe portal that initialises an [`UninitAnchor`]'s buffer element-wise.
+/// Acquire a guard by calling `.write()`.
+#[derive(Debug)]
+#[must_use]
+pub struct UninitPortal<T> {
+    /// Counts as strong portal of the anchor.
+    portal: RwPortal<[MaybeUninit<T>]>,
+
+    /// Shared with the anchor.
+    initialized: Arc<Initialized>,
+}
+
+/// A threadsafe mutable portal to a fully initialised buffer, as returned by the `assume_init_portal` methods.
+/// Acquire a guard by calling `.read()` or `.write()`.
+#[must_use]
+pub struct InitPortal<T>(RwPortal<[MaybeUninit<T>]>);
+
+/// A write guard of an [`UninitPortal`], through which elements of the buffer can be initialised.
+#[must_use]
+pub struct UninitWriteGuard<'a, T> {
+    /// Poisons the anchor if released while panicking, like other write guards.
+    guard: PortalWriteGuard<'a, [MaybeUninit<T>]>,
+
+    /// Marked for each initialised element.
+    initialized: &'a Initialized,
+}
+
+/// A guard of an [`InitPortal`], through which the buffer is seen as initialised.
+struct InitGuard<G>(G);
+
+impl<'a, T> RwAnchor<'a, [MaybeUninit<T>]> {
+    /// Creates a new [`UninitAnchor`] instance, capturing `reference` as buffer to initialise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::RwAnchor,
+    ///     std::{mem::MaybeUninit, thread},
+    /// };
+    ///
+    /// let mut buffer = [MaybeUninit::<u8>::uninit(); 4];
+    /// let anchor = RwAnchor::new_uninit_slice(&mut buffer);
+    ///
+    /// let portal = anchor.portal();
+    /// thread::spawn(move || {
+    ///     let mut guard = portal.write();
+    ///     let written = guard.init_from(0, b"data".iter().copied());
+    ///     assert_eq!(written, 4);
+    /// })
+    /// .join()
+    /// .unwrap();
+    ///
+    /// let portal = anchor.assume_init_portal().unwrap();
+    /// assert_eq!(&*portal.read(), b"data");
+    /// # drop(portal);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new_uninit_slice(reference: &'a mut [MaybeUninit<T>]) -> UninitAnchor<'a, T> {
+        UninitAnchor {
+            initialized: reference.len().pipe(Initialized::new).pipe(Arc::new),
+            anchor: RwAnchor::new(reference),
+        }
+    }
+}
+
+impl<'a, T> UninitAnchor<'a, T> {
+    /// Creates a portal of indefinite lifetime associated with this anchor.
+    #[inline]
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    pub fn portal(&self) -> UninitPortal<T> {
+        UninitPortal {
+            portal: self.anchor.portal(),
+            initialized: Arc::clone(&self.initialized),
+        }
+    }
+
+    /// Returns the number of elements in the buffer.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.initialized.elements.len()
+    }
+
+    /// Returns `true` iff the buffer has no elements.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of elements that have been initialised so far.
+    #[inline]
+    #[must_use]
+    pub fn initialized(&self) -> usize {
+        self.initialized.count()
+    }
+
+    /// Creates a portal that sees the buffer as initialised slice, iff all of its elements have been initialised.
+    /// [`UninitPortal`]s stay usable, since they can't de-initialise elements.
+    ///
+    /// # Errors
+    ///
+    /// Iff an element hasn't been initialised yet.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {ref_portals::sync::RwAnchor, std::mem::MaybeUninit};
+    ///
+    /// let mut buffer = [MaybeUninit::<u8>::uninit(); 2];
+    /// let anchor = RwAnchor::new_uninit_slice(&mut buffer);
+    ///
+    /// let portal = anchor.portal();
+    /// portal.write().init(1, 2);
+    /// assert_eq!(anchor.assume_init_portal().err().unwrap().index(), 0);
+    ///
+    /// portal.write().init(0, 1);
+    /// let init_portal = anchor.assume_init_portal().unwrap();
+    /// assert_eq!(&*init_portal.read(), [1, 2]);
+    /// # drop(init_portal);
+    /// # drop(portal);
+    /// ```
+    #[inline]
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    pub fn assume_init_portal(&self) -> Result<InitPortal<T>, Uninitialized> {
+        self.initialized.check()?;
+        Ok(InitPortal(self.anchor.portal()))
+    }
+}
+
+impl<T> UninitPortal<T> {
+    /// Acquires a write guard through which elements can be initialised, blocking while the buffer is locked.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write(&self) -> UninitWriteGuard<'_, T> {
+        UninitWriteGuard {
+            guard: self.portal.write_guard("sync::UninitPortal::write"),
+            initialized: &self.initialized,
+        }
+    }
+
+    /// Creates a portal that sees the buffer as initialised slice, iff all of its elements have been initialised.
+    /// See [`UninitAnchor::assume_init_portal`].
+    ///
+    /// # Errors
+    ///
+    /// Iff an element hasn't been initialised yet.
+    #[inline]
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    pub fn assume_init_portal(&self) -> Result<InitPortal<T>, Uninitialized> {
+        self.initialized.check()?;
+        Ok(InitPortal(self.portal.clone()))
+    }
+}
+
+impl<T> Clone for UninitPortal<T> {
+    #[inline]
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    fn clone(&self) -> Self {
+        Self {
+            portal: self.portal.clone(),
+            initialized: Arc::clone(&self.initialized),
+        }
+    }
+}
+
+impl<'a, T> UninitWriteGuard<'a, T> {
+    /// Returns the number of elements in the buffer.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.guard.len()
+    }
+
+    /// Returns `true` iff the buffer has no elements.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.guard.is_empty()
+    }
+
+    /// Returns `true` iff the element at `index` has been initialised.
+    ///
+    /// # Panics
+    ///
+    /// Iff `index` is out of bounds.
+    #[inline]
+    #[must_use]
+    pub fn is_init(&self, index: usize) -> bool {
+        self.initialized.is_init(index)
+    }
+
+    /// Initialises the element at `index` with `value`, or replaces it (dropping the previous value) if it was initialised already.
+    ///
+    /// # Panics
+    ///
+    /// Iff `index` is out of bounds.
+    pub fn init(&mut self, index: usize, value: T) -> &mut T {
+        let element = &mut self.guard[index];
+        if self.initialized.is_init(index) {
+            let element = unsafe {
+                //SAFETY: Initialised, as marked.
+                &mut *element.as_mut_ptr()
+            };
+            // The new value is in place before the previous one's `Drop` runs, which may panic.
+            drop(mem::replace(element, value));
+            element
+        } else {
+            element.write(value);
+            self.initialized.mark(index);
+            unsafe {
+                //SAFETY: Just initialised.
+                &mut *element.as_mut_ptr()
+            }
+        }
+    }
+
+    /// Initialises elements starting at `start` with `values`, until either runs out.
+    /// Returns the number of elements written.
+    ///
+    /// # Panics
+    ///
+    /// Iff `start` is greater than the buffer's length.
+    pub fn init_from(&mut self, start: usize, values: impl IntoIterator<Item = T>) -> usize {
+        assert!(start <= self.len(), "`start` out of bounds");
+        let mut written = 0;
+        for (index, value) in (start..self.len()).zip(values) {
+            self.init(index, value);
+            written += 1;
+        }
+        written
+    }
+
+    /// Returns the element at `index` iff it has been initialised.
+    ///
+    /// # Panics
+    ///
+    /// Iff `index` is out of bounds.
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.initialized.is_init(index).then(|| unsafe {
+            //SAFETY: Initialised, as marked.
+            &*self.guard[index].as_ptr()
+        })
+    }
+
+    /// Returns the element at `index` mutably iff it has been initialised.
+    ///
+    /// # Panics
+    ///
+    /// Iff `index` is out of bounds.
+    #[must_use]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if self.initialized.is_init(index) {
+            Some(unsafe {
+                //SAFETY: Initialised, as marked.
+                &mut *self.guard[index].as_mut_ptr()
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> Debug for UninitWriteGuard<'a, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UninitWriteGuard")
+            .field("len", &self.len())
+            .field("initialized", &self.initialized.count())
+            .finish()
+    }
+}
+
+impl<T> InitPortal<T> {
+    /// Acquires a read guard, blocking while the buffer is locked for writing.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn read<'a>(&'a self) -> impl Deref<Target = [T]> + AsRef<[T]> + 'a {
+        InitGuard(self.0.read())
+    }
+
+    /// Acquires a write guard, blocking while the buffer is locked.
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned.
+    #[inline]
+    #[must_use]
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write<'a>(&'a self) -> impl DerefMut<Target = [T]> + AsRef<[T]> + AsMut<[T]> + 'a {
+        InitGuard(self.0.write())
+    }
+}
+
+impl<T> Clone for InitPortal<T> {
+    #[inline]
+    #[cfg_attr(feature = "backtrace", track_caller)]
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Debug> Debug for InitPortal<T> {
+    /// Shows the buffer iff it can be read without blocking, otherwise `<locked>` or `<poisoned>`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let guard = (self.0).0.target.try_read();
+        let target = match peek(&guard) {
+            Peek::Value(buffer) => Peek::Value(unsafe {
+                //SAFETY: Initialised, since this portal exists.
+                assume_init(buffer)
+            }),
+            Peek::Locked => Peek::Locked,
+            Peek::Poisoned => Peek::Poisoned,
+            Peek::Expired => Peek::Expired,
+        };
+        f.debug_tuple("InitPortal").field(&target).finish()
+    }
+}
+
+impl<T, G: Deref<Target = [MaybeUninit<T>]>> Deref for InitGuard<G> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &[T] {
+        unsafe {
+            //SAFETY: Only created through an `InitPortal`, which exists only once the buffer was initialised.
+            assume_init(&self.0)
+        }
+    }
+}
+
+impl<T, G: DerefMut<Target = [MaybeUninit<T>]>> DerefMut for InitGuard<G> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe {
+            //SAFETY: As in `deref`.
+            assume_init_mut(&mut self.0)
+        }
+    }
+}
+
+impl<T, G: Deref<Target = [MaybeUninit<T>]>> AsRef<[T]> for InitGuard<G> {
+    #[inline]
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, G: DerefMut<Target = [MaybeUninit<T>]>> AsMut<[T]> for InitGuard<G> {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
@@ -0,0 +1,82 @@
+//! Long writes that step aside for blocked readers.
+
+use {
+    super::{Ordering, RwPortal},
+    crate::watchdog::check_blocking,
+    std::{
+        ops::ControlFlow,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+impl<T: ?Sized> RwPortal<T> {
+    /// Calls `f` with the target repeatedly until it breaks, e.g. to rebuild a large collection incrementally.
+    /// Once the write lock has been held for `budget` and readers are blocked on it, it's released between two calls,
+    /// then re-acquired after the readers got through or, at the latest, after another `budget` has passed.
+    ///
+    /// Readers only count as blocked while waiting in [`read`](#method.read) or [`read_poisonable`](#method.read_poisonable) (or their variants on other portal types).
+    ///
+    /// # Panics
+    ///
+    /// Iff the anchor has been poisoned. If `f` panics, the anchor is poisoned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::sync::RwAnchor,
+    ///     std::{ops::ControlFlow, thread, time::Duration},
+    /// };
+    ///
+    /// let mut squares = Vec::new();
+    /// let anchor = RwAnchor::new(&mut squares);
+    /// let portal = anchor.portal();
+    ///
+    /// thread::scope(|scope| {
+    ///     scope.spawn(|| {
+    ///         portal.write_yielding(Duration::from_micros(100), |squares| {
+    ///             squares.push(squares.len() * squares.len());
+    ///             if squares.len() < 10_000 {
+    ///                 ControlFlow::Continue(())
+    ///             } else {
+    ///                 ControlFlow::Break(())
+    ///             }
+    ///         })
+    ///     });
+    ///     // Not blocked for the whole rebuild.
+    ///     let partial = portal.read().len();
+    ///     assert!(partial <= 10_000);
+    /// });
+    ///
+    /// drop(portal);
+    /// drop(anchor);
+    /// assert_eq!(squares.len(), 10_000);
+    /// assert_eq!(squares[9_999], 9_999 * 9_999);
+    /// ```
+    #[cfg_attr(feature = "watchdog", track_caller)]
+    pub fn write_yielding(&self, budget: Duration, mut f: impl FnMut(&mut T) -> ControlFlow<()>) {
+        check_blocking("sync::RwPortal::write_yielding", &*self.0, true);
+        loop {
+            let mut guard = self.write();
+            let acquired = Instant::now();
+            loop {
+                if f(&mut guard).is_break() {
+                    return;
+                }
+                if acquired.elapsed() >= budget
+                    && self.0.readers_waiting.load(Ordering::Relaxed) > 0
+                {
+                    break;
+                }
+            }
+            drop(guard);
+
+            let released = Instant::now();
+            while self.0.readers_waiting.load(Ordering::Relaxed) > 0 && released.elapsed() < budget
+            {
+                thread::yield_now();
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Handing anchored bytes to APIs based on [`bytes::Bytes`](https://docs.rs/bytes/1/bytes/struct.Bytes.html) without copying them.
+//! Only available with the `bytes` feature.
+
+use {
+    super::{Anchor, Portal},
+    bytes::Bytes,
+    std::ops::Deref,
+};
+
+/// Keeps the bytes viewed by a [`Bytes`] anchored, counted as strong portal until its last clone is dropped.
+/// Only [`BytesAnchor`] creates these, since [`Bytes`] doesn't check whether the anchor still exists before reading.
+struct Owner(Portal<[u8]>);
+
+impl AsRef<[u8]> for Owner {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// An [`Anchor`] to a byte buffer that, when dropped, waits until all [`Bytes`](https://docs.rs/bytes/1/bytes/struct.Bytes.html) views of it have been dropped,
+/// e.g. so that anchored network buffers can flow into codecs as `Bytes` without copying them.
+/// Dereferences to that anchor, so portals are created and checked as usual.
+///
+/// Dropping it while the current thread holds one of its views or portals deadlocks.
+///
+/// # Example
+///
+/// ```rust
+/// use {bytes::Bytes, ref_portals::sync::BytesAnchor, std::thread};
+///
+/// let buffer = b"GET / HTTP/1.1\r\n".to_vec();
+/// let anchor = BytesAnchor::new(&buffer);
+///
+/// let request: Bytes = anchor.bytes();
+/// let method = request.slice(..3);
+/// let worker = thread::spawn(move || assert_eq!(method, "GET"));
+///
+/// drop(request);
+/// drop(anchor); // Waits for the worker to drop `method`.
+/// worker.join().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct BytesAnchor<'a> {
+    /// Dropped once idle.
+    anchor: Anchor<'a, [u8]>,
+}
+
+impl<'a> BytesAnchor<'a> {
+    /// Anchors `buffer`.
+    #[must_use]
+    #[cfg_attr(feature = "diagnostics", track_caller)]
+    pub fn new(buffer: &'a [u8]) -> Self {
+        Self {
+            anchor: Anchor::new(buffer),
+        }
+    }
+
+    /// Creates a [`Bytes`](https://docs.rs/bytes/1/bytes/struct.Bytes.html) view of the whole buffer, without copying it.
+    /// Slices and clones of it refer to the buffer too, and keep the anchor in use until they're dropped.
+    #[must_use]
+    pub fn bytes(&self) -> Bytes {
+        Bytes::from_owner(Owner(self.anchor.portal()))
+    }
+}
+
+impl<'a> Deref for BytesAnchor<'a> {
+    type Target = Anchor<'a, [u8]>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.anchor
+    }
+}
+
+impl<'a> Drop for BytesAnchor<'a> {
+    fn drop(&mut self) {
+        self.anchor.wait_idle();
+    }
+}
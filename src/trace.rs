@@ -0,0 +1,173 @@
+//! Portal and guard creation backtraces, captured only with the `backtrace` feature.
+//! Without it, all types here are zero-sized and all operations are no-ops.
+
+#[cfg(feature = "backtrace")]
+use std::{
+    backtrace::Backtrace,
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+use std::{
+    fmt::{self, Display, Formatter},
+    marker::PhantomData,
+};
+
+/// The creation backtraces of an anchor's live (strong) portals or guards.
+#[derive(Debug, Default)]
+pub(crate) struct Traces {
+    /// The next portal key and the backtraces by portal key.
+    #[cfg(feature = "backtrace")]
+    live: Mutex<(u64, BTreeMap<u64, Backtrace>)>,
+}
+
+/// Identifies a portal's backtrace in its anchor's `Traces`.
+#[derive(Debug)]
+pub(crate) struct TraceId {
+    /// The key in `Traces::live`.
+    #[cfg(feature = "backtrace")]
+    id: u64,
+}
+
+/// Forgets a guard's backtrace when dropped.
+#[derive(Debug)]
+pub(crate) struct Traced<'a> {
+    /// Where the backtrace is recorded.
+    #[cfg(feature = "backtrace")]
+    traces: &'a Traces,
+
+    /// Identifies the backtrace in `traces`.
+    #[cfg(feature = "backtrace")]
+    id: TraceId,
+
+    /// Ties this to the `Traces` without the `backtrace` feature.
+    _phantom: PhantomData<&'a Traces>,
+}
+
+/// Lists an anchor's backtraces as those of `what`, as returned by [`Traces::listed`].
+pub(crate) struct Listed<'a> {
+    /// Listed when displayed.
+    #[cfg(feature = "backtrace")]
+    traces: &'a Traces,
+
+    /// What the backtraces belong to, e.g. "Surviving portal".
+    #[cfg(feature = "backtrace")]
+    what: &'static str,
+
+    /// Ties this to the `Traces` without the `backtrace` feature.
+    _phantom: PhantomData<&'a Traces>,
+}
+
+impl Traces {
+    /// Locks `live`, ignoring poison since the map is always consistent.
+    #[cfg(feature = "backtrace")]
+    fn lock(&self) -> MutexGuard<'_, (u64, BTreeMap<u64, Backtrace>)> {
+        self.live.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Records the current backtrace for a new portal.
+    #[cfg(feature = "backtrace")]
+    pub(crate) fn capture(&self) -> TraceId {
+        let backtrace = Backtrace::force_capture();
+        let mut live = self.lock();
+        let id = live.0;
+        live.0 += 1;
+        live.1.insert(id, backtrace);
+        TraceId { id }
+    }
+
+    /// Records the current backtrace for a new portal.
+    #[cfg(not(feature = "backtrace"))]
+    #[allow(clippy::unused_self)]
+    #[inline]
+    pub(crate) const fn capture(&self) -> TraceId {
+        TraceId {}
+    }
+
+    /// Records the current backtrace for a new guard, until the returned value is dropped.
+    #[cfg(feature = "backtrace")]
+    pub(crate) fn capture_scoped(&self) -> Traced<'_> {
+        Traced {
+            traces: self,
+            id: self.capture(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Records the current backtrace for a new guard, until the returned value is dropped.
+    #[cfg(not(feature = "backtrace"))]
+    #[allow(clippy::unused_self)]
+    #[inline]
+    pub(crate) const fn capture_scoped(&self) -> Traced<'_> {
+        Traced {
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Lists the backtraces as those of `what` when displayed, each after a leading line break.
+    /// Formats as nothing without the `backtrace` feature.
+    #[cfg(feature = "backtrace")]
+    #[inline]
+    pub(crate) const fn listed(&self, what: &'static str) -> Listed<'_> {
+        Listed {
+            traces: self,
+            what,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Lists the backtraces as those of `what` when displayed, each after a leading line break.
+    /// Formats as nothing without the `backtrace` feature.
+    #[cfg(not(feature = "backtrace"))]
+    #[allow(clippy::unused_self)]
+    #[inline]
+    pub(crate) const fn listed(&self, _: &'static str) -> Listed<'_> {
+        Listed {
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Forgets the backtrace of a dropped portal.
+    #[cfg(feature = "backtrace")]
+    pub(crate) fn release(&self, trace: &TraceId) {
+        drop(self.lock().1.remove(&trace.id));
+    }
+
+    /// Forgets the backtrace of a dropped portal.
+    #[cfg(not(feature = "backtrace"))]
+    #[allow(clippy::unused_self)]
+    #[inline]
+    pub(crate) const fn release(&self, _: &TraceId) {}
+}
+
+/// Lists the surviving portals' backtraces on separate lines, after a leading line break.
+/// Formats as nothing without the `backtrace` feature.
+impl Display for Traces {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.listed("Surviving portal").fmt(f)
+    }
+}
+
+impl<'a> Display for Listed<'a> {
+    #[cfg(feature = "backtrace")]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, backtrace) in self.traces.lock().1.values().enumerate() {
+            write!(f, "\n\n{} #{} was created at:\n{}", self.what, i, backtrace)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(feature = "backtrace"))]
+    #[inline]
+    fn fmt(&self, _: &mut Formatter<'_>) -> fmt::Result {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "backtrace")]
+impl<'a> Drop for Traced<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.traces.release(&self.id);
+    }
+}
@@ -0,0 +1,100 @@
+//! Glue for GUI main loops, whose idle and timeout callbacks often outlive the state they act on.
+//! Only available with the `ui` feature.
+//!
+//! The wrappers here upgrade a weak portal each time they are called and skip the call, logging at debug level, once that fails.
+//! They then return `None`, which maps onto removing the callback's source in most main loops.
+//!
+//! Anchors can't defer their drop to a later main loop iteration: An `rc::Anchor` dropped while strong portals exist still has to freeze the thread,
+//! since its target may be gone by the time the main loop runs again.
+//! Registering callbacks through these wrappers avoids this, as they only hold strong portals while running.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ref_portals::{rc::RwAnchor, ui};
+//!
+//! let mut idle_callbacks: Vec<Box<dyn Fn() -> Option<()>>> = Vec::new();
+//!
+//! let mut frames = 0;
+//! let anchor = RwAnchor::new(&mut frames);
+//! idle_callbacks.push(Box::new({
+//!     let count = ui::callback_mut(anchor.weak_portal(), |frames, ()| *frames += 1);
+//!     move || count(())
+//! }));
+//!
+//! // One main loop iteration:
+//! idle_callbacks.retain(|callback| callback().is_some());
+//! assert_eq!(idle_callbacks.len(), 1);
+//!
+//! drop(anchor); // Doesn't panic.
+//! idle_callbacks.retain(|callback| callback().is_some());
+//! assert!(idle_callbacks.is_empty());
+//! assert_eq!(frames, 1);
+//! ```
+
+use {
+    crate::{
+        poison::UpgradeError,
+        rc::{WeakPortal, WeakRwPortal},
+    },
+    log::debug,
+    std::any,
+};
+
+/// Wraps `body` into a `'static` callback that reads through `weak_portal`.
+/// Once the anchor has been dropped, the callback does nothing and returns `None`.
+///
+/// # Example
+///
+/// ```rust
+/// use ref_portals::{rc::Anchor, ui};
+///
+/// let x = "Scoped".to_owned();
+/// let anchor = Anchor::new(&x);
+/// let len = ui::callback(anchor.weak_portal(), |x, ()| x.len());
+/// assert_eq!(len(()), Some(6));
+///
+/// drop(anchor);
+/// assert_eq!(len(()), None);
+/// ```
+pub fn callback<T: ?Sized + 'static, A, R>(
+    weak_portal: WeakPortal<T>,
+    body: impl Fn(&T, A) -> R + 'static,
+) -> impl Fn(A) -> Option<R> + 'static {
+    move |argument| {
+        if let Some(portal) = weak_portal.try_upgrade() {
+            Some(body(&portal, argument))
+        } else {
+            skipped::<T>(UpgradeError::Dropped);
+            None
+        }
+    }
+}
+
+/// Wraps `body` into a `'static` callback that writes through `weak_portal`.
+/// Once the anchor has been dropped or poisoned, the callback does nothing and returns `None`.
+///
+/// # Panics
+///
+/// The callback panics if the target is already borrowed, for example by a reentrant call from a nested main loop.
+pub fn callback_mut<T: ?Sized + 'static, A, R>(
+    weak_portal: WeakRwPortal<T>,
+    body: impl Fn(&mut T, A) -> R + 'static,
+) -> impl Fn(A) -> Option<R> + 'static {
+    move |argument| match weak_portal.try_upgrade_checked() {
+        Ok(portal) => Some(body(&mut portal.borrow_mut(), argument)),
+        Err(error) => {
+            skipped::<T>(error);
+            None
+        }
+    }
+}
+
+/// Logs that a callback on a `T` was skipped because of `error`.
+fn skipped<T: ?Sized>(error: UpgradeError) {
+    debug!(
+        "Skipped UI callback on `{}`: {}",
+        any::type_name::<T>(),
+        error,
+    );
+}
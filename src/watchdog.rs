@@ -0,0 +1,404 @@
+//! Reports portal guards that were held for too long, to help find stalls.
+//! Only available with the `watchdog` feature.
+//!
+//! Each guard records when and where it was acquired.
+//! If a threshold is set, guards held for longer than that are reported once they are released,
+//! by default through [`log::warn!`](https://docs.rs/log/0.4/log/macro.warn.html).
+//!
+//! Threadsafe guards are also tracked per thread, so that blocking on an anchor the same thread still holds a conflicting guard for
+//! (by locking it again or through `wait_idle`) panics instead of deadlocking.
+
+#[cfg(feature = "watchdog")]
+use {
+    crate::messages::{self, Report, Violation},
+    log::warn,
+    std::{
+        any,
+        cell::RefCell,
+        convert::TryFrom,
+        fmt::{self, Display, Formatter},
+        panic::Location,
+        ptr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex, PoisonError, RwLock,
+        },
+        thread::{self, Thread, ThreadId},
+        time::{Duration, Instant},
+    },
+};
+
+/// The threshold in nanoseconds, or `u64::MAX` while disabled.
+#[cfg(feature = "watchdog")]
+static THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+
+#[cfg(feature = "watchdog")]
+thread_local! {
+    /// The threadsafe guards currently held on this thread.
+    static HELD_HERE: RefCell<Vec<Lock>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The threadsafe guards currently held on any thread, along with that thread,
+/// so that anchors dropped while in use can name the threads holding guards.
+#[cfg(feature = "watchdog")]
+static HOLDERS: Mutex<Vec<(Lock, Thread)>> = Mutex::new(Vec::new());
+
+/// The custom report hook, if set.
+#[cfg(feature = "watchdog")]
+#[allow(clippy::type_complexity)]
+static HOOK: RwLock<Option<Box<dyn Fn(&LongHeldGuard) + Send + Sync>>> = RwLock::new(None);
+
+/// Sets how long a guard may be held before it's reported on release.
+/// `None` (the default) disables reporting.
+#[cfg(feature = "watchdog")]
+pub fn set_threshold(threshold: Option<Duration>) {
+    let nanos = threshold.map_or(u64::MAX, |threshold| {
+        u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX - 1)
+    });
+    THRESHOLD.store(nanos, Ordering::Relaxed);
+}
+
+/// Replaces the default report (a `log` warning) with `hook`.
+/// The hook runs on the releasing thread, after the guard's lock has been released.
+///
+/// # Example
+///
+/// ```rust
+/// use {
+///     ref_portals::{sync::RwAnchor, watchdog},
+///     std::{sync::{Arc, Mutex}, thread, time::Duration},
+/// };
+///
+/// let reports = Arc::new(Mutex::new(Vec::new()));
+/// watchdog::set_hook({
+///     let reports = Arc::clone(&reports);
+///     move |guard| reports.lock().unwrap().push(guard.location.line())
+/// });
+/// watchdog::set_threshold(Some(Duration::from_millis(10)));
+///
+/// let mut x = "Scoped".to_owned();
+/// let anchor = RwAnchor::new(&mut x);
+/// let portal = anchor.portal();
+/// let line = {
+///     let (_guard, line) = (portal.write(), line!());
+///     thread::sleep(Duration::from_millis(20));
+///     line
+/// };
+///
+/// assert_eq!(*reports.lock().unwrap(), vec![line]);
+/// # watchdog::set_threshold(None);
+/// ```
+#[cfg(feature = "watchdog")]
+pub fn set_hook(hook: impl Fn(&LongHeldGuard) + Send + Sync + 'static) {
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(hook));
+}
+
+/// Restores the default report (a `log` warning).
+#[cfg(feature = "watchdog")]
+pub fn reset_hook() {
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// A guard that was held for longer than the threshold, as passed to the hook.
+#[cfg(feature = "watchdog")]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct LongHeldGuard {
+    /// The method that acquired the guard, e.g. `"sync::RwPortal::write"`.
+    pub kind: &'static str,
+
+    /// The name of the anchored type, as returned by [`core::any::type_name`].
+    pub type_name: &'static str,
+
+    /// Where the guard was acquired.
+    pub location: &'static Location<'static>,
+
+    /// How long the guard was held.
+    pub held_for: Duration,
+}
+
+#[cfg(feature = "watchdog")]
+impl Display for LongHeldGuard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} guard for `{}` acquired at {} was held for {:?}",
+            self.kind, self.type_name, self.location, self.held_for,
+        )
+    }
+}
+
+/// Implemented by all portal guards, to let long-running operations check how long they have been holding one.
+pub trait HeldFor {
+    /// Returns how long this guard has been held so far.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use {
+    ///     ref_portals::{sync::RwAnchor, watchdog::HeldFor},
+    ///     std::time::Duration,
+    /// };
+    ///
+    /// let mut x = vec![0; 1000];
+    /// let anchor = RwAnchor::new(&mut x);
+    /// let portal = anchor.portal();
+    ///
+    /// let mut guard = portal.write();
+    /// for i in 0..1000 {
+    ///     if guard.held_for() > Duration::from_millis(5) {
+    ///         // Let others in before continuing.
+    ///         drop(guard);
+    ///         guard = portal.write();
+    ///     }
+    ///     guard[i] = i;
+    /// }
+    /// # drop(guard);
+    /// ```
+    #[cfg(feature = "watchdog")]
+    fn held_for(&self) -> Duration;
+}
+
+/// Records when and where a guard was acquired.
+/// Zero-sized without the `watchdog` feature.
+#[derive(Debug)]
+pub(crate) struct Held {
+    /// See [`LongHeldGuard::kind`].
+    #[cfg(feature = "watchdog")]
+    kind: &'static str,
+
+    /// See [`LongHeldGuard::type_name`].
+    #[cfg(feature = "watchdog")]
+    type_name: &'static str,
+
+    /// See [`LongHeldGuard::location`].
+    #[cfg(feature = "watchdog")]
+    location: &'static Location<'static>,
+
+    /// When the guard was acquired.
+    #[cfg(feature = "watchdog")]
+    since: Instant,
+
+    /// What the guard locks, iff it's tracked through `.tracked(…)`.
+    #[cfg(feature = "watchdog")]
+    lock: Option<Lock>,
+}
+
+/// A threadsafe guard's lock on an anchor, as tracked on the thread holding it.
+#[cfg(feature = "watchdog")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lock {
+    /// The address of the anchor's shared state.
+    anchor: usize,
+
+    /// Whether the guard excludes all others.
+    exclusive: bool,
+
+    /// See [`LongHeldGuard::kind`].
+    kind: &'static str,
+
+    /// See [`LongHeldGuard::location`].
+    location: &'static Location<'static>,
+
+    /// The thread holding the guard.
+    thread: ThreadId,
+}
+
+/// Panics iff this thread holds a guard on `anchor` that blocking `kind` would wait for, which would deadlock.  
+/// Exclusive access conflicts with any guard, shared access only with exclusive ones.
+///
+/// # Example
+///
+/// ```rust
+/// # use assert_panic::assert_panic;
+/// use ref_portals::sync::RwAnchor;
+///
+/// let mut x = "Scoped".to_owned();
+/// let anchor = RwAnchor::new(&mut x);
+/// let portal = anchor.portal();
+///
+/// let guard = portal.read();
+/// assert_panic!(
+///     { portal.write(); },
+///     String,
+///     starts with "Would deadlock on a guard held by this thread: sync::RwPortal::write at ",
+/// );
+/// drop(guard);
+/// # drop(portal);
+/// ```
+#[cfg(feature = "watchdog")]
+#[track_caller]
+pub(crate) fn check_blocking<A: ?Sized>(kind: &'static str, anchor: &A, exclusive: bool) {
+    let anchor = ptr::from_ref(anchor).cast::<()>().addr();
+    let conflict = HELD_HERE.with(|held| {
+        held.borrow()
+            .iter()
+            .find(|lock| lock.anchor == anchor && (exclusive || lock.exclusive))
+            .copied()
+    });
+    if let Some(lock) = conflict {
+        messages::panic(
+            Report::new(Violation::SelfDeadlock).with_details(&format_args!(
+                ": {} at {} waits for the {} guard acquired at {}",
+                kind,
+                Location::caller(),
+                lock.kind,
+                lock.location,
+            )),
+        )
+    }
+}
+
+/// Panics iff this thread holds a guard on `anchor` that blocking `kind` would wait for, which would deadlock.  
+/// Only with the `watchdog` feature.
+#[cfg(not(feature = "watchdog"))]
+#[inline]
+pub(crate) const fn check_blocking<A: ?Sized>(_: &'static str, _: &A, _: bool) {}
+
+/// Lists the guards on `anchor` this thread holds, each after a leading line break.
+#[cfg(feature = "watchdog")]
+pub(crate) fn held_here<A: ?Sized>(anchor: &A) -> String {
+    let anchor = ptr::from_ref(anchor).cast::<()>().addr();
+    HELD_HERE.with(|held| {
+        held.borrow()
+            .iter()
+            .filter(|lock| lock.anchor == anchor)
+            .map(|lock| format!("\n{} guard acquired at {}", lock.kind, lock.location))
+            .collect::<Vec<_>>()
+            .concat()
+    })
+}
+
+/// Lists the guards on `anchor` held on any thread and the names of those threads, each after a leading line break.
+#[cfg(feature = "watchdog")]
+pub(crate) fn holders<A: ?Sized>(anchor: &A) -> String {
+    let anchor = ptr::from_ref(anchor).cast::<()>().addr();
+    HOLDERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .filter(|(lock, _)| lock.anchor == anchor)
+        .map(|(lock, thread)| {
+            format!(
+                "\n{} guard acquired at {} is held by thread {:?} ({:?})",
+                lock.kind,
+                lock.location,
+                thread.name().unwrap_or("<unnamed>"),
+                lock.thread,
+            )
+        })
+        .collect::<Vec<_>>()
+        .concat()
+}
+
+/// Lists the guards on `anchor` held on any thread and the names of those threads, each after a leading line break.  
+/// Only with the `watchdog` feature, so this is always empty.
+#[cfg(not(feature = "watchdog"))]
+#[inline]
+pub(crate) const fn holders<A: ?Sized>(_: &A) -> String {
+    String::new()
+}
+
+/// Lists the guards on `anchor` this thread holds, each after a leading line break.  
+/// Only with the `watchdog` feature, so this is always empty.
+#[cfg(not(feature = "watchdog"))]
+#[inline]
+pub(crate) const fn held_here<A: ?Sized>(_: &A) -> String {
+    String::new()
+}
+
+impl Held {
+    /// Starts timing a guard for `T` that was just acquired by `kind`.
+    #[cfg(feature = "watchdog")]
+    #[track_caller]
+    pub(crate) fn start<T: ?Sized>(kind: &'static str) -> Self {
+        Self {
+            kind,
+            type_name: any::type_name::<T>(),
+            location: Location::caller(),
+            since: Instant::now(),
+            lock: None,
+        }
+    }
+
+    /// Tracks this guard as locking `anchor`'s shared state on the current thread, for [`check_blocking`] and [`holders`].
+    #[cfg(feature = "watchdog")]
+    pub(crate) fn tracked<A: ?Sized>(mut self, anchor: &A, exclusive: bool) -> Self {
+        let thread = thread::current();
+        let lock = Lock {
+            anchor: ptr::from_ref(anchor).cast::<()>().addr(),
+            exclusive,
+            kind: self.kind,
+            location: self.location,
+            thread: thread.id(),
+        };
+        HELD_HERE.with(|held| held.borrow_mut().push(lock));
+        HOLDERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((lock, thread));
+        self.lock = Some(lock);
+        self
+    }
+
+    /// Returns how long the guard has been held so far.
+    #[cfg(feature = "watchdog")]
+    pub(crate) fn held_for(&self) -> Duration {
+        self.since.elapsed()
+    }
+
+    /// Starts timing a guard for `T` that was just acquired by `kind`.
+    #[cfg(not(feature = "watchdog"))]
+    #[allow(clippy::extra_unused_type_parameters)]
+    #[inline]
+    pub(crate) const fn start<T: ?Sized>(_: &'static str) -> Self {
+        Self {}
+    }
+
+    /// Tracks this guard as locking `anchor`'s shared state on the current thread.
+    #[cfg(not(feature = "watchdog"))]
+    #[inline]
+    pub(crate) const fn tracked<A: ?Sized>(self, _: &A, _: bool) -> Self {
+        self
+    }
+}
+
+#[cfg(feature = "watchdog")]
+impl Drop for Held {
+    fn drop(&mut self) {
+        if let Some(lock) = self.lock {
+            // May be gone already while the thread exits.
+            HELD_HERE
+                .try_with(|held| {
+                    let mut held = held.borrow_mut();
+                    if let Some(index) = held.iter().position(|held| *held == lock) {
+                        held.swap_remove(index);
+                    }
+                })
+                .ok();
+            let mut holders = HOLDERS.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(index) = holders.iter().position(|(held, _)| *held == lock) {
+                holders.swap_remove(index);
+            }
+        }
+
+        let threshold = THRESHOLD.load(Ordering::Relaxed);
+        if threshold == u64::MAX {
+            return;
+        }
+        let held_for = self.since.elapsed();
+        if held_for <= Duration::from_nanos(threshold) {
+            return;
+        }
+        let report = LongHeldGuard {
+            kind: self.kind,
+            type_name: self.type_name,
+            location: self.location,
+            held_for,
+        };
+        match &*HOOK.read().unwrap_or_else(PoisonError::into_inner) {
+            Some(hook) => hook(&report),
+            None => warn!("{report}"),
+        }
+    }
+}
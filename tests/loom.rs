@@ -0,0 +1,89 @@
+//! Model checks of threadsafe anchor lifecycles.
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+
+#![cfg(loom)]
+
+use {
+    loom::thread,
+    ref_portals::sync::{Anchor, RwAnchor, WAnchor},
+};
+
+#[test]
+fn wait_idle_then_drop() {
+    loom::model(|| {
+        let x = "Scoped".to_owned();
+        let anchor = Anchor::new(&x);
+        let portal = anchor.portal();
+        let handle = thread::spawn(move || assert_eq!(*portal, "Scoped"));
+
+        anchor.wait_idle();
+        drop(anchor);
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn concurrent_writes_then_drop() {
+    loom::model(|| {
+        let mut x = 0;
+        let anchor = RwAnchor::new(&mut x);
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let portal = anchor.portal();
+                thread::spawn(move || *portal.write() += 1)
+            })
+            .collect();
+
+        anchor.wait_idle();
+        drop(anchor);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(x, 2);
+    });
+}
+
+#[test]
+fn blocking_drop_races_portal_drop() {
+    loom::model(|| {
+        let x = "Scoped".to_owned();
+        let anchor = Anchor::new_blocking(&x);
+        let portal = anchor.portal();
+        let handle = thread::spawn(move || assert_eq!(*portal, "Scoped"));
+
+        drop(anchor);
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn blocking_drop_races_writes() {
+    loom::model(|| {
+        let mut x = 0;
+        let anchor = RwAnchor::new_blocking(&mut x);
+        let portal = anchor.portal();
+        let handle = thread::spawn(move || *portal.write() += 1);
+
+        anchor.drop_blocking();
+        handle.join().unwrap();
+        assert_eq!(x, 1);
+    });
+}
+
+#[test]
+fn blocking_drop_races_clone() {
+    loom::model(|| {
+        let mut x = 0;
+        let anchor = WAnchor::new_blocking(&mut x);
+        let portal = anchor.portal();
+        let handle = thread::spawn(move || {
+            let clone = portal.clone();
+            drop(portal);
+            *clone.lock() += 1;
+        });
+
+        drop(anchor);
+        handle.join().unwrap();
+        assert_eq!(x, 1);
+    });
+}